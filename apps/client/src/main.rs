@@ -1,6 +1,21 @@
-use unen_engine::core::application::Application;
+use unen_engine::core::{application::Application, config::EngineConfig};
 
 fn main() {
-    let mut app = Application::new("UnnamedClient");
+    let config = EngineConfig::builder()
+        .title("UnnamedClient")
+        .optional_file("client.toml")
+        .env("UNEN")
+        .args(std::env::args().skip(1))
+        .build();
+
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Invalid configuration: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut app = Application::with_config(config);
     app.run();
 }
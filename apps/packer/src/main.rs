@@ -0,0 +1,83 @@
+//! Packs an asset directory into a single archive for shipping builds.
+//!
+//! ```text
+//! unen-packer <asset directory> <output archive> [--no-compression]
+//! ```
+
+use std::{fs::File, io::BufWriter, path::PathBuf, process};
+
+use unen_engine::asset::{archive::Compression, ArchiveWriter};
+
+const USAGE: &str =
+    "Usage: unen-packer <asset directory> <output archive> [--no-compression]";
+
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    compression: Compression,
+}
+
+impl Args {
+    fn parse<I: Iterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut paths = Vec::new();
+        let mut compression = Compression::Lz4;
+
+        for arg in args {
+            match arg.as_str() {
+                "--no-compression" => compression = Compression::None,
+                flag if flag.starts_with("--") => {
+                    return Err(format!("Unknown flag '{flag}'"));
+                }
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        match <[PathBuf; 2]>::try_from(paths) {
+            Ok([input, output]) => Ok(Self {
+                input,
+                output,
+                compression,
+            }),
+            Err(_) => Err("Expected an input and an output path".to_string()),
+        }
+    }
+}
+
+fn pack(args: &Args) -> Result<(), String> {
+    let mut writer = ArchiveWriter::new();
+    let added =
+        writer
+            .add_dir(&args.input, args.compression)
+            .map_err(|err| {
+                format!("Failed to pack '{}': {err}", args.input.display())
+            })?;
+
+    let file = File::create(&args.output).map_err(|err| {
+        format!("Failed to create '{}': {err}", args.output.display())
+    })?;
+    writer.write(BufWriter::new(file)).map_err(|err| {
+        format!("Failed to write '{}': {err}", args.output.display())
+    })?;
+
+    println!(
+        "Packed {added} files into '{}' ({} bytes of data)",
+        args.output.display(),
+        writer.data_size(),
+    );
+    Ok(())
+}
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = pack(&args) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,114 @@
+//! Compares the spatial indexes against testing every entity.
+
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkId, Criterion,
+};
+use unen_engine::{
+    math::{Aabb, Ray, Vec3},
+    scene::Entity,
+    spatial::{Bvh, LinearIndex, SpatialIndex, UniformGrid},
+};
+
+const COUNTS: [usize; 3] = [100, 1000, 10000];
+
+/// Deterministic boxes up to 4 units wide, spread so that the density stays
+/// the same for every count.
+fn boxes(count: usize, seed: u64) -> Vec<(Entity, Aabb)> {
+    let mut state = seed;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 40) as f32 / (1u64 << 24) as f32
+    };
+
+    let side = (count as f32).cbrt() * 8.0;
+    (0..count as u32)
+        .map(|i| {
+            let center = Vec3::new(random(), random(), random()) * side;
+            let size = Vec3::new(random(), random(), random()) * 4.0;
+            (Entity::from_raw(i, 0), Aabb::from_center_size(center, size))
+        })
+        .collect()
+}
+
+fn indexes(count: usize) -> Vec<(&'static str, Box<dyn SpatialIndex>)> {
+    let mut indexes: Vec<(&str, Box<dyn SpatialIndex>)> = vec![
+        ("linear", Box::new(LinearIndex::new())),
+        ("grid", Box::new(UniformGrid::new(4.0))),
+        ("bvh", Box::new(Bvh::new())),
+    ];
+    for (_, index) in &mut indexes {
+        for (entity, bounds) in boxes(count, 1) {
+            index.insert(entity, bounds);
+        }
+    }
+    indexes
+}
+
+/// Runs the query from 64 points spread over the same area as the boxes.
+fn bench_query<F>(c: &mut Criterion, name: &str, query: F)
+where
+    F: Fn(&dyn SpatialIndex, Vec3, Vec3),
+{
+    let mut group = c.benchmark_group(name);
+    for count in COUNTS {
+        let probes: Vec<_> = boxes(64, 2)
+            .into_iter()
+            .map(|(_, probe)| {
+                // The probes are spread for 64 boxes
+                let scale = (count as f32 / 64.0).cbrt();
+                (probe.center() * scale, probe.size() - 2.0)
+            })
+            .collect();
+        for (index_name, index) in indexes(count) {
+            group.bench_function(BenchmarkId::new(index_name, count), |b| {
+                b.iter(|| {
+                    for (point, direction) in &probes {
+                        query(index.as_ref(), *point, *direction);
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn queries(c: &mut Criterion) {
+    bench_query(c, "radius", |index, point, _| {
+        black_box(index.query_radius(point, 6.0));
+    });
+    bench_query(c, "raycast_first", |index, point, direction| {
+        if let Some(ray) = Ray::new(point, direction) {
+            black_box(index.raycast_first(&ray, f32::INFINITY));
+        }
+    });
+    bench_query(c, "nearest_8", |index, point, _| {
+        black_box(index.nearest(point, 8));
+    });
+}
+
+fn updates(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    for count in COUNTS {
+        let start = boxes(count, 1);
+        for (index_name, mut index) in indexes(count) {
+            let mut offset = 0.0;
+            group.bench_function(BenchmarkId::new(index_name, count), |b| {
+                b.iter(|| {
+                    // Every entity moves a bit, like on a regular step
+                    offset = if offset > 0.0 { 0.0 } else { 0.05 };
+                    for (entity, bounds) in &start {
+                        let moved =
+                            Aabb::new(bounds.min + offset, bounds.max + offset);
+                        index.update(*entity, moved);
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, queries, updates);
+criterion_main!(benches);
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    core::event::{AnimationEvent, Dispatcher, Event},
+    scene::World,
+};
+
+use super::{Animation, AnimationId, Error};
+
+struct Player {
+    animation: Box<dyn Animation>,
+    /// Seconds since the start.
+    time: f32,
+    speed: f32,
+    paused: bool,
+}
+
+/// Plays animations on a `World`.
+#[derive(Default)]
+pub struct Animator {
+    /// Ordered so that later animations win over the same property.
+    players: BTreeMap<AnimationId, Player>,
+    next_id: u64,
+}
+
+impl Animator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts playing the animation on the next update.
+    pub fn play<A: Animation>(&mut self, animation: A) -> AnimationId {
+        let id = AnimationId(self.next_id);
+        self.next_id += 1;
+        self.players.insert(
+            id,
+            Player {
+                animation: Box::new(animation),
+                time: 0.0,
+                speed: 1.0,
+                paused: false,
+            },
+        );
+        id
+    }
+
+    /// Stops the animation where it is, no event is sent.
+    pub fn stop(&mut self, id: AnimationId) -> Result<(), Error> {
+        self.players
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(Error::NoSuchAnimation(id))
+    }
+
+    pub fn stop_all(&mut self) {
+        self.players.clear();
+    }
+
+    pub fn set_paused(
+        &mut self,
+        id: AnimationId,
+        paused: bool,
+    ) -> Result<(), Error> {
+        self.player_mut(id)?.paused = paused;
+        Ok(())
+    }
+
+    /// Multiplies the time passing for the animation, negative speeds play
+    /// it backwards down to the start.
+    pub fn set_speed(
+        &mut self,
+        id: AnimationId,
+        speed: f32,
+    ) -> Result<(), Error> {
+        self.player_mut(id)?.speed = speed;
+        Ok(())
+    }
+
+    /// Jumps to the passed second, applied on the next update.
+    pub fn seek(&mut self, id: AnimationId, time: f32) -> Result<(), Error> {
+        self.player_mut(id)?.time = time.max(0.0);
+        Ok(())
+    }
+
+    pub fn is_playing(&self, id: AnimationId) -> bool {
+        self.players.contains_key(&id)
+    }
+
+    pub fn is_paused(&self, id: AnimationId) -> bool {
+        self.players.get(&id).is_some_and(|player| player.paused)
+    }
+
+    /// Seconds since the start of the animation.
+    pub fn time(&self, id: AnimationId) -> Option<f32> {
+        self.players.get(&id).map(|player| player.time)
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// Advances every animation by `delta` seconds and applies them, the
+    /// finished ones are removed after sending `AnimationEvent::Finished`.
+    ///
+    /// _The `Engine` does this on every step with the delta of its clock._
+    pub fn update(
+        &mut self,
+        delta: f32,
+        world: &mut World,
+        dispatcher: &Dispatcher,
+    ) {
+        crate::profile_scope!("Animator::update", "animation");
+
+        let mut finished = Vec::new();
+        for (id, player) in &mut self.players {
+            if !player.paused {
+                player.time = (player.time + delta * player.speed).max(0.0);
+            }
+
+            let duration = player.animation.duration();
+            player.animation.apply(player.time.min(duration), world);
+            if player.time >= duration {
+                finished.push(*id);
+            }
+        }
+
+        for id in finished {
+            self.players.remove(&id);
+            dispatcher.send(Event::Animation(AnimationEvent::Finished(id)));
+        }
+    }
+
+    fn player_mut(&mut self, id: AnimationId) -> Result<&mut Player, Error> {
+        self.players.get_mut(&id).ok_or(Error::NoSuchAnimation(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        animation::{lens, Easing, Sampler, Sequence, Track, Tween},
+        core::event::create_handler,
+        math::{Transform, Vec3},
+    };
+
+    #[test]
+    fn animations_play_and_finish() {
+        let (dispatcher, consumer) = create_handler();
+        let mut world = World::new();
+        let entity = world.spawn_with(Transform::IDENTITY);
+        let mut animator = Animator::new();
+
+        let movement = animator.play(
+            Sequence::new()
+                .then(
+                    Tween::new(Vec3::ZERO, Vec3::X, 1.0)
+                        .target(lens::translation(entity)),
+                )
+                .then(
+                    Track::new(Vec3::ONE)
+                        .with_keyframe(1.0, Vec3::splat(2.0), Easing::Linear)
+                        .target(lens::scale(entity)),
+                ),
+        );
+        let forever = animator.play(
+            Tween::new(0.0, 1.0, 1.0)
+                .target(|_: &mut World, _: f32| {})
+                .looping(),
+        );
+
+        animator.update(0.5, &mut world, &dispatcher);
+        let transform = world.get::<Transform>(entity).unwrap();
+        assert_eq!(transform.translation, Vec3::new(0.5, 0.0, 0.0));
+        assert_eq!(transform.scale, Vec3::ONE);
+
+        // Paused animations stay where they are
+        animator.set_paused(movement, true).unwrap();
+        animator.update(10.0, &mut world, &dispatcher);
+        assert_eq!(animator.time(movement), Some(0.5));
+        assert!(consumer.poll().is_none());
+
+        animator.set_paused(movement, false).unwrap();
+        animator.set_speed(movement, 2.0).unwrap();
+        animator.update(0.5, &mut world, &dispatcher);
+        let transform = world.get::<Transform>(entity).unwrap();
+        assert_eq!(transform.translation, Vec3::X);
+        assert_eq!(transform.scale, Vec3::splat(1.5));
+
+        animator.update(1.0, &mut world, &dispatcher);
+        let transform = world.get::<Transform>(entity).unwrap();
+        assert_eq!(transform.scale, Vec3::splat(2.0));
+        assert!(!animator.is_playing(movement));
+        assert!(matches!(
+            consumer.poll(),
+            Some(Event::Animation(AnimationEvent::Finished(id))) if id == movement
+        ));
+
+        assert!(animator.is_playing(forever));
+        animator.stop(forever).unwrap();
+        assert_eq!(
+            animator.stop(forever),
+            Err(Error::NoSuchAnimation(forever))
+        );
+        assert!(animator.is_empty());
+        assert!(consumer.poll().is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::scene::World;
+
+use super::Animation;
+
+/// Does nothing for a while, useful in sequences.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delay(pub f32);
+
+impl Animation for Delay {
+    fn duration(&self) -> f32 {
+        self.0
+    }
+
+    fn apply(&mut self, _: f32, _: &mut World) {}
+}
+
+/// Plays animations one after the other.
+#[derive(Default)]
+pub struct Sequence {
+    /// With the time they start at.
+    animations: Vec<(f32, Box<dyn Animation>)>,
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts the animation after the previous ones.
+    ///
+    /// _Anything after an endless animation never starts._
+    pub fn then<A: Animation>(mut self, animation: A) -> Self {
+        let start = self.duration();
+        self.animations.push((start, Box::new(animation)));
+        self
+    }
+
+    /// Waits the passed seconds before the next animation.
+    pub fn then_wait(self, seconds: f32) -> Self {
+        self.then(Delay(seconds))
+    }
+}
+
+impl Animation for Sequence {
+    fn duration(&self) -> f32 {
+        self.animations
+            .last()
+            .map_or(0.0, |(start, animation)| start + animation.duration())
+    }
+
+    /// Previous animations are applied at their end, so the later ones win
+    /// when animating the same property.
+    fn apply(&mut self, time: f32, world: &mut World) {
+        for (start, animation) in &mut self.animations {
+            if time < *start {
+                break;
+            }
+            let local = (time - *start).min(animation.duration());
+            animation.apply(local, world);
+        }
+    }
+}
+
+/// Plays animations at the same time.
+#[derive(Default)]
+pub struct Parallel {
+    animations: Vec<Box<dyn Animation>>,
+}
+
+impl Parallel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<A: Animation>(mut self, animation: A) -> Self {
+        self.animations.push(Box::new(animation));
+        self
+    }
+}
+
+impl Animation for Parallel {
+    /// Finishes with the longest animation.
+    fn duration(&self) -> f32 {
+        self.animations
+            .iter()
+            .map(|animation| animation.duration())
+            .fold(0.0, f32::max)
+    }
+
+    fn apply(&mut self, time: f32, world: &mut World) {
+        for animation in &mut self.animations {
+            animation.apply(time.min(animation.duration()), world);
+        }
+    }
+}
+
+/// Plays an animation multiple times.
+pub struct Repeat<A> {
+    animation: A,
+    /// `None` to repeat forever.
+    count: Option<u32>,
+    /// Plays backwards every other time.
+    ping_pong: bool,
+}
+
+impl<A: Animation> Repeat<A> {
+    /// Repeats forever.
+    pub fn new(animation: A) -> Self {
+        Self {
+            animation,
+            count: None,
+            ping_pong: false,
+        }
+    }
+
+    pub fn with_count(mut self, count: Option<u32>) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn with_ping_pong(mut self, ping_pong: bool) -> Self {
+        self.ping_pong = ping_pong;
+        self
+    }
+}
+
+impl<A: Animation> Animation for Repeat<A> {
+    fn duration(&self) -> f32 {
+        let duration = self.animation.duration();
+        match self.count {
+            Some(count) => duration * count as f32,
+            // Repeating nothing forever takes no time
+            None if duration <= 0.0 => 0.0,
+            None => f32::INFINITY,
+        }
+    }
+
+    fn apply(&mut self, time: f32, world: &mut World) {
+        let duration = self.animation.duration();
+        if duration <= 0.0 || !duration.is_finite() {
+            self.animation.apply(time.min(duration), world);
+            return;
+        }
+
+        let mut pass = (time / duration).floor();
+        let mut local = time - pass * duration;
+        // The end of the last pass, not the start of the one after
+        if let Some(count) = self.count {
+            if pass >= count as f32 {
+                pass = count.saturating_sub(1) as f32;
+                local = duration;
+            }
+        }
+        if self.ping_pong && pass % 2.0 == 1.0 {
+            local = duration - local;
+        }
+        self.animation.apply(local, world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        animation::{Sampler, Tween},
+        math::EPSILON,
+    };
+
+    struct Value(f32);
+
+    fn value(world: &World) -> f32 {
+        world.resource::<Value>().unwrap().0
+    }
+
+    fn tween(from: f32, to: f32, duration: f32) -> impl Animation {
+        Tween::new(from, to, duration).target(|world: &mut World, value| {
+            world.insert_resource(Value(value));
+        })
+    }
+
+    fn assert_at(animation: &mut impl Animation, time: f32, expected: f32) {
+        let mut world = World::new();
+        animation.apply(time, &mut world);
+        assert!(
+            (value(&world) - expected).abs() < EPSILON,
+            "{} at {time}, expected {expected}",
+            value(&world)
+        );
+    }
+
+    #[test]
+    fn sequences_chain_animations() {
+        let mut sequence = Sequence::new()
+            .then(tween(0.0, 1.0, 1.0))
+            .then_wait(1.0)
+            .then(tween(1.0, 3.0, 2.0));
+        assert_eq!(sequence.duration(), 4.0);
+
+        assert_at(&mut sequence, 0.5, 0.5);
+        assert_at(&mut sequence, 1.5, 1.0);
+        assert_at(&mut sequence, 3.0, 2.0);
+        assert_at(&mut sequence, 4.0, 3.0);
+    }
+
+    #[test]
+    fn parallel_groups_run_together() {
+        let mut world = World::new();
+        let mut group = Parallel::new().with(tween(0.0, 1.0, 1.0)).with(
+            Tween::new(0.0, 4.0, 2.0).target(
+                |world: &mut World, value: f32| {
+                    world.insert_resource(value as u32);
+                },
+            ),
+        );
+        assert_eq!(group.duration(), 2.0);
+
+        group.apply(1.5, &mut world);
+        assert_eq!(value(&world), 1.0);
+        assert_eq!(world.resource::<u32>(), Some(&3));
+    }
+
+    #[test]
+    fn repeating_and_ping_pong() {
+        let mut twice = tween(0.0, 1.0, 1.0).repeat(2);
+        assert_eq!(twice.duration(), 2.0);
+        assert_at(&mut twice, 1.25, 0.25);
+        assert_at(&mut twice, 2.0, 1.0);
+
+        let mut looping = tween(0.0, 1.0, 1.0).looping();
+        assert_eq!(looping.duration(), f32::INFINITY);
+        assert_at(&mut looping, 10.5, 0.5);
+
+        let mut ping_pong = tween(0.0, 1.0, 1.0).ping_pong();
+        assert_at(&mut ping_pong, 0.25, 0.25);
+        assert_at(&mut ping_pong, 1.25, 0.75);
+        assert_at(&mut ping_pong, 2.25, 0.25);
+
+        let mut bounded = tween(0.0, 1.0, 1.0).ping_pong().with_count(Some(2));
+        assert_at(&mut bounded, 2.0, 0.0);
+    }
+}
@@ -0,0 +1,204 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+/// Shapes the progress of an animation, maps `0.0..=1.0` to a value that
+/// starts at `0.0` and ends at `1.0`.
+///
+/// _`Back` and `Elastic` overshoot outside of that range on the way._
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize,
+)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    /// Jumps in the passed amount of equal steps.
+    Steps(u32),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => out(t, |t| t * t),
+            Self::QuadInOut => in_out(t, |t| t * t),
+            Self::CubicIn => t.powi(3),
+            Self::CubicOut => out(t, |t| t.powi(3)),
+            Self::CubicInOut => in_out(t, |t| t.powi(3)),
+            Self::QuartIn => t.powi(4),
+            Self::QuartOut => out(t, |t| t.powi(4)),
+            Self::QuartInOut => in_out(t, |t| t.powi(4)),
+            Self::SineIn => sine(t),
+            Self::SineOut => out(t, sine),
+            Self::SineInOut => in_out(t, sine),
+            Self::ExpoIn => expo(t),
+            Self::ExpoOut => out(t, expo),
+            Self::ExpoInOut => in_out(t, expo),
+            Self::CircIn => circ(t),
+            Self::CircOut => out(t, circ),
+            Self::CircInOut => in_out(t, circ),
+            Self::BackIn => back(t),
+            Self::BackOut => out(t, back),
+            Self::BackInOut => in_out(t, back),
+            Self::ElasticIn => elastic(t),
+            Self::ElasticOut => out(t, elastic),
+            Self::ElasticInOut => in_out(t, elastic),
+            Self::BounceIn => out(t, bounce),
+            Self::BounceOut => bounce(t),
+            Self::BounceInOut => in_out(t, |t| out(t, bounce)),
+            Self::Steps(steps) => {
+                let steps = steps.max(1) as f32;
+                (t * steps).floor().min(steps) / steps
+            }
+        }
+    }
+}
+
+/// Mirrors an ease-in curve.
+fn out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    1.0 - ease_in(1.0 - t)
+}
+
+/// Ease-in for the first half, mirrored for the second.
+fn in_out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    if t < 0.5 {
+        ease_in(t * 2.0) * 0.5
+    } else {
+        1.0 - ease_in((1.0 - t) * 2.0) * 0.5
+    }
+}
+
+fn sine(t: f32) -> f32 {
+    1.0 - (t * PI * 0.5).cos()
+}
+
+fn expo(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else {
+        2f32.powf(10.0 * t - 10.0)
+    }
+}
+
+fn circ(t: f32) -> f32 {
+    1.0 - (1.0 - t * t).sqrt()
+}
+
+fn back(t: f32) -> f32 {
+    const OVERSHOOT: f32 = 1.70158;
+    t * t * ((OVERSHOOT + 1.0) * t - OVERSHOOT)
+}
+
+fn elastic(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+    -(2f32.powf(10.0 * t - 10.0))
+        * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+}
+
+/// Ease-out, the others mirror it.
+fn bounce(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+
+    const ALL: [Easing; 29] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::QuartIn,
+        Easing::QuartOut,
+        Easing::QuartInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::ExpoInOut,
+        Easing::CircIn,
+        Easing::CircOut,
+        Easing::CircInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+        Easing::Steps(4),
+    ];
+
+    #[test]
+    fn curves_start_at_zero_and_end_at_one() {
+        for easing in ALL {
+            assert!(easing.apply(0.0).abs() < 1e-3, "{easing:?} at 0");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-3, "{easing:?} at 1");
+            // Out of range progress is clamped
+            assert_eq!(easing.apply(2.0), easing.apply(1.0));
+        }
+    }
+
+    #[test]
+    fn curve_shapes() {
+        assert!((Easing::QuadIn.apply(0.5) - 0.25).abs() < EPSILON);
+        assert!((Easing::QuadOut.apply(0.5) - 0.75).abs() < EPSILON);
+        assert!((Easing::CubicInOut.apply(0.5) - 0.5).abs() < EPSILON);
+        assert!((Easing::CubicInOut.apply(0.25) - 0.0625).abs() < EPSILON);
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+        assert_eq!(Easing::Steps(4).apply(0.3), 0.25);
+        assert_eq!(Easing::Steps(4).apply(0.99), 0.75);
+    }
+}
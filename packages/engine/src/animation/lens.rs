@@ -0,0 +1,69 @@
+//! Common lenses, anything else can be animated with `component()`,
+//! `resource()` or a closure.
+
+use crate::{
+    math::{Quat, Transform, Vec3},
+    scene::{Entity, World},
+};
+
+/// Writes animated values to the world.
+pub trait Lens<T>: 'static {
+    fn apply(&mut self, world: &mut World, value: T);
+}
+
+impl<T, F: FnMut(&mut World, T) + 'static> Lens<T> for F {
+    fn apply(&mut self, world: &mut World, value: T) {
+        self(world, value)
+    }
+}
+
+/// Sets a field of a component, nothing happens if the entity does not
+/// have it.
+pub fn component<C, T, F>(entity: Entity, mut set: F) -> impl Lens<T>
+where
+    C: 'static,
+    F: FnMut(&mut C, T) + 'static,
+{
+    move |world: &mut World, value: T| {
+        if let Some(component) = world.get_mut::<C>(entity) {
+            set(component, value);
+        }
+    }
+}
+
+/// Sets a field of a resource, nothing happens if it is missing.
+pub fn resource<R, T, F>(mut set: F) -> impl Lens<T>
+where
+    R: 'static,
+    F: FnMut(&mut R, T) + 'static,
+{
+    move |world: &mut World, value: T| {
+        if let Some(resource) = world.resource_mut::<R>() {
+            set(resource, value);
+        }
+    }
+}
+
+pub fn transform(entity: Entity) -> impl Lens<Transform> {
+    component(entity, |transform: &mut Transform, value| {
+        *transform = value
+    })
+}
+
+pub fn translation(entity: Entity) -> impl Lens<Vec3> {
+    component(entity, |transform: &mut Transform, value| {
+        transform.translation = value;
+    })
+}
+
+pub fn rotation(entity: Entity) -> impl Lens<Quat> {
+    component(entity, |transform: &mut Transform, value| {
+        transform.rotation = value;
+    })
+}
+
+pub fn scale(entity: Entity) -> impl Lens<Vec3> {
+    component(entity, |transform: &mut Transform, value| {
+        transform.scale = value;
+    })
+}
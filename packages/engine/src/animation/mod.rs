@@ -0,0 +1,204 @@
+//! Tweens, keyframes and their composition.
+//!
+//! A `Tween` goes from a value to another with an `Easing`, a `Track`
+//! samples a list of keyframes. Both produce values of any `Animatable`
+//! type, a `Lens` writes them to the `World` (a component field, a resource,
+//! ...) turning them into an `Animation`. Animations can be chained in a
+//! `Sequence`, run together in a `Parallel` group and repeated (optionally
+//! going back and forth) with `Repeat`.
+//!
+//! The `Animator` plays animations, advancing them with the delta of the
+//! engine clock: pausing the `Time` pauses every animation. Finished
+//! animations are reported with an `AnimationEvent`.
+//!
+//! ```
+//! # use unen_engine::{math::{Transform, Vec3}, scene::World};
+//! # use unen_engine::animation::{lens, Animation, Easing, Sampler, Sequence, Tween};
+//! let mut world = World::new();
+//! let entity = world.spawn_with(Transform::IDENTITY);
+//!
+//! let bounce = Sequence::new()
+//!     .then(
+//!         Tween::new(Vec3::ZERO, Vec3::Y, 0.5)
+//!             .with_easing(Easing::QuadOut)
+//!             .target(lens::translation(entity)),
+//!     )
+//!     .then(
+//!         Tween::new(Vec3::Y, Vec3::ZERO, 0.5)
+//!             .with_easing(Easing::BounceOut)
+//!             .target(lens::translation(entity)),
+//!     )
+//!     .looping();
+//! // engine.animator_mut().play(bounce);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    math::{Quat, Transform, Vec2, Vec3, Vec4},
+    render::Color,
+    scene::World,
+};
+
+pub mod animator;
+pub mod composite;
+pub mod easing;
+pub mod lens;
+pub mod tween;
+
+pub use animator::Animator;
+pub use composite::{Delay, Parallel, Repeat, Sequence};
+pub use easing::Easing;
+pub use lens::Lens;
+pub use tween::{Animated, FnSampler, Keyframe, Sampler, Track, Tween};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The animation finished, was stopped or never existed.
+    #[error("No such animation '{0}'")]
+    NoSuchAnimation(AnimationId),
+}
+
+/// Identifies an animation played by an `Animator`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct AnimationId(pub(crate) u64);
+
+impl std::fmt::Display for AnimationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Animation({})", self.0)
+    }
+}
+
+/// Values that can be interpolated.
+pub trait Animatable: Clone + 'static {
+    /// Returns the value `t` of the way to `other`, `t` can go outside of
+    /// `0.0..=1.0` with overshooting easings.
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Animatable for Vec2 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Animatable for Vec3 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Animatable for Vec4 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Animatable for Quat {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.slerp(*other, t)
+    }
+}
+
+impl Animatable for Transform {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: self.translation.interpolate(&other.translation, t),
+            rotation: self.rotation.interpolate(&other.rotation, t),
+            scale: self.scale.interpolate(&other.scale, t),
+        }
+    }
+}
+
+impl Animatable for Color {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Vec4::from(*self).interpolate(&Vec4::from(*other), t).into()
+    }
+}
+
+/// Something that changes the world over time.
+///
+/// The state only depends on the time, so animations can be sampled in any
+/// order: backwards, skipping ahead or many times at the same time.
+pub trait Animation: 'static {
+    /// Seconds, `f32::INFINITY` for endless animations.
+    fn duration(&self) -> f32;
+
+    /// Applies the state `time` seconds after the start, `time` is between
+    /// `0.0` and the duration.
+    fn apply(&mut self, time: f32, world: &mut World);
+
+    /// Plays the animation `count` times.
+    fn repeat(self, count: u32) -> Repeat<Self>
+    where
+        Self: Sized,
+    {
+        Repeat::new(self).with_count(Some(count))
+    }
+
+    /// Plays the animation forever.
+    fn looping(self) -> Repeat<Self>
+    where
+        Self: Sized,
+    {
+        Repeat::new(self)
+    }
+
+    /// Plays the animation forever, backwards every other time.
+    fn ping_pong(self) -> Repeat<Self>
+    where
+        Self: Sized,
+    {
+        Repeat::new(self).with_ping_pong(true)
+    }
+}
+
+impl Animation for Box<dyn Animation> {
+    fn duration(&self) -> f32 {
+        self.as_ref().duration()
+    }
+
+    fn apply(&mut self, time: f32, world: &mut World) {
+        self.as_mut().apply(time, world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use super::*;
+    use crate::math::EPSILON;
+
+    #[test]
+    fn interpolation() {
+        assert_eq!(2.0.interpolate(&4.0, 0.25), 2.5);
+        assert_eq!(Vec2::ZERO.interpolate(&Vec2::ONE, 0.5), Vec2::splat(0.5));
+
+        let half =
+            Quat::IDENTITY.interpolate(&Quat::from_rotation_z(FRAC_PI_2), 0.5);
+        assert!(half.abs_diff_eq(Quat::from_rotation_z(FRAC_PI_4), EPSILON));
+
+        let color =
+            Color::BLACK.interpolate(&Color::WHITE.with_alpha(0.0), 0.5);
+        assert_eq!(color, Color::rgba(0.5, 0.5, 0.5, 0.5));
+    }
+}
@@ -0,0 +1,239 @@
+use std::marker::PhantomData;
+
+use crate::scene::World;
+
+use super::{Animatable, Animation, Easing, Lens};
+
+/// Produces a value for every point in time.
+pub trait Sampler: 'static {
+    type Value;
+
+    /// Seconds.
+    fn duration(&self) -> f32;
+
+    /// Returns the value `time` seconds after the start, clamped to the
+    /// duration.
+    fn sample(&self, time: f32) -> Self::Value;
+
+    /// Turns the sampler into an animation writing to the lens.
+    fn target<L: Lens<Self::Value>>(self, lens: L) -> Animated<Self, L>
+    where
+        Self: Sized,
+    {
+        Animated {
+            sampler: self,
+            lens,
+        }
+    }
+}
+
+/// Goes from a value to another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    /// Seconds.
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl<T: Animatable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration: duration.max(0.0),
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+impl<T: Animatable> Sampler for Tween<T> {
+    type Value = T;
+
+    fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    fn sample(&self, time: f32) -> T {
+        let progress = if self.duration > 0.0 {
+            time / self.duration
+        } else {
+            1.0
+        };
+        self.from.interpolate(&self.to, self.easing.apply(progress))
+    }
+}
+
+/// Value reached at a point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe<T> {
+    /// Seconds since the start of the track.
+    pub time: f32,
+    pub value: T,
+    /// Used to get here from the previous keyframe.
+    pub easing: Easing,
+}
+
+/// Keyframes sampled over time, holding the first and last values before
+/// and after them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    /// Sorted by time.
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Track<T> {
+    /// Creates a track starting with the passed value.
+    pub fn new(value: T) -> Self {
+        Self {
+            keyframes: vec![Keyframe {
+                time: 0.0,
+                value,
+                easing: Easing::Linear,
+            }],
+        }
+    }
+
+    /// Adds a keyframe, replacing the one at the same time.
+    pub fn with_keyframe(
+        mut self,
+        time: f32,
+        value: T,
+        easing: Easing,
+    ) -> Self {
+        self.insert(Keyframe {
+            time: time.max(0.0),
+            value,
+            easing,
+        });
+        self
+    }
+
+    pub fn insert(&mut self, keyframe: Keyframe<T>) {
+        let index = self
+            .keyframes
+            .partition_point(|other| other.time < keyframe.time);
+        match self.keyframes.get_mut(index) {
+            Some(other) if other.time == keyframe.time => *other = keyframe,
+            _ => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+}
+
+impl<T: Animatable> Sampler for Track<T> {
+    type Value = T;
+
+    fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    fn sample(&self, time: f32) -> T {
+        // There is always at least a keyframe
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let Some(to) = self.keyframes.get(next) else {
+            return self.keyframes[self.keyframes.len() - 1].value.clone();
+        };
+        if next == 0 {
+            return to.value.clone();
+        }
+
+        let from = &self.keyframes[next - 1];
+        let progress = (time - from.time) / (to.time - from.time);
+        from.value.interpolate(&to.value, to.easing.apply(progress))
+    }
+}
+
+/// Sampler writing its values with a lens.
+pub struct Animated<S, L> {
+    sampler: S,
+    lens: L,
+}
+
+impl<S: Sampler, L: Lens<S::Value>> Animation for Animated<S, L> {
+    fn duration(&self) -> f32 {
+        self.sampler.duration()
+    }
+
+    fn apply(&mut self, time: f32, world: &mut World) {
+        let value = self.sampler.sample(time);
+        self.lens.apply(world, value);
+    }
+}
+
+/// Samples a function of the time, for procedural animations.
+pub struct FnSampler<T, F> {
+    duration: f32,
+    sample: F,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T, F: Fn(f32) -> T> FnSampler<T, F> {
+    pub fn new(duration: f32, sample: F) -> Self {
+        Self {
+            duration,
+            sample,
+            value: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static, F: Fn(f32) -> T + 'static> Sampler for FnSampler<T, F> {
+    type Value = T;
+
+    fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    fn sample(&self, time: f32) -> T {
+        (self.sample)(time.min(self.duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Vec2, EPSILON};
+
+    #[test]
+    fn tweens_follow_the_easing() {
+        let tween = Tween::new(0.0, 10.0, 2.0);
+        assert_eq!(tween.sample(0.0), 0.0);
+        assert_eq!(tween.sample(1.0), 5.0);
+        assert_eq!(tween.sample(5.0), 10.0);
+
+        let eased = tween.with_easing(Easing::QuadIn);
+        assert_eq!(eased.sample(1.0), 2.5);
+
+        // Instant tweens are always at the end
+        assert_eq!(Tween::new(1.0, 2.0, 0.0).sample(0.0), 2.0);
+    }
+
+    #[test]
+    fn tracks_interpolate_keyframes() {
+        let track = Track::new(Vec2::ZERO)
+            .with_keyframe(2.0, Vec2::new(4.0, 0.0), Easing::Linear)
+            .with_keyframe(1.0, Vec2::new(0.0, 2.0), Easing::Steps(2))
+            // Replaces the previous one
+            .with_keyframe(2.0, Vec2::new(2.0, 0.0), Easing::Linear);
+
+        assert_eq!(track.keyframes().len(), 3);
+        assert_eq!(track.duration(), 2.0);
+        assert_eq!(track.sample(0.4), Vec2::ZERO);
+        assert_eq!(track.sample(0.6), Vec2::new(0.0, 1.0));
+        assert_eq!(track.sample(1.0), Vec2::new(0.0, 2.0));
+        assert!((track.sample(1.5) - Vec2::new(1.0, 1.0)).length() < EPSILON);
+        assert_eq!(track.sample(10.0), Vec2::new(2.0, 0.0));
+    }
+}
@@ -0,0 +1,624 @@
+//! Packed asset archives.
+//!
+//! Shipping builds read their assets from a single archive instead of loose
+//! files. The layout is:
+//!
+//! ```text
+//! header  magic "UNPK", version u32, index offset u64
+//! data    the stored bytes of every entry, back to back
+//! index   entry count u32, then for every entry:
+//!         path length u16, path (UTF-8, `/` separated),
+//!         offset u64, stored size u64, size u64, compression u8, hash u64
+//! ```
+//!
+//! Every number is little endian. The hash is the XXH3 of the original
+//! bytes, entries with the same content share their data.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Component, Path},
+    sync::Mutex,
+};
+
+use thiserror::Error;
+use xxhash_rust::xxh3::xxh3_64;
+
+use super::AssetSource;
+
+const MAGIC: &[u8; 4] = b"UNPK";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 16;
+/// Smallest index entry, the one with an empty path.
+const MIN_INDEX_ENTRY_SIZE: u64 = 2 + 8 + 8 + 8 + 1 + 8;
+/// Best ratio LZ4 can achieve, bigger sizes in the index are corrupted.
+const MAX_LZ4_RATIO: u64 = 255;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// Failed to read or write the archive.
+    #[error("IO error: {0}")]
+    Io(String),
+    /// Not an archive.
+    #[error("Invalid archive magic")]
+    InvalidMagic,
+    /// Written by an incompatible version of the packer.
+    #[error("Unsupported archive version {0}")]
+    UnsupportedVersion(u32),
+    /// The index is malformed.
+    #[error("Corrupted archive index: {0}")]
+    CorruptedIndex(String),
+    /// Entry paths must be relative and valid UTF-8.
+    #[error("Invalid entry path '{0}'")]
+    InvalidPath(String),
+    /// Two entries have the same path.
+    #[error("Duplicate entry '{0}'")]
+    DuplicateEntry(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
+
+/// How the bytes of an entry are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Lz4,
+}
+
+impl Compression {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::None),
+            1 => Some(Self::Lz4),
+            _ => None,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Lz4 => 1,
+        }
+    }
+}
+
+/// Information about a single file inside an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Position of the stored bytes from the start of the archive.
+    pub offset: u64,
+    pub stored_size: u64,
+    /// Size once decompressed.
+    pub size: u64,
+    pub compression: Compression,
+    /// XXH3 of the decompressed bytes.
+    pub hash: u64,
+}
+
+/// Builds an archive in memory.
+#[derive(Debug, Default)]
+pub struct ArchiveWriter {
+    /// Sorted so the output does not depend on the insertion order.
+    entries: BTreeMap<String, ArchiveEntry>,
+    data: Vec<u8>,
+    /// Offset of the stored data by content hash, more than one when the
+    /// hashes collide.
+    stored: HashMap<u64, Vec<(u64, u64, Compression)>>,
+}
+
+impl ArchiveWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Amount of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Size of the data section, shared entries are only counted once.
+    pub fn data_size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Adds a file at the passed path, relative to the asset root.
+    ///
+    /// _Entries are only compressed when it makes them smaller._
+    pub fn add<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        bytes: &[u8],
+        compression: Compression,
+    ) -> Result<&ArchiveEntry, Error> {
+        let path = entry_path(path.as_ref())?;
+        if self.entries.contains_key(&path) {
+            return Err(Error::DuplicateEntry(path));
+        }
+
+        let hash = xxh3_64(bytes);
+        let shared = self.stored.get(&hash).and_then(|candidates| {
+            candidates
+                .iter()
+                .find(|stored| self.stored_equals(**stored, bytes))
+                .copied()
+        });
+        let (offset, stored_size, compression) = match shared {
+            Some(stored) => stored,
+            None => {
+                let compressed = match compression {
+                    Compression::None => None,
+                    Compression::Lz4 => Some(lz4_flex::compress(bytes))
+                        .filter(|compressed| compressed.len() < bytes.len()),
+                };
+                let (stored_bytes, compression) = match &compressed {
+                    Some(compressed) => {
+                        (compressed.as_slice(), Compression::Lz4)
+                    }
+                    None => (bytes, Compression::None),
+                };
+
+                let stored = (
+                    HEADER_SIZE + self.data.len() as u64,
+                    stored_bytes.len() as u64,
+                    compression,
+                );
+                self.data.extend_from_slice(stored_bytes);
+                self.stored.entry(hash).or_default().push(stored);
+                stored
+            }
+        };
+
+        Ok(self.entries.entry(path).or_insert(ArchiveEntry {
+            offset,
+            stored_size,
+            size: bytes.len() as u64,
+            compression,
+            hash,
+        }))
+    }
+
+    /// Returns `true` if the stored data decodes to the passed bytes.
+    fn stored_equals(
+        &self,
+        (offset, stored_size, compression): (u64, u64, Compression),
+        bytes: &[u8],
+    ) -> bool {
+        let start = (offset - HEADER_SIZE) as usize;
+        let stored = &self.data[start..start + stored_size as usize];
+        match compression {
+            Compression::None => stored == bytes,
+            Compression::Lz4 => lz4_flex::decompress(stored, bytes.len())
+                .is_ok_and(|decompressed| decompressed == bytes),
+        }
+    }
+
+    /// Adds every file inside the passed directory, recursively.
+    ///
+    /// Returns the amount of added files.
+    ///
+    /// _Symbolic links to files are followed, the ones to directories are
+    /// skipped since they can loop._
+    pub fn add_dir<P: AsRef<Path>>(
+        &mut self,
+        root: P,
+        compression: Compression,
+    ) -> Result<usize, Error> {
+        let root = root.as_ref();
+        let mut pending = vec![root.to_path_buf()];
+        let mut added = 0;
+
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                if file_type.is_symlink()
+                    && !std::fs::metadata(&path)?.is_file()
+                {
+                    continue;
+                }
+
+                let bytes = std::fs::read(&path)?;
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                self.add(relative, &bytes, compression)?;
+                added += 1;
+            }
+        }
+
+        Ok(added)
+    }
+
+    /// Writes the whole archive.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(HEADER_SIZE + self.data_size()).to_le_bytes())?;
+        writer.write_all(&self.data)?;
+
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (path, entry) in &self.entries {
+            writer.write_all(&(path.len() as u16).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&entry.offset.to_le_bytes())?;
+            writer.write_all(&entry.stored_size.to_le_bytes())?;
+            writer.write_all(&entry.size.to_le_bytes())?;
+            writer.write_all(&[entry.compression.as_u8()])?;
+            writer.write_all(&entry.hash.to_le_bytes())?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Reads assets from an archive built by `ArchiveWriter`, the drop-in
+/// replacement for `FileSource` in shipping builds.
+pub struct ArchiveSource {
+    entries: HashMap<String, ArchiveEntry>,
+    reader: Mutex<Box<dyn ReadSeek>>,
+}
+
+impl ArchiveSource {
+    /// Opens the archive file at the passed path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads the index, the entries are read on demand.
+    pub fn from_reader<R: Read + Seek + Send + 'static>(
+        mut reader: R,
+    ) -> Result<Self, Error> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::InvalidMagic);
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let index_offset = read_u64(&mut reader)?;
+        let file_size = reader.seek(SeekFrom::End(0))?;
+        if !(HEADER_SIZE..=file_size).contains(&index_offset) {
+            return Err(Error::CorruptedIndex(format!(
+                "Index offset {index_offset} is out of bounds"
+            )));
+        }
+
+        reader.seek(SeekFrom::Start(index_offset))?;
+        let count = read_u32(&mut reader)?;
+        // The count is not trusted until that many entries are read
+        let fits = (file_size - index_offset) / MIN_INDEX_ENTRY_SIZE;
+        let mut entries =
+            HashMap::with_capacity(u64::from(count).min(fits) as usize);
+        for _ in 0..count {
+            let mut path = vec![0; read_u16(&mut reader)? as usize];
+            reader.read_exact(&mut path)?;
+            let path = String::from_utf8(path)
+                .map_err(|err| Error::CorruptedIndex(err.to_string()))?;
+
+            let offset = read_u64(&mut reader)?;
+            let stored_size = read_u64(&mut reader)?;
+            let size = read_u64(&mut reader)?;
+            let mut compression = [0];
+            reader.read_exact(&mut compression)?;
+            let compression =
+                Compression::from_u8(compression[0]).ok_or_else(|| {
+                    Error::CorruptedIndex(format!(
+                        "Unknown compression {} for '{path}'",
+                        compression[0]
+                    ))
+                })?;
+            let hash = read_u64(&mut reader)?;
+
+            let end = offset.checked_add(stored_size);
+            if offset < HEADER_SIZE || end.is_none_or(|end| end > index_offset)
+            {
+                return Err(Error::CorruptedIndex(format!(
+                    "'{path}' is out of bounds"
+                )));
+            }
+            let valid_size = match compression {
+                Compression::None => size == stored_size,
+                Compression::Lz4 => {
+                    size <= stored_size.saturating_mul(MAX_LZ4_RATIO)
+                }
+            };
+            if !valid_size {
+                return Err(Error::CorruptedIndex(format!(
+                    "'{path}' has an invalid size {size}"
+                )));
+            }
+
+            let entry = ArchiveEntry {
+                offset,
+                stored_size,
+                size,
+                compression,
+                hash,
+            };
+            if entries.insert(path.clone(), entry).is_some() {
+                return Err(Error::DuplicateEntry(path));
+            }
+        }
+
+        Ok(Self {
+            entries,
+            reader: Mutex::new(Box::new(reader)),
+        })
+    }
+
+    /// Returns the information about the entry at the passed path.
+    pub fn entry<P: AsRef<Path>>(&self, path: P) -> Option<&ArchiveEntry> {
+        self.entries.get(&entry_path(path.as_ref()).ok()?)
+    }
+
+    /// Returns the paths of every entry, in no particular order.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl AssetSource for ArchiveSource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let entry = self.entry(path).ok_or(io::ErrorKind::NotFound)?;
+
+        let mut stored = vec![0; entry.stored_size as usize];
+        {
+            let mut reader = self
+                .reader
+                .lock()
+                .map_err(|_| io::Error::other("Archive reader poisoned"))?;
+            reader.seek(SeekFrom::Start(entry.offset))?;
+            reader.read_exact(&mut stored)?;
+        }
+
+        let bytes = match entry.compression {
+            Compression::None => stored,
+            Compression::Lz4 => {
+                lz4_flex::decompress(&stored, entry.size as usize).map_err(
+                    |err| io::Error::new(io::ErrorKind::InvalidData, err),
+                )?
+            }
+        };
+        if xxh3_64(&bytes) != entry.hash {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Hash mismatch for '{}'", path.display()),
+            ));
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Converts a path to the form stored in the index.
+fn entry_path(path: &Path) -> Result<String, Error> {
+    let invalid = || Error::InvalidPath(path.display().to_string());
+
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => {
+                parts.push(part.to_str().ok_or_else(invalid)?)
+            }
+            Component::CurDir => {}
+            _ => return Err(invalid()),
+        }
+    }
+    if parts.is_empty() {
+        return Err(invalid());
+    }
+
+    let path = parts.join("/");
+    if path.len() > u16::MAX as usize {
+        return Err(invalid());
+    }
+    Ok(path)
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn archive(writer: &ArchiveWriter) -> ArchiveSource {
+        let mut bytes = Vec::new();
+        writer.write(&mut bytes).unwrap();
+        ArchiveSource::from_reader(Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = "repeat ".repeat(100);
+        let mut writer = ArchiveWriter::new();
+        let entry = *writer
+            .add("./configs/game.txt", text.as_bytes(), Compression::Lz4)
+            .unwrap();
+        assert_eq!(entry.compression, Compression::Lz4);
+        assert!(entry.stored_size < entry.size);
+        // Too small to benefit from compression
+        let entry = *writer
+            .add("player.png", &[1, 2, 3], Compression::Lz4)
+            .unwrap();
+        assert_eq!(entry.compression, Compression::None);
+
+        let source = archive(&writer);
+        assert_eq!(source.len(), 2);
+        assert_eq!(
+            source.read(Path::new("configs/game.txt")).unwrap(),
+            text.as_bytes()
+        );
+        assert_eq!(source.read(Path::new("./player.png")).unwrap(), [1, 2, 3]);
+        assert_eq!(
+            source.read(Path::new("missing.png")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn identical_content_is_shared() {
+        let mut writer = ArchiveWriter::new();
+        writer.add("a.txt", b"same", Compression::None).unwrap();
+        writer.add("b/a.txt", b"same", Compression::None).unwrap();
+        assert_eq!(writer.data_size(), 4);
+        assert_eq!(
+            writer.add("a.txt", b"other", Compression::None),
+            Err(Error::DuplicateEntry("a.txt".to_string()))
+        );
+        assert!(matches!(
+            writer.add("../a.txt", b"", Compression::None),
+            Err(Error::InvalidPath(_))
+        ));
+
+        let source = archive(&writer);
+        assert_eq!(source.entry("a.txt"), source.entry("b/a.txt"));
+        assert_eq!(source.read(Path::new("b/a.txt")).unwrap(), b"same");
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        let mut writer = ArchiveWriter::new();
+        writer.add("a.txt", b"hello", Compression::None).unwrap();
+        let mut bytes = Vec::new();
+        writer.write(&mut bytes).unwrap();
+
+        let mut corrupted = bytes.clone();
+        corrupted[HEADER_SIZE as usize] = b'j';
+        let source =
+            ArchiveSource::from_reader(Cursor::new(corrupted)).unwrap();
+        assert_eq!(
+            source.read(Path::new("a.txt")).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert_eq!(
+            ArchiveSource::from_reader(Cursor::new(wrong_magic)).err(),
+            Some(Error::InvalidMagic)
+        );
+
+        bytes.truncate(bytes.len() - 4);
+        assert!(matches!(
+            ArchiveSource::from_reader(Cursor::new(bytes)),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_index_is_rejected() {
+        let mut writer = ArchiveWriter::new();
+        writer.add("a.txt", b"hello", Compression::None).unwrap();
+        let mut bytes = Vec::new();
+        writer.write(&mut bytes).unwrap();
+        let index = (HEADER_SIZE + writer.data_size()) as usize;
+        // Offset of the first entry, after the count and its path
+        let entry = index + 4 + 2 + "a.txt".len();
+
+        let patched = |at: usize, value: u64| {
+            let mut bytes = bytes.clone();
+            bytes[at..at + 8].copy_from_slice(&value.to_le_bytes());
+            ArchiveSource::from_reader(Cursor::new(bytes)).err()
+        };
+        let corrupted = |err| matches!(err, Some(Error::CorruptedIndex(_)));
+
+        // Index offset inside the header or past the end
+        assert!(corrupted(patched(8, 4)));
+        assert!(corrupted(patched(8, u64::MAX)));
+        // Entry overflowing, inside the header or with an impossible size
+        assert!(corrupted(patched(entry, u64::MAX)));
+        assert!(corrupted(patched(entry, 0)));
+        assert!(corrupted(patched(entry + 16, 1 << 40)));
+        // Uncompressed entry smaller than what is stored
+        assert!(corrupted(patched(entry + 16, 4)));
+
+        // A huge count only fails once the entries run out
+        let mut huge_count = bytes.clone();
+        huge_count[index..index + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            ArchiveSource::from_reader(Cursor::new(huge_count)),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn hash_collisions_are_not_shared() {
+        let mut writer = ArchiveWriter::new();
+        writer.add("a.txt", b"aaaa", Compression::None).unwrap();
+        // Pretend the other content has the same hash
+        let stored = writer.stored[&xxh3_64(b"aaaa")].clone();
+        writer.stored.insert(xxh3_64(b"bbbb"), stored);
+        writer.add("b.txt", b"bbbb", Compression::None).unwrap();
+        assert_eq!(writer.data_size(), 8);
+
+        let source = archive(&writer);
+        assert_eq!(source.read(Path::new("b.txt")).unwrap(), b"bbbb");
+    }
+
+    #[test]
+    fn pack_directory() {
+        let root = std::env::temp_dir()
+            .join(format!("unen-engine-archive-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("textures")).unwrap();
+        std::fs::write(root.join("textures/player.png"), "png").unwrap();
+        std::fs::write(root.join("readme.txt"), "text").unwrap();
+        // Links back to a parent would recurse forever if followed
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("textures/loop")).unwrap();
+
+        let mut writer = ArchiveWriter::new();
+        let added = writer.add_dir(&root, Compression::Lz4);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(added, Ok(2));
+
+        let source = archive(&writer);
+        let mut paths: Vec<_> = source.paths().collect();
+        paths.sort();
+        assert_eq!(paths, ["readme.txt", "textures/player.png"]);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+};
+
+use crossbeam::channel::Sender;
+
+use super::AssetId;
+
+/// Shared by every clone of a handle, dropping the last one notifies the
+/// `AssetServer` so it can free the asset.
+#[derive(Debug)]
+pub(crate) struct HandleInner {
+    pub id: AssetId,
+    pub path: Option<PathBuf>,
+    drops: Sender<AssetId>,
+}
+
+impl HandleInner {
+    pub fn new(
+        id: AssetId,
+        path: Option<PathBuf>,
+        drops: Sender<AssetId>,
+    ) -> Arc<Self> {
+        Arc::new(Self { id, path, drops })
+    }
+}
+
+impl Drop for HandleInner {
+    fn drop(&mut self) {
+        let _ = self.drops.send(self.id);
+    }
+}
+
+/// Reference counted pointer to an asset of type `T` owned by the
+/// `AssetServer`.
+///
+/// The asset stays loaded as long as at least one handle exists, cloning a
+/// handle is cheap.
+pub struct Handle<T> {
+    inner: Arc<HandleInner>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(inner: Arc<HandleInner>) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn downgrade(&self) -> Weak<HandleInner> {
+        Arc::downgrade(&self.inner)
+    }
+
+    pub fn id(&self) -> AssetId {
+        self.inner.id
+    }
+
+    /// Path the asset was loaded from, relative to the asset root. `None` for
+    /// assets added with `AssetServer::add()`.
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.path.as_deref()
+    }
+
+    /// Amount of handles pointing to the same asset.
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handle")
+            .field("id", &self.inner.id)
+            .field("path", &self.inner.path)
+            .finish()
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    any::{Any, TypeId},
+    path::Path,
+    sync::Arc,
+};
+
+use super::Asset;
+use crate::render::Image;
+
+/// Information about the asset being loaded.
+#[derive(Debug, Clone, Copy)]
+pub struct LoadContext<'a> {
+    /// Path relative to the asset root.
+    pub path: &'a Path,
+}
+
+/// Turns raw bytes into an asset.
+///
+/// Loaders run on workers, so they should not touch anything outside of the
+/// passed bytes.
+pub trait AssetLoader: Send + Sync + 'static {
+    type Asset: Asset;
+
+    /// File extensions handled by this loader, without the leading dot.
+    fn extensions(&self) -> &[&str];
+
+    /// Decodes the asset, the error is reported through
+    /// `AssetEvent::LoadFailed`.
+    fn load(
+        &self,
+        bytes: &[u8],
+        context: LoadContext,
+    ) -> Result<Self::Asset, String>;
+}
+
+/// Type erased `AssetLoader`.
+pub(crate) trait ErasedLoader: Send + Sync {
+    fn load(
+        &self,
+        bytes: &[u8],
+        context: LoadContext,
+    ) -> Result<Arc<dyn Any + Send + Sync>, String>;
+
+    fn asset_type(&self) -> TypeId;
+}
+
+impl<L: AssetLoader> ErasedLoader for L {
+    fn load(
+        &self,
+        bytes: &[u8],
+        context: LoadContext,
+    ) -> Result<Arc<dyn Any + Send + Sync>, String> {
+        AssetLoader::load(self, bytes, context)
+            .map(|asset| Arc::new(asset) as Arc<dyn Any + Send + Sync>)
+    }
+
+    fn asset_type(&self) -> TypeId {
+        TypeId::of::<L::Asset>()
+    }
+}
+
+/// Decodes PNG files into an `Image`.
+#[derive(Debug, Default)]
+pub struct ImageLoader;
+
+impl AssetLoader for ImageLoader {
+    type Asset = Image;
+
+    fn extensions(&self) -> &[&str] {
+        &["png"]
+    }
+
+    fn load(&self, bytes: &[u8], _: LoadContext) -> Result<Image, String> {
+        Image::decode(bytes).map_err(|err| err.to_string())
+    }
+}
+
+/// Loads UTF-8 text files as a `String`.
+#[derive(Debug, Default)]
+pub struct TextLoader;
+
+impl AssetLoader for TextLoader {
+    type Asset = String;
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    fn load(&self, bytes: &[u8], _: LoadContext) -> Result<String, String> {
+        String::from_utf8(bytes.to_vec()).map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,68 @@
+//! Asset loading.
+//!
+//! The `AssetServer` reads files from an `AssetSource` (by default the
+//! `asset_root` directory of the `EngineConfig`) and turns them into assets
+//! with the `AssetLoader` registered for their extension. Loading happens on
+//! the `WorkerPool`, progress is reported with `AssetEvent`s.
+//!
+//! Shipping builds can pack every asset into a single archive with
+//! `ArchiveWriter` (see the `unen-packer` app), pointing the `asset_root` to
+//! the archive file makes the `Engine` read from it with an `ArchiveSource`.
+//!
+//! With the `hot-reload` feature the `Engine` also watches the asset root,
+//! modified files are loaded again and replace the old version behind every
+//! existing handle.
+//!
+//! ```no_run
+//! # use unen_engine::{asset::Handle, core::engine::Engine, render::Image};
+//! let mut engine = Engine::default();
+//! let image: Handle<Image> = engine.assets_mut().load("player.png");
+//! // ... a few steps later
+//! if let Some(image) = engine.assets().get(&image) {
+//!     println!("{}x{}", image.width(), image.height());
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+pub mod archive;
+pub mod handle;
+pub mod loader;
+pub mod server;
+pub mod source;
+#[cfg(feature = "hot-reload")]
+pub mod watcher;
+
+pub use archive::{ArchiveSource, ArchiveWriter};
+pub use handle::Handle;
+pub use loader::{AssetLoader, ImageLoader, LoadContext, TextLoader};
+pub use server::{AssetServer, LoadState};
+pub use source::{AssetSource, FileSource};
+#[cfg(feature = "hot-reload")]
+pub use watcher::AssetWatcher;
+
+/// Anything that can be stored in the `AssetServer`.
+pub trait Asset: Send + Sync + 'static {}
+
+impl<T: Send + Sync + 'static> Asset for T {}
+
+/// Identifies an asset, unique for the lifetime of an `AssetServer`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct AssetId(pub(crate) u64);
+
+impl std::fmt::Display for AssetId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Asset({})", self.0)
+    }
+}
@@ -0,0 +1,608 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Weak},
+};
+
+use crossbeam::channel::{unbounded, Receiver, Sender};
+
+#[cfg(feature = "hot-reload")]
+use super::AssetWatcher;
+use super::{
+    handle::HandleInner,
+    loader::{ErasedLoader, ImageLoader, LoadContext, TextLoader},
+    source::is_contained,
+    Asset, AssetId, AssetLoader, AssetSource, Handle,
+};
+use crate::{
+    audio::AudioLoader,
+    core::{
+        event::{AssetEvent, Dispatcher, Event},
+        scheduler::Job,
+    },
+    render::text::FontLoader,
+    script::ScriptLoader,
+};
+
+/// Loading progress of an asset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadState {
+    /// The asset does not exist, or it was already freed.
+    NotLoaded,
+    /// Waiting for a worker.
+    Loading,
+    Loaded,
+    /// Contains the reason.
+    Failed(String),
+}
+
+type AnyAsset = Arc<dyn Any + Send + Sync>;
+
+struct Entry {
+    path: Option<PathBuf>,
+    type_id: TypeId,
+    state: LoadState,
+    asset: Option<AnyAsset>,
+    handle: Weak<HandleInner>,
+}
+
+/// Result of a load, sent back by the worker.
+struct Completed {
+    id: AssetId,
+    result: Result<AnyAsset, String>,
+}
+
+/// Loads and owns every asset.
+///
+/// `::load()` only queues the work, the `Engine` hands the queued jobs to its
+/// `WorkerPool` and collects the results on every step. Progress is reported
+/// with `AssetEvent`s.
+pub struct AssetServer {
+    source: Arc<dyn AssetSource>,
+    /// Loaders by extension and asset type.
+    loaders: HashMap<(String, TypeId), Arc<dyn ErasedLoader>>,
+    entries: HashMap<AssetId, Entry>,
+    /// Used to deduplicate loads of the same path.
+    paths: HashMap<(PathBuf, TypeId), AssetId>,
+    next_id: u64,
+    jobs: Vec<Job>,
+    completed_sender: Sender<Completed>,
+    completed_receiver: Receiver<Completed>,
+    drop_sender: Sender<AssetId>,
+    drop_receiver: Receiver<AssetId>,
+    #[cfg(feature = "hot-reload")]
+    watcher: Option<AssetWatcher>,
+}
+
+impl AssetServer {
+    /// Creates a new server reading from the passed source, loaders for the
+    /// built-in asset types are already registered.
+    pub fn new<S: AssetSource>(source: S) -> Self {
+        let (completed_sender, completed_receiver) = unbounded();
+        let (drop_sender, drop_receiver) = unbounded();
+
+        let mut server = Self {
+            source: Arc::new(source),
+            loaders: HashMap::new(),
+            entries: HashMap::new(),
+            paths: HashMap::new(),
+            next_id: 0,
+            jobs: Vec::new(),
+            completed_sender,
+            completed_receiver,
+            drop_sender,
+            drop_receiver,
+            #[cfg(feature = "hot-reload")]
+            watcher: None,
+        };
+        server.register_loader(ImageLoader);
+        server.register_loader(TextLoader);
+        server.register_loader(AudioLoader);
+        server.register_loader(FontLoader);
+        server.register_loader(ScriptLoader);
+
+        server
+    }
+
+    /// Registers a loader for every extension it handles.
+    ///
+    /// _A loader previously registered for the same extension and asset type
+    /// gets replaced._
+    pub fn register_loader<L: AssetLoader>(&mut self, loader: L) {
+        let extensions: Vec<String> = loader
+            .extensions()
+            .iter()
+            .map(|extension| extension.to_lowercase())
+            .collect();
+        let loader: Arc<dyn ErasedLoader> = Arc::new(loader);
+
+        for extension in extensions {
+            let key = (extension, loader.asset_type());
+            if self.loaders.insert(key.clone(), loader.clone()).is_some() {
+                log::warn!("Replaced asset loader for '.{}' files", key.0);
+            }
+        }
+    }
+
+    /// Starts loading the asset at the passed path, relative to the asset
+    /// root.
+    ///
+    /// Loading a path that is already loaded (or loading) returns a handle to
+    /// the same asset.
+    pub fn load<T: Asset, P: AsRef<Path>>(&mut self, path: P) -> Handle<T> {
+        let path = normalize(path.as_ref());
+        let type_id = TypeId::of::<T>();
+
+        if let Some(inner) = self
+            .paths
+            .get(&(path.clone(), type_id))
+            .and_then(|id| self.entries.get(id))
+            .and_then(|entry| entry.handle.upgrade())
+        {
+            return Handle::new(inner);
+        }
+
+        let handle = self.create_entry::<T>(Some(path.clone()));
+        self.paths.insert((path.clone(), type_id), handle.id());
+        self.queue_load(handle.id(), path, type_id);
+
+        handle
+    }
+
+    /// Loads the asset at the passed path again, once done it replaces the
+    /// old one behind every existing handle.
+    ///
+    /// Returns the amount of assets being reloaded, the same path can be
+    /// loaded as different asset types.
+    pub fn reload<P: AsRef<Path>>(&mut self, path: P) -> usize {
+        let path = normalize(path.as_ref());
+        let assets: Vec<_> = self
+            .paths
+            .iter()
+            .filter(|((loaded, _), _)| *loaded == path)
+            .map(|((_, type_id), id)| (*id, *type_id))
+            .collect();
+
+        for (id, type_id) in &assets {
+            self.queue_load(*id, path.clone(), *type_id);
+        }
+
+        assets.len()
+    }
+
+    /// Reloads every changed asset reported by the watcher.
+    #[cfg(feature = "hot-reload")]
+    pub fn watch(&mut self, watcher: AssetWatcher) {
+        self.watcher = Some(watcher);
+    }
+
+    /// Adds an asset created at runtime.
+    pub fn add<T: Asset>(&mut self, asset: T) -> Handle<T> {
+        let handle = self.create_entry::<T>(None);
+        if let Some(entry) = self.entries.get_mut(&handle.id()) {
+            entry.state = LoadState::Loaded;
+            entry.asset = Some(Arc::new(asset));
+        }
+        handle
+    }
+
+    /// Returns the asset, `None` until it is loaded.
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<&T> {
+        self.entries
+            .get(&handle.id())?
+            .asset
+            .as_ref()?
+            .downcast_ref::<T>()
+    }
+
+    pub fn load_state(&self, id: AssetId) -> LoadState {
+        self.entries
+            .get(&id)
+            .map(|entry| entry.state.clone())
+            .unwrap_or(LoadState::NotLoaded)
+    }
+
+    pub fn is_loaded(&self, id: AssetId) -> bool {
+        self.load_state(id) == LoadState::Loaded
+    }
+
+    /// Amount of assets being tracked, in any state.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Amount of assets still loading.
+    pub fn loading(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|entry| entry.state == LoadState::Loading)
+            .count()
+    }
+
+    /// Returns the queued loading jobs, they must be executed by a worker.
+    ///
+    /// _The `Engine` does this on every step._
+    pub fn take_jobs(&mut self) -> Vec<Job> {
+        std::mem::take(&mut self.jobs)
+    }
+
+    /// Stores finished loads and frees assets without handles, dispatching
+    /// the matching events.
+    ///
+    /// _The `Engine` does this on every step._
+    pub fn update(&mut self, dispatcher: &Dispatcher) {
+        crate::profile_scope!("AssetServer::update", "asset");
+
+        #[cfg(feature = "hot-reload")]
+        if let Some(watcher) = &self.watcher {
+            for path in watcher.changed() {
+                if self.reload(&path) > 0 {
+                    log::info!("Reloading asset '{}'", path.display());
+                }
+            }
+        }
+
+        while let Ok(Completed { id, result }) =
+            self.completed_receiver.try_recv()
+        {
+            // Freed while loading
+            let Some(entry) = self.entries.get_mut(&id) else {
+                continue;
+            };
+            let path = entry.path.clone().unwrap_or_default();
+
+            match result {
+                Ok(asset) => {
+                    entry.state = LoadState::Loaded;
+                    let event = match entry.asset.replace(asset) {
+                        Some(_) => AssetEvent::Reloaded(id, path),
+                        None => AssetEvent::Loaded(id, path),
+                    };
+                    dispatcher.send(Event::Asset(event));
+                }
+                // A failed reload keeps the previous version
+                Err(err) if entry.asset.is_some() => {
+                    dispatcher.send(Event::Asset(AssetEvent::LoadFailed(
+                        id, path, err,
+                    )));
+                }
+                Err(err) => {
+                    entry.state = LoadState::Failed(err.clone());
+                    dispatcher.send(Event::Asset(AssetEvent::LoadFailed(
+                        id, path, err,
+                    )));
+                }
+            }
+        }
+
+        while let Ok(id) = self.drop_receiver.try_recv() {
+            let Some(entry) = self.entries.get(&id) else {
+                continue;
+            };
+            // A new handle could have been created in the meantime
+            if entry.handle.strong_count() > 0 {
+                continue;
+            }
+
+            if let Some(path) = entry.path.clone() {
+                let key = (path, entry.type_id);
+                if self.paths.get(&key) == Some(&id) {
+                    self.paths.remove(&key);
+                }
+            }
+            self.entries.remove(&id);
+            dispatcher.send(Event::Asset(AssetEvent::Unloaded(id)));
+        }
+    }
+
+    fn create_entry<T: Asset>(&mut self, path: Option<PathBuf>) -> Handle<T> {
+        let id = AssetId(self.next_id);
+        self.next_id += 1;
+
+        let handle = Handle::new(HandleInner::new(
+            id,
+            path.clone(),
+            self.drop_sender.clone(),
+        ));
+        self.entries.insert(
+            id,
+            Entry {
+                path,
+                type_id: TypeId::of::<T>(),
+                state: LoadState::Loading,
+                asset: None,
+                handle: handle.downgrade(),
+            },
+        );
+
+        handle
+    }
+
+    /// Queues a job that loads the asset and reports back to `::update()`.
+    fn queue_load(&mut self, id: AssetId, path: PathBuf, type_id: TypeId) {
+        let sender = self.completed_sender.clone();
+
+        if !is_contained(&path) {
+            let _ = sender.send(Completed {
+                id,
+                result: Err(format!(
+                    "Path '{}' leaves the asset root",
+                    path.display()
+                )),
+            });
+            return;
+        }
+
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let Some(loader) = self.loaders.get(&(extension.clone(), type_id))
+        else {
+            let _ = sender.send(Completed {
+                id,
+                result: Err(format!("No loader for '.{extension}' files")),
+            });
+            return;
+        };
+
+        let loader = loader.clone();
+        let source = self.source.clone();
+        self.jobs.push(Box::new(move || {
+            crate::profile_scope!("AssetServer::load", "asset");
+
+            let result = source
+                .read(&path)
+                .map_err(|err| {
+                    format!("Failed to read '{}': {err}", path.display())
+                })
+                .and_then(|bytes| {
+                    loader.load(&bytes, LoadContext { path: &path })
+                });
+            let _ = sender.send(Completed { id, result });
+        }));
+    }
+}
+
+/// Removes `.` components, so the same file always maps to the same path.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::core::event::{self, Consumer};
+
+    /// Serves files from memory and counts the reads.
+    #[derive(Default)]
+    struct MemorySource {
+        files: HashMap<PathBuf, Vec<u8>>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl MemorySource {
+        fn with(mut self, path: &str, content: &str) -> Self {
+            self.files.insert(path.into(), content.as_bytes().to_vec());
+            self
+        }
+    }
+
+    impl AssetSource for MemorySource {
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.files
+                .get(path)
+                .cloned()
+                .ok_or(io::ErrorKind::NotFound.into())
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Level(Vec<u32>);
+
+    struct LevelLoader;
+
+    impl AssetLoader for LevelLoader {
+        type Asset = Level;
+
+        fn extensions(&self) -> &[&str] {
+            &["lvl"]
+        }
+
+        fn load(&self, bytes: &[u8], _: LoadContext) -> Result<Level, String> {
+            String::from_utf8_lossy(bytes)
+                .split(',')
+                .map(|value| {
+                    value.trim().parse().map_err(|_| "bad value".into())
+                })
+                .collect::<Result<_, String>>()
+                .map(Level)
+        }
+    }
+
+    /// Runs the queued jobs on the current thread.
+    fn run(server: &mut AssetServer, dispatcher: &Dispatcher) {
+        for job in server.take_jobs() {
+            job();
+        }
+        server.update(dispatcher);
+    }
+
+    fn events(consumer: &Consumer) -> Vec<String> {
+        std::iter::from_fn(|| consumer.poll())
+            .map(|event| match event {
+                Event::Asset(event) => event.to_string(),
+                other => panic!("unexpected event {other}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn load_custom_asset() {
+        let (dispatcher, consumer) = event::create_handler();
+        let mut server = AssetServer::new(
+            MemorySource::default().with("levels/one.lvl", "1, 2, 3"),
+        );
+        server.register_loader(LevelLoader);
+
+        let handle = server.load::<Level, _>("./levels/one.lvl");
+        assert_eq!(server.load_state(handle.id()), LoadState::Loading);
+        assert_eq!(server.get(&handle), None);
+
+        run(&mut server, &dispatcher);
+        assert_eq!(server.get(&handle), Some(&Level(vec![1, 2, 3])));
+        assert_eq!(handle.path(), Some(Path::new("levels/one.lvl")));
+        assert_eq!(events(&consumer), vec!["Loaded"]);
+    }
+
+    #[test]
+    fn concurrent_loads_are_deduplicated() {
+        let (dispatcher, _consumer) = event::create_handler();
+        let source = MemorySource::default().with("a.txt", "hello");
+        let reads = source.reads.clone();
+        let mut server = AssetServer::new(source);
+
+        let first = server.load::<String, _>("a.txt");
+        let second = server.load::<String, _>("a.txt");
+        assert_eq!(first, second);
+        assert_eq!(first.strong_count(), 2);
+
+        run(&mut server, &dispatcher);
+        let third = server.load::<String, _>("a.txt");
+        assert_eq!(third, first);
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        assert_eq!(server.get(&third).map(String::as_str), Some("hello"));
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let (dispatcher, consumer) = event::create_handler();
+        let mut server =
+            AssetServer::new(MemorySource::default().with("bad.lvl", "1, x"));
+        server.register_loader(LevelLoader);
+
+        let missing = server.load::<String, _>("missing.txt");
+        let unknown = server.load::<String, _>("file.unknown");
+        let invalid = server.load::<Level, _>("bad.lvl");
+        run(&mut server, &dispatcher);
+
+        assert!(matches!(
+            server.load_state(missing.id()),
+            LoadState::Failed(err) if err.contains("missing.txt")
+        ));
+        assert_eq!(
+            server.load_state(unknown.id()),
+            LoadState::Failed("No loader for '.unknown' files".to_string())
+        );
+        assert_eq!(
+            server.load_state(invalid.id()),
+            LoadState::Failed("bad value".to_string())
+        );
+        assert_eq!(events(&consumer).len(), 3);
+    }
+
+    #[test]
+    fn paths_outside_the_root_are_refused() {
+        let (dispatcher, consumer) = event::create_handler();
+        let source = MemorySource::default().with("../secret.txt", "secret");
+        let reads = source.reads.clone();
+        let mut server = AssetServer::new(source);
+
+        let parent = server.load::<String, _>("../secret.txt");
+        let nested = server.load::<String, _>("a/../../secret.txt");
+        let absolute = server.load::<String, _>("/etc/passwd");
+        run(&mut server, &dispatcher);
+
+        for handle in [parent, nested, absolute] {
+            assert!(matches!(
+                server.load_state(handle.id()),
+                LoadState::Failed(err) if err.contains("leaves the asset root")
+            ));
+        }
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+        assert_eq!(events(&consumer).len(), 3);
+    }
+
+    #[test]
+    fn assets_are_freed_with_their_last_handle() {
+        let (dispatcher, consumer) = event::create_handler();
+        let mut server = AssetServer::new(MemorySource::default());
+
+        let handle = server.add(String::from("runtime"));
+        let clone = handle.clone();
+        let id = handle.id();
+        assert!(server.is_loaded(id));
+
+        drop(handle);
+        server.update(&dispatcher);
+        assert!(server.is_loaded(id));
+
+        drop(clone);
+        server.update(&dispatcher);
+        assert_eq!(server.load_state(id), LoadState::NotLoaded);
+        assert!(server.is_empty());
+        assert_eq!(events(&consumer), vec!["Unloaded"]);
+    }
+
+    #[test]
+    fn reload_swaps_asset_behind_handles() {
+        let (dispatcher, consumer) = event::create_handler();
+        let mut server =
+            AssetServer::new(MemorySource::default().with("config.txt", "old"));
+
+        let text = server.load::<String, _>("config.txt");
+        let clone = text.clone();
+        run(&mut server, &dispatcher);
+        assert_eq!(server.reload("missing.txt"), 0);
+        let _ = events(&consumer);
+
+        server.source =
+            Arc::new(MemorySource::default().with("config.txt", "new"));
+        assert_eq!(server.reload("./config.txt"), 1);
+        run(&mut server, &dispatcher);
+        assert_eq!(server.get(&clone).map(String::as_str), Some("new"));
+        assert_eq!(events(&consumer), vec!["Reloaded"]);
+
+        // Failing keeps the old version around
+        server.source = Arc::new(MemorySource::default());
+        server.reload("config.txt");
+        run(&mut server, &dispatcher);
+        assert_eq!(server.get(&text).map(String::as_str), Some("new"));
+        assert!(server.is_loaded(text.id()));
+        assert_eq!(events(&consumer), vec!["LoadFailed"]);
+    }
+
+    #[test]
+    fn reloading_a_freed_path() {
+        let (dispatcher, _consumer) = event::create_handler();
+        let source = MemorySource::default().with("a.txt", "hello");
+        let reads = source.reads.clone();
+        let mut server = AssetServer::new(source);
+
+        let first = server.load::<String, _>("a.txt");
+        let first_id = first.id();
+        drop(first);
+        // Requested again before the drop was processed
+        let second = server.load::<String, _>("a.txt");
+        assert_ne!(second.id(), first_id);
+
+        run(&mut server, &dispatcher);
+        assert!(server.is_loaded(second.id()));
+        assert_eq!(server.load_state(first_id), LoadState::NotLoaded);
+        assert_eq!(server.len(), 1);
+        assert_eq!(reads.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+/// Where the raw bytes of assets come from.
+///
+/// Paths are always relative to the root of the source.
+pub trait AssetSource: Send + Sync + 'static {
+    /// Reads the whole file at the passed path.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+/// Reads assets from a directory on disk.
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    root: PathBuf,
+}
+
+impl FileSource {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl AssetSource for FileSource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        if !is_contained(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "path leaves the asset root",
+            ));
+        }
+        std::fs::read(self.root.join(path))
+    }
+}
+
+/// Returns `false` for absolute paths and paths with `..`, joining them to
+/// the root would reach files outside of it.
+pub(crate) fn is_contained(path: &Path) -> bool {
+    path.components().all(|component| {
+        matches!(component, Component::Normal(_) | Component::CurDir)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_source_stays_inside_root() {
+        let dir = std::env::temp_dir()
+            .join(format!("unen-engine-source-{}", std::process::id()));
+        let root = dir.join("assets");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+        std::fs::write(root.join("public.txt"), "public").unwrap();
+
+        let source = FileSource::new(&root);
+        assert_eq!(source.read(Path::new("./public.txt")).unwrap(), b"public");
+        for path in ["../secret.txt", "sub/../../secret.txt"] {
+            let err = source.read(Path::new(path)).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        let absolute = dir.join("secret.txt");
+        assert_eq!(
+            source.read(&absolute).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crossbeam::channel::{unbounded, Receiver};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// Failed to start watching the directory.
+    #[error("Failed to watch '{0}': {1}")]
+    WatchFailed(PathBuf, String),
+}
+
+/// Watches a directory for modified files.
+///
+/// Pass it to `AssetServer::watch()` to reload assets as soon as they change.
+pub struct AssetWatcher {
+    root: PathBuf,
+    /// Stops watching when dropped.
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+}
+
+impl AssetWatcher {
+    /// Starts watching every file inside the passed directory.
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self, Error> {
+        let watch_failed = |err: &dyn std::fmt::Display| {
+            Error::WatchFailed(root.as_ref().to_path_buf(), err.to_string())
+        };
+        // Reported paths are absolute
+        let root = root
+            .as_ref()
+            .canonicalize()
+            .map_err(|err| watch_failed(&err))?;
+
+        let (sender, receiver) = unbounded();
+        let mut watcher = notify::recommended_watcher(
+            move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        log::warn!("Failed to watch assets: {err}");
+                        return;
+                    }
+                };
+                // Editors usually save by replacing the file
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_)
+                ) {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+            },
+        )
+        .map_err(|err| watch_failed(&err))?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|err| watch_failed(&err))?;
+
+        Ok(Self {
+            root,
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    /// Directory being watched.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the files changed since the last call, relative to the root.
+    ///
+    /// _A single save usually produces several notifications, they are
+    /// merged._
+    pub fn changed(&self) -> Vec<PathBuf> {
+        let changed: BTreeSet<PathBuf> = self
+            .receiver
+            .try_iter()
+            .filter_map(|path| {
+                path.strip_prefix(&self.root).ok().map(Path::to_path_buf)
+            })
+            .collect();
+
+        changed.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn reports_changed_files() {
+        let root = std::env::temp_dir()
+            .join(format!("unen-engine-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("textures")).unwrap();
+
+        let watcher = AssetWatcher::new(&root).unwrap();
+        std::fs::write(root.join("textures/player.png"), "png").unwrap();
+
+        let start = Instant::now();
+        let mut changed = Vec::new();
+        while changed.is_empty() && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+            changed = watcher.changed();
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(changed, vec![PathBuf::from("textures/player.png")]);
+    }
+
+    #[test]
+    fn missing_directory_fails() {
+        assert!(matches!(
+            AssetWatcher::new("/definitely/not/here"),
+            Err(Error::WatchFailed(..))
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+use std::{io::Cursor, sync::Arc, time::Duration};
+
+use super::Error;
+use crate::asset::{AssetLoader, LoadContext};
+
+/// Decoded sound, samples are interleaved and normalized to `[-1, 1]`.
+///
+/// _Cloning is cheap, the samples are shared._
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioClip {
+    sample_rate: u32,
+    channels: u16,
+    samples: Arc<[f32]>,
+}
+
+impl AudioClip {
+    /// Creates a clip from interleaved samples.
+    pub fn from_samples(
+        sample_rate: u32,
+        channels: u16,
+        samples: Vec<f32>,
+    ) -> Result<Self, Error> {
+        if sample_rate == 0 || channels == 0 {
+            return Err(Error::InvalidClip(format!(
+                "{channels} channels at {sample_rate} Hz"
+            )));
+        }
+        if !samples.len().is_multiple_of(channels as usize) {
+            return Err(Error::InvalidClip(format!(
+                "{} samples can not be split in {channels} channels",
+                samples.len()
+            )));
+        }
+
+        Ok(Self {
+            sample_rate,
+            channels,
+            samples: samples.into(),
+        })
+    }
+
+    /// Decodes a WAV or OGG Vorbis file, the format is detected from the
+    /// content.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        match bytes.get(..4) {
+            Some(b"RIFF") => Self::decode_wav(bytes),
+            Some(b"OggS") => Self::decode_ogg(bytes),
+            _ => Err(Error::UnsupportedFormat),
+        }
+    }
+
+    /// Decodes a WAV file with integer or float samples.
+    pub fn decode_wav(bytes: &[u8]) -> Result<Self, Error> {
+        let decode_failed = |err: hound::Error| Error::Decode(err.to_string());
+
+        let reader =
+            hound::WavReader::new(Cursor::new(bytes)).map_err(decode_failed)?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .collect::<Result<_, _>>()
+                .map_err(decode_failed)?,
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 * scale))
+                    .collect::<Result<_, _>>()
+                    .map_err(decode_failed)?
+            }
+        };
+
+        Self::from_samples(spec.sample_rate, spec.channels, samples)
+    }
+
+    /// Decodes an OGG Vorbis file.
+    pub fn decode_ogg(bytes: &[u8]) -> Result<Self, Error> {
+        let decode_failed =
+            |err: lewton::VorbisError| Error::Decode(err.to_string());
+
+        let mut reader =
+            lewton::inside_ogg::OggStreamReader::new(Cursor::new(bytes))
+                .map_err(decode_failed)?;
+        let sample_rate = reader.ident_hdr.audio_sample_rate;
+        let channels = reader.ident_hdr.audio_channels as u16;
+
+        let mut samples = Vec::new();
+        while let Some(packet) =
+            reader.read_dec_packet_itl().map_err(decode_failed)?
+        {
+            samples.extend(
+                packet
+                    .into_iter()
+                    .map(|sample| sample as f32 / i16::MAX as f32),
+            );
+        }
+
+        Self::from_samples(sample_rate, channels, samples)
+    }
+
+    /// Encodes as a 32-bit float WAV file.
+    pub fn encode_wav(&self) -> Result<Vec<u8>, Error> {
+        let encode_failed = |err: hound::Error| Error::Encode(err.to_string());
+
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(
+            &mut bytes,
+            wav_spec(self.sample_rate, self.channels),
+        )
+        .map_err(encode_failed)?;
+        for sample in self.samples.iter() {
+            writer.write_sample(*sample).map_err(encode_failed)?;
+        }
+        writer.finalize().map_err(encode_failed)?;
+
+        Ok(bytes.into_inner())
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Interleaved samples.
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Amount of samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate as f64)
+    }
+
+    /// Returns the first two channels of the frame, mono is duplicated.
+    pub(crate) fn stereo_frame(&self, frame: usize) -> [f32; 2] {
+        let start = frame * self.channels as usize;
+        match self.channels {
+            1 => [self.samples[start]; 2],
+            _ => [self.samples[start], self.samples[start + 1]],
+        }
+    }
+}
+
+/// Format written by the engine, 32-bit float samples.
+pub(crate) fn wav_spec(sample_rate: u32, channels: u16) -> hound::WavSpec {
+    hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    }
+}
+
+/// Decodes WAV and OGG Vorbis files into an `AudioClip`.
+#[derive(Debug, Default)]
+pub struct AudioLoader;
+
+impl AssetLoader for AudioLoader {
+    type Asset = AudioClip;
+
+    fn extensions(&self) -> &[&str] {
+        &["wav", "ogg"]
+    }
+
+    fn load(&self, bytes: &[u8], _: LoadContext) -> Result<AudioClip, String> {
+        AudioClip::decode(bytes).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(spec: hound::WavSpec, samples: &[i32]) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        for sample in samples {
+            writer.write_sample(*sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn decode_integer_wav() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let bytes = wav(spec, &[0, 16384, -32768, 32767]);
+
+        let clip = AudioClip::decode(&bytes).unwrap();
+        assert_eq!(clip.sample_rate(), 22050);
+        assert_eq!(clip.channels(), 2);
+        assert_eq!(clip.frames(), 2);
+        assert_eq!(&clip.samples()[..3], [0.0, 0.5, -1.0]);
+        assert_eq!(clip.stereo_frame(1)[0], -1.0);
+    }
+
+    #[test]
+    fn float_wav_round_trip() {
+        let clip =
+            AudioClip::from_samples(48000, 1, vec![0.25, -0.5, 1.0]).unwrap();
+        let decoded = AudioClip::decode(&clip.encode_wav().unwrap()).unwrap();
+        assert_eq!(decoded, clip);
+        assert_eq!(decoded.stereo_frame(2), [1.0, 1.0]);
+        assert_eq!(decoded.duration(), Duration::from_secs_f64(3.0 / 48000.0));
+    }
+
+    #[test]
+    fn decode_ogg() {
+        // Mono, 8 kHz, 5 short blocks with a floor and a residue
+        let bytes = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/tone.ogg"
+        ));
+
+        let clip = AudioClip::decode(bytes).unwrap();
+        assert_eq!(clip.sample_rate(), 8000);
+        assert_eq!(clip.channels(), 1);
+        // The first block only primes the overlap
+        assert_eq!(clip.frames(), 4 * 128);
+        let peak = clip
+            .samples()
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!((0.01..1.0).contains(&peak), "peak of {peak}");
+    }
+
+    #[test]
+    fn invalid_data() {
+        assert_eq!(
+            AudioClip::decode(b"ID3 mp3"),
+            Err(Error::UnsupportedFormat)
+        );
+        assert!(matches!(
+            AudioClip::decode(b"OggS not really"),
+            Err(Error::Decode(_))
+        ));
+        assert!(matches!(
+            AudioClip::decode(b"RIFF not really"),
+            Err(Error::Decode(_))
+        ));
+        assert!(matches!(
+            AudioClip::from_samples(44100, 2, vec![0.0; 3]),
+            Err(Error::InvalidClip(_))
+        ));
+    }
+}
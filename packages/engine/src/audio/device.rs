@@ -0,0 +1,93 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use super::{mixer::CHANNELS, AudioOutput, Error, SharedMixer};
+
+/// Plays the mixed audio on the default output device.
+pub struct DeviceOutput {
+    device: cpal::Device,
+    config: cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+    /// Playing while alive.
+    stream: Option<cpal::Stream>,
+}
+
+impl DeviceOutput {
+    /// Opens the default output device with its preferred configuration.
+    pub fn new() -> Result<Self, Error> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(Error::NoDevice)?;
+        let supported = device
+            .default_output_config()
+            .map_err(|err| Error::Output(err.to_string()))?;
+
+        Ok(Self {
+            device,
+            sample_format: supported.sample_format(),
+            config: supported.config(),
+            stream: None,
+        })
+    }
+
+    fn build_stream<T>(&self, mixer: SharedMixer) -> Result<cpal::Stream, Error>
+    where
+        T: cpal::SizedSample + cpal::FromSample<f32>,
+    {
+        let channels = self.config.channels as usize;
+        let mut buffer = Vec::new();
+
+        self.device
+            .build_output_stream(
+                &self.config,
+                move |data: &mut [T], _| {
+                    let frames = data.len() / channels;
+                    buffer.resize(frames * CHANNELS, 0.0);
+                    match mixer.lock() {
+                        Ok(mut mixer) => mixer.mix(&mut buffer),
+                        Err(_) => buffer.fill(0.0),
+                    }
+
+                    for (frame, stereo) in
+                        data.chunks_mut(channels).zip(buffer.chunks(CHANNELS))
+                    {
+                        for (channel, sample) in frame.iter_mut().enumerate() {
+                            let value = match (channels, channel) {
+                                (1, _) => (stereo[0] + stereo[1]) * 0.5,
+                                (_, 0 | 1) => stereo[channel],
+                                _ => 0.0,
+                            };
+                            *sample = T::from_sample(value);
+                        }
+                    }
+                },
+                |err| log::error!("Audio output error: {err}"),
+                None,
+            )
+            .map_err(|err| Error::Output(err.to_string()))
+    }
+}
+
+impl AudioOutput for DeviceOutput {
+    fn sample_rate(&self) -> u32 {
+        self.config.sample_rate.0
+    }
+
+    fn start(&mut self, mixer: SharedMixer) -> Result<(), Error> {
+        let stream = match self.sample_format {
+            cpal::SampleFormat::F32 => self.build_stream::<f32>(mixer),
+            cpal::SampleFormat::I16 => self.build_stream::<i16>(mixer),
+            cpal::SampleFormat::U16 => self.build_stream::<u16>(mixer),
+            format => {
+                return Err(Error::Output(format!(
+                    "Unsupported sample format {format}"
+                )))
+            }
+        }?;
+        stream
+            .play()
+            .map_err(|err| Error::Output(err.to_string()))?;
+
+        self.stream = Some(stream);
+        Ok(())
+    }
+}
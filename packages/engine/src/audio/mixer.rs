@@ -0,0 +1,439 @@
+use std::collections::BTreeMap;
+
+use super::{AudioClip, Error};
+
+/// The mixer always outputs interleaved stereo.
+pub const CHANNELS: usize = 2;
+
+/// Identifies a playing sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VoiceId(u64);
+
+/// Identifies a bus, every bus is routed to `BusId::MASTER`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BusId(u32);
+
+impl BusId {
+    /// Every sound ends up here, its volume affects everything.
+    pub const MASTER: Self = Self(0);
+}
+
+impl std::fmt::Display for BusId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bus({})", self.0)
+    }
+}
+
+/// How a voice is played, can be changed while playing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackSettings {
+    /// Linear gain, `1.0` leaves the clip as it is.
+    pub volume: f32,
+    /// From `-1.0` (left) to `1.0` (right).
+    pub pan: f32,
+    /// Playback speed, also changes the pitch. `2.0` is an octave higher.
+    pub pitch: f32,
+    /// Restarts from the beginning when the end is reached.
+    pub looping: bool,
+    pub bus: BusId,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pan: 0.0,
+            pitch: 1.0,
+            looping: false,
+            bus: BusId::MASTER,
+        }
+    }
+}
+
+impl PlaybackSettings {
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_pan(mut self, pan: f32) -> Self {
+        self.pan = pan;
+        self
+    }
+
+    pub fn with_pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn with_bus(mut self, bus: BusId) -> Self {
+        self.bus = bus;
+        self
+    }
+}
+
+struct Voice {
+    clip: AudioClip,
+    settings: PlaybackSettings,
+    /// Position in frames of the clip.
+    position: f64,
+    paused: bool,
+}
+
+struct Bus {
+    name: String,
+    volume: f32,
+}
+
+/// Mixes any amount of voices into a stereo buffer.
+///
+/// Clips with a different sample rate are resampled, mono clips are played
+/// on both channels.
+pub struct Mixer {
+    sample_rate: u32,
+    /// Ordered so the mixing order is deterministic.
+    voices: BTreeMap<VoiceId, Voice>,
+    /// Indexed by `BusId`.
+    buses: Vec<Bus>,
+    max_voices: usize,
+    next_voice: u64,
+}
+
+impl Mixer {
+    /// Voices allowed by default.
+    pub const DEFAULT_MAX_VOICES: usize = 256;
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            voices: BTreeMap::new(),
+            buses: vec![Bus {
+                name: "master".to_string(),
+                volume: 1.0,
+            }],
+            max_voices: Self::DEFAULT_MAX_VOICES,
+            next_voice: 0,
+        }
+    }
+
+    pub fn with_max_voices(mut self, max_voices: usize) -> Self {
+        self.max_voices = max_voices;
+        self
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Changes the output sample rate, playing voices are resampled.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Starts playing the clip.
+    pub fn play(
+        &mut self,
+        clip: &AudioClip,
+        settings: PlaybackSettings,
+    ) -> Result<VoiceId, Error> {
+        if self.bus_volume(settings.bus).is_none() {
+            return Err(Error::NoSuchBus(settings.bus));
+        }
+        if self.voices.len() >= self.max_voices {
+            return Err(Error::VoiceLimitReached(self.max_voices));
+        }
+
+        let id = VoiceId(self.next_voice);
+        self.next_voice += 1;
+        self.voices.insert(
+            id,
+            Voice {
+                clip: clip.clone(),
+                settings,
+                position: 0.0,
+                paused: false,
+            },
+        );
+
+        Ok(id)
+    }
+
+    /// Returns `false` if the voice already finished.
+    pub fn stop(&mut self, voice: VoiceId) -> bool {
+        self.voices.remove(&voice).is_some()
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Returns `false` if the voice already finished.
+    pub fn set_paused(&mut self, voice: VoiceId, paused: bool) -> bool {
+        match self.voices.get_mut(&voice) {
+            Some(voice) => {
+                voice.paused = paused;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` until the voice finishes or gets stopped, paused
+    /// voices are still playing.
+    pub fn is_playing(&self, voice: VoiceId) -> bool {
+        self.voices.contains_key(&voice)
+    }
+
+    pub fn is_paused(&self, voice: VoiceId) -> bool {
+        self.voices.get(&voice).is_some_and(|voice| voice.paused)
+    }
+
+    pub fn settings(&self, voice: VoiceId) -> Option<&PlaybackSettings> {
+        self.voices.get(&voice).map(|voice| &voice.settings)
+    }
+
+    pub fn settings_mut(
+        &mut self,
+        voice: VoiceId,
+    ) -> Option<&mut PlaybackSettings> {
+        self.voices.get_mut(&voice).map(|voice| &mut voice.settings)
+    }
+
+    /// Amount of playing voices.
+    pub fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Creates a new bus routed to the master bus.
+    pub fn add_bus(&mut self, name: &str) -> BusId {
+        self.buses.push(Bus {
+            name: name.to_string(),
+            volume: 1.0,
+        });
+        BusId(self.buses.len() as u32 - 1)
+    }
+
+    /// Finds a bus by name.
+    pub fn bus(&self, name: &str) -> Option<BusId> {
+        self.buses
+            .iter()
+            .position(|bus| bus.name == name)
+            .map(|index| BusId(index as u32))
+    }
+
+    pub fn bus_volume(&self, bus: BusId) -> Option<f32> {
+        self.buses.get(bus.0 as usize).map(|bus| bus.volume)
+    }
+
+    pub fn set_bus_volume(
+        &mut self,
+        bus: BusId,
+        volume: f32,
+    ) -> Result<(), Error> {
+        let bus = self
+            .buses
+            .get_mut(bus.0 as usize)
+            .ok_or(Error::NoSuchBus(bus))?;
+        bus.volume = volume;
+        Ok(())
+    }
+
+    /// Overwrites the buffer with the next interleaved stereo frames.
+    ///
+    /// _Samples are clamped to `[-1, 1]`._
+    pub fn mix(&mut self, output: &mut [f32]) {
+        crate::profile_scope!("Mixer::mix", "audio");
+
+        output.fill(0.0);
+
+        let master = self.buses[BusId::MASTER.0 as usize].volume;
+        let sample_rate = self.sample_rate as f64;
+        let buses = &self.buses;
+        self.voices.retain(|_, voice| {
+            if voice.paused {
+                return true;
+            }
+
+            let bus = match voice.settings.bus {
+                BusId::MASTER => 1.0,
+                bus => buses[bus.0 as usize].volume,
+            };
+            let gain = voice.settings.volume * bus * master;
+            let [left, right] = pan_gains(voice.settings.pan);
+            let step = voice.clip.sample_rate() as f64 / sample_rate
+                * voice.settings.pitch.max(0.0) as f64;
+
+            voice.mix(output, [gain * left, gain * right], step)
+        });
+
+        for sample in output {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+}
+
+impl Voice {
+    /// Adds the voice to the output, returns `false` once finished.
+    fn mix(&mut self, output: &mut [f32], gains: [f32; 2], step: f64) -> bool {
+        let frames = self.clip.frames();
+        if frames == 0 {
+            return false;
+        }
+
+        for frame in output.chunks_exact_mut(CHANNELS) {
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            // Interpolate towards the next frame, silence after the end
+            let next = match index + 1 {
+                next if next < frames => Some(next),
+                _ if self.settings.looping => Some(0),
+                _ => None,
+            };
+            let current = self.clip.stereo_frame(index);
+            let next =
+                next.map_or([0.0; 2], |next| self.clip.stereo_frame(next));
+
+            for channel in 0..CHANNELS {
+                let sample = current[channel]
+                    + (next[channel] - current[channel]) * fraction;
+                frame[channel] += sample * gains[channel];
+            }
+
+            self.position += step;
+            if self.position >= frames as f64 {
+                if !self.settings.looping {
+                    return false;
+                }
+                self.position %= frames as f64;
+            }
+        }
+
+        true
+    }
+}
+
+/// Left and right gains, the centered sound keeps its full volume on both
+/// channels and fades out on the opposite side while panning.
+pub(crate) fn pan_gains(pan: f32) -> [f32; 2] {
+    let pan = pan.clamp(-1.0, 1.0);
+    [1.0 - pan.max(0.0), 1.0 + pan.min(0.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(sample_rate: u32, samples: &[f32]) -> AudioClip {
+        AudioClip::from_samples(sample_rate, 1, samples.to_vec()).unwrap()
+    }
+
+    /// Mixes the passed amount of frames and compares them to the expected
+    /// samples.
+    fn assert_mix(mixer: &mut Mixer, expected: &[f32]) {
+        let mut output = vec![0.0; expected.len()];
+        mixer.mix(&mut output);
+        for (sample, expected) in output.iter().zip(expected) {
+            assert!(
+                (sample - expected).abs() < 1e-6,
+                "{output:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn voices_are_summed_and_finish() {
+        let mut mixer = Mixer::new(4);
+        let short = mixer
+            .play(&clip(4, &[0.5, 0.25]), PlaybackSettings::default())
+            .unwrap();
+        let quiet = mixer
+            .play(
+                &clip(4, &[0.1, 0.1, 0.1]),
+                PlaybackSettings::default().with_volume(0.5),
+            )
+            .unwrap();
+
+        assert_mix(&mut mixer, &[0.55, 0.55, 0.3, 0.3, 0.05, 0.05, 0.0, 0.0]);
+        assert!(!mixer.is_playing(short));
+        assert!(!mixer.is_playing(quiet));
+        assert_eq!(mixer.voice_count(), 0);
+    }
+
+    #[test]
+    fn pan_and_looping() {
+        let mut mixer = Mixer::new(4);
+        let voice = mixer
+            .play(
+                &clip(4, &[1.0, 0.5]),
+                PlaybackSettings::default()
+                    .with_pan(-0.5)
+                    .with_looping(true),
+            )
+            .unwrap();
+
+        assert_mix(&mut mixer, &[1.0, 0.5, 0.5, 0.25, 1.0, 0.5]);
+        assert!(mixer.is_playing(voice));
+
+        mixer.settings_mut(voice).unwrap().pan = 1.0;
+        assert_mix(&mut mixer, &[0.0, 0.5]);
+    }
+
+    #[test]
+    fn pitch_and_resampling() {
+        let mut mixer = Mixer::new(8);
+        // Half the output rate, so every frame is interpolated once
+        mixer
+            .play(&clip(4, &[0.0, 1.0]), PlaybackSettings::default())
+            .unwrap();
+        assert_mix(&mut mixer, &[0.0, 0.0, 0.5, 0.5, 1.0, 1.0, 0.5, 0.5]);
+
+        mixer
+            .play(
+                &clip(8, &[0.0, 0.25, 0.5, 0.75]),
+                PlaybackSettings::default().with_pitch(2.0),
+            )
+            .unwrap();
+        assert_mix(&mut mixer, &[0.0, 0.0, 0.5, 0.5]);
+        assert_eq!(mixer.voice_count(), 0);
+    }
+
+    #[test]
+    fn buses_and_pausing() {
+        let mut mixer = Mixer::new(4).with_max_voices(2);
+        let music = mixer.add_bus("music");
+        assert_eq!(mixer.bus("music"), Some(music));
+        mixer.set_bus_volume(music, 0.5).unwrap();
+        mixer.set_bus_volume(BusId::MASTER, 0.5).unwrap();
+
+        let song = clip(4, &[1.0; 4]);
+        let voice = mixer
+            .play(&song, PlaybackSettings::default().with_bus(music))
+            .unwrap();
+        assert_mix(&mut mixer, &[0.25, 0.25]);
+
+        assert!(mixer.set_paused(voice, true));
+        assert_mix(&mut mixer, &[0.0, 0.0]);
+        mixer.set_paused(voice, false);
+        assert_mix(&mut mixer, &[0.25, 0.25]);
+
+        let unknown = BusId(7);
+        assert_eq!(
+            mixer.play(&song, PlaybackSettings::default().with_bus(unknown)),
+            Err(Error::NoSuchBus(unknown))
+        );
+        mixer.play(&song, PlaybackSettings::default()).unwrap();
+        assert_eq!(
+            mixer.play(&song, PlaybackSettings::default()),
+            Err(Error::VoiceLimitReached(2))
+        );
+
+        // Clipping
+        mixer.set_bus_volume(BusId::MASTER, 4.0).unwrap();
+        assert_mix(&mut mixer, &[1.0, 1.0]);
+    }
+}
@@ -0,0 +1,162 @@
+//! Audio playback.
+//!
+//! Clips are decoded from WAV or OGG Vorbis files (also through the
+//! `AssetServer`) and played by the `Mixer`, which sums every voice into a
+//! stereo buffer. An `AudioOutput` pulls the mixed samples: `DeviceOutput`
+//! plays them on the speakers (`audio-device` feature), `FileOutput` writes
+//! them to a WAV file and `NullOutput` discards them.
+//!
+//! Entities with an `AudioEmitter` are heard from the entity with the
+//! `AudioListener`, see the `spatial` module.
+//!
+//! ```
+//! # use unen_engine::audio::{Audio, AudioClip, NullOutput, PlaybackSettings};
+//! let audio = Audio::new(NullOutput::default()).unwrap();
+//! let clip = AudioClip::from_samples(44100, 1, vec![0.0; 44100]).unwrap();
+//!
+//! let mut mixer = audio.mixer();
+//! let music = mixer.add_bus("music");
+//! mixer
+//!     .play(&clip, PlaybackSettings::default().with_bus(music).with_looping(true))
+//!     .unwrap();
+//! ```
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use thiserror::Error;
+
+pub mod clip;
+#[cfg(feature = "audio-device")]
+pub mod device;
+pub mod mixer;
+pub mod output;
+pub mod spatial;
+
+pub use clip::{AudioClip, AudioLoader};
+#[cfg(feature = "audio-device")]
+pub use device::DeviceOutput;
+pub use mixer::{BusId, Mixer, PlaybackSettings, VoiceId};
+pub use output::{AudioOutput, FileOutput, NullOutput, SharedMixer};
+pub use spatial::{
+    Attenuation, AudioEmitter, AudioListener, Occlusion, SpatialAudio,
+    SpatialSettings,
+};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The data is not a WAV or OGG Vorbis file.
+    #[error("Unsupported audio format")]
+    UnsupportedFormat,
+    /// The file is corrupted.
+    #[error("Failed to decode audio: {0}")]
+    Decode(String),
+    /// Failed to encode audio.
+    #[error("Failed to encode audio: {0}")]
+    Encode(String),
+    /// The samples do not make a valid clip.
+    #[error("Invalid audio clip: {0}")]
+    InvalidClip(String),
+    /// The bus was never created.
+    #[error("No such bus '{0}'")]
+    NoSuchBus(BusId),
+    /// Too many voices are playing.
+    #[error("Reached the limit of {0} voices")]
+    VoiceLimitReached(usize),
+    /// There is no audio device available.
+    #[error("No audio output device available")]
+    NoDevice,
+    /// The output failed.
+    #[error("Audio output error: {0}")]
+    Output(String),
+    /// The output was not started yet.
+    #[error("Audio output not started")]
+    NotStarted,
+}
+
+/// Mixer connected to an output.
+pub struct Audio {
+    mixer: SharedMixer,
+    /// Kept alive to keep pulling samples.
+    _output: Box<dyn AudioOutput>,
+}
+
+impl Audio {
+    /// Starts the output with a new mixer.
+    pub fn new<O: AudioOutput + 'static>(output: O) -> Result<Self, Error> {
+        let mixer = Arc::new(Mutex::new(Mixer::new(output.sample_rate())));
+        Self::with_mixer(output, mixer)
+    }
+
+    /// Starts the output with the passed mixer, changing its sample rate to
+    /// the one of the output.
+    pub fn with_mixer<O: AudioOutput + 'static>(
+        mut output: O,
+        mixer: SharedMixer,
+    ) -> Result<Self, Error> {
+        lock(&mixer).set_sample_rate(output.sample_rate());
+        output.start(mixer.clone())?;
+
+        Ok(Self {
+            mixer,
+            _output: Box::new(output),
+        })
+    }
+
+    /// Switches to another output, voices keep playing.
+    pub fn set_output<O: AudioOutput + 'static>(
+        &mut self,
+        output: O,
+    ) -> Result<(), Error> {
+        *self = Self::with_mixer(output, self.mixer.clone())?;
+        Ok(())
+    }
+
+    /// Locks the mixer, the output waits while it is locked.
+    pub fn mixer(&self) -> MutexGuard<'_, Mixer> {
+        lock(&self.mixer)
+    }
+
+    /// Returns the mixer shared with the output.
+    pub fn shared_mixer(&self) -> SharedMixer {
+        self.mixer.clone()
+    }
+}
+
+impl Default for Audio {
+    /// Plays on the default device when available, otherwise audio is
+    /// discarded.
+    fn default() -> Self {
+        #[cfg(feature = "audio-device")]
+        match DeviceOutput::new().and_then(Self::new) {
+            Ok(audio) => return audio,
+            Err(err) => log::warn!("Audio disabled: {err}"),
+        }
+
+        Self::new(NullOutput::default())
+            .expect("the null output can always start")
+    }
+}
+
+/// The mixer stays usable even if a thread panicked while holding it.
+fn lock(mixer: &Mutex<Mixer>) -> MutexGuard<'_, Mixer> {
+    mixer.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_outputs_keeps_voices() {
+        let mut audio = Audio::new(NullOutput::new(8)).unwrap();
+        let clip = AudioClip::from_samples(8, 1, vec![1.0; 8]).unwrap();
+        let voice = audio
+            .mixer()
+            .play(&clip, PlaybackSettings::default())
+            .unwrap();
+
+        audio.set_output(NullOutput::new(16)).unwrap();
+        assert_eq!(audio.mixer().sample_rate(), 16);
+        assert!(audio.mixer().is_playing(voice));
+    }
+}
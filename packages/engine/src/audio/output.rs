@@ -0,0 +1,237 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{clip::wav_spec, lock, mixer::CHANNELS, Error, Mixer};
+
+/// How often the `NullOutput` pulls the frames due.
+const NULL_PULL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Mixer shared between the game and the output.
+pub type SharedMixer = Arc<Mutex<Mixer>>;
+
+/// Where the mixed audio goes.
+///
+/// Outputs pull samples from the mixer, usually from their own thread.
+pub trait AudioOutput {
+    /// Sample rate the mixer must run at.
+    fn sample_rate(&self) -> u32;
+
+    /// Starts pulling samples from the passed mixer, dropping the output
+    /// stops it.
+    fn start(&mut self, mixer: SharedMixer) -> Result<(), Error>;
+}
+
+/// Pulls the mixed audio at the output rate and discards it, so voices
+/// advance and finish as if they were played.
+#[derive(Debug)]
+pub struct NullOutput {
+    sample_rate: u32,
+    /// Cleared to stop the pulling thread.
+    running: Option<Arc<AtomicBool>>,
+}
+
+impl Default for NullOutput {
+    fn default() -> Self {
+        Self::new(48000)
+    }
+}
+
+impl NullOutput {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            running: None,
+        }
+    }
+}
+
+impl AudioOutput for NullOutput {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn start(&mut self, mixer: SharedMixer) -> Result<(), Error> {
+        if let Some(running) = self.running.take() {
+            running.store(false, Ordering::Relaxed);
+        }
+
+        let running = Arc::new(AtomicBool::new(true));
+        let sample_rate = self.sample_rate;
+        let flag = running.clone();
+        thread::Builder::new()
+            .name("null-audio".to_string())
+            .spawn(move || {
+                let started = Instant::now();
+                let mut pulled = 0;
+                let mut buffer = Vec::new();
+                while flag.load(Ordering::Relaxed) {
+                    thread::sleep(NULL_PULL_INTERVAL);
+                    let due = (started.elapsed().as_secs_f64()
+                        * sample_rate as f64)
+                        as usize;
+                    buffer.resize((due - pulled) * CHANNELS, 0.0);
+                    lock(&mixer).mix(&mut buffer);
+                    pulled = due;
+                }
+            })
+            .map_err(|err| Error::Output(err.to_string()))?;
+        self.running = Some(running);
+
+        Ok(())
+    }
+}
+
+impl Drop for NullOutput {
+    fn drop(&mut self) {
+        if let Some(running) = &self.running {
+            running.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Writes the mixed audio to a WAV file, samples are only pulled when
+/// calling `::render()`, useful for headless tests and offline rendering.
+pub struct FileOutput {
+    sample_rate: u32,
+    writer: Option<hound::WavWriter<BufWriter<File>>>,
+    mixer: Option<SharedMixer>,
+}
+
+impl FileOutput {
+    /// Creates the file, it is finished when dropped or with `::finish()`.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        sample_rate: u32,
+    ) -> Result<Self, Error> {
+        let writer = hound::WavWriter::create(
+            path,
+            wav_spec(sample_rate, CHANNELS as u16),
+        )
+        .map_err(|err| Error::Output(err.to_string()))?;
+
+        Ok(Self {
+            sample_rate,
+            writer: Some(writer),
+            mixer: None,
+        })
+    }
+
+    /// Mixes and writes the passed amount of frames.
+    pub fn render(&mut self, frames: usize) -> Result<(), Error> {
+        let (Some(mixer), Some(writer)) = (&self.mixer, &mut self.writer)
+        else {
+            return Err(Error::NotStarted);
+        };
+
+        let mut buffer = vec![0.0; frames * CHANNELS];
+        mixer
+            .lock()
+            .map_err(|_| Error::Output("Mixer poisoned".to_string()))?
+            .mix(&mut buffer);
+        for sample in buffer {
+            writer
+                .write_sample(sample)
+                .map_err(|err| Error::Output(err.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Same as `::render()` with a duration instead of frames.
+    pub fn render_duration(&mut self, duration: Duration) -> Result<(), Error> {
+        self.render((duration.as_secs_f64() * self.sample_rate as f64) as usize)
+    }
+
+    /// Finishes the file, no more samples can be written.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        match self.writer.take() {
+            Some(writer) => writer
+                .finalize()
+                .map_err(|err| Error::Output(err.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl AudioOutput for FileOutput {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn start(&mut self, mixer: SharedMixer) -> Result<(), Error> {
+        self.mixer = Some(mixer);
+        Ok(())
+    }
+}
+
+impl Drop for FileOutput {
+    fn drop(&mut self) {
+        if let Err(err) = self.finish() {
+            log::error!("Failed to finish audio file: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{Audio, AudioClip, PlaybackSettings};
+
+    #[test]
+    fn null_output_advances_voices() {
+        let output = NullOutput::new(1000);
+        let mixer = Arc::new(Mutex::new(Mixer::new(output.sample_rate())));
+        let _audio = Audio::with_mixer(output, mixer.clone()).unwrap();
+        // Lasts 50 milliseconds
+        let clip = AudioClip::from_samples(1000, 1, vec![0.5; 50]).unwrap();
+        let voice = lock(&mixer)
+            .play(&clip, PlaybackSettings::default())
+            .unwrap();
+
+        let start = Instant::now();
+        while lock(&mixer).is_playing(voice) {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn render_to_file() {
+        let path = std::env::temp_dir()
+            .join(format!("unen-engine-audio-{}.wav", std::process::id()));
+        let mut output = FileOutput::create(&path, 8).unwrap();
+        assert_eq!(output.render(1), Err(Error::NotStarted));
+
+        let mixer = Arc::new(Mutex::new(Mixer::new(output.sample_rate())));
+        output.start(mixer.clone()).unwrap();
+        let clip = AudioClip::from_samples(8, 1, vec![0.5; 8]).unwrap();
+        mixer
+            .lock()
+            .unwrap()
+            .play(&clip, PlaybackSettings::default().with_pan(1.0))
+            .unwrap();
+
+        output.render(4).unwrap();
+        output.render_duration(Duration::from_millis(750)).unwrap();
+        output.finish().unwrap();
+        drop(output);
+
+        let written = AudioClip::decode(&std::fs::read(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        let written = written.unwrap();
+        assert_eq!(written.channels(), 2);
+        assert_eq!(written.frames(), 10);
+        assert_eq!(&written.samples()[..4], [0.0, 0.5, 0.0, 0.5]);
+        // The clip ended after 8 frames
+        assert_eq!(&written.samples()[16..], [0.0; 4]);
+    }
+}
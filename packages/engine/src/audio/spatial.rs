@@ -0,0 +1,561 @@
+//! Positional audio.
+//!
+//! An entity with an `AudioListener` (usually the camera) hears every entity
+//! with an `AudioEmitter`. Their `GlobalTransform`s are used to compute the
+//! volume, panning and doppler shift of each emitter on every
+//! `Engine::step()`.
+//!
+//! The listener looks along `-Z` with `+X` to its right, the same as
+//! `Transform::looking_at()`.
+
+use std::collections::HashMap;
+
+use super::{AudioClip, Mixer, PlaybackSettings, VoiceId};
+use crate::{
+    math::{Quat, Vec3},
+    scene::{Entity, GlobalTransform, World},
+};
+
+/// How the volume decreases with the distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attenuation {
+    /// Same volume at any distance.
+    None,
+    /// Full volume up to `min_distance`, silent from `max_distance`.
+    Linear {
+        min_distance: f32,
+        max_distance: f32,
+    },
+    /// Physically based falloff, halves the volume every time the distance
+    /// doubles when `rolloff` is `1.0`.
+    Inverse {
+        reference_distance: f32,
+        rolloff: f32,
+    },
+    /// Falls off faster than `Inverse` for `rolloff` over `1.0`.
+    Exponential {
+        reference_distance: f32,
+        rolloff: f32,
+    },
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::Inverse {
+            reference_distance: 1.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl Attenuation {
+    /// Returns the gain at the passed distance, from `0.0` to `1.0`.
+    pub fn gain(&self, distance: f32) -> f32 {
+        match *self {
+            Self::None => 1.0,
+            Self::Linear {
+                min_distance,
+                max_distance,
+            } => {
+                if distance <= min_distance {
+                    1.0
+                } else if distance >= max_distance {
+                    0.0
+                } else {
+                    1.0 - (distance - min_distance)
+                        / (max_distance - min_distance)
+                }
+            }
+            Self::Inverse {
+                reference_distance,
+                rolloff,
+            } => {
+                let distance = distance.max(reference_distance);
+                reference_distance
+                    / (reference_distance
+                        + rolloff * (distance - reference_distance))
+            }
+            Self::Exponential {
+                reference_distance,
+                rolloff,
+            } => (distance.max(reference_distance) / reference_distance)
+                .powf(-rolloff),
+        }
+    }
+}
+
+/// Decides how much of a sound is blocked between two points, usually by
+/// casting a ray against the level geometry.
+pub trait Occlusion {
+    /// Returns `0.0` when nothing is in the way and `1.0` when the sound is
+    /// fully blocked.
+    fn occlusion(&self, listener: Vec3, emitter: Vec3) -> f32;
+}
+
+impl<F: Fn(Vec3, Vec3) -> f32> Occlusion for F {
+    fn occlusion(&self, listener: Vec3, emitter: Vec3) -> f32 {
+        self(listener, emitter)
+    }
+}
+
+/// Point of view used to compute the spatial parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ListenerState {
+    pub position: Vec3,
+    pub rotation: Quat,
+    /// Units per second.
+    pub velocity: Vec3,
+}
+
+/// Point in space a sound comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EmitterState {
+    pub position: Vec3,
+    /// Units per second.
+    pub velocity: Vec3,
+    pub attenuation: Attenuation,
+}
+
+/// Settings shared by every emitter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialSettings {
+    /// Units per second, `343.0` for meters.
+    pub speed_of_sound: f32,
+    /// Scales the doppler effect, `0.0` disables it.
+    pub doppler_factor: f32,
+}
+
+impl Default for SpatialSettings {
+    fn default() -> Self {
+        Self {
+            speed_of_sound: 343.0,
+            doppler_factor: 1.0,
+        }
+    }
+}
+
+/// Result of the spatialization, applied on top of the emitter's
+/// `PlaybackSettings`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialParams {
+    /// Multiplies the volume.
+    pub gain: f32,
+    /// Added to the pan.
+    pub pan: f32,
+    /// Multiplies the pitch.
+    pub pitch: f32,
+}
+
+impl SpatialParams {
+    /// Combines with the settings of a voice.
+    pub fn apply(&self, settings: PlaybackSettings) -> PlaybackSettings {
+        settings
+            .with_volume(settings.volume * self.gain)
+            .with_pan((settings.pan + self.pan).clamp(-1.0, 1.0))
+            .with_pitch(settings.pitch * self.pitch)
+    }
+}
+
+/// Computes how the emitter sounds from the listener.
+pub fn spatialize(
+    listener: &ListenerState,
+    emitter: &EmitterState,
+    settings: &SpatialSettings,
+    occlusion: Option<&dyn Occlusion>,
+) -> SpatialParams {
+    let offset = emitter.position - listener.position;
+    let distance = offset.length();
+    let direction = offset.normalize_or_zero();
+
+    let occluded = occlusion.map_or(0.0, |occlusion| {
+        occlusion
+            .occlusion(listener.position, emitter.position)
+            .clamp(0.0, 1.0)
+    });
+    let gain = emitter.attenuation.gain(distance) * (1.0 - occluded);
+
+    // Sine of the angle to the right of the listener
+    let pan = (listener.rotation.inverse() * direction).x;
+
+    // Velocities faster than sound would flip or explode the shift
+    let speed = settings.speed_of_sound;
+    let limit = speed * 0.5;
+    let towards_emitter = (listener.velocity.dot(direction)
+        * settings.doppler_factor)
+        .clamp(-limit, limit);
+    let away_from_listener = (emitter.velocity.dot(direction)
+        * settings.doppler_factor)
+        .clamp(-limit, limit);
+    let pitch = if speed > 0.0 {
+        (speed + towards_emitter) / (speed + away_from_listener)
+    } else {
+        1.0
+    };
+
+    SpatialParams { gain, pan, pitch }
+}
+
+/// Marks the entity whose `GlobalTransform` is used as the ears, only the
+/// first one is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AudioListener;
+
+/// Plays a clip from the position of the entity.
+///
+/// It starts playing as soon as it gets to a world with a `GlobalTransform`,
+/// removing the component or despawning the entity stops it.
+#[derive(Debug, Clone)]
+pub struct AudioEmitter {
+    pub clip: AudioClip,
+    /// Settings before spatialization.
+    pub settings: PlaybackSettings,
+    pub attenuation: Attenuation,
+    voice: Option<VoiceId>,
+    finished: bool,
+}
+
+impl AudioEmitter {
+    pub fn new(clip: AudioClip) -> Self {
+        Self {
+            clip,
+            settings: PlaybackSettings::default(),
+            attenuation: Attenuation::default(),
+            voice: None,
+            finished: false,
+        }
+    }
+
+    pub fn with_settings(mut self, settings: PlaybackSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    /// Voice playing the clip, `None` before starting and after finishing.
+    pub fn voice(&self) -> Option<VoiceId> {
+        self.voice
+    }
+
+    /// Returns `true` once a non looping clip played until the end.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// Keeps the voices of every `AudioEmitter` in sync with the world.
+#[derive(Default)]
+pub struct SpatialAudio {
+    pub settings: SpatialSettings,
+    occlusion: Option<Box<dyn Occlusion>>,
+    listener: Option<(Entity, Vec3)>,
+    /// Previous position and voice of every emitter.
+    emitters: HashMap<Entity, (Vec3, VoiceId)>,
+}
+
+impl SpatialAudio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_settings(mut self, settings: SpatialSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Sets the hook deciding how much each emitter is occluded.
+    pub fn set_occlusion<O: Occlusion + 'static>(&mut self, occlusion: O) {
+        self.occlusion = Some(Box::new(occlusion));
+    }
+
+    pub fn clear_occlusion(&mut self) {
+        self.occlusion = None;
+    }
+
+    /// Starts, updates and stops the voices of the emitters. Velocities are
+    /// derived from the movement since the last update, `delta` seconds
+    /// ago.
+    ///
+    /// _The `Engine` does this on every step, after propagating the
+    /// transforms._
+    pub fn update(&mut self, world: &mut World, mixer: &mut Mixer, delta: f32) {
+        crate::profile_scope!("SpatialAudio::update", "audio");
+
+        let velocity = |previous: Option<Vec3>, position: Vec3| match previous {
+            Some(previous) if delta > 0.0 => (position - previous) / delta,
+            _ => Vec3::ZERO,
+        };
+
+        let listener =
+            world.query::<AudioListener>().next().map(|(entity, _)| {
+                let transform = global_transform(world, entity);
+                let previous = self
+                    .listener
+                    .filter(|(previous, _)| *previous == entity)
+                    .map(|(_, position)| position);
+                let state = ListenerState {
+                    position: transform.translation,
+                    rotation: transform.rotation,
+                    velocity: velocity(previous, transform.translation),
+                };
+                (entity, state)
+            });
+        self.listener =
+            listener.map(|(entity, state)| (entity, state.position));
+        let listener = listener.map(|(_, state)| state).unwrap_or_default();
+
+        let mut emitters = HashMap::with_capacity(self.emitters.len());
+        let entities: Vec<Entity> = world
+            .query::<AudioEmitter>()
+            .map(|(entity, _)| entity)
+            .collect();
+        for entity in entities {
+            if !world.has::<GlobalTransform>(entity) {
+                continue;
+            }
+            let position = global_transform(world, entity).translation;
+            let previous = self.emitters.remove(&entity);
+            let Some(emitter) = world.get_mut::<AudioEmitter>(entity) else {
+                continue;
+            };
+
+            let state = EmitterState {
+                position,
+                velocity: velocity(
+                    previous.map(|(position, _)| position),
+                    position,
+                ),
+                attenuation: emitter.attenuation,
+            };
+            let params = spatialize(
+                &listener,
+                &state,
+                &self.settings,
+                self.occlusion.as_deref(),
+            );
+            let settings = params.apply(emitter.settings);
+
+            match emitter.voice {
+                Some(voice) if mixer.is_playing(voice) => {
+                    if let Some(current) = mixer.settings_mut(voice) {
+                        *current = settings;
+                    }
+                }
+                Some(_) => {
+                    emitter.voice = None;
+                    emitter.finished = true;
+                }
+                None if !emitter.finished => {
+                    match mixer.play(&emitter.clip, settings) {
+                        Ok(voice) => emitter.voice = Some(voice),
+                        Err(err) => {
+                            log::warn!(
+                                "Failed to play emitter {entity}: {err}"
+                            );
+                        }
+                    }
+                }
+                None => {}
+            }
+
+            if let Some(voice) = emitter.voice {
+                emitters.insert(entity, (position, voice));
+            }
+        }
+
+        // Whatever is left lost its emitter
+        for (_, voice) in self.emitters.values() {
+            mixer.stop(*voice);
+        }
+        self.emitters = emitters;
+    }
+}
+
+fn global_transform(world: &World, entity: Entity) -> crate::math::Transform {
+    world
+        .get::<GlobalTransform>(entity)
+        .map(|global| global.0)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        audio::mixer::CHANNELS,
+        math::{Transform, EPSILON},
+        scene::propagate_transforms,
+    };
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!((value - expected).abs() < EPSILON, "{value} != {expected}");
+    }
+
+    #[test]
+    fn attenuation_models() {
+        let linear = Attenuation::Linear {
+            min_distance: 2.0,
+            max_distance: 4.0,
+        };
+        assert_close(linear.gain(1.0), 1.0);
+        assert_close(linear.gain(3.0), 0.5);
+        assert_close(linear.gain(10.0), 0.0);
+
+        let inverse = Attenuation::default();
+        assert_close(inverse.gain(0.5), 1.0);
+        assert_close(inverse.gain(2.0), 0.5);
+        assert_close(inverse.gain(4.0), 0.25);
+
+        let exponential = Attenuation::Exponential {
+            reference_distance: 1.0,
+            rolloff: 2.0,
+        };
+        assert_close(exponential.gain(2.0), 0.25);
+        assert_close(Attenuation::None.gain(100.0), 1.0);
+    }
+
+    #[test]
+    fn panning_follows_the_listener_rotation() {
+        let settings = SpatialSettings::default();
+        let emitter = EmitterState {
+            position: Vec3::new(5.0, 0.0, 0.0),
+            attenuation: Attenuation::None,
+            ..Default::default()
+        };
+
+        let facing_forward = ListenerState::default();
+        let params = spatialize(&facing_forward, &emitter, &settings, None);
+        assert_close(params.pan, 1.0);
+        assert_close(params.pitch, 1.0);
+
+        // Turned around, the emitter is now on the left
+        let facing_back = ListenerState {
+            rotation: Quat::from_rotation_y(std::f32::consts::PI),
+            ..Default::default()
+        };
+        let params = spatialize(&facing_back, &emitter, &settings, None);
+        assert_close(params.pan, -1.0);
+
+        let in_front = EmitterState {
+            position: Vec3::new(0.0, 0.0, -5.0),
+            ..emitter
+        };
+        assert_close(
+            spatialize(&facing_forward, &in_front, &settings, None).pan,
+            0.0,
+        );
+    }
+
+    #[test]
+    fn doppler_and_occlusion() {
+        let settings = SpatialSettings {
+            speed_of_sound: 100.0,
+            doppler_factor: 1.0,
+        };
+        let listener = ListenerState::default();
+        let approaching = EmitterState {
+            position: Vec3::new(0.0, 0.0, -10.0),
+            velocity: Vec3::new(0.0, 0.0, 20.0),
+            attenuation: Attenuation::None,
+        };
+        let params = spatialize(&listener, &approaching, &settings, None);
+        assert_close(params.pitch, 100.0 / 80.0);
+
+        let leaving = EmitterState {
+            velocity: -approaching.velocity,
+            ..approaching
+        };
+        assert_close(
+            spatialize(&listener, &leaving, &settings, None).pitch,
+            100.0 / 120.0,
+        );
+
+        let disabled = SpatialSettings {
+            doppler_factor: 0.0,
+            ..settings
+        };
+        assert_close(
+            spatialize(&listener, &approaching, &disabled, None).pitch,
+            1.0,
+        );
+
+        let wall =
+            |_: Vec3, emitter: Vec3| if emitter.z < 0.0 { 0.75 } else { 0.0 };
+        let params =
+            spatialize(&listener, &approaching, &settings, Some(&wall));
+        assert_close(params.gain, 0.25);
+    }
+
+    /// Mixes a frame and returns the left and right samples.
+    fn mix(mixer: &mut Mixer) -> [f32; 2] {
+        let mut output = [0.0; CHANNELS];
+        mixer.mix(&mut output);
+        output
+    }
+
+    #[test]
+    fn emitters_render_offline() {
+        let mut world = World::new();
+        let mut mixer = Mixer::new(100);
+        let mut spatial = SpatialAudio::new();
+
+        let listener = world.spawn_with(Transform::IDENTITY);
+        world.insert(listener, AudioListener).unwrap();
+        let clip = AudioClip::from_samples(100, 1, vec![0.8; 100]).unwrap();
+        let emitter = world
+            .spawn_with(Transform::from_translation(Vec3::new(-2.0, 0.0, 0.0)));
+        world
+            .insert(emitter, AudioEmitter::new(clip.clone()))
+            .unwrap();
+        // Without a transform it can not be placed
+        world.spawn_with(AudioEmitter::new(clip));
+
+        propagate_transforms(&mut world);
+        spatial.update(&mut world, &mut mixer, 0.01);
+        assert_eq!(mixer.voice_count(), 1);
+        // Fully on the left, at half the volume
+        let [left, right] = mix(&mut mixer);
+        assert_close(left, 0.4);
+        assert_close(right, 0.0);
+
+        // Moving away lowers the pitch
+        world.get_mut::<Transform>(emitter).unwrap().translation.x = -4.0;
+        propagate_transforms(&mut world);
+        spatial.update(&mut world, &mut mixer, 0.01);
+        let voice =
+            world.get::<AudioEmitter>(emitter).unwrap().voice().unwrap();
+        assert!(mixer.settings(voice).unwrap().pitch < 1.0);
+        let [left, _] = mix(&mut mixer);
+        assert_close(left, 0.2);
+
+        world.despawn(emitter);
+        spatial.update(&mut world, &mut mixer, 0.01);
+        assert_eq!(mixer.voice_count(), 0);
+    }
+
+    #[test]
+    fn finished_emitters_do_not_restart() {
+        let mut world = World::new();
+        let mut mixer = Mixer::new(100);
+        let mut spatial = SpatialAudio::new();
+
+        let clip = AudioClip::from_samples(100, 1, vec![0.5; 2]).unwrap();
+        let emitter = world.spawn_with(Transform::IDENTITY);
+        world.insert(emitter, AudioEmitter::new(clip)).unwrap();
+        propagate_transforms(&mut world);
+
+        spatial.update(&mut world, &mut mixer, 0.01);
+        let mut output = [0.0; 4 * CHANNELS];
+        mixer.mix(&mut output);
+        spatial.update(&mut world, &mut mixer, 0.01);
+        spatial.update(&mut world, &mut mixer, 0.01);
+
+        let emitter = world.get::<AudioEmitter>(emitter).unwrap();
+        assert!(emitter.is_finished());
+        assert_eq!(emitter.voice(), None);
+        assert_eq!(mixer.voice_count(), 0);
+    }
+}
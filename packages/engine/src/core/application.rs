@@ -1,48 +1,111 @@
+use std::{collections::HashMap, sync::Arc};
+
 use winit::{
     application::ApplicationHandler,
     event::WindowEvent,
-    event_loop::{ControlFlow, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::Window,
 };
 
 use super::{
+    config::{EngineConfig, WindowConfig},
     engine::{Engine, EngineState},
     event::{
         event_handler::{EventHandler, RawCallback},
         Event,
     },
+    window::{self, WindowId},
+};
+#[cfg(feature = "wgpu")]
+use crate::{
+    render::{self, Color, Renderer, SurfaceId, WgpuRenderer},
+    ui,
 };
 
+/// Called every time a window is redrawn, the frame is already started and
+/// cleared.
+#[cfg(feature = "wgpu")]
+pub type RenderCallback = Box<
+    dyn FnMut(
+        &mut Engine,
+        &mut dyn Renderer,
+        WindowId,
+    ) -> Result<(), render::Error>,
+>;
+
+/// Pixels scrolled by a line of the mouse wheel.
+const PIXELS_PER_LINE: f32 = 24.0;
+
 pub struct Application {
-    window: Option<Window>,
+    /// All open windows.
+    windows: HashMap<WindowId, Arc<Window>>,
+    /// Maps the ids given by `winit` to our own ids.
+    window_ids: HashMap<winit::window::WindowId, WindowId>,
     engine: Engine,
-    title: String,
     event_handler: EventHandler<Engine>,
+    /// Created together with the first window.
+    #[cfg(feature = "wgpu")]
+    renderer: Option<WgpuRenderer>,
+    /// Surface of every window.
+    #[cfg(feature = "wgpu")]
+    surfaces: HashMap<WindowId, SurfaceId>,
+    #[cfg(feature = "wgpu")]
+    render_callback: Option<RenderCallback>,
 }
 
 impl Application {
+    /// Creates a new `Application` with the default configuration and the
+    /// passed window title.
     pub fn new(title: &str) -> Self {
-        let engine = Engine::default();
+        let mut config = EngineConfig::default();
+        config.window.title = title.to_string();
+
+        Self::with_config(config)
+    }
+
+    /// Creates a new `Application` with the passed configuration.
+    ///
+    /// _The configuration is expected to be already validated, refer to
+    /// `EngineConfig::builder()`._
+    pub fn with_config(config: EngineConfig) -> Self {
+        let engine = Engine::new(config);
 
-        let window = None;
+        let windows = HashMap::default();
+        let window_ids = HashMap::default();
 
         let event_handler = EventHandler::default();
 
         Self {
-            window,
+            windows,
+            window_ids,
             engine,
-            title: title.to_string(),
             event_handler,
+            #[cfg(feature = "wgpu")]
+            renderer: None,
+            #[cfg(feature = "wgpu")]
+            surfaces: HashMap::default(),
+            #[cfg(feature = "wgpu")]
+            render_callback: None,
         }
     }
 
     pub fn run(&mut self) {
         // Start the engine
-        self.engine.run();
+        if let Err(err) = self.engine.run() {
+            log::error!("Failed to start engine: {err}");
+            return;
+        }
 
         // Creates the event loop and sets it to `ControlFlow::Poll`, that way
         // we continously run the event loop
-        let event_loop = EventLoop::new().unwrap();
+        let event_loop = match EventLoop::new() {
+            Ok(event_loop) => event_loop,
+            Err(err) => {
+                log::error!("Failed to create event_loop: {err}");
+                let _ = self.engine.shutdown();
+                return;
+            }
+        };
         event_loop.set_control_flow(ControlFlow::Poll);
 
         match event_loop.run_app(self) {
@@ -57,55 +120,266 @@ impl Application {
     pub fn set_event_handler(&mut self, handler: RawCallback<Engine>) {
         self.event_handler.set_callback(handler);
     }
+
+    /// Sets the callback that draws the content of every window.
+    #[cfg(feature = "wgpu")]
+    pub fn set_render_callback(&mut self, callback: RenderCallback) {
+        self.render_callback = Some(callback);
+    }
+}
+
+impl Application {
+    /// Opens a new window and starts tracking it with the passed `id`.
+    fn open_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        id: WindowId,
+        config: &WindowConfig,
+    ) -> Result<(), window::Error> {
+        let window = Arc::new(window::create(event_loop, config)?);
+        #[cfg(feature = "wgpu")]
+        self.create_surface(id, window.clone(), config.vsync);
+        self.window_ids.insert(window.id(), id);
+        self.windows.insert(id, window);
+
+        log::info!("Opened window '{id}'");
+
+        Ok(())
+    }
+
+    /// Closes the window with the passed `id`.
+    ///
+    /// Closing the primary window shuts the engine down.
+    fn close_window(&mut self, id: WindowId) {
+        if id == WindowId::PRIMARY {
+            if let Err(err) = self.engine.shutdown() {
+                log::error!("Failed to shutdown engine: {err}");
+            }
+            return;
+        }
+
+        if let Some(window) = self.windows.remove(&id) {
+            self.window_ids.remove(&window.id());
+            #[cfg(feature = "wgpu")]
+            if let (Some(renderer), Some(surface)) =
+                (self.renderer.as_mut(), self.surfaces.remove(&id))
+            {
+                let _ = renderer.destroy_surface(surface);
+            }
+            self.engine
+                .dispatch(Event::Window(id, super::event::WindowEvent::Closed));
+            log::info!("Closed window '{id}'");
+        }
+    }
+
+    /// Creates the surface of a window, failing to do so is only logged since
+    /// the window is still usable without it.
+    #[cfg(feature = "wgpu")]
+    fn create_surface(
+        &mut self,
+        id: WindowId,
+        window: Arc<Window>,
+        vsync: bool,
+    ) {
+        let surface = match self.renderer.as_mut() {
+            Some(renderer) => renderer.create_window_surface(window, vsync),
+            None => WgpuRenderer::with_window(window, vsync).map(
+                |(renderer, surface)| {
+                    self.renderer = Some(renderer);
+                    surface
+                },
+            ),
+        };
+
+        match surface {
+            Ok(surface) => {
+                self.surfaces.insert(id, surface);
+            }
+            Err(err) => {
+                log::error!("Failed to create surface of '{id}': {err}")
+            }
+        }
+    }
+
+    /// Draws a new frame into the window with the passed `id`.
+    #[cfg(feature = "wgpu")]
+    fn render(&mut self, id: WindowId) -> Result<(), render::Error> {
+        let (Some(renderer), Some(&surface)) =
+            (self.renderer.as_mut(), self.surfaces.get(&id))
+        else {
+            return Ok(());
+        };
+
+        renderer.begin_frame(surface)?;
+        renderer.clear(Color::BLACK)?;
+        let result = match self.render_callback.as_mut() {
+            Some(callback) => callback(&mut self.engine, renderer, id),
+            None => Ok(()),
+        };
+        let result = result.and_then(|_| match id {
+            WindowId::PRIMARY => self
+                .engine
+                .render_console(renderer, surface)
+                .map_err(|err| match err {
+                    ui::Error::Render(err) => err,
+                    err => render::Error::Backend(err.to_string()),
+                }),
+            _ => Ok(()),
+        });
+        // Drawn last to stay on top, only on the window it takes input from
+        #[cfg(feature = "debug-overlay")]
+        let result = result.and_then(|_| match id {
+            WindowId::PRIMARY => {
+                self.engine.render_debug_overlay(renderer, surface)
+            }
+            _ => Ok(()),
+        });
+        // The frame has to be finished even if the callback failed
+        renderer.end_frame()?;
+
+        result
+    }
+
+    /// Applies the window commands found in the passed event.
+    fn apply_window_command(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        event: &Event,
+    ) {
+        let Event::Window(id, command) = event else {
+            return;
+        };
+
+        match command {
+            super::event::WindowEvent::Open(config) => {
+                if self.windows.contains_key(id) {
+                    log::warn!("Window '{id}' is already open");
+                    return;
+                }
+                let event = match self.open_window(event_loop, *id, config) {
+                    Ok(_) => super::event::WindowEvent::Opened,
+                    Err(err) => {
+                        log::error!("{err}");
+                        super::event::WindowEvent::OpenFailed(err.to_string())
+                    }
+                };
+                self.engine.dispatch(Event::Window(*id, event));
+            }
+            super::event::WindowEvent::Close => self.close_window(*id),
+            _ => {
+                let Some(window) = self.windows.get(id) else {
+                    return;
+                };
+                if let Some(event) = window::apply(event_loop, window, command)
+                {
+                    self.engine.dispatch(Event::Window(*id, event));
+                }
+            }
+        }
+    }
 }
 
 impl ApplicationHandler for Application {
-    fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        // Sets initial attributes for our window
-        let mut window_attributes = Window::default_attributes();
-        window_attributes.title = self.title.clone();
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        // Some platforms resume more than once
+        if self.windows.contains_key(&WindowId::PRIMARY) {
+            return;
+        }
 
-        self.window =
-            Some(event_loop.create_window(window_attributes).unwrap());
+        let config = self.engine.config().window.clone();
+        if let Err(err) =
+            self.open_window(event_loop, WindowId::PRIMARY, &config)
+        {
+            log::error!("{err}");
+            if let Err(err) = self.engine.shutdown() {
+                log::error!("Failed to shutdown engine: {err}");
+            }
+            event_loop.exit();
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Step the engine once per loop iteration, no matter how many windows
+        // are open
+        self.engine.step();
+        // Handle events at other areas
+        {
+            crate::profile_scope!("Application::handle_events");
+            while let Some(event) = self.engine.require_event() {
+                self.apply_window_command(event_loop, &event);
+                self.event_handler.step(&mut self.engine, &event);
+            }
+        }
+
+        // Stop the event loop when the engine gets at the
+        // `EngineState::Stopped` state
+        if self.engine.state() == EngineState::Stopped {
+            event_loop.exit();
+            return;
+        }
+
+        // Queue a RedrawRequested event for every window.
+        //
+        // You only need to call this if you've determined that you need to
+        // redraw in applications which do not always need to.
+        for window in self.windows.values() {
+            window.request_redraw();
+        }
     }
 
     fn window_event(
         &mut self,
-        event_loop: &winit::event_loop::ActiveEventLoop,
-        _window_id: winit::window::WindowId,
+        _event_loop: &ActiveEventLoop,
+        window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
+        // Ignore events from windows that are not tracked anymore
+        let Some(&id) = self.window_ids.get(&window_id) else {
+            return;
+        };
+
         match event {
             WindowEvent::CloseRequested => {
-                self.engine.shutdown();
+                self.close_window(id);
+            }
+            WindowEvent::Resized(size) => {
+                #[cfg(feature = "wgpu")]
+                if let (Some(renderer), Some(&surface)) =
+                    (self.renderer.as_mut(), self.surfaces.get(&id))
+                {
+                    // Minimized windows report a zero size
+                    if size.width > 0 && size.height > 0 {
+                        if let Err(err) = renderer.resize_surface(
+                            surface,
+                            size.width,
+                            size.height,
+                        ) {
+                            log::error!("{err}");
+                        }
+                    }
+                }
+                self.engine.dispatch(Event::Window(
+                    id,
+                    super::event::WindowEvent::Resized(size.width, size.height),
+                ));
+            }
+            WindowEvent::Focused(focused) => {
+                self.engine.dispatch(Event::Window(
+                    id,
+                    super::event::WindowEvent::Focused(focused),
+                ));
             }
             WindowEvent::RedrawRequested => {
-                // Redraw the application
+                // Redraw the window
                 //
                 // It's preferable for applications that do not render
                 // continously to render in this event rather than in
                 // AboutToWait, since rendering in here allows the program to
                 // gracefully handle redraws requested by the OS.
-
-                // Step the engine
-                self.engine.step();
-                // Handle events at other areas
-                while let Some(event) = self.engine.require_event() {
-                    self.event_handler.step(&mut self.engine, &event);
+                #[cfg(feature = "wgpu")]
+                if let Err(err) = self.render(id) {
+                    log::error!("Failed to render '{id}': {err}");
                 }
-
-                // Stop the event loop when the engine gets at the
-                // `EngineState::Stopped` state
-                if self.engine.state() == EngineState::Stopped {
-                    event_loop.exit();
-                }
-
-                // Queue a RedrawRequested event.
-                //
-                // You only need to call this if you've determined that you need
-                // to redraw in applications which do not always need to.
-                // Applications that redraw continously can render here instead.
-                self.window.as_ref().unwrap().request_redraw();
             }
             WindowEvent::KeyboardInput {
                 device_id: _,
@@ -114,13 +388,27 @@ impl ApplicationHandler for Application {
             } => match event.state {
                 winit::event::ElementState::Pressed => {
                     self.engine.dispatch(Event::Keyboard(
+                        id,
                         super::event::keyboard_event::KeyboardEvent::Pressed(
                             event.physical_key,
                         ),
                     ));
+                    // Control characters are already sent as keys
+                    if let Some(text) = event
+                        .text
+                        .filter(|text| !text.chars().any(char::is_control))
+                    {
+                        self.engine.dispatch(Event::Keyboard(
+                            id,
+                            super::event::keyboard_event::KeyboardEvent::Text(
+                                text.to_string(),
+                            ),
+                        ));
+                    }
                 }
                 winit::event::ElementState::Released => {
                     self.engine.dispatch(Event::Keyboard(
+                        id,
                         super::event::keyboard_event::KeyboardEvent::Released(
                             event.physical_key,
                         ),
@@ -134,11 +422,13 @@ impl ApplicationHandler for Application {
             } => match state {
                 winit::event::ElementState::Pressed => {
                     self.engine.dispatch(Event::Mouse(
+                        id,
                         super::event::mouse_event::MouseEvent::Pressed(button),
                     ));
                 }
                 winit::event::ElementState::Released => {
                     self.engine.dispatch(Event::Mouse(
+                        id,
                         super::event::mouse_event::MouseEvent::Released(button),
                     ));
                 }
@@ -148,12 +438,36 @@ impl ApplicationHandler for Application {
                 position,
             } => {
                 self.engine.dispatch(Event::Mouse(
+                    id,
                     super::event::mouse_event::MouseEvent::Moved(
-                        position.x as u32,
-                        position.y as u32,
+                        crate::math::Vec2::new(
+                            position.x as f32,
+                            position.y as f32,
+                        ),
                     ),
                 ));
             }
+            WindowEvent::MouseWheel {
+                device_id: _,
+                delta,
+                phase: _,
+            } => {
+                let delta = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                        crate::math::Vec2::new(x, y) * PIXELS_PER_LINE
+                    }
+                    winit::event::MouseScrollDelta::PixelDelta(position) => {
+                        crate::math::Vec2::new(
+                            position.x as f32,
+                            position.y as f32,
+                        )
+                    }
+                };
+                self.engine.dispatch(Event::Mouse(
+                    id,
+                    super::event::mouse_event::MouseEvent::Scrolled(delta),
+                ));
+            }
             _ => (),
         }
     }
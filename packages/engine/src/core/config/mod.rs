@@ -0,0 +1,534 @@
+//! Engine configuration.
+//!
+//! An `EngineConfig` can be built in code and then overridden, in order, by a
+//! configuration file (TOML or RON), environment variables and command line
+//! arguments:
+//!
+//! ```no_run
+//! # use unen_engine::core::config::EngineConfig;
+//! let config = EngineConfig::builder()
+//!     .title("Game")
+//!     .file("game.toml")
+//!     .env("UNEN")
+//!     .args(std::env::args().skip(1))
+//!     .build()
+//!     .expect("invalid configuration");
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::logging::LogConfig;
+use crate::script::ScriptLimits;
+
+pub mod source;
+pub mod window;
+
+pub use window::{WindowConfig, WindowIcon, WindowMode};
+
+/// Highest supported tick rate.
+pub const MAX_TICK_RATE: u32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Failed to read a configuration file.
+    #[error("Failed to read config file '{0}': {1}")]
+    Read(PathBuf, std::io::Error),
+    /// The file extension is not a supported format.
+    #[error("Unsupported config file format '{0}', expected .toml or .ron")]
+    UnsupportedFormat(PathBuf),
+    /// Failed to parse a configuration file.
+    #[error("Failed to parse config file '{0}': {1}")]
+    Parse(PathBuf, String),
+    /// An override points to a key that does not exist.
+    #[error("Unknown config key '{0}'")]
+    UnknownKey(String),
+    /// A command line argument is not in the `--key=value` form.
+    #[error("Invalid argument '{0}', expected '--key=value'")]
+    InvalidArgument(String),
+    /// A command line argument has no value.
+    #[error("Missing value for argument '--{0}'")]
+    MissingArgumentValue(String),
+    /// The merged values do not match the expected types.
+    #[error("Invalid config value: {0}")]
+    InvalidValue(String),
+    /// Window size can not be zero.
+    #[error("Invalid window size {0}x{1}: both sides must be greater than 0")]
+    InvalidWindowSize(u32, u32),
+    /// Minimum window size is bigger than the maximum one.
+    #[error(
+        "Invalid window size limits: minimum {0:?} is bigger than maximum {1:?}"
+    )]
+    InvalidWindowSizeLimits((u32, u32), (u32, u32)),
+    /// Raw icon does not contain `width * height` RGBA pixels.
+    #[error("Invalid window icon: expected {0} bytes got {1}")]
+    InvalidWindowIcon(usize, usize),
+    /// Worker count can not be zero.
+    #[error("Invalid worker count: at least 1 worker is required")]
+    InvalidWorkerCount,
+    /// Tick rate must be inside `1..=MAX_TICK_RATE`.
+    #[error("Invalid tick rate {0}: must be between 1 and {MAX_TICK_RATE}")]
+    InvalidTickRate(u32),
+    /// Asset root can not be empty.
+    #[error("Invalid asset root: path can not be empty")]
+    EmptyAssetRoot,
+}
+
+/// Everything needed to configure an `Engine`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    /// Main window configuration.
+    pub window: WindowConfig,
+    /// Amount of workers, `None` uses every available thread.
+    pub workers: Option<usize>,
+    /// Fixed updates per second.
+    pub tick_rate: u32,
+    /// Directory that contains the assets.
+    pub asset_root: PathBuf,
+    /// Logging configuration.
+    pub logging: LogConfig,
+    /// Console script run when the engine starts.
+    pub startup_script: Option<PathBuf>,
+    /// Sandboxing limits of the gameplay scripts.
+    pub script_limits: ScriptLimits,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            window: WindowConfig::default(),
+            workers: None,
+            tick_rate: 60,
+            asset_root: PathBuf::from("assets"),
+            logging: LogConfig::default(),
+            startup_script: None,
+            script_limits: ScriptLimits::default(),
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Creates a new `EngineConfigBuilder` starting from the default values.
+    pub fn builder() -> EngineConfigBuilder {
+        EngineConfigBuilder::default()
+    }
+
+    /// Sets the logging configuration.
+    pub fn with_logging(mut self, logging: LogConfig) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    /// Loads a configuration file, missing values use the defaults.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::builder().file(path).build()
+    }
+
+    /// Checks if every value is usable.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.window.width == 0 || self.window.height == 0 {
+            return Err(Error::InvalidWindowSize(
+                self.window.width,
+                self.window.height,
+            ));
+        }
+
+        if let (Some(min), Some(max)) =
+            (self.window.min_size, self.window.max_size)
+        {
+            if min.0 > max.0 || min.1 > max.1 {
+                return Err(Error::InvalidWindowSizeLimits(min, max));
+            }
+        }
+
+        if let Some(WindowIcon::Rgba {
+            width,
+            height,
+            rgba,
+        }) = &self.window.icon
+        {
+            let expected = *width as usize * *height as usize * 4;
+            if rgba.len() != expected {
+                return Err(Error::InvalidWindowIcon(expected, rgba.len()));
+            }
+        }
+
+        if self.workers == Some(0) {
+            return Err(Error::InvalidWorkerCount);
+        }
+
+        if self.tick_rate == 0 || self.tick_rate > MAX_TICK_RATE {
+            return Err(Error::InvalidTickRate(self.tick_rate));
+        }
+
+        if self.asset_root.as_os_str().is_empty() {
+            return Err(Error::EmptyAssetRoot);
+        }
+
+        Ok(())
+    }
+}
+
+/// Helper that builds an `EngineConfig` from code, files, environment
+/// variables and command line arguments.
+///
+/// Every call is applied in order, later calls override earlier ones. The
+/// first error is kept and returned by `::build()`.
+#[derive(Debug, Default)]
+pub struct EngineConfigBuilder {
+    config: EngineConfig,
+    error: Option<Error>,
+}
+
+impl EngineConfigBuilder {
+    /// Sets the window title.
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.config.window.title = title.into();
+        self
+    }
+
+    /// Sets the window size.
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.config.window.width = width;
+        self.config.window.height = height;
+        self
+    }
+
+    /// Sets the window mode.
+    pub fn window_mode(mut self, mode: WindowMode) -> Self {
+        self.config.window.mode = mode;
+        self
+    }
+
+    /// Sets the vertical sync.
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.config.window.vsync = vsync;
+        self
+    }
+
+    /// Sets the whole window configuration.
+    pub fn window(mut self, window: WindowConfig) -> Self {
+        self.config.window = window;
+        self
+    }
+
+    /// Sets the amount of workers.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.config.workers = Some(workers);
+        self
+    }
+
+    /// Sets the fixed updates per second.
+    pub fn tick_rate(mut self, tick_rate: u32) -> Self {
+        self.config.tick_rate = tick_rate;
+        self
+    }
+
+    /// Sets the default log level.
+    pub fn log_level(mut self, level: log::LevelFilter) -> Self {
+        self.config.logging.level = level;
+        self
+    }
+
+    /// Sets the whole logging configuration.
+    pub fn logging(mut self, logging: LogConfig) -> Self {
+        self.config.logging = logging;
+        self
+    }
+
+    /// Sets the asset root directory.
+    pub fn asset_root<P: Into<PathBuf>>(mut self, asset_root: P) -> Self {
+        self.config.asset_root = asset_root.into();
+        self
+    }
+
+    /// Sets the console script run on start.
+    pub fn startup_script<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.config.startup_script = Some(path.into());
+        self
+    }
+
+    /// Sets the sandboxing limits of the gameplay scripts.
+    pub fn script_limits(mut self, limits: ScriptLimits) -> Self {
+        self.config.script_limits = limits;
+        self
+    }
+
+    /// Overrides the current values with the ones found in the passed file.
+    ///
+    /// The format is chosen from the extension, `.toml` or `.ron`.
+    pub fn file<P: AsRef<Path>>(self, path: P) -> Self {
+        let path = path.as_ref();
+        self.try_apply(|config| {
+            let content = std::fs::read_to_string(path)
+                .map_err(|err| Error::Read(path.to_path_buf(), err))?;
+            let value = source::Format::from_path(path)?
+                .parse(&content)
+                .map_err(|err| Error::Parse(path.to_path_buf(), err))?;
+
+            let mut merged = to_value(config)?;
+            source::merge(&mut merged, value);
+            from_value(merged)
+        })
+    }
+
+    /// Same as `::file()`, but does nothing if the file does not exist.
+    pub fn optional_file<P: AsRef<Path>>(self, path: P) -> Self {
+        if path.as_ref().exists() {
+            self.file(path)
+        } else {
+            self
+        }
+    }
+
+    /// Overrides a single value using its dotted key, e.g. `window.width`.
+    pub fn set(self, key: &str, value: &str) -> Self {
+        self.try_apply(|config| {
+            let mut merged = to_value(config)?;
+            source::set(&mut merged, key, value)?;
+            from_value(merged)
+        })
+    }
+
+    /// Overrides the current values with environment variables starting with
+    /// `prefix`, e.g. `UNEN_WINDOW__WIDTH=1280` or `UNEN_TICK_RATE=30`.
+    ///
+    /// _Variables that are not a configuration key are only logged._
+    pub fn env(self, prefix: &str) -> Self {
+        self.env_from(prefix, std::env::vars())
+    }
+
+    /// Same as `::env()`, but reads the variables from the passed iterator.
+    pub fn env_from<I>(self, prefix: &str, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let overrides = source::env_overrides(prefix, vars);
+        self.try_apply(|config| {
+            let mut merged = to_value(config)?;
+            for (key, value) in overrides {
+                match source::set(&mut merged, &key, &value) {
+                    // Other programs can use variables with the same prefix
+                    Err(Error::UnknownKey(key)) => log::warn!(
+                        "Ignoring unknown configuration key '{key}' from the \
+                         environment"
+                    ),
+                    result => result?,
+                }
+            }
+            from_value(merged)
+        })
+    }
+
+    /// Overrides the current values with command line arguments, e.g.
+    /// `--window.width=1280`. The special `--config <path>` argument loads a
+    /// configuration file.
+    ///
+    /// _The program name must not be part of the passed arguments._
+    pub fn args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let overrides = match source::arg_overrides(args) {
+            Ok(overrides) => overrides,
+            Err(err) => {
+                self.error.get_or_insert(err);
+                return self;
+            }
+        };
+
+        for (key, value) in overrides {
+            self = match key.as_str() {
+                "config" => self.file(value),
+                _ => self.set(&key, &value),
+            };
+        }
+        self
+    }
+
+    /// Validates and returns the final configuration.
+    pub fn build(self) -> Result<EngineConfig, Error> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        self.config.validate()?;
+        Ok(self.config)
+    }
+
+    /// Applies the passed function if no error happened before.
+    fn try_apply<F>(mut self, apply: F) -> Self
+    where
+        F: FnOnce(&EngineConfig) -> Result<EngineConfig, Error>,
+    {
+        if self.error.is_none() {
+            match apply(&self.config) {
+                Ok(config) => self.config = config,
+                Err(err) => self.error = Some(err),
+            }
+        }
+        self
+    }
+}
+
+fn to_value(config: &EngineConfig) -> Result<serde_json::Value, Error> {
+    serde_json::to_value(config)
+        .map_err(|err| Error::InvalidValue(err.to_string()))
+}
+
+fn from_value(value: serde_json::Value) -> Result<EngineConfig, Error> {
+    serde_json::from_value(value)
+        .map_err(|err| Error::InvalidValue(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("unen-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn default_is_valid() {
+        assert!(EngineConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn toml_file_keeps_code_values() {
+        let path = temp_file(
+            "partial.toml",
+            "tick_rate = 30\n[window]\nwidth = 800\nmode = \"borderless\"\n\
+             min_size = [640, 360]\nicon = \"icon.png\"\n",
+        );
+
+        let config = EngineConfig::builder()
+            .title("Game")
+            .file(&path)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.window.title, "Game");
+        assert_eq!(config.window.width, 800);
+        assert_eq!(config.window.height, 720);
+        assert_eq!(config.window.mode, WindowMode::Borderless);
+        assert_eq!(config.window.min_size, Some((640, 360)));
+        assert_eq!(
+            config.window.icon,
+            Some(WindowIcon::Path(PathBuf::from("icon.png"))),
+        );
+        assert_eq!(config.tick_rate, 30);
+    }
+
+    #[test]
+    fn ron_file() {
+        let path = temp_file(
+            "full.ron",
+            "(workers: Some(2), asset_root: \"data\", logging: (level: \"warn\"))",
+        );
+
+        let config = EngineConfig::from_file(&path).unwrap();
+
+        assert_eq!(config.workers, Some(2));
+        assert_eq!(config.asset_root, PathBuf::from("data"));
+        assert_eq!(config.logging.level, log::LevelFilter::Warn);
+    }
+
+    #[test]
+    fn precedence_file_env_args() {
+        let path =
+            temp_file("precedence.toml", "tick_rate = 30\nworkers = 8\n");
+        let vars = vec![
+            ("UNEN_TICK_RATE".to_string(), "20".to_string()),
+            ("UNEN_WORKERS".to_string(), "4".to_string()),
+        ];
+        let args = ["--workers=2"].map(String::from);
+
+        let config = EngineConfig::builder()
+            .file(&path)
+            .env_from("UNEN", vars)
+            .args(args)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.tick_rate, 20);
+        assert_eq!(config.workers, Some(2));
+    }
+
+    #[test]
+    fn unknown_env_vars_are_ignored() {
+        let vars = vec![
+            ("UNEN_HOME".to_string(), "/opt/unen".to_string()),
+            ("UNEN_TICK_RATE".to_string(), "20".to_string()),
+        ];
+
+        let config = EngineConfig::builder()
+            .env_from("UNEN", vars)
+            .build()
+            .unwrap();
+        assert_eq!(config.tick_rate, 20);
+
+        let result = EngineConfig::builder().set("home", "/opt/unen").build();
+        assert!(matches!(result, Err(Error::UnknownKey(key)) if key == "home"));
+    }
+
+    #[test]
+    fn clear_errors() {
+        let result = EngineConfig::builder().size(0, 720).build();
+        assert!(matches!(result, Err(Error::InvalidWindowSize(0, 720))));
+
+        let result = EngineConfig::builder()
+            .window(
+                WindowConfig::default()
+                    .with_min_size(800, 600)
+                    .with_max_size(640, 480),
+            )
+            .build();
+        assert!(matches!(result, Err(Error::InvalidWindowSizeLimits(..))));
+
+        let result = EngineConfig::builder()
+            .window(WindowConfig::default().with_icon(WindowIcon::Rgba {
+                width: 2,
+                height: 2,
+                rgba: vec![0; 4],
+            }))
+            .build();
+        assert!(matches!(result, Err(Error::InvalidWindowIcon(16, 4))));
+
+        let result = EngineConfig::builder().tick_rate(0).build();
+        assert!(matches!(result, Err(Error::InvalidTickRate(0))));
+
+        let result = EngineConfig::builder().workers(0).build();
+        assert!(matches!(result, Err(Error::InvalidWorkerCount)));
+
+        let result = EngineConfig::builder()
+            .args(["--window.widht=1".to_string()])
+            .build();
+        assert!(
+            matches!(result, Err(Error::UnknownKey(key)) if key == "window.widht")
+        );
+
+        let result =
+            EngineConfig::builder().set("window.width", "wide").build();
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+
+        let result = EngineConfig::builder().file("game.yaml").build();
+        assert!(matches!(result, Err(Error::Read(..))));
+
+        let path = temp_file("game.yaml", "tick_rate: 30");
+        let result = EngineConfig::builder().file(&path).build();
+        assert!(
+            matches!(result, Err(Error::UnsupportedFormat(file)) if file == path)
+        );
+    }
+}
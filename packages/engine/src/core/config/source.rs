@@ -0,0 +1,241 @@
+//! Sources that can override an `EngineConfig`.
+//!
+//! Every source is converted into a `serde_json::Value` tree that is merged on
+//! top of the current configuration, that way files, environment variables
+//! and command line arguments all follow the same rules.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::Error;
+
+/// Supported configuration file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Ron,
+}
+
+impl Format {
+    /// Guesses the format from the extension of the passed path.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(Self::Toml),
+            Some("ron") => Ok(Self::Ron),
+            _ => Err(Error::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Parses the passed content into a value tree.
+    pub fn parse(&self, content: &str) -> Result<Value, String> {
+        match self {
+            Self::Toml => {
+                toml::from_str(content).map_err(|err| err.to_string())
+            }
+            Self::Ron => ron::from_str(content)
+                .map(from_ron)
+                .map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// Converts a RON value tree into a JSON one.
+///
+/// _RON can not be deserialized directly into a `serde_json::Value` because
+/// it does not know how to handle `Some(...)` without type information._
+fn from_ron(value: ron::Value) -> Value {
+    match value {
+        ron::Value::Bool(value) => Value::Bool(value),
+        ron::Value::Char(value) => Value::String(value.to_string()),
+        ron::Value::Map(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let key = match from_ron(key) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, from_ron(value))
+                })
+                .collect(),
+        ),
+        ron::Value::Number(ron::Number::Integer(value)) => value.into(),
+        ron::Value::Number(ron::Number::Float(value)) => {
+            serde_json::Number::from_f64(value.get())
+                .map(Value::Number)
+                .unwrap_or(Value::Null)
+        }
+        ron::Value::Option(value) => {
+            value.map_or(Value::Null, |value| from_ron(*value))
+        }
+        ron::Value::String(value) => Value::String(value),
+        ron::Value::Seq(values) => {
+            Value::Array(values.into_iter().map(from_ron).collect())
+        }
+        ron::Value::Unit => Value::Null,
+    }
+}
+
+/// Recursively merges `other` on top of `base`.
+///
+/// Objects are merged key by key, any other value replaces the base one.
+pub fn merge(base: &mut Value, other: Value) {
+    match (base, other) {
+        (Value::Object(base), Value::Object(other)) => {
+            for (key, value) in other {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, other) => *base = other,
+    }
+}
+
+/// Sets the value found at the dotted `key` (e.g. `window.width`), parsing
+/// `raw` according to the type of the current value.
+pub fn set(root: &mut Value, key: &str, raw: &str) -> Result<(), Error> {
+    let mut current = root;
+    for part in key.split('.') {
+        current = match current {
+            Value::Object(map) => map
+                .get_mut(part)
+                .ok_or_else(|| Error::UnknownKey(key.to_string()))?,
+            _ => return Err(Error::UnknownKey(key.to_string())),
+        };
+    }
+
+    *current = parse_value(current, raw);
+
+    Ok(())
+}
+
+/// Parses a raw string using the current value as a type hint.
+fn parse_value(current: &Value, raw: &str) -> Value {
+    match current {
+        Value::String(_) => Value::String(raw.to_string()),
+        _ => serde_json::from_str(raw)
+            .unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
+
+/// Collects overrides from environment variables starting with `prefix`.
+///
+/// Nested keys are separated by a double underscore, so `UNEN_WINDOW__WIDTH`
+/// maps to `window.width` and `UNEN_TICK_RATE` maps to `tick_rate`.
+pub fn env_overrides<I>(prefix: &str, vars: I) -> Vec<(String, String)>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let prefix = format!("{}_", prefix.to_uppercase());
+    vars.into_iter()
+        .filter_map(|(name, value)| {
+            let key = name.strip_prefix(&prefix)?;
+            Some((key.to_lowercase().replace("__", "."), value))
+        })
+        .collect()
+}
+
+/// Collects overrides from command line arguments.
+///
+/// Both `--window.width=1280` and `--window.width 1280` are accepted.
+pub fn arg_overrides<I>(args: I) -> Result<Vec<(String, String)>, Error>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut overrides = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.strip_prefix("--") else {
+            return Err(Error::InvalidArgument(arg));
+        };
+
+        match arg.split_once('=') {
+            Some((key, value)) => {
+                overrides.push((key.to_string(), value.to_string()))
+            }
+            None => match args.next() {
+                Some(value) => overrides.push((arg.to_string(), value)),
+                None => {
+                    return Err(Error::MissingArgumentValue(arg.to_string()))
+                }
+            },
+        }
+    }
+
+    Ok(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_nested() {
+        let mut base = serde_json::json!({
+            "window": { "width": 1, "height": 2 },
+            "tick_rate": 60,
+        });
+        merge(&mut base, serde_json::json!({ "window": { "width": 3 } }));
+
+        assert_eq!(base["window"]["width"], 3);
+        assert_eq!(base["window"]["height"], 2);
+        assert_eq!(base["tick_rate"], 60);
+    }
+
+    #[test]
+    fn set_uses_type_hint() {
+        let mut base = serde_json::json!({
+            "window": { "title": "a", "width": 1 },
+        });
+        set(&mut base, "window.title", "1234").unwrap();
+        set(&mut base, "window.width", "1234").unwrap();
+
+        assert_eq!(base["window"]["title"], "1234");
+        assert_eq!(base["window"]["width"], 1234);
+        assert!(matches!(
+            set(&mut base, "window.depth", "1"),
+            Err(Error::UnknownKey(_)),
+        ));
+    }
+
+    #[test]
+    fn env_keys() {
+        let vars = vec![
+            ("UNEN_WINDOW__WIDTH".to_string(), "800".to_string()),
+            ("UNEN_TICK_RATE".to_string(), "30".to_string()),
+            ("PATH".to_string(), "/bin".to_string()),
+        ];
+        assert_eq!(
+            env_overrides("unen", vars),
+            vec![
+                ("window.width".to_string(), "800".to_string()),
+                ("tick_rate".to_string(), "30".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn arg_forms() {
+        let args =
+            ["--workers=2", "--window.mode", "borderless"].map(String::from);
+        assert_eq!(
+            arg_overrides(args).unwrap(),
+            vec![
+                ("workers".to_string(), "2".to_string()),
+                ("window.mode".to_string(), "borderless".to_string()),
+            ],
+        );
+        assert!(matches!(
+            arg_overrides(["oops".to_string()]),
+            Err(Error::InvalidArgument(_)),
+        ));
+        assert!(matches!(
+            arg_overrides(["--workers".to_string()]),
+            Err(Error::MissingArgumentValue(_)),
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// How a window is presented.
+#[derive(
+    Debug, Display, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowMode {
+    /// Regular window with decorations.
+    #[default]
+    Windowed,
+    /// Fullscreen window that keeps the desktop video mode.
+    Borderless,
+    /// Exclusive fullscreen.
+    Fullscreen,
+}
+
+/// Icon displayed by the window and the task bar.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WindowIcon {
+    /// Image file that will be decoded when the window gets created.
+    Path(PathBuf),
+    /// Raw RGBA8 pixels.
+    Rgba {
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    },
+}
+
+/// Configuration of a window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// Title of the window.
+    pub title: String,
+    /// Width of the window in physical pixels.
+    pub width: u32,
+    /// Height of the window in physical pixels.
+    pub height: u32,
+    /// Smallest size the window can be resized to.
+    pub min_size: Option<(u32, u32)>,
+    /// Biggest size the window can be resized to.
+    pub max_size: Option<(u32, u32)>,
+    /// Flags if the user can resize the window.
+    pub resizable: bool,
+    /// Flags if the window has a title bar and borders.
+    pub decorations: bool,
+    /// Presentation mode.
+    pub mode: WindowMode,
+    /// Flags if presenting should wait for the vertical sync.
+    pub vsync: bool,
+    /// Flags if the cursor is visible while over the window.
+    pub cursor_visible: bool,
+    /// Optional icon.
+    pub icon: Option<WindowIcon>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Unnamed".to_string(),
+            width: 1280,
+            height: 720,
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            decorations: true,
+            mode: WindowMode::default(),
+            vsync: true,
+            cursor_visible: true,
+            icon: None,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Creates a new configuration with the passed title.
+    pub fn new<S: Into<String>>(title: S) -> Self {
+        Self {
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the size.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the smallest size.
+    pub fn with_min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    /// Sets the biggest size.
+    pub fn with_max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    /// Sets if the window can be resized.
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Sets if the window has decorations.
+    pub fn with_decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Sets the presentation mode.
+    pub fn with_mode(mut self, mode: WindowMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets if the cursor is visible.
+    pub fn with_cursor_visible(mut self, cursor_visible: bool) -> Self {
+        self.cursor_visible = cursor_visible;
+        self
+    }
+
+    /// Sets the icon.
+    pub fn with_icon(mut self, icon: WindowIcon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+}
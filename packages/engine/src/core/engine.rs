@@ -1,15 +1,47 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, path::Path, time::Instant};
 
 use strum::Display;
 
 use super::{
+    config::{EngineConfig, WindowConfig},
     event::{self, Event},
-    scheduler::{pool::WorkerPool, worker::WorkerInstruction},
+    logging::{self, LogBuffer},
+    scheduler::{
+        pool::WorkerPool,
+        worker::{WorkerInstruction, WorkerKind},
+    },
+    time::Time,
+    window::WindowId,
     Error,
 };
+use crate::{
+    animation::Animator,
+    asset::{ArchiveSource, AssetServer, FileSource},
+    audio::{Audio, SpatialAudio},
+    debug::{
+        self, command::tokenize, cvar::Storage, Console, LineKind, Tweakables,
+        Value,
+    },
+    math::Vec2,
+    net::{self, NetworkHost},
+    physics::PhysicsWorld,
+    render::{Fonts, Renderer, SurfaceId, TextRenderer},
+    scene::{self, World},
+    script::ScriptHost,
+    spatial::{self, Bvh},
+    ui,
+};
+#[cfg(feature = "debug-overlay")]
+use crate::{
+    debug::{DebugOverlay, DebugStatus},
+    render,
+};
+
+/// Deepest nesting of scripts running other scripts with `exec`.
+const MAX_EXEC_DEPTH: usize = 8;
 
 /// All the possible states a `Engine` can be at.
-#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum EngineState {
     /// Currently stopped and can only be start with `::start()`.
     Stopped,
@@ -40,24 +72,65 @@ pub struct Engine {
     /// Contains the events that are already handled by the engine's internals
     /// and can be used by other areas.
     ready_events: VecDeque<Event>,
+    /// Most recent log entries, empty if the engine logger is not installed.
+    log_buffer: LogBuffer,
+    /// Frame and fixed timestep clock.
+    time: Time,
+    /// Configuration used to create this instance.
+    config: EngineConfig,
+    /// Entities and their components.
+    world: World,
+    /// Loaded assets.
+    assets: AssetServer,
+    /// Audio mixer and output.
+    audio: Audio,
+    /// Voices of the positional emitters.
+    spatial_audio: SpatialAudio,
+    /// Rigid bodies stepped on every fixed tick.
+    physics: PhysicsWorld,
+    /// Bounds of the entities with `SpatialBounds`.
+    spatial_index: Bvh,
+    /// Tweens and keyframes driven by the clock.
+    animator: Animator,
+    /// Values edited from the debug overlay.
+    tweakables: Tweakables,
+    /// Developer console and its commands.
+    console: Console,
+    /// Draws the console text, without fonts only the panel is drawn.
+    console_text: TextRenderer,
+    /// Scripts being run by `::exec_script()`, stops the ones running
+    /// themselves.
+    exec_depth: usize,
+    /// Gameplay scripts.
+    scripts: ScriptHost,
+    /// Connections to other hosts, once started.
+    network: Option<NetworkHost>,
+    #[cfg(feature = "debug-overlay")]
+    debug_overlay: DebugOverlay,
 }
 
 impl Default for Engine {
     fn default() -> Self {
+        Self::new(EngineConfig::default())
+    }
+}
+
+impl Engine {
+    /// Creates a new `Engine` with the passed configuration.
+    pub fn new(config: EngineConfig) -> Self {
         // The logger is started here to make sure we have logging always
         // available
-
-        // We do not want a env_logger during tests
-        #[cfg(not(test))]
-        {
-            // Read the env values that configure the logger
-            let env = env_logger::Env::default()
-                .filter_or("MY_LOG_LEVEL", "info")
-                .write_style_or("MY_LOG_STYLE", "always");
-
-            // Initialize the logger from env
-            env_logger::init_from_env(env);
-        }
+        let log_buffer = if config.logging.enabled {
+            match logging::init(&config.logging) {
+                Ok(log_buffer) => log_buffer,
+                Err(err) => {
+                    log::warn!("Failed to initialize engine logger: {err}");
+                    LogBuffer::new(0)
+                }
+            }
+        } else {
+            LogBuffer::new(0)
+        };
 
         let (event_dispatcher, event_consumer) = event::create_handler();
 
@@ -66,20 +139,73 @@ impl Default for Engine {
             event_dispatcher,
         };
 
-        let worker_pool = WorkerPool::default();
+        let worker_pool = match config.workers {
+            Some(workers) => WorkerPool::new(workers),
+            None => WorkerPool::default(),
+        };
 
         let ready_events = VecDeque::default();
 
+        let time = Time::new(config.tick_rate);
+
+        let assets = Self::create_asset_server(&config);
+
+        let mut console = Console::default();
+        debug::register_builtins(&mut console);
+
+        let scripts = ScriptHost::new(config.script_limits);
+
         Self {
             data,
             event_consumer,
             worker_pool,
             ready_events,
+            log_buffer,
+            time,
+            config,
+            world: World::default(),
+            assets,
+            audio: Audio::default(),
+            spatial_audio: SpatialAudio::default(),
+            physics: PhysicsWorld::default(),
+            spatial_index: Bvh::default(),
+            animator: Animator::default(),
+            tweakables: Tweakables::default(),
+            console,
+            console_text: TextRenderer::new(Fonts::new()),
+            exec_depth: 0,
+            scripts,
+            network: None,
+            #[cfg(feature = "debug-overlay")]
+            debug_overlay: DebugOverlay::default(),
         }
     }
-}
 
-impl Engine {
+    /// Internal function that reads the assets from the archive at the asset
+    /// root, or from the directory if it is not a file. Only directories are
+    /// watched for hot reloading.
+    fn create_asset_server(config: &EngineConfig) -> AssetServer {
+        if config.asset_root.is_file() {
+            match ArchiveSource::open(&config.asset_root) {
+                Ok(archive) => return AssetServer::new(archive),
+                Err(err) => log::error!(
+                    "Failed to open asset archive '{}': {err}",
+                    config.asset_root.display()
+                ),
+            }
+        }
+
+        #[allow(unused_mut)]
+        let mut assets = AssetServer::new(FileSource::new(&config.asset_root));
+        #[cfg(feature = "hot-reload")]
+        match crate::asset::AssetWatcher::new(&config.asset_root) {
+            Ok(watcher) => assets.watch(watcher),
+            Err(err) => log::warn!("Asset hot reloading disabled: {err}"),
+        }
+
+        assets
+    }
+
     /// Start.
     pub fn run(&mut self) -> Result<(), Error> {
         match self.data.state {
@@ -118,6 +244,7 @@ impl Engine {
 
     /// Runs one iteration.
     pub fn step(&mut self) {
+        crate::profile_scope!("Engine::step");
         self.update();
     }
 
@@ -126,6 +253,12 @@ impl Engine {
         // TODO: there should be something here to start the engine
         self.data.state = EngineState::Running;
         log::info!("Successfully started engine");
+
+        if let Some(path) = self.config.startup_script.clone() {
+            if let Err(err) = self.exec_script(&path) {
+                log::error!("Failed to run '{}': {err}", path.display());
+            }
+        }
     }
 
     /// Internal function that handles the engine's stop.
@@ -137,7 +270,79 @@ impl Engine {
 
     /// Internal function that updates the engine.
     fn update(&mut self) {
+        self.time.update();
+        #[cfg(feature = "debug-overlay")]
+        self.debug_overlay.record_frame(self.time.real_delta());
+        self.update_assets();
+        if let Some(network) = &mut self.network {
+            network.update(Instant::now(), &self.data.event_dispatcher);
+        }
         self.handle_all_events();
+        self.update_console();
+        self.scripts.update(
+            &mut self.world,
+            &self.assets,
+            &self.data.event_dispatcher,
+            self.time.delta_secs(),
+        );
+
+        while self.time.expend_fixed_tick() {
+            self.fixed_update();
+        }
+
+        self.physics.sync_transforms(&mut self.world);
+        self.animator.update(
+            self.time.delta_secs(),
+            &mut self.world,
+            &self.data.event_dispatcher,
+        );
+
+        // Everything that moved entities already ran
+        scene::propagate_transforms(&mut self.world);
+        spatial::sync_bounds(&mut self.spatial_index, &self.world);
+        self.spatial_audio.update(
+            &mut self.world,
+            &mut self.audio.mixer(),
+            self.time.delta_secs(),
+        );
+    }
+
+    /// Internal function that starts the queued asset loads and collects the
+    /// finished ones.
+    fn update_assets(&mut self) {
+        for job in self.assets.take_jobs() {
+            // Without workers the loads would never finish
+            if self.worker_pool.is_empty() {
+                job();
+            } else {
+                self.instruct(WorkerInstruction::Execute(job));
+            }
+        }
+        self.assets.update(&self.data.event_dispatcher);
+    }
+
+    /// Internal function that mirrors the log and runs the lines typed into
+    /// the console.
+    fn update_console(&mut self) {
+        self.console.mirror_log(&self.log_buffer);
+        while let Some(line) = self.console.take_pending() {
+            match self.execute(&line) {
+                Ok(output) => self.console.print(LineKind::Output, &output),
+                Err(err) => {
+                    self.console.print(LineKind::Error, &err.to_string())
+                }
+            }
+        }
+    }
+
+    /// Internal function that runs a single fixed tick.
+    fn fixed_update(&mut self) {
+        crate::profile_scope!("Engine::fixed_update");
+        self.physics.step(
+            self.time.fixed_delta().as_secs_f32(),
+            &self.data.event_dispatcher,
+        );
+        // TODO: there should be something here to run fixed systems
     }
 
     /// Gets the current state.
@@ -145,11 +350,364 @@ impl Engine {
         self.data.state
     }
 
+    /// Returns the configuration used to create this instance.
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// Returns the frame and fixed timestep clock.
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    /// Returns the frame and fixed timestep clock, to pause the game.
+    pub fn time_mut(&mut self) -> &mut Time {
+        &mut self.time
+    }
+
+    /// Returns the world containing every entity.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Returns the world containing every entity.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Returns the server owning every asset.
+    pub fn assets(&self) -> &AssetServer {
+        &self.assets
+    }
+
+    /// Returns the server owning every asset.
+    pub fn assets_mut(&mut self) -> &mut AssetServer {
+        &mut self.assets
+    }
+
+    /// Returns the audio mixer and output.
+    pub fn audio(&self) -> &Audio {
+        &self.audio
+    }
+
+    /// Returns the audio mixer and output.
+    pub fn audio_mut(&mut self) -> &mut Audio {
+        &mut self.audio
+    }
+
+    /// Returns the system playing the positional emitters.
+    pub fn spatial_audio(&self) -> &SpatialAudio {
+        &self.spatial_audio
+    }
+
+    /// Returns the system playing the positional emitters.
+    pub fn spatial_audio_mut(&mut self) -> &mut SpatialAudio {
+        &mut self.spatial_audio
+    }
+
+    /// Returns the rigid bodies simulated on every fixed tick.
+    pub fn physics(&self) -> &PhysicsWorld {
+        &self.physics
+    }
+
+    /// Returns the rigid bodies simulated on every fixed tick.
+    pub fn physics_mut(&mut self) -> &mut PhysicsWorld {
+        &mut self.physics
+    }
+
+    /// Returns the player of every animation.
+    pub fn animator(&self) -> &Animator {
+        &self.animator
+    }
+
+    /// Returns the player of every animation.
+    pub fn animator_mut(&mut self) -> &mut Animator {
+        &mut self.animator
+    }
+
+    /// Returns the index of the entities with `SpatialBounds`, as of the
+    /// last step.
+    pub fn spatial_index(&self) -> &Bvh {
+        &self.spatial_index
+    }
+
+    /// Returns the values edited from the debug overlay.
+    pub fn tweakables(&self) -> &Tweakables {
+        &self.tweakables
+    }
+
+    /// Returns the values edited from the debug overlay, to register them.
+    pub fn tweakables_mut(&mut self) -> &mut Tweakables {
+        &mut self.tweakables
+    }
+
+    /// Returns the debug overlay.
+    #[cfg(feature = "debug-overlay")]
+    pub fn debug_overlay(&self) -> &DebugOverlay {
+        &self.debug_overlay
+    }
+
+    /// Returns the debug overlay, to change its visibility or scale.
+    #[cfg(feature = "debug-overlay")]
+    pub fn debug_overlay_mut(&mut self) -> &mut DebugOverlay {
+        &mut self.debug_overlay
+    }
+
+    /// Draws the debug overlay into the active frame.
+    ///
+    /// _The `Application` does this after the render callback._
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug_overlay(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        surface: SurfaceId,
+    ) -> Result<(), render::Error> {
+        let status = DebugStatus {
+            workers: self.worker_pool.status(),
+            entities: self.world.len(),
+            fixed_ticks: self.time.ticks(),
+            paused: self.time.is_paused(),
+        };
+        self.debug_overlay.render(
+            renderer,
+            surface,
+            &status,
+            &mut self.tweakables,
+        )
+    }
+
+    /// Starts updating the network host on every step, its socket is run
+    /// by a `WorkerKind::Networking` worker.
+    ///
+    /// _The worker is taken from the generic ones, so the pool should have
+    /// at least two._
+    pub fn start_network(
+        &mut self,
+        mut network: NetworkHost,
+    ) -> Result<(), net::Error> {
+        if let Some(running) = &self.network {
+            return Err(net::Error::AlreadyRunning(running.local_addr()));
+        }
+
+        if let Some(job) = network.take_job() {
+            // Without workers the socket would never be read
+            if self.worker_pool.is_empty() {
+                std::thread::spawn(job);
+            } else {
+                self.instruct(WorkerInstruction::Specialize(
+                    WorkerKind::Networking,
+                    job,
+                ));
+            }
+        }
+        log::info!("Started network at '{}'", network.local_addr());
+        self.network = Some(network);
+        Ok(())
+    }
+
+    /// Stops the network host, the other sides are told their connections
+    /// were closed.
+    pub fn stop_network(&mut self) {
+        self.network = None;
+    }
+
+    /// Returns the network host, if started.
+    pub fn network(&self) -> Option<&NetworkHost> {
+        self.network.as_ref()
+    }
+
+    /// Returns the network host, to connect and send messages.
+    pub fn network_mut(&mut self) -> Option<&mut NetworkHost> {
+        self.network.as_mut()
+    }
+
+    /// Returns the developer console.
+    pub fn console(&self) -> &Console {
+        &self.console
+    }
+
+    /// Returns the developer console, to register commands and cvars.
+    pub fn console_mut(&mut self) -> &mut Console {
+        &mut self.console
+    }
+
+    /// Returns the text renderer of the console, to add its fonts.
+    pub fn console_text_mut(&mut self) -> &mut TextRenderer {
+        &mut self.console_text
+    }
+
+    /// Draws the console into the active frame when it is open.
+    ///
+    /// _The `Application` does this after the render callback._
+    pub fn render_console(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        surface: SurfaceId,
+    ) -> Result<(), ui::Error> {
+        let (width, height) = renderer.surface_size(surface)?;
+        self.console.draw(
+            renderer,
+            Vec2::new(width as f32, height as f32),
+            &mut self.console_text,
+        )
+    }
+
+    /// Returns the gameplay scripts.
+    pub fn scripts(&self) -> &ScriptHost {
+        &self.scripts
+    }
+
+    /// Returns the gameplay scripts, to add scripts and bind components and
+    /// resources.
+    pub fn scripts_mut(&mut self) -> &mut ScriptHost {
+        &mut self.scripts
+    }
+
+    /// Runs a line of console commands separated by `;`, returns what they
+    /// printed.
+    ///
+    /// _Stops at the first failing command._
+    pub fn execute(&mut self, line: &str) -> Result<String, debug::Error> {
+        let mut outputs = Vec::new();
+        for tokens in tokenize(line)? {
+            let Some((name, args)) = tokens.split_first() else {
+                continue;
+            };
+            let output = if let Some(command) = self.console.command(name) {
+                let args = command.parse(args)?;
+                let handler = command.handler();
+                handler(self, &args).map_err(debug::Error::Failed)?
+            } else {
+                match args {
+                    [] => {
+                        let value = self.cvar(name).ok_or_else(|| {
+                            debug::Error::UnknownCommand(name.clone())
+                        })?;
+                        format!("{name} = {value}")
+                    }
+                    [value] => {
+                        self.set_cvar(name, value)?;
+                        String::new()
+                    }
+                    _ => {
+                        return Err(debug::Error::Usage(format!(
+                            "{name} [value]"
+                        )))
+                    }
+                }
+            };
+            if !output.is_empty() {
+                outputs.push(output);
+            }
+        }
+        Ok(outputs.join("\n"))
+    }
+
+    /// Runs every line of a script file in the console, skipping empty
+    /// lines and the ones starting with `#` or `//`.
+    ///
+    /// _Failing lines are reported and skipped, only failing to read the
+    /// file is an error._
+    pub fn exec_script(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), debug::Error> {
+        if self.exec_depth >= MAX_EXEC_DEPTH {
+            return Err(debug::Error::ExecTooDeep(MAX_EXEC_DEPTH));
+        }
+        let path = path.as_ref();
+        let script = std::fs::read_to_string(path)
+            .map_err(|err| debug::Error::Io(err.to_string()))?;
+
+        self.exec_depth += 1;
+        for (number, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("//")
+            {
+                continue;
+            }
+            match self.execute(line) {
+                Ok(output) => self.console.print(LineKind::Output, &output),
+                Err(err) => {
+                    log::warn!("{}:{}: {err}", path.display(), number + 1);
+                    self.console.print(LineKind::Error, &err.to_string());
+                }
+            }
+        }
+        self.exec_depth -= 1;
+        Ok(())
+    }
+
+    /// Returns the value of a cvar.
+    pub fn cvar(&self, name: &str) -> Option<Value> {
+        match &self.console.cvar(name)?.storage {
+            Storage::Value(value) => Some(value.clone()),
+            Storage::Bound { get, .. } => Some(get(self)),
+        }
+    }
+
+    /// Parses the value with the type of the cvar and changes it.
+    pub fn set_cvar(
+        &mut self,
+        name: &str,
+        value: &str,
+    ) -> Result<(), debug::Error> {
+        let cvar = self
+            .console
+            .cvar(name)
+            .ok_or_else(|| debug::Error::UnknownCommand(name.to_string()))?;
+        let kind = cvar.kind();
+        let value = kind.parse(value).ok_or_else(|| {
+            debug::Error::InvalidArgument(
+                name.to_string(),
+                kind,
+                value.to_string(),
+            )
+        })?;
+
+        match &cvar.storage {
+            Storage::Bound { set, .. } => {
+                let set = set.clone();
+                set(self, value).map_err(debug::Error::Failed)
+            }
+            Storage::Value(_) => {
+                if let Some(cvar) = self.console.cvar_mut(name) {
+                    cvar.storage = Storage::Value(value);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the buffer containing the most recent log entries.
+    ///
+    /// _It stays empty when the engine did not install its own logger._
+    pub fn log_buffer(&self) -> &LogBuffer {
+        &self.log_buffer
+    }
+
     /// Dispatches the passed event.
     pub fn dispatch(&self, event: Event) {
         self.data.event_dispatcher.send(event);
     }
 
+    /// Requests a new window, returns the id it will have once opened.
+    ///
+    /// _The result is reported with either `WindowEvent::Opened` or
+    /// `WindowEvent::OpenFailed`._
+    pub fn open_window(&self, config: WindowConfig) -> WindowId {
+        let id = WindowId::new();
+        self.dispatch(Event::Window(id, event::WindowEvent::Open(config)));
+        id
+    }
+
+    /// Requests the window with the passed `id` to close.
+    pub fn close_window(&self, id: WindowId) {
+        self.dispatch(Event::Window(id, event::WindowEvent::Close));
+    }
+
     /// Sends an instruction to an available worker.
     pub fn instruct(&mut self, instruction: WorkerInstruction) {
         match self.worker_pool.send(instruction) {
@@ -162,6 +720,7 @@ impl Engine {
 
     /// Handle all pending events.
     fn handle_all_events(&mut self) {
+        crate::profile_scope!("Engine::handle_all_events");
         let mut pending = true;
         while pending {
             pending = self.handle_event();
@@ -171,6 +730,7 @@ impl Engine {
     /// Handle a single event.
     pub fn handle_event(&mut self) -> bool {
         if let Some(event) = self.event_consumer.poll() {
+            crate::profile_scope!("Engine::handle_event");
             match &event {
                 event::Event::Engine(engine_event) => match engine_event {
                     event::engine_event::EngineEvent::Started => {
@@ -186,7 +746,45 @@ impl Engine {
                 },
 
                 // Ignore mouse events just so we don't spam the log that much
-                event::Event::Mouse(_) => {}
+                event::Event::Mouse(..) => {}
+
+                // Window commands are applied by the `Application`
+                event::Event::Window(..) => {}
+
+                event::Event::Asset(asset_event) => match asset_event {
+                    event::AssetEvent::LoadFailed(_, path, err) => {
+                        log::error!(
+                            "Failed to load asset '{}': {err}",
+                            path.display()
+                        );
+                    }
+                    _ => log::debug!("{asset_event:?}"),
+                },
+
+                // Contacts change too often to be logged
+                event::Event::Physics(..) => {}
+
+                event::Event::Animation(animation_event) => {
+                    log::debug!("{animation_event:?}");
+                }
+
+                event::Event::Script(script_event) => match script_event {
+                    event::ScriptEvent::Failed(id, err) => {
+                        log::error!("Script '{id}' failed: {err}");
+                    }
+                    _ => log::debug!("{script_event:?}"),
+                },
+
+                event::Event::Network(network_event) => match network_event {
+                    event::NetworkEvent::Connected(id) => {
+                        log::info!("Connection '{id}' established");
+                    }
+                    event::NetworkEvent::Disconnected(id, reason) => {
+                        log::info!("Connection '{id}' ended: {reason}");
+                    }
+                    // Messages are for the game to handle
+                    event::NetworkEvent::Received(..) => {}
+                },
 
                 #[cfg(test)]
                 event::Event::Dummy => {}
@@ -198,6 +796,14 @@ impl Engine {
                 }
             }
 
+            // Typing into the console must not reach the game
+            if self.console.handle_event(&event) {
+                return true;
+            }
+            self.scripts.queue_event(&event);
+            #[cfg(feature = "debug-overlay")]
+            self.debug_overlay.handle_event(&event);
+
             // Forward event to other areas
             self.ready_events.push_back(event);
 
@@ -235,4 +841,447 @@ mod tests {
             .expect("engine should be able to shutdown");
         assert_eq!(engine.state(), EngineState::Stopped);
     }
+
+    #[test]
+    fn step_propagates_transforms() {
+        use crate::{
+            math::{Transform, Vec3},
+            scene::GlobalTransform,
+        };
+
+        let mut engine = Engine::default();
+        let world = engine.world_mut();
+        let parent = world
+            .spawn_with(Transform::from_translation(Vec3::new(1.0, 2.0, 0.0)));
+        let child = world.spawn_with(Transform::from_translation(Vec3::X));
+        world.set_parent(child, Some(parent)).unwrap();
+
+        engine.step();
+
+        assert_eq!(
+            engine.world().get::<GlobalTransform>(child),
+            Some(&GlobalTransform(Transform::from_translation(Vec3::new(
+                2.0, 2.0, 0.0
+            ))))
+        );
+    }
+
+    #[test]
+    fn animations_pause_with_the_clock() {
+        use std::time::Duration;
+
+        use crate::animation::{Sampler, Tween};
+
+        let mut engine = Engine::default();
+        let id = engine
+            .animator_mut()
+            .play(Tween::new(0.0, 1.0, 0.01).target(
+                |world: &mut World, value: f32| {
+                    world.insert_resource(value);
+                },
+            ));
+
+        engine.time_mut().pause();
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(5));
+            engine.step();
+        }
+        assert_eq!(engine.animator().time(id), Some(0.0));
+
+        engine.time_mut().resume();
+        while engine.animator().is_playing(id) {
+            std::thread::sleep(Duration::from_millis(5));
+            engine.step();
+            assert!(engine.time().elapsed() < Duration::from_secs(5));
+        }
+        assert_eq!(engine.world().resource::<f32>(), Some(&1.0));
+        // Events sent during a step are handled on the next one
+        engine.step();
+        assert!(std::iter::from_fn(|| engine.require_event()).any(|event| {
+            matches!(
+                event,
+                Event::Animation(event::AnimationEvent::Finished(finished))
+                    if finished == id
+            )
+        }));
+    }
+
+    #[test]
+    fn fixed_ticks_move_bodies() {
+        use std::time::Duration;
+
+        use crate::{
+            math::{Transform, Vec2},
+            physics::{Collider, RigidBody},
+        };
+
+        let mut engine = Engine::default();
+        let body = engine.physics_mut().add(
+            RigidBody::dynamic(Collider::circle(1.0))
+                .with_position(Vec2::new(0.0, 10.0)),
+        );
+        let world = engine.world_mut();
+        let entity = world.spawn_with(Transform::IDENTITY);
+        world.insert(entity, body).unwrap();
+
+        while engine.time().ticks() < 2 {
+            std::thread::sleep(engine.time().fixed_delta());
+            engine.step();
+            assert!(engine.time().elapsed() < Duration::from_secs(5));
+        }
+
+        let transform = engine.world().get::<Transform>(entity).unwrap();
+        assert!(transform.translation.y < 10.0);
+        assert_eq!(
+            transform.translation.y,
+            engine.physics().get(body).unwrap().position.y
+        );
+    }
+
+    #[test]
+    fn step_loads_assets() {
+        use std::time::{Duration, Instant};
+
+        use crate::asset::LoadState;
+
+        let root = std::env::temp_dir()
+            .join(format!("unen-engine-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("greeting.txt"), "hello").unwrap();
+
+        let mut engine = Engine::new(
+            EngineConfig::builder().asset_root(&root).build().unwrap(),
+        );
+        let handle = engine.assets_mut().load::<String, _>("greeting.txt");
+        let missing = engine.assets_mut().load::<String, _>("missing.txt");
+
+        let start = Instant::now();
+        while engine.assets().loading() > 0 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            engine.step();
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+        engine.step();
+
+        assert_eq!(engine.assets().get(&handle).unwrap(), "hello");
+        assert!(matches!(
+            engine.assets().load_state(missing.id()),
+            LoadState::Failed(_)
+        ));
+        let events: Vec<_> =
+            std::iter::from_fn(|| engine.require_event()).collect();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::Asset(event::AssetEvent::Loaded(id, _)) if *id == handle.id()
+        )));
+    }
+
+    #[test]
+    fn assets_are_read_from_archives() {
+        use std::time::{Duration, Instant};
+
+        use crate::asset::{archive::Compression, ArchiveWriter};
+
+        let path = std::env::temp_dir()
+            .join(format!("unen-engine-archive-{}.unpk", std::process::id()));
+        let mut writer = ArchiveWriter::new();
+        writer
+            .add("greeting.txt", b"packed", Compression::Lz4)
+            .unwrap();
+        writer.write(std::fs::File::create(&path).unwrap()).unwrap();
+
+        let mut engine = Engine::new(
+            EngineConfig::builder().asset_root(&path).build().unwrap(),
+        );
+        let handle = engine.assets_mut().load::<String, _>("greeting.txt");
+
+        let start = Instant::now();
+        while engine.assets().loading() > 0 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            engine.step();
+        }
+        assert_eq!(engine.assets().get(&handle).unwrap(), "packed");
+
+        drop(engine);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn scripts_run_again_when_reloaded() {
+        use std::time::{Duration, Instant};
+
+        use crate::script::Script;
+
+        let root = std::env::temp_dir()
+            .join(format!("unen-engine-scripts-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("main.rhai"), r#"emit("version", 1);"#)
+            .unwrap();
+
+        let mut engine = Engine::new(
+            EngineConfig::builder().asset_root(&root).build().unwrap(),
+        );
+        let script = engine.assets_mut().load::<Script, _>("main.rhai");
+        engine.scripts_mut().add(script);
+
+        let wait_for_version = |engine: &mut Engine, version: u32| {
+            let start = Instant::now();
+            loop {
+                assert!(start.elapsed() < Duration::from_secs(5));
+                engine.step();
+                while let Some(event) = engine.require_event() {
+                    if let Event::Script(event::ScriptEvent::Emitted(
+                        name,
+                        data,
+                    )) = event
+                    {
+                        assert_eq!(name, "version");
+                        if data == version {
+                            return;
+                        }
+                    }
+                }
+            }
+        };
+        wait_for_version(&mut engine, 1);
+
+        std::fs::write(root.join("main.rhai"), r#"emit("version", 2);"#)
+            .unwrap();
+        assert_eq!(engine.assets_mut().reload("main.rhai"), 1);
+        wait_for_version(&mut engine, 2);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn engines_exchange_network_messages() {
+        use std::time::Duration;
+
+        use crate::net::{Channel, NetConfig};
+
+        let config = || EngineConfig::builder().workers(2).build().unwrap();
+        let bind =
+            || NetworkHost::bind("127.0.0.1:0", NetConfig::default()).unwrap();
+        let mut server = Engine::new(config());
+        server.start_network(bind()).unwrap();
+        assert!(matches!(
+            server.start_network(bind()),
+            Err(net::Error::AlreadyRunning(_))
+        ));
+        let mut client = Engine::new(config());
+        client.start_network(bind()).unwrap();
+
+        let address = server.network().unwrap().local_addr();
+        let network = client.network_mut().unwrap();
+        let connection = network.connect(address);
+        network
+            .send(connection, Channel::Reliable, b"hello")
+            .unwrap();
+
+        let start = Instant::now();
+        let received = 'outer: loop {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            client.step();
+            server.step();
+            while let Some(event) = server.require_event() {
+                if let Event::Network(event::NetworkEvent::Received(
+                    _,
+                    channel,
+                    bytes,
+                )) = event
+                {
+                    break 'outer (channel, bytes);
+                }
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+
+        assert_eq!(received, (Channel::Reliable, b"hello".to_vec()));
+        assert!(client.network().unwrap().is_connected(connection));
+    }
+
+    #[cfg(feature = "hot-reload")]
+    #[test]
+    fn step_reloads_modified_assets() {
+        use std::time::{Duration, Instant};
+
+        let root = std::env::temp_dir()
+            .join(format!("unen-engine-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("config.txt"), "old").unwrap();
+
+        let mut engine = Engine::new(
+            EngineConfig::builder().asset_root(&root).build().unwrap(),
+        );
+        let handle = engine.assets_mut().load::<String, _>("config.txt");
+        let start = Instant::now();
+        while !engine.assets().is_loaded(handle.id()) {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            engine.step();
+        }
+
+        std::fs::write(root.join("config.txt"), "new").unwrap();
+        let mut reloaded = false;
+        while !reloaded {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(10));
+            engine.step();
+            reloaded =
+                std::iter::from_fn(|| engine.require_event()).any(|event| {
+                    matches!(
+                        event,
+                        Event::Asset(event::AssetEvent::Reloaded(..))
+                    )
+                });
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(engine.assets().get(&handle).unwrap(), "new");
+    }
+
+    #[test]
+    fn window_requests_are_forwarded() {
+        let mut engine = Engine::default();
+        let id = engine.open_window(WindowConfig::new("Inspector"));
+        engine.close_window(id);
+        engine.step();
+
+        assert!(matches!(
+            engine.require_event(),
+            Some(Event::Window(opened, event::WindowEvent::Open(config)))
+                if opened == id && config.title == "Inspector"
+        ));
+        assert!(matches!(
+            engine.require_event(),
+            Some(Event::Window(closed, event::WindowEvent::Close))
+                if closed == id
+        ));
+    }
+
+    #[test]
+    fn console_runs_commands_and_cvars() {
+        use crate::debug::{Command, Cvar, LineKind, ValueKind};
+
+        let mut engine = Engine::default();
+        engine.run().unwrap();
+        engine
+            .console_mut()
+            .register_cvar(Cvar::new("god", "", false));
+        engine.console_mut().register(Command::new(
+            "count",
+            "Counts the entities",
+            |engine, _| Ok(engine.world().len().to_string()),
+        ));
+
+        assert_eq!(
+            engine.execute("spawn 1 2; spawn; count").unwrap(),
+            "Spawned Entity(0v0)\nSpawned Entity(1v0)\n2"
+        );
+        engine
+            .execute("timescale 0.5; time.paused on; god yes")
+            .unwrap();
+        assert_eq!(engine.time().scale(), 0.5);
+        assert!(engine.time().is_paused());
+        assert_eq!(engine.cvar("god"), Some(Value::Bool(true)));
+        assert_eq!(engine.execute("time.scale").unwrap(), "time.scale = 0.5");
+        assert_eq!(
+            engine.execute("god maybe"),
+            Err(debug::Error::InvalidArgument(
+                "god".to_string(),
+                ValueKind::Bool,
+                "maybe".to_string()
+            ))
+        );
+        assert_eq!(
+            engine.execute("fly"),
+            Err(debug::Error::UnknownCommand("fly".to_string()))
+        );
+
+        // Typed lines run on the next step
+        engine.console_mut().submit("echo hi");
+        engine.console_mut().submit("fly");
+        engine.step();
+        let output = engine.console().output().cloned().collect::<Vec<_>>();
+        assert_eq!(output[output.len() - 2].text, "hi");
+        assert_eq!(output[output.len() - 1].kind, LineKind::Error);
+
+        engine.execute("quit").unwrap();
+        assert_eq!(engine.state(), EngineState::Stopped);
+    }
+
+    #[test]
+    fn console_keeps_its_input() {
+        use winit::keyboard::{KeyCode, PhysicalKey};
+
+        let mut engine = Engine::default();
+        let text = || {
+            Event::Keyboard(
+                WindowId::PRIMARY,
+                event::KeyboardEvent::Text("w".to_string()),
+            )
+        };
+        engine.dispatch(Event::Keyboard(
+            WindowId::PRIMARY,
+            event::KeyboardEvent::Pressed(PhysicalKey::Code(
+                KeyCode::Backquote,
+            )),
+        ));
+        engine.dispatch(text());
+        engine.dispatch(text());
+        engine.step();
+        assert!(engine.console().is_open());
+        assert_eq!(engine.console().input(), "w");
+        assert!(engine.require_event().is_none());
+
+        engine.console_mut().set_open(false);
+        engine.dispatch(text());
+        engine.step();
+        assert!(matches!(
+            engine.require_event(),
+            Some(Event::Keyboard(_, event::KeyboardEvent::Text(_)))
+        ));
+    }
+
+    #[test]
+    fn exec_stops_recursive_scripts() {
+        let path = std::env::temp_dir()
+            .join(format!("unen-recursive-{}.cfg", std::process::id()));
+        std::fs::write(&path, format!("spawn\nexec {}", path.display()))
+            .unwrap();
+
+        let mut engine = Engine::default();
+        let result = engine.exec_script(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!(engine.world().len(), MAX_EXEC_DEPTH);
+        assert_eq!(
+            engine.console().output().last().unwrap().text,
+            debug::Error::ExecTooDeep(MAX_EXEC_DEPTH).to_string()
+        );
+    }
+
+    #[test]
+    fn startup_script_runs_on_start() {
+        let path = std::env::temp_dir()
+            .join(format!("unen-startup-{}.cfg", std::process::id()));
+        std::fs::write(&path, "# Slow motion\ntimescale 0.25\n\nfly\nspawn")
+            .unwrap();
+        let config = EngineConfig::builder()
+            .startup_script(&path)
+            .build()
+            .unwrap();
+
+        let mut engine = Engine::new(config);
+        engine.run().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Failing lines do not stop the script
+        assert_eq!(engine.time().scale(), 0.25);
+        assert_eq!(engine.world().len(), 1);
+        assert!(engine
+            .console()
+            .output()
+            .any(|line| line.kind == crate::debug::LineKind::Error));
+    }
 }
@@ -0,0 +1,10 @@
+use strum::Display;
+
+use crate::animation::AnimationId;
+
+/// Events produced by the `Animator`.
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// The animation reached its end and stopped playing.
+    Finished(AnimationId),
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+use strum::Display;
+
+use crate::asset::AssetId;
+
+/// Events produced by the `AssetServer`.
+#[derive(Debug, Display)]
+pub enum AssetEvent {
+    /// The asset at the attached path finished loading.
+    Loaded(AssetId, PathBuf),
+    /// The asset at the attached path could not be loaded, contains the
+    /// reason. A failed reload keeps the previous version.
+    LoadFailed(AssetId, PathBuf, String),
+    /// The asset at the attached path changed and the new version replaced
+    /// the old one behind every handle.
+    Reloaded(AssetId, PathBuf),
+    /// Every handle of the asset was dropped and it got freed.
+    Unloaded(AssetId),
+}
@@ -7,4 +7,6 @@ pub enum KeyboardEvent {
     Pressed(PhysicalKey),
     /// The attached `PhysicalKey` was released.
     Released(PhysicalKey),
+    /// Text typed with the pressed keys, sent after `Pressed`.
+    Text(String),
 }
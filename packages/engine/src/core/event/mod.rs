@@ -2,16 +2,28 @@ use std::sync::mpsc::{channel, Receiver, Sender};
 
 use strum::Display;
 
+use super::window::WindowId;
+
+pub mod animation_event;
+pub mod asset_event;
 pub mod engine_event;
 pub mod event_handler;
 pub mod keyboard_event;
 pub mod mouse_event;
+pub mod network_event;
+pub mod physics_event;
+pub mod script_event;
 pub mod window_event;
 
+pub use animation_event::AnimationEvent;
+pub use asset_event::AssetEvent;
 pub use engine_event::EngineEvent;
 pub use event_handler::EventHandler;
 pub use keyboard_event::KeyboardEvent;
 pub use mouse_event::MouseEvent;
+pub use network_event::NetworkEvent;
+pub use physics_event::PhysicsEvent;
+pub use script_event::ScriptEvent;
 pub use window_event::WindowEvent;
 
 /// Main enum that defines all our events.
@@ -19,20 +31,30 @@ pub use window_event::WindowEvent;
 /// There **is** a naming convention for any `Event`:
 ///
 /// - past-sentence names are refered to events that already occurred.
-/// Other events are yet to occurr and reacting to them can have some sort of
-/// influence on the final result.
+///   Other events are yet to occurr and reacting to them can have some sort of
+///   influence on the final result.
 ///
 /// **Events must contain only simple data.**
 #[derive(Debug, Display)]
 pub enum Event {
     /// Events produced by the engine.
     Engine(EngineEvent),
-    /// Events produced by a window.
-    Window(WindowEvent),
-    /// Events produced by the keyboard.
-    Keyboard(KeyboardEvent),
-    /// Events produced by the mouse.
-    Mouse(MouseEvent),
+    /// Events produced by (or sent to) the attached window.
+    Window(WindowId, WindowEvent),
+    /// Events produced by the keyboard while the attached window had focus.
+    Keyboard(WindowId, KeyboardEvent),
+    /// Events produced by the mouse over the attached window.
+    Mouse(WindowId, MouseEvent),
+    /// Events produced by the asset system.
+    Asset(AssetEvent),
+    /// Events produced by the physics simulation.
+    Physics(PhysicsEvent),
+    /// Events produced by the animation system.
+    Animation(AnimationEvent),
+    /// Events produced by scripts.
+    Script(ScriptEvent),
+    /// Events produced by the network.
+    Network(NetworkEvent),
 
     /// Only used during tests.
     #[cfg(test)]
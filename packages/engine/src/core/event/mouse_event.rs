@@ -1,6 +1,8 @@
 use strum::Display;
 use winit::event::MouseButton;
 
+use crate::math::Vec2;
+
 /// Events produced by a mouse.
 #[derive(Debug, Display)]
 pub enum MouseEvent {
@@ -8,6 +10,10 @@ pub enum MouseEvent {
     Pressed(MouseButton),
     /// The attached `MouseButton` was released.
     Released(MouseButton),
-    /// The mouse was moved
-    Moved(u32, u32),
+    /// The cursor was moved, the position is in pixels relative to the
+    /// top-left corner of the window.
+    Moved(Vec2),
+    /// The wheel was scrolled, the delta is in pixels and positive values
+    /// scroll the content right and down.
+    Scrolled(Vec2),
 }
@@ -0,0 +1,14 @@
+use strum::Display;
+
+use crate::net::{Channel, ConnectionId, DisconnectReason};
+
+/// Events produced by the `NetworkHost`.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum NetworkEvent {
+    /// The handshake completed, messages can be exchanged.
+    Connected(ConnectionId),
+    /// The connection ended, or could not be made.
+    Disconnected(ConnectionId, DisconnectReason),
+    /// A message arrived on the channel.
+    Received(ConnectionId, Channel, Vec<u8>),
+}
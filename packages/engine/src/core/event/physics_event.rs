@@ -0,0 +1,17 @@
+use strum::Display;
+
+use crate::physics::BodyId;
+
+/// Events produced by the `PhysicsWorld`.
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum PhysicsEvent {
+    /// The two bodies started touching.
+    CollisionStarted(BodyId, BodyId),
+    /// The two bodies stopped touching, also sent when one is removed.
+    CollisionEnded(BodyId, BodyId),
+    /// The second body entered the sensor in the first one.
+    SensorEntered(BodyId, BodyId),
+    /// The second body left the sensor in the first one, also sent when one
+    /// is removed.
+    SensorExited(BodyId, BodyId),
+}
@@ -0,0 +1,14 @@
+use strum::Display;
+
+use crate::script::ScriptId;
+
+/// Events produced by scripts and the `ScriptHost`.
+#[derive(Debug, Display, PartialEq)]
+pub enum ScriptEvent {
+    /// A script emitted the named event with the attached data, `null` when
+    /// it had none.
+    Emitted(String, serde_json::Value),
+    /// The script failed to compile or raised an error, contains the reason.
+    /// It keeps running the callbacks it already registered.
+    Failed(ScriptId, String),
+}
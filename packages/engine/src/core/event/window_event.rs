@@ -1,5 +1,41 @@
 use strum::Display;
+use winit::window::CursorIcon;
+
+use crate::core::config::{WindowConfig, WindowMode};
 
 /// Events produced by a window.
+///
+/// Events that are not named in the past tense are commands that will be
+/// applied to the window once handled by the `Application`.
 #[derive(Debug, Display)]
-pub enum WindowEvent {}
+pub enum WindowEvent {
+    /// The window was opened.
+    Opened,
+    /// The window could not be opened, contains the reason.
+    OpenFailed(String),
+    /// The window was closed.
+    Closed,
+    /// The window was resized to the attached physical size.
+    Resized(u32, u32),
+    /// The window gained (`true`) or lost (`false`) focus.
+    Focused(bool),
+    /// The window changed to the attached mode.
+    ModeChanged(WindowMode),
+
+    /// Opens a new window with the passed configuration.
+    Open(WindowConfig),
+    /// Closes the window. Closing the primary window shuts the engine down.
+    Close,
+    /// Changes the title.
+    SetTitle(String),
+    /// Changes the inner size in physical pixels.
+    SetSize(u32, u32),
+    /// Changes the presentation mode.
+    SetMode(WindowMode),
+    /// Switches between windowed and borderless fullscreen.
+    ToggleFullscreen,
+    /// Changes the cursor icon.
+    SetCursorIcon(CursorIcon),
+    /// Shows or hides the cursor.
+    SetCursorVisible(bool),
+}
@@ -0,0 +1,532 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
+
+use crate::core::event::Event;
+
+use super::{Error, History, State, Transition};
+
+/// Hierarchical state machine running hooks on a context of type `C`.
+pub struct StateMachine<S, C> {
+    /// Entered on start, along with its parents.
+    initial: S,
+    states: BTreeMap<S, State<S, C>>,
+    /// First child added to every state.
+    first_children: BTreeMap<S, S>,
+    /// Checked in the order they were added.
+    transitions: Vec<Transition<S, C>>,
+    /// Active states, from the root to the leaf.
+    active: Vec<S>,
+    /// Children or leaves remembered by states with history.
+    history: BTreeMap<S, S>,
+}
+
+impl<S: Copy + Ord + Debug, C> StateMachine<S, C> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            initial,
+            states: BTreeMap::new(),
+            first_children: BTreeMap::new(),
+            transitions: Vec::new(),
+            active: Vec::new(),
+            history: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a state, replacing the one with the same id.
+    pub fn with_state(mut self, state: State<S, C>) -> Self {
+        if let Some(parent) = state.parent {
+            self.first_children.entry(parent).or_insert(state.id);
+        }
+        self.states.insert(state.id, state);
+        self
+    }
+
+    pub fn with_transition(mut self, transition: Transition<S, C>) -> Self {
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Checks the states and transitions, then enters the initial state.
+    pub fn start(&mut self, context: &mut C) -> Result<(), Error<S>> {
+        if self.is_started() {
+            return Err(Error::AlreadyStarted);
+        }
+        self.validate()?;
+
+        for state in self.ancestors(self.initial) {
+            self.enter(state, context);
+        }
+        self.descend(context);
+        log::debug!("State machine started in {}", self.dump());
+        Ok(())
+    }
+
+    /// Exits every state, remembering their history for the next start.
+    pub fn stop(&mut self, context: &mut C) {
+        self.exit_to(0, context);
+    }
+
+    pub fn is_started(&self) -> bool {
+        !self.active.is_empty()
+    }
+
+    /// Active leaf state.
+    pub fn current(&self) -> Option<S> {
+        self.active.last().copied()
+    }
+
+    /// Active states, from the root to the leaf.
+    pub fn path(&self) -> &[S] {
+        &self.active
+    }
+
+    /// Whether the state or one of its children is active.
+    pub fn is_in(&self, state: S) -> bool {
+        self.active.contains(&state)
+    }
+
+    /// Active states separated by `>`, for debugging.
+    pub fn dump(&self) -> String {
+        if self.active.is_empty() {
+            return "<stopped>".to_string();
+        }
+        self.active
+            .iter()
+            .map(|state| format!("{state:?}"))
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+
+    /// Takes the first transition triggered by the event, looking from the
+    /// leaf up to the root. Returns whether one was taken.
+    pub fn handle(&mut self, event: &Event, context: &mut C) -> bool {
+        self.take_transition(context, |trigger| {
+            trigger.is_some_and(|trigger| trigger(event))
+        })
+    }
+
+    /// Runs the update hooks from the root to the leaf, then takes the first
+    /// transition without trigger allowed by its guard. Returns whether one
+    /// was taken.
+    ///
+    /// _Only one transition is taken per update._
+    pub fn update(&mut self, delta: f32, context: &mut C) -> bool {
+        for index in 0..self.active.len() {
+            let state = self.active[index];
+            if let Some(hook) = self.state_mut(state).update.as_mut() {
+                hook(context, delta);
+            }
+        }
+        self.take_transition(context, |trigger| trigger.is_none())
+    }
+
+    /// Moves to the state right away, ignoring the transitions.
+    pub fn transition_to(
+        &mut self,
+        state: S,
+        context: &mut C,
+    ) -> Result<(), Error<S>> {
+        if !self.states.contains_key(&state) {
+            return Err(Error::UnknownState(state));
+        }
+        match self.current() {
+            Some(current) => self.transition(current, state, context),
+            None => {
+                self.validate()?;
+                for state in self.ancestors(state) {
+                    self.enter(state, context);
+                }
+                self.descend(context);
+            }
+        }
+        Ok(())
+    }
+
+    fn take_transition(
+        &mut self,
+        context: &mut C,
+        matches: impl Fn(Option<&dyn Fn(&Event) -> bool>) -> bool,
+    ) -> bool {
+        let found = self.active.iter().rev().find_map(|&state| {
+            self.transitions.iter().find(|transition| {
+                transition.from == state
+                    && matches(transition.trigger.as_deref())
+                    && transition.allows(context)
+            })
+        });
+        let Some((from, to)) = found.map(|found| (found.from, found.to)) else {
+            return false;
+        };
+
+        self.transition(from, to, context);
+        true
+    }
+
+    fn transition(&mut self, from: S, to: S, context: &mut C) {
+        let previous = self.dump();
+        let source = self.ancestors(from);
+        let target = self.ancestors(to);
+        // The source is always exited, even when it contains the target
+        let shared = source
+            .iter()
+            .zip(&target)
+            .take_while(|(a, b)| a == b)
+            .count()
+            .min(source.len().min(target.len()) - 1);
+
+        self.exit_to(shared, context);
+        for &state in &target[shared..] {
+            self.enter(state, context);
+        }
+        self.descend(context);
+        log::debug!("State machine moved from {previous} to {}", self.dump());
+    }
+
+    /// Exits the active states until only `len` are left.
+    fn exit_to(&mut self, len: usize, context: &mut C) {
+        let Some(&leaf) = self.active.last() else {
+            return;
+        };
+        for index in len..self.active.len() {
+            let state = self.active[index];
+            let Some(&child) = self.active.get(index + 1) else {
+                break;
+            };
+            match self.states[&state].history {
+                History::None => {}
+                History::Shallow => {
+                    self.history.insert(state, child);
+                }
+                History::Deep => {
+                    self.history.insert(state, leaf);
+                }
+            }
+        }
+
+        while self.active.len() > len {
+            let state = self.active.pop().expect("there are active states");
+            if let Some(hook) = self.state_mut(state).exit.as_mut() {
+                hook(context);
+            }
+        }
+    }
+
+    fn enter(&mut self, state: S, context: &mut C) {
+        self.active.push(state);
+        if let Some(hook) = self.state_mut(state).enter.as_mut() {
+            hook(context);
+        }
+    }
+
+    /// Enters children of the leaf until reaching a state without them.
+    fn descend(&mut self, context: &mut C) {
+        while let Some(&state) = self.active.last() {
+            let remembered = self.history.get(&state).copied();
+            match (self.states[&state].history, remembered) {
+                (History::Deep, Some(leaf)) => {
+                    let path = self.ancestors(leaf);
+                    let start = path
+                        .iter()
+                        .position(|&other| other == state)
+                        .map_or(path.len(), |index| index + 1);
+                    for &state in &path[start..] {
+                        self.enter(state, context);
+                    }
+                    if start == path.len() {
+                        break;
+                    }
+                }
+                (History::Shallow, Some(child)) => self.enter(child, context),
+                _ => {
+                    let initial = self.states[&state]
+                        .initial
+                        .or_else(|| self.first_children.get(&state).copied());
+                    match initial {
+                        Some(child) => self.enter(child, context),
+                        None => break,
+                    }
+                }
+            }
+        }
+    }
+
+    /// The state and its parents, from the root.
+    fn ancestors(&self, state: S) -> Vec<S> {
+        let mut ancestors = vec![state];
+        while let Some(parent) = self
+            .states
+            .get(ancestors.last().unwrap())
+            .and_then(|s| s.parent)
+        {
+            // Cycles are rejected on start
+            if ancestors.len() > self.states.len() {
+                break;
+            }
+            ancestors.push(parent);
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
+    fn state_mut(&mut self, state: S) -> &mut State<S, C> {
+        self.states.get_mut(&state).expect("states are validated")
+    }
+
+    fn validate(&self) -> Result<(), Error<S>> {
+        let known = |state: S| {
+            self.states
+                .contains_key(&state)
+                .then_some(())
+                .ok_or(Error::UnknownState(state))
+        };
+
+        known(self.initial)?;
+        for state in self.states.values() {
+            if let Some(parent) = state.parent {
+                known(parent)?;
+            }
+            if let Some(initial) = state.initial {
+                known(initial)?;
+                if self.states[&initial].parent != Some(state.id) {
+                    return Err(Error::NotAChild(state.id, initial));
+                }
+            }
+
+            let mut seen = BTreeSet::from([state.id]);
+            let mut current = state.parent;
+            while let Some(parent) = current {
+                if !seen.insert(parent) {
+                    return Err(Error::Cycle(state.id));
+                }
+                current = self.states.get(&parent).and_then(|s| s.parent);
+            }
+        }
+        for transition in &self.transitions {
+            known(transition.from)?;
+            known(transition.to)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event::EngineEvent;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    enum Ui {
+        Menu,
+        Main,
+        Options,
+        Audio,
+        Video,
+        Game,
+    }
+
+    /// Records the hooks that ran.
+    type Log = Vec<String>;
+
+    fn state(id: Ui) -> State<Ui, Log> {
+        State::new(id)
+            .on_enter(move |log: &mut Log| log.push(format!("+{id:?}")))
+            .on_exit(move |log: &mut Log| log.push(format!("-{id:?}")))
+    }
+
+    fn on_shutdown(event: &Event) -> bool {
+        matches!(event, Event::Engine(EngineEvent::Shutdown))
+    }
+
+    fn on_started(event: &Event) -> bool {
+        matches!(event, Event::Engine(EngineEvent::Started))
+    }
+
+    fn menus(history: History) -> StateMachine<Ui, Log> {
+        StateMachine::new(Ui::Menu)
+            .with_state(state(Ui::Menu).with_history(history))
+            .with_state(state(Ui::Main).with_parent(Ui::Menu))
+            .with_state(state(Ui::Options).with_parent(Ui::Menu))
+            .with_state(
+                state(Ui::Audio)
+                    .with_parent(Ui::Options)
+                    .on_update(|log, _| log.push("Audio".to_string())),
+            )
+            .with_state(state(Ui::Video).with_parent(Ui::Options))
+            .with_state(state(Ui::Game))
+            .with_transition(
+                Transition::new(Ui::Menu, Ui::Game).with_trigger(on_started),
+            )
+            .with_transition(
+                Transition::new(Ui::Game, Ui::Menu).with_trigger(on_shutdown),
+            )
+    }
+
+    #[test]
+    fn transitions_exit_and_enter_the_hierarchy() {
+        let mut log = Log::new();
+        let mut machine = menus(History::None)
+            .with_transition(
+                Transition::new(Ui::Main, Ui::Video)
+                    .with_guard(|log: &Log| log.len() > 3),
+            )
+            // Children are checked before their parents
+            .with_transition(
+                Transition::new(Ui::Options, Ui::Game).with_trigger(on_started),
+            );
+
+        assert_eq!(machine.dump(), "<stopped>");
+        machine.start(&mut log).unwrap();
+        assert_eq!(machine.path(), [Ui::Menu, Ui::Main]);
+        assert_eq!(log, ["+Menu", "+Main"]);
+        assert_eq!(machine.start(&mut log), Err(Error::AlreadyStarted));
+
+        // The guard is checked on every update
+        log.push("Waiting".to_string());
+        assert!(!machine.update(0.1, &mut log));
+        log.push("Waiting".to_string());
+        assert!(machine.update(0.1, &mut log));
+        assert_eq!(machine.dump(), "Menu > Options > Video");
+        assert!(machine.is_in(Ui::Options));
+        assert_eq!(log[4..], ["-Main", "+Options", "+Video"]);
+
+        log.clear();
+        assert!(!machine.handle(&Event::Dummy, &mut log));
+        assert!(machine.handle(&Event::Engine(EngineEvent::Started), &mut log));
+        assert_eq!(log, ["-Video", "-Options", "-Menu", "+Game"]);
+
+        // Targets enter their initial children
+        machine.update(0.1, &mut log);
+        assert!(machine.handle(&Event::Engine(EngineEvent::Shutdown), &mut log));
+        assert_eq!(machine.current(), Some(Ui::Main));
+
+        log.clear();
+        machine.transition_to(Ui::Options, &mut log).unwrap();
+        machine.update(0.1, &mut log);
+        assert_eq!(log, ["-Main", "+Options", "+Audio", "Audio"]);
+
+        // Transitions to a parent exit and enter it again
+        log.clear();
+        machine.transition_to(Ui::Menu, &mut log).unwrap();
+        assert_eq!(log, ["-Audio", "-Options", "-Menu", "+Menu", "+Main"]);
+
+        machine.stop(&mut log);
+        assert!(!machine.is_started());
+    }
+
+    #[test]
+    fn history_states() {
+        let started = Event::Engine(EngineEvent::Started);
+        let shutdown = Event::Engine(EngineEvent::Shutdown);
+        let mut log = Log::new();
+
+        let mut shallow = menus(History::Shallow);
+        shallow.start(&mut log).unwrap();
+        shallow.transition_to(Ui::Video, &mut log).unwrap();
+        shallow.handle(&started, &mut log);
+        shallow.handle(&shutdown, &mut log);
+        assert_eq!(shallow.dump(), "Menu > Options > Audio");
+
+        let mut deep = menus(History::Deep);
+        deep.start(&mut log).unwrap();
+        deep.transition_to(Ui::Video, &mut log).unwrap();
+        deep.handle(&started, &mut log);
+        log.clear();
+        deep.handle(&shutdown, &mut log);
+        assert_eq!(deep.dump(), "Menu > Options > Video");
+        assert_eq!(log, ["-Game", "+Menu", "+Options", "+Video"]);
+
+        // Also remembered across restarts
+        deep.stop(&mut log);
+        deep.start(&mut log).unwrap();
+        assert_eq!(deep.current(), Some(Ui::Video));
+    }
+
+    #[test]
+    fn invalid_machines_do_not_start() {
+        let mut log = Log::new();
+        let unknown = StateMachine::new(Ui::Menu)
+            .with_state(state(Ui::Menu))
+            .with_transition(Transition::new(Ui::Menu, Ui::Game))
+            .start(&mut log);
+        assert_eq!(unknown, Err(Error::UnknownState(Ui::Game)));
+
+        let not_a_child = StateMachine::new(Ui::Menu)
+            .with_state(state(Ui::Menu).with_initial(Ui::Game))
+            .with_state(state(Ui::Game))
+            .start(&mut log);
+        assert_eq!(not_a_child, Err(Error::NotAChild(Ui::Menu, Ui::Game)));
+
+        let cycle = StateMachine::new(Ui::Menu)
+            .with_state(state(Ui::Menu).with_parent(Ui::Main))
+            .with_state(state(Ui::Main).with_parent(Ui::Menu))
+            .start(&mut log);
+        assert!(matches!(cycle, Err(Error::Cycle(_))));
+
+        // Moving to a state also starts the machine
+        let mut orphan = StateMachine::new(Ui::Menu)
+            .with_state(state(Ui::Menu))
+            .with_state(state(Ui::Game).with_parent(Ui::Main));
+        assert_eq!(
+            orphan.transition_to(Ui::Game, &mut log),
+            Err(Error::UnknownState(Ui::Main))
+        );
+        assert!(!orphan.is_started());
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn engine_lifecycle() {
+        use crate::core::engine::EngineState;
+
+        #[derive(Default)]
+        struct Lifecycle {
+            ready: bool,
+            stopped: bool,
+        }
+
+        let mut machine = StateMachine::new(EngineState::Stopped)
+            .with_state(State::new(EngineState::Stopped))
+            .with_state(State::new(EngineState::Starting))
+            .with_state(State::new(EngineState::Running))
+            .with_state(
+                State::new(EngineState::Stopping).on_enter(
+                    |lifecycle: &mut Lifecycle| lifecycle.stopped = true,
+                ),
+            )
+            .with_transition(
+                Transition::new(EngineState::Stopped, EngineState::Starting)
+                    .with_trigger(on_started),
+            )
+            .with_transition(
+                Transition::new(EngineState::Starting, EngineState::Running)
+                    .with_guard(|lifecycle: &Lifecycle| lifecycle.ready),
+            )
+            .with_transition(
+                Transition::new(EngineState::Running, EngineState::Stopping)
+                    .with_trigger(on_shutdown),
+            )
+            .with_transition(Transition::new(
+                EngineState::Stopping,
+                EngineState::Stopped,
+            ));
+
+        let mut lifecycle = Lifecycle::default();
+        machine.start(&mut lifecycle).unwrap();
+        // Shutting down is only possible while running
+        assert!(!machine
+            .handle(&Event::Engine(EngineEvent::Shutdown), &mut lifecycle));
+        machine.handle(&Event::Engine(EngineEvent::Started), &mut lifecycle);
+        machine.update(0.0, &mut lifecycle);
+        assert_eq!(machine.current(), Some(EngineState::Starting));
+
+        lifecycle.ready = true;
+        machine.update(0.0, &mut lifecycle);
+        machine.handle(&Event::Engine(EngineEvent::Shutdown), &mut lifecycle);
+        assert_eq!(machine.current(), Some(EngineState::Stopping));
+        machine.update(0.0, &mut lifecycle);
+        assert_eq!(machine.current(), Some(EngineState::Stopped));
+        assert!(lifecycle.stopped);
+    }
+}
@@ -0,0 +1,85 @@
+//! Hierarchical state machines for gameplay, UI flows or anything else
+//! moving between states.
+//!
+//! States are nested with `State::with_parent`, entering a state also enters
+//! its parents and one of its children, down to a leaf. Transitions leave
+//! from a state or any of its children, either when handling a matching
+//! `Event` or on updates, as long as their guard allows it.
+//!
+//! ```
+//! use unen_engine::core::{
+//!     event::{Event, EngineEvent},
+//!     fsm::{History, State, StateMachine, Transition},
+//! };
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+//! enum Game {
+//!     Loading,
+//!     Playing,
+//!     Exploring,
+//!     Fighting,
+//! }
+//!
+//! struct Context {
+//!     loaded: bool,
+//! }
+//!
+//! let mut machine = StateMachine::new(Game::Loading)
+//!     .with_state(State::new(Game::Loading))
+//!     .with_state(State::new(Game::Playing).with_history(History::Shallow))
+//!     .with_state(State::new(Game::Exploring).with_parent(Game::Playing))
+//!     .with_state(State::new(Game::Fighting).with_parent(Game::Playing))
+//!     .with_transition(
+//!         Transition::new(Game::Loading, Game::Playing)
+//!             .with_guard(|context: &Context| context.loaded),
+//!     )
+//!     .with_transition(
+//!         Transition::new(Game::Playing, Game::Loading).with_trigger(|event| {
+//!             matches!(event, Event::Engine(EngineEvent::Shutdown))
+//!         }),
+//!     );
+//!
+//! let mut context = Context { loaded: false };
+//! machine.start(&mut context).unwrap();
+//! machine.update(0.1, &mut context);
+//! assert_eq!(machine.current(), Some(Game::Loading));
+//!
+//! context.loaded = true;
+//! machine.update(0.1, &mut context);
+//! assert_eq!(machine.dump(), "Playing > Exploring");
+//! ```
+
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+pub mod machine;
+pub mod state;
+
+pub use machine::StateMachine;
+pub use state::{State, Transition};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error<S: Debug> {
+    #[error("State {0:?} does not exist")]
+    UnknownState(S),
+    #[error("State {1:?} is not a child of {0:?}")]
+    NotAChild(S, S),
+    #[error("State {0:?} is its own ancestor")]
+    Cycle(S),
+    #[error("The state machine already started")]
+    AlreadyStarted,
+}
+
+/// What a state remembers of its children when exited, to go back to them
+/// when entered again.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum History {
+    /// Always enters the initial child.
+    #[default]
+    None,
+    /// Enters the last active child, then its initial children.
+    Shallow,
+    /// Enters the last active leaf and everything above it.
+    Deep,
+}
@@ -0,0 +1,117 @@
+use crate::core::event::Event;
+
+use super::History;
+
+type Hook<C> = Box<dyn FnMut(&mut C)>;
+type UpdateHook<C> = Box<dyn FnMut(&mut C, f32)>;
+type Trigger = Box<dyn Fn(&Event) -> bool>;
+type Guard<C> = Box<dyn Fn(&C) -> bool>;
+
+/// State of a `StateMachine`, running its hooks on the context of the
+/// machine.
+pub struct State<S, C> {
+    pub(crate) id: S,
+    pub(crate) parent: Option<S>,
+    /// Child entered along with this state, the first one by default.
+    pub(crate) initial: Option<S>,
+    pub(crate) history: History,
+    pub(crate) enter: Option<Hook<C>>,
+    pub(crate) exit: Option<Hook<C>>,
+    pub(crate) update: Option<UpdateHook<C>>,
+}
+
+impl<S, C> State<S, C> {
+    pub fn new(id: S) -> Self {
+        Self {
+            id,
+            parent: None,
+            initial: None,
+            history: History::None,
+            enter: None,
+            exit: None,
+            update: None,
+        }
+    }
+
+    /// Nests this state inside another one, it is only active while the
+    /// parent is.
+    pub fn with_parent(mut self, parent: S) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Sets the child entered along with this state.
+    pub fn with_initial(mut self, child: S) -> Self {
+        self.initial = Some(child);
+        self
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Runs when the state becomes active, after its parent.
+    pub fn on_enter(mut self, hook: impl FnMut(&mut C) + 'static) -> Self {
+        self.enter = Some(Box::new(hook));
+        self
+    }
+
+    /// Runs when the state stops being active, after its children.
+    pub fn on_exit(mut self, hook: impl FnMut(&mut C) + 'static) -> Self {
+        self.exit = Some(Box::new(hook));
+        self
+    }
+
+    /// Runs on every update with the delta in seconds, before the children.
+    pub fn on_update(
+        mut self,
+        hook: impl FnMut(&mut C, f32) + 'static,
+    ) -> Self {
+        self.update = Some(Box::new(hook));
+        self
+    }
+}
+
+/// Moves a `StateMachine` from a state, or any of its children, to another.
+///
+/// Transitions are external: the source is exited and entered again even
+/// when the target is nested inside it.
+pub struct Transition<S, C> {
+    pub(crate) from: S,
+    pub(crate) to: S,
+    /// `None` for transitions checked on every update.
+    pub(crate) trigger: Option<Trigger>,
+    pub(crate) guard: Option<Guard<C>>,
+}
+
+impl<S, C> Transition<S, C> {
+    /// Creates a transition taken on the first update its guard allows.
+    pub fn new(from: S, to: S) -> Self {
+        Self {
+            from,
+            to,
+            trigger: None,
+            guard: None,
+        }
+    }
+
+    /// Only takes the transition when handling matching events.
+    pub fn with_trigger(
+        mut self,
+        trigger: impl Fn(&Event) -> bool + 'static,
+    ) -> Self {
+        self.trigger = Some(Box::new(trigger));
+        self
+    }
+
+    /// Only takes the transition while the guard holds.
+    pub fn with_guard(mut self, guard: impl Fn(&C) -> bool + 'static) -> Self {
+        self.guard = Some(Box::new(guard));
+        self
+    }
+
+    pub(crate) fn allows(&self, context: &C) -> bool {
+        self.guard.as_ref().is_none_or(|guard| guard(context))
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Local};
+use log::Level;
+
+/// A single log record stored inside a `LogBuffer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Monotonic number of this entry, can be used to only fetch new entries.
+    pub sequence: u64,
+    /// When the record was logged.
+    pub time: DateTime<Local>,
+    /// Severity of the record.
+    pub level: Level,
+    /// Module that produced the record.
+    pub target: String,
+    /// Formatted message.
+    pub message: String,
+}
+
+struct Inner {
+    entries: VecDeque<LogEntry>,
+    next_sequence: u64,
+}
+
+/// Ring buffer that keeps the most recent log entries in memory so they can be
+/// displayed inside the engine (e.g. by a debug console).
+///
+/// Can be freely cloned, every clone refers to the same entries.
+#[derive(Clone)]
+pub struct LogBuffer {
+    inner: Arc<Mutex<Inner>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    /// Creates a new buffer that keeps at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        let inner = Inner {
+            entries: VecDeque::with_capacity(capacity),
+            next_sequence: 0,
+        };
+
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            capacity,
+        }
+    }
+
+    /// Stores the passed `log::Record`, dropping the oldest entry when full.
+    pub fn record(&self, record: &log::Record) {
+        self.push(record.level(), record.target(), record.args().to_string());
+    }
+
+    /// Stores a new entry, dropping the oldest entry when full.
+    pub fn push(&self, level: Level, target: &str, message: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.len() == self.capacity {
+            inner.entries.pop_front();
+        }
+
+        let sequence = inner.next_sequence;
+        inner.next_sequence += 1;
+        inner.entries.push_back(LogEntry {
+            sequence,
+            time: Local::now(),
+            level,
+            target: target.to_string(),
+            message,
+        });
+    }
+
+    /// Returns a copy of all the stored entries, oldest first.
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.inner.lock().unwrap().entries.iter().cloned().collect()
+    }
+
+    /// Returns a copy of the stored entries with a sequence equal or greater
+    /// than the passed one, oldest first.
+    pub fn entries_since(&self, sequence: u64) -> Vec<LogEntry> {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .filter(|entry| entry.sequence >= sequence)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the sequence that will be given to the next entry.
+    pub fn next_sequence(&self) -> u64 {
+        self.inner.lock().unwrap().next_sequence
+    }
+
+    /// Returns the amount of stored entries.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if there are no stored entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum amount of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Removes all the stored entries.
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+}
+
+impl std::fmt::Debug for LogBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogBuffer")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_when_full() {
+        let buffer = LogBuffer::new(2);
+        buffer.push(Level::Info, "a", "first".to_string());
+        buffer.push(Level::Info, "a", "second".to_string());
+        buffer.push(Level::Warn, "b", "third".to_string());
+
+        let messages = buffer
+            .entries()
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["second", "third"]);
+    }
+
+    #[test]
+    fn entries_since_sequence() {
+        let buffer = LogBuffer::new(8);
+        buffer.push(Level::Info, "a", "first".to_string());
+        let sequence = buffer.next_sequence();
+        buffer.push(Level::Info, "a", "second".to_string());
+
+        let entries = buffer.entries_since(sequence);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message, "second");
+    }
+
+    #[test]
+    fn zero_capacity_stores_nothing() {
+        let buffer = LogBuffer::new(0);
+        buffer.push(Level::Error, "a", "ignored".to_string());
+        assert!(buffer.is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Log file that gets rotated once it grows past a maximum size.
+///
+/// When rotating, `game.log` becomes `game.log.1`, `game.log.1` becomes
+/// `game.log.2` and so on, until `max_files` rotated files exist. The oldest
+/// one is then deleted.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    /// Opens (or creates) the file at the passed `path`, new content is
+    /// appended.
+    pub fn new<P: Into<PathBuf>>(
+        path: P,
+        max_size: u64,
+        max_files: usize,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let file = Self::open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    /// Returns the path of the current log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the path of the nth rotated file.
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn open(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Moves every file one index up and starts a new empty file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            // Nothing to keep, just start over
+            self.file = File::create(&self.path)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = Self::open(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+/// Every call to `write` is written whole into a single file, so records
+/// written at once are never split between two files.
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("unen-logging-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn rotates_when_full() {
+        let dir = temp_dir("rotate");
+        let mut file = RotatingFile::new(dir.join("game.log"), 10, 2).unwrap();
+
+        file.write_all(b"aaaaaaaa\n").unwrap();
+        file.write_all(b"bbbbbbbb\n").unwrap();
+        file.write_all(b"cccccccc\n").unwrap();
+        file.write_all(b"dddddddd\n").unwrap();
+        file.flush().unwrap();
+
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+        assert_eq!(read(file.path().to_path_buf()), "dddddddd\n");
+        assert_eq!(read(file.rotated_path(1)), "cccccccc\n");
+        assert_eq!(read(file.rotated_path(2)), "bbbbbbbb\n");
+        assert!(!file.rotated_path(3).exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn records_are_not_split() {
+        let dir = temp_dir("split");
+        let mut file = RotatingFile::new(dir.join("game.log"), 16, 1).unwrap();
+
+        file.write_all(b"short\n").unwrap();
+        file.write_all(b"longer than the maximum size\n").unwrap();
+        file.flush().unwrap();
+
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+        assert_eq!(
+            read(file.path().to_path_buf()),
+            "longer than the maximum size\n"
+        );
+        assert_eq!(read(file.rotated_path(1)), "short\n");
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,360 @@
+//! Configurable logging subsystem.
+//!
+//! The engine installs its own `log::Log` implementation that can filter
+//! records per module, write them to the terminal, to a rotating file and to
+//! an in-memory `LogBuffer` that can be displayed inside the engine.
+
+use std::{
+    io::{IsTerminal, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use env_filter::{Builder as FilterBuilder, Filter};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod buffer;
+pub mod file;
+
+pub use buffer::{LogBuffer, LogEntry};
+pub use file::RotatingFile;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Another logger was already installed.
+    #[error("A logger is already installed")]
+    AlreadyInitialized,
+    /// Failed to open the log file.
+    #[error("Failed to open log file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// When the terminal output should be colored.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStyle {
+    /// Only when writing to a terminal.
+    #[default]
+    Auto,
+    /// Always.
+    Always,
+    /// Never.
+    Never,
+}
+
+/// Configuration of the optional log file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileLogConfig {
+    /// Path of the log file.
+    pub path: PathBuf,
+    /// Size in bytes after which the file gets rotated.
+    pub max_size: u64,
+    /// Amount of rotated files that are kept.
+    pub max_files: usize,
+}
+
+impl Default for FileLogConfig {
+    fn default() -> Self {
+        Self::new("logs/engine.log")
+    }
+}
+
+impl FileLogConfig {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            max_size: 5 * 1024 * 1024,
+            max_files: 3,
+        }
+    }
+}
+
+/// Everything needed to set up the logging.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Flags if the engine should install its logger. Disable it when the game
+    /// already installed its own logger.
+    pub enabled: bool,
+    /// Default level used by modules without a filter.
+    pub level: LevelFilter,
+    /// Per-module filters, e.g. `("wgpu", LevelFilter::Warn)`.
+    pub filters: Vec<(String, LevelFilter)>,
+    /// Name of the environment variable that can override the filters, uses
+    /// the same syntax as `RUST_LOG`.
+    pub env_var: Option<String>,
+    /// Terminal output style.
+    pub style: LogStyle,
+    /// Flags if records should be written to the terminal.
+    pub terminal: bool,
+    /// Optional log file.
+    pub file: Option<FileLogConfig>,
+    /// Amount of entries kept by the in-memory `LogBuffer`.
+    pub buffer_capacity: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            level: LevelFilter::Info,
+            filters: Vec::new(),
+            env_var: Some("MY_LOG_LEVEL".to_string()),
+            style: LogStyle::Always,
+            terminal: true,
+            file: None,
+            buffer_capacity: 1024,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Configuration that does not install any logger.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the default level.
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Adds a filter for the passed module.
+    pub fn with_filter<S: Into<String>>(
+        mut self,
+        module: S,
+        level: LevelFilter,
+    ) -> Self {
+        self.filters.push((module.into(), level));
+        self
+    }
+
+    /// Sets the environment variable that can override the filters.
+    pub fn with_env_var<S: Into<String>>(mut self, env_var: Option<S>) -> Self {
+        self.env_var = env_var.map(Into::into);
+        self
+    }
+
+    /// Sets the terminal output style.
+    pub fn with_style(mut self, style: LogStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets if records should be written to the terminal.
+    pub fn with_terminal(mut self, terminal: bool) -> Self {
+        self.terminal = terminal;
+        self
+    }
+
+    /// Sets the log file.
+    pub fn with_file(mut self, file: Option<FileLogConfig>) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Sets the capacity of the in-memory `LogBuffer`.
+    pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.buffer_capacity = capacity;
+        self
+    }
+
+    /// Builds the module filter described by this configuration.
+    fn build_filter(&self) -> Filter {
+        let mut builder = FilterBuilder::new();
+        builder.filter_level(self.level);
+        for (module, level) in &self.filters {
+            builder.filter_module(module, *level);
+        }
+
+        if let Some(env_var) = &self.env_var {
+            if let Ok(spec) = std::env::var(env_var) {
+                builder.parse(&spec);
+            }
+        }
+
+        builder.build()
+    }
+}
+
+/// The `log::Log` implementation installed by the engine.
+pub struct EngineLogger {
+    filter: Filter,
+    colored: Option<bool>,
+    file: Option<Mutex<RotatingFile>>,
+    buffer: LogBuffer,
+}
+
+impl EngineLogger {
+    /// Creates a new logger from the passed configuration.
+    pub fn new(config: &LogConfig) -> Result<Self, Error> {
+        let colored = config.terminal.then(|| match config.style {
+            LogStyle::Auto => std::io::stderr().is_terminal(),
+            LogStyle::Always => true,
+            LogStyle::Never => false,
+        });
+
+        let file = match &config.file {
+            Some(file) => Some(Mutex::new(RotatingFile::new(
+                file.path.clone(),
+                file.max_size,
+                file.max_files,
+            )?)),
+            None => None,
+        };
+
+        Ok(Self {
+            filter: config.build_filter(),
+            colored,
+            file,
+            buffer: LogBuffer::new(config.buffer_capacity),
+        })
+    }
+
+    /// Returns the buffer that receives every accepted record.
+    pub fn buffer(&self) -> &LogBuffer {
+        &self.buffer
+    }
+
+    /// Returns the most verbose level accepted by this logger.
+    pub fn max_level(&self) -> LevelFilter {
+        self.filter.filter()
+    }
+
+    fn level_color(level: Level) -> &'static str {
+        match level {
+            Level::Error => "\x1b[31m",
+            Level::Warn => "\x1b[33m",
+            Level::Info => "\x1b[32m",
+            Level::Debug => "\x1b[34m",
+            Level::Trace => "\x1b[36m",
+        }
+    }
+}
+
+impl Log for EngineLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.filter.matches(record) {
+            return;
+        }
+
+        let time = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f");
+        let level = record.level();
+        let target = record.target();
+        let message = record.args();
+
+        if let Some(colored) = self.colored {
+            if colored {
+                eprintln!(
+                    "[{time} {}{level:<5}\x1b[0m {target}] {message}",
+                    Self::level_color(level),
+                );
+            } else {
+                eprintln!("[{time} {level:<5} {target}] {message}");
+            }
+        }
+
+        if let Some(file) = &self.file {
+            // Written at once so rotating never splits the record
+            let line = format!("[{time} {level:<5} {target}] {message}\n");
+            let _ = file.lock().unwrap().write_all(line.as_bytes());
+        }
+
+        self.buffer.record(record);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Installs the engine logger as the global logger.
+///
+/// Returns the `LogBuffer` that will receive every accepted record. Fails
+/// without panicking if another logger was already installed.
+pub fn init(config: &LogConfig) -> Result<LogBuffer, Error> {
+    let logger = EngineLogger::new(config)?;
+    let buffer = logger.buffer().clone();
+    let max_level = logger.max_level();
+
+    log::set_boxed_logger(Box::new(logger))
+        .map_err(|_| Error::AlreadyInitialized)?;
+    log::set_max_level(max_level);
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(logger: &EngineLogger, level: Level, target: &str, message: &str) {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .target(target)
+                .args(format_args!("{message}"))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn per_module_filters() {
+        let config = LogConfig::default()
+            .with_level(LevelFilter::Warn)
+            .with_filter("game::ai", LevelFilter::Debug)
+            .with_env_var::<String>(None)
+            .with_terminal(false);
+        let logger = EngineLogger::new(&config).unwrap();
+
+        log(&logger, Level::Info, "game::render", "dropped");
+        log(&logger, Level::Warn, "game::render", "kept warn");
+        log(&logger, Level::Debug, "game::ai::path", "kept debug");
+        log(&logger, Level::Trace, "game::ai", "dropped");
+
+        let messages = logger
+            .buffer()
+            .entries()
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["kept warn", "kept debug"]);
+    }
+
+    #[test]
+    fn writes_to_file() {
+        let dir = std::env::temp_dir()
+            .join(format!("unen-logging-file-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("engine.log");
+
+        let config = LogConfig::default()
+            .with_env_var::<String>(None)
+            .with_terminal(false)
+            .with_file(Some(FileLogConfig::new(&path)));
+        let logger = EngineLogger::new(&config).unwrap();
+
+        log(&logger, Level::Error, "engine", "something broke");
+        logger.flush();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("ERROR engine] something broke"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
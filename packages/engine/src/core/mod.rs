@@ -2,9 +2,15 @@ use engine::EngineState;
 use thiserror::Error;
 
 pub mod application;
+pub mod config;
 pub mod engine;
 pub mod event;
+pub mod fsm;
+pub mod logging;
+pub mod profiler;
 pub mod scheduler;
+pub mod time;
+pub mod window;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -0,0 +1,134 @@
+//! Export of captures to the Chrome Trace Event format.
+//!
+//! Only the subset of the format needed to display the spans is produced:
+//! complete events (`"ph": "X"`) for spans and metadata events (`"ph": "M"`)
+//! for thread names.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use super::{Capture, Error};
+
+/// All the spans are reported as part of the same process.
+const PROCESS_ID: u32 = 1;
+
+#[derive(Serialize)]
+struct Trace<'a> {
+    #[serde(rename = "traceEvents")]
+    trace_events: Vec<TraceEvent<'a>>,
+    #[serde(rename = "displayTimeUnit")]
+    display_time_unit: &'static str,
+}
+
+#[derive(Serialize)]
+struct TraceEvent<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cat: Option<&'a str>,
+    ph: &'static str,
+    /// Timestamp in microseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ts: Option<f64>,
+    /// Duration in microseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: u32,
+    tid: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<ThreadNameArgs<'a>>,
+}
+
+#[derive(Serialize)]
+struct ThreadNameArgs<'a> {
+    name: &'a str,
+}
+
+fn build(capture: &Capture) -> Trace<'_> {
+    let mut trace_events =
+        Vec::with_capacity(capture.span_count() + capture.threads.len());
+
+    for thread in &capture.threads {
+        trace_events.push(TraceEvent {
+            name: "thread_name",
+            cat: None,
+            ph: "M",
+            ts: None,
+            dur: None,
+            pid: PROCESS_ID,
+            tid: thread.id,
+            args: Some(ThreadNameArgs { name: &thread.name }),
+        });
+
+        for span in &thread.spans {
+            trace_events.push(TraceEvent {
+                name: span.name,
+                cat: Some(span.category),
+                ph: "X",
+                ts: Some(span.start.as_secs_f64() * 1_000_000.0),
+                dur: Some(span.duration.as_secs_f64() * 1_000_000.0),
+                pid: PROCESS_ID,
+                tid: thread.id,
+                args: None,
+            });
+        }
+    }
+
+    Trace {
+        trace_events,
+        display_time_unit: "ms",
+    }
+}
+
+/// Serializes the passed capture into a JSON string.
+pub fn to_string(capture: &Capture) -> Result<String, Error> {
+    Ok(serde_json::to_string(&build(capture))?)
+}
+
+/// Serializes the passed capture into the passed writer.
+pub fn to_writer<W: Write>(
+    capture: &Capture,
+    mut writer: W,
+) -> Result<(), Error> {
+    serde_json::to_writer(&mut writer, &build(capture))?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::profiler::{Span, ThreadProfile};
+
+    #[test]
+    fn exports_spans_and_thread_names() {
+        let capture = Capture {
+            threads: vec![ThreadProfile {
+                id: 3,
+                name: "Worker \"Generic(0)\"".to_string(),
+                spans: vec![Span {
+                    name: "job",
+                    category: "worker",
+                    start: Duration::from_micros(10),
+                    duration: Duration::from_micros(5),
+                }],
+            }],
+        };
+
+        let json = to_string(&capture).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let events = value["traceEvents"].as_array().unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["ph"], "M");
+        assert_eq!(events[0]["args"]["name"], "Worker \"Generic(0)\"");
+        assert_eq!(events[1]["ph"], "X");
+        assert_eq!(events[1]["name"], "job");
+        assert_eq!(events[1]["cat"], "worker");
+        assert_eq!(events[1]["tid"], 3);
+        assert_eq!(events[1]["ts"].as_f64().unwrap(), 10.0);
+        assert_eq!(events[1]["dur"].as_f64().unwrap(), 5.0);
+    }
+}
@@ -0,0 +1,370 @@
+//! Built-in frame profiler.
+//!
+//! Spans are recorded with the [`profile_scope!`](crate::profile_scope) macro
+//! and collected per thread while a capture is running. A finished `Capture`
+//! can be exported to the Chrome Trace Event format and opened in any
+//! compatible trace viewer (`chrome://tracing`, Perfetto, Speedscope...).
+//!
+//! The macros only expand to something when the `profiling` feature is
+//! enabled, so instrumented code has zero cost when it is disabled.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+pub mod chrome;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Failed to serialize the capture.
+    #[error("Failed to serialize capture: {0}")]
+    Serialize(#[from] serde_json::Error),
+    /// Failed to write the capture.
+    #[error("Failed to write capture: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A single finished span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    /// Name displayed by the trace viewer.
+    pub name: &'static str,
+    /// Category used to group and filter spans.
+    pub category: &'static str,
+    /// Start time relative to the profiler epoch.
+    pub start: Duration,
+    /// How long the span took.
+    pub duration: Duration,
+}
+
+/// All the spans recorded by a single thread.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadProfile {
+    /// Unique id given to the thread by the profiler.
+    pub id: u64,
+    /// Human readable name of the thread.
+    pub name: String,
+    /// Recorded spans in the order they finished.
+    pub spans: Vec<Span>,
+}
+
+/// Result of a capture, contains the spans of every thread that recorded
+/// something while it was running.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Capture {
+    /// Profiles of the threads that took part in the capture.
+    pub threads: Vec<ThreadProfile>,
+}
+
+impl Capture {
+    /// Returns the total amount of recorded spans.
+    pub fn span_count(&self) -> usize {
+        self.threads.iter().map(|thread| thread.spans.len()).sum()
+    }
+
+    /// Returns `true` if no spans were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.span_count() == 0
+    }
+
+    /// Serializes the capture into a Chrome Trace Event JSON string.
+    pub fn to_chrome_trace(&self) -> Result<String, Error> {
+        chrome::to_string(self)
+    }
+
+    /// Writes the capture as Chrome Trace Event JSON to the passed `path`.
+    pub fn write_chrome_trace<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), Error> {
+        let file = std::fs::File::create(path)?;
+        chrome::to_writer(self, std::io::BufWriter::new(file))
+    }
+}
+
+/// Data owned by a single thread, shared with the profiler so it can be
+/// drained when a capture stops.
+struct ThreadData {
+    id: u64,
+    name: String,
+    spans: Vec<Span>,
+}
+
+/// Global state of the profiler.
+struct Profiler {
+    /// Every timestamp is relative to this instant.
+    epoch: Instant,
+    /// Flags if spans should be recorded.
+    capturing: AtomicBool,
+    /// Used to give every thread an unique id.
+    next_thread_id: AtomicU64,
+    /// All threads that recorded at least once.
+    threads: Mutex<Vec<Arc<Mutex<ThreadData>>>>,
+}
+
+impl Profiler {
+    fn get() -> &'static Self {
+        static PROFILER: OnceLock<Profiler> = OnceLock::new();
+        PROFILER.get_or_init(|| Self {
+            epoch: Instant::now(),
+            capturing: AtomicBool::new(false),
+            next_thread_id: AtomicU64::new(0),
+            threads: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn register_thread(&self) -> Arc<Mutex<ThreadData>> {
+        let id = self.next_thread_id.fetch_add(1, Ordering::Relaxed);
+        let name = match std::thread::current().name() {
+            Some(name) => name.to_string(),
+            None => format!("Thread {id}"),
+        };
+
+        let data = Arc::new(Mutex::new(ThreadData {
+            id,
+            name,
+            spans: Vec::new(),
+        }));
+        self.threads.lock().unwrap().push(Arc::clone(&data));
+
+        data
+    }
+}
+
+thread_local! {
+    static THREAD: Arc<Mutex<ThreadData>> = Profiler::get().register_thread();
+}
+
+/// Starts a new capture, discarding anything that was recorded before.
+pub fn start_capture() {
+    let profiler = Profiler::get();
+    for thread in profiler.threads.lock().unwrap().iter() {
+        thread.lock().unwrap().spans.clear();
+    }
+    profiler.capturing.store(true, Ordering::Release);
+}
+
+/// Stops the current capture and returns everything that was recorded.
+pub fn stop_capture() -> Capture {
+    let profiler = Profiler::get();
+    profiler.capturing.store(false, Ordering::Release);
+
+    let mut threads = profiler.threads.lock().unwrap();
+    let profiles = threads
+        .iter()
+        .filter_map(|thread| {
+            let mut thread = thread.lock().unwrap();
+            if thread.spans.is_empty() {
+                return None;
+            }
+            Some(ThreadProfile {
+                id: thread.id,
+                name: thread.name.clone(),
+                spans: std::mem::take(&mut thread.spans),
+            })
+        })
+        .collect();
+
+    // Forget about threads that already exited
+    threads.retain(|thread| Arc::strong_count(thread) > 1);
+
+    Capture { threads: profiles }
+}
+
+/// Returns `true` if a capture is currently running.
+pub fn is_capturing() -> bool {
+    Profiler::get().capturing.load(Ordering::Acquire)
+}
+
+/// Sets the name the current thread will have inside captures.
+pub fn set_thread_name<S: Into<String>>(name: S) {
+    let name = name.into();
+    THREAD.with(|thread| thread.lock().unwrap().name = name);
+}
+
+/// Guard that records a `Span` when dropped.
+///
+/// _Prefer using [`profile_scope!`](crate::profile_scope) instead of creating
+/// it manually._
+pub struct Scope {
+    name: &'static str,
+    category: &'static str,
+    start: Option<Instant>,
+}
+
+impl Scope {
+    /// Starts a new scope. Nothing is recorded if there is no running capture.
+    pub fn new(name: &'static str, category: &'static str) -> Self {
+        let start = is_capturing().then(Instant::now);
+        Self {
+            name,
+            category,
+            start,
+        }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let Some(start) = self.start else {
+            return;
+        };
+
+        let duration = start.elapsed();
+        let profiler = Profiler::get();
+        let span = Span {
+            name: self.name,
+            category: self.category,
+            start: start.saturating_duration_since(profiler.epoch),
+            duration,
+        };
+
+        // The capture could have stopped while this scope was alive
+        if profiler.capturing.load(Ordering::Acquire) {
+            THREAD.with(|thread| thread.lock().unwrap().spans.push(span));
+        }
+    }
+}
+
+/// Profiles the rest of the enclosing scope.
+///
+/// Accepts a name and an optional category:
+///
+/// ```
+/// # use unen_engine::profile_scope;
+/// profile_scope!("Engine::step");
+/// profile_scope!("load_texture", "assets");
+/// ```
+#[cfg(feature = "profiling")]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        $crate::profile_scope!($name, "engine");
+    };
+    ($name:expr, $category:expr) => {
+        let _profile_scope =
+            $crate::core::profiler::Scope::new($name, $category);
+    };
+}
+
+/// Profiles the rest of the enclosing scope.
+///
+/// _The `profiling` feature is disabled, so this expands to nothing._
+#[cfg(not(feature = "profiling"))]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {};
+    ($name:expr, $category:expr) => {};
+}
+
+/// Sets the name of the current thread inside captures.
+#[cfg(feature = "profiling")]
+#[macro_export]
+macro_rules! profile_thread {
+    ($name:expr) => {
+        $crate::core::profiler::set_thread_name($name);
+    };
+}
+
+/// Sets the name of the current thread inside captures.
+///
+/// _The `profiling` feature is disabled, so this expands to nothing._
+#[cfg(not(feature = "profiling"))]
+#[macro_export]
+macro_rules! profile_thread {
+    ($name:expr) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use serial_test::serial;
+
+    use super::*;
+
+    fn spans_named<'a>(
+        capture: &'a Capture,
+        name: &str,
+    ) -> impl Iterator<Item = &'a Span> + 'a {
+        let name = name.to_string();
+        capture
+            .threads
+            .iter()
+            .flat_map(|thread| thread.spans.iter())
+            .filter(move |span| span.name == name)
+    }
+
+    #[test]
+    #[serial]
+    fn records_only_while_capturing() {
+        {
+            let _scope = Scope::new("before", "test");
+        }
+
+        start_capture();
+        {
+            let _scope = Scope::new("during", "test");
+        }
+        let capture = stop_capture();
+
+        {
+            let _scope = Scope::new("after", "test");
+        }
+
+        assert_eq!(spans_named(&capture, "before").count(), 0);
+        assert_eq!(spans_named(&capture, "during").count(), 1);
+        assert_eq!(spans_named(&capture, "after").count(), 0);
+    }
+
+    #[test]
+    #[serial]
+    fn nested_spans_are_contained() {
+        start_capture();
+        {
+            let _outer = Scope::new("outer", "test");
+            let _inner = Scope::new("inner", "test");
+            thread::sleep(Duration::from_millis(2));
+        }
+        let capture = stop_capture();
+
+        let outer = spans_named(&capture, "outer").next().unwrap();
+        let inner = spans_named(&capture, "inner").next().unwrap();
+        assert!(outer.start <= inner.start);
+        assert!(outer.start + outer.duration >= inner.start + inner.duration);
+    }
+
+    #[test]
+    #[serial]
+    fn collects_from_multiple_threads() {
+        start_capture();
+        let handles = (0..3)
+            .map(|id| {
+                thread::spawn(move || {
+                    set_thread_name(format!("Test {id}"));
+                    let _scope = Scope::new("work", "test");
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let capture = stop_capture();
+
+        for id in 0..3 {
+            let name = format!("Test {id}");
+            let thread = capture
+                .threads
+                .iter()
+                .find(|thread| thread.name == name)
+                .expect("thread should be part of the capture");
+            assert_eq!(thread.spans.len(), 1);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use strum::EnumCount;
 use thiserror::Error;
 
 use super::worker::{
-    Worker, WorkerInstruction, WorkerKind, WorkerNotification,
+    Worker, WorkerInstruction, WorkerKind, WorkerNotification, WorkerState,
 };
 
 #[derive(Debug, Error, PartialEq, Eq)]
@@ -137,6 +137,14 @@ impl WorkerPool {
         self.workers.len()
     }
 
+    /// Returns the kind and state of every `Worker`.
+    pub fn status(&self) -> Vec<(WorkerKind, WorkerState)> {
+        self.workers
+            .iter()
+            .map(|worker| (worker.kind(), worker.state()))
+            .collect()
+    }
+
     /// Returns `true` if the `WorkerPool` contains no `Worker` instances.
     pub fn is_empty(&self) -> bool {
         self.workers.is_empty()
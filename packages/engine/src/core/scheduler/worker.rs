@@ -98,6 +98,7 @@ impl Worker {
 
         let thread = thread::spawn(move || {
             let kind = *kind_clone.lock().unwrap();
+            crate::profile_thread!(format!("Worker {kind}"));
             loop {
                 match received_clone.recv() {
                     Ok(instruction) => {
@@ -120,7 +121,13 @@ impl Worker {
                                     WorkerNotification::JobStarted(kind),
                                 );
 
-                                job();
+                                {
+                                    crate::profile_scope!(
+                                        "Worker::execute",
+                                        "worker"
+                                    );
+                                    job();
+                                }
 
                                 {
                                     let mut state = state_clone.lock().unwrap();
@@ -162,7 +169,13 @@ impl Worker {
 
                                         Self::notify(&kind, &notification_sender, WorkerNotification::SpecializedJobStarted(*kind));
 
-                                        job();
+                                        {
+                                            crate::profile_scope!(
+                                                "Worker::specialized",
+                                                "worker"
+                                            );
+                                            job();
+                                        }
 
                                         // We need to be absolutely sure we
                                         // notify BEFORE changing the kind to
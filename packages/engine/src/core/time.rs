@@ -0,0 +1,228 @@
+use std::time::{Duration, Instant};
+
+/// Longest frame that is taken into account, avoids running a huge amount of
+/// fixed ticks after a hiccup (e.g. the window being dragged).
+const MAX_DELTA: Duration = Duration::from_millis(250);
+/// Fastest game time, every frame runs this many times the fixed ticks.
+pub const MAX_SCALE: f32 = 10.0;
+
+/// Keeps track of the frame time and of the fixed timestep.
+#[derive(Debug, Clone)]
+pub struct Time {
+    /// When the clock was created.
+    startup: Instant,
+    /// When the last update happened.
+    last_update: Option<Instant>,
+    /// Time between the last two updates.
+    delta: Duration,
+    /// Same as `delta` but ignoring pauses and hiccups.
+    real_delta: Duration,
+    /// Time since the first update.
+    elapsed: Duration,
+    /// Duration of a single fixed tick.
+    fixed_delta: Duration,
+    /// Time that still needs to be consumed by fixed ticks.
+    accumulator: Duration,
+    /// Amount of fixed ticks that already happened.
+    ticks: u64,
+    /// Stops the clock, updates report no time passing.
+    paused: bool,
+    /// Multiplies the game time.
+    scale: f32,
+}
+
+impl Time {
+    /// Creates a new clock that runs `tick_rate` fixed ticks per second.
+    pub fn new(tick_rate: u32) -> Self {
+        Self {
+            startup: Instant::now(),
+            last_update: None,
+            delta: Duration::ZERO,
+            real_delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            fixed_delta: Duration::from_secs(1) / tick_rate.max(1),
+            accumulator: Duration::ZERO,
+            ticks: 0,
+            paused: false,
+            scale: 1.0,
+        }
+    }
+
+    /// Advances the clock to the current instant.
+    pub fn update(&mut self) {
+        self.update_with(Instant::now());
+    }
+
+    /// Advances the clock to the passed instant.
+    pub fn update_with(&mut self, now: Instant) {
+        let delta = match self.last_update {
+            Some(last_update) => now.saturating_duration_since(last_update),
+            None => Duration::ZERO,
+        };
+        self.advance(delta);
+        self.last_update = Some(now);
+    }
+
+    /// Advances the clock by the passed duration, nothing passes while
+    /// paused.
+    pub fn advance(&mut self, delta: Duration) {
+        self.real_delta = delta;
+        self.delta = if self.paused {
+            Duration::ZERO
+        } else {
+            // Rounded to whole nanoseconds so a scale of 1 is exact
+            let nanos = delta.min(MAX_DELTA).as_nanos() as f64;
+            Duration::from_nanos((nanos * self.scale as f64).round() as u64)
+        };
+        self.elapsed += self.delta;
+        self.accumulator += self.delta;
+    }
+
+    /// Consumes a fixed tick if enough time was accumulated.
+    ///
+    /// Should be called in a loop until it returns `false`.
+    pub fn expend_fixed_tick(&mut self) -> bool {
+        if self.accumulator >= self.fixed_delta {
+            self.accumulator -= self.fixed_delta;
+            self.ticks += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Stops the game time: the delta is zero, no fixed ticks run and
+    /// everything driven by the clock (physics, animations) stays still.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Speeds up (above `1.0`) or slows down the game time, values are
+    /// clamped between zero and `MAX_SCALE`.
+    ///
+    /// Returns `false` and keeps the current scale if it is not finite.
+    pub fn set_scale(&mut self, scale: f32) -> bool {
+        if !scale.is_finite() {
+            return false;
+        }
+        self.scale = scale.clamp(0.0, MAX_SCALE);
+        true
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Returns the time between the last two updates.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the time between the last two updates in seconds.
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Returns the real time between the last two updates, even while
+    /// paused. Useful to measure frame times.
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+
+    /// Returns the time since the first update.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the real time since the clock was created.
+    pub fn since_startup(&self) -> Duration {
+        self.startup.elapsed()
+    }
+
+    /// Returns the duration of a fixed tick.
+    pub fn fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    /// Returns the amount of fixed ticks that already happened.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns how far we are between the last and the next fixed tick, in
+    /// the `0.0..1.0` range. Useful to interpolate rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.fixed_delta.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_ticks_from_accumulated_time() {
+        let mut time = Time::new(10);
+        time.advance(Duration::from_millis(250));
+
+        let mut ticks = 0;
+        while time.expend_fixed_tick() {
+            ticks += 1;
+        }
+
+        assert_eq!(ticks, 2);
+        assert_eq!(time.ticks(), 2);
+        assert!((time.alpha() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn pausing_stops_the_clock() {
+        let mut time = Time::new(10);
+        time.pause();
+        time.advance(Duration::from_millis(200));
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.real_delta(), Duration::from_millis(200));
+        assert_eq!(time.elapsed(), Duration::ZERO);
+        assert!(!time.expend_fixed_tick());
+
+        time.resume();
+        time.advance(Duration::from_millis(200));
+        assert_eq!(time.elapsed(), Duration::from_millis(200));
+        assert!(time.expend_fixed_tick());
+    }
+
+    #[test]
+    fn delta_is_clamped() {
+        let mut time = Time::new(60);
+        time.advance(Duration::from_secs(10));
+        assert_eq!(time.delta(), MAX_DELTA);
+        assert_eq!(time.real_delta(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn scale_changes_game_time() {
+        let mut time = Time::new(10);
+        assert!(time.set_scale(0.5));
+        time.advance(Duration::from_millis(200));
+        assert_eq!(time.delta(), Duration::from_millis(100));
+        assert_eq!(time.real_delta(), Duration::from_millis(200));
+        assert!(time.expend_fixed_tick());
+        assert!(!time.expend_fixed_tick());
+
+        time.set_scale(-1.0);
+        assert_eq!(time.scale(), 0.0);
+        time.set_scale(1e30);
+        assert_eq!(time.scale(), MAX_SCALE);
+        assert!(!time.set_scale(f32::NAN));
+        assert!(!time.set_scale(f32::INFINITY));
+        assert_eq!(time.scale(), MAX_SCALE);
+    }
+}
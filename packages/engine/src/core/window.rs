@@ -0,0 +1,231 @@
+//! Helpers that translate the engine window configuration and commands into
+//! `winit` calls.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use thiserror::Error;
+use winit::{
+    dpi::PhysicalSize,
+    event_loop::ActiveEventLoop,
+    window::{Fullscreen, Icon, Window, WindowAttributes},
+};
+
+use super::{
+    config::{WindowConfig, WindowIcon, WindowMode},
+    event::WindowEvent,
+};
+
+/// Identifies a window opened by the `Application`.
+///
+/// Ids are given by the engine, so a window can be referenced (e.g. by an
+/// `Open` command) before it actually exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(u64);
+
+impl WindowId {
+    /// The main window, created from `EngineConfig::window`. Closing it shuts
+    /// the engine down.
+    pub const PRIMARY: Self = Self(0);
+
+    /// Returns a new unique id.
+    pub fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Returns the raw value of this id.
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for WindowId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for WindowId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Window({})", self.0)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The platform refused to create the window.
+    #[error("Failed to create window: {0}")]
+    CreationFailed(String),
+    /// Failed to read or decode the icon.
+    #[error("Failed to load window icon: {0}")]
+    IconLoadFailed(String),
+}
+
+/// Creates a new window described by the passed configuration.
+///
+/// _A broken icon is only logged, it does not prevent the window creation._
+pub fn create(
+    event_loop: &ActiveEventLoop,
+    config: &WindowConfig,
+) -> Result<Window, Error> {
+    let mut attributes = Window::default_attributes()
+        .with_title(config.title.clone())
+        .with_inner_size(PhysicalSize::new(config.width, config.height))
+        .with_resizable(config.resizable)
+        .with_decorations(config.decorations)
+        .with_fullscreen(fullscreen(event_loop, config.mode));
+
+    if let Some((width, height)) = config.min_size {
+        attributes =
+            attributes.with_min_inner_size(PhysicalSize::new(width, height));
+    }
+    if let Some((width, height)) = config.max_size {
+        attributes =
+            attributes.with_max_inner_size(PhysicalSize::new(width, height));
+    }
+    attributes = with_icon(attributes, config.icon.as_ref());
+
+    let window = event_loop
+        .create_window(attributes)
+        .map_err(|err| Error::CreationFailed(err.to_string()))?;
+    window.set_cursor_visible(config.cursor_visible);
+
+    Ok(window)
+}
+
+fn with_icon(
+    attributes: WindowAttributes,
+    icon: Option<&WindowIcon>,
+) -> WindowAttributes {
+    match icon.map(load_icon) {
+        Some(Ok(icon)) => attributes.with_window_icon(Some(icon)),
+        Some(Err(err)) => {
+            log::warn!("{err}");
+            attributes
+        }
+        None => attributes,
+    }
+}
+
+/// Loads the passed icon.
+pub fn load_icon(icon: &WindowIcon) -> Result<Icon, Error> {
+    let (rgba, width, height) = match icon {
+        WindowIcon::Path(path) => {
+            let image = image::open(path)
+                .map_err(|err| {
+                    Error::IconLoadFailed(format!(
+                        "'{}': {err}",
+                        path.display()
+                    ))
+                })?
+                .into_rgba8();
+            let (width, height) = image.dimensions();
+            (image.into_raw(), width, height)
+        }
+        WindowIcon::Rgba {
+            width,
+            height,
+            rgba,
+        } => (rgba.clone(), *width, *height),
+    };
+
+    Icon::from_rgba(rgba, width, height)
+        .map_err(|err| Error::IconLoadFailed(err.to_string()))
+}
+
+/// Returns the `winit` fullscreen mode for the passed `WindowMode`.
+pub fn fullscreen(
+    event_loop: &ActiveEventLoop,
+    mode: WindowMode,
+) -> Option<Fullscreen> {
+    match mode {
+        WindowMode::Windowed => None,
+        WindowMode::Borderless => Some(Fullscreen::Borderless(None)),
+        WindowMode::Fullscreen => event_loop
+            .primary_monitor()
+            .and_then(|monitor| monitor.video_modes().next())
+            .map(Fullscreen::Exclusive),
+    }
+}
+
+/// Returns the current `WindowMode` of the passed window.
+pub fn mode(window: &Window) -> WindowMode {
+    match window.fullscreen() {
+        None => WindowMode::Windowed,
+        Some(Fullscreen::Borderless(_)) => WindowMode::Borderless,
+        Some(Fullscreen::Exclusive(_)) => WindowMode::Fullscreen,
+    }
+}
+
+/// Applies a window command.
+///
+/// Returns the event that should be dispatched as a consequence, if any.
+pub fn apply(
+    event_loop: &ActiveEventLoop,
+    window: &Window,
+    command: &WindowEvent,
+) -> Option<WindowEvent> {
+    match command {
+        WindowEvent::SetTitle(title) => window.set_title(title),
+        WindowEvent::SetSize(width, height) => {
+            let _ =
+                window.request_inner_size(PhysicalSize::new(*width, *height));
+        }
+        WindowEvent::SetMode(mode) => {
+            window.set_fullscreen(fullscreen(event_loop, *mode));
+            return Some(WindowEvent::ModeChanged(*mode));
+        }
+        WindowEvent::ToggleFullscreen => {
+            let mode = match mode(window) {
+                WindowMode::Windowed => WindowMode::Borderless,
+                _ => WindowMode::Windowed,
+            };
+            window.set_fullscreen(fullscreen(event_loop, mode));
+            return Some(WindowEvent::ModeChanged(mode));
+        }
+        WindowEvent::SetCursorIcon(icon) => window.set_cursor(*icon),
+        WindowEvent::SetCursorVisible(visible) => {
+            window.set_cursor_visible(*visible)
+        }
+        // Opening and closing is handled by the `Application`
+        WindowEvent::Open(_) | WindowEvent::Close => {}
+        // Not a command, nothing to apply
+        WindowEvent::Opened
+        | WindowEvent::OpenFailed(_)
+        | WindowEvent::Closed
+        | WindowEvent::Resized(..)
+        | WindowEvent::Focused(_)
+        | WindowEvent::ModeChanged(_) => {}
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_ids() {
+        let first = WindowId::new();
+        let second = WindowId::new();
+        assert_ne!(first, second);
+        assert_ne!(first, WindowId::PRIMARY);
+    }
+
+    #[test]
+    fn load_raw_icon() {
+        let icon = WindowIcon::Rgba {
+            width: 2,
+            height: 2,
+            rgba: vec![255; 16],
+        };
+        assert!(load_icon(&icon).is_ok());
+    }
+
+    #[test]
+    fn missing_icon_file() {
+        let icon = WindowIcon::Path("does/not/exist.png".into());
+        assert!(matches!(load_icon(&icon), Err(Error::IconLoadFailed(_))));
+    }
+}
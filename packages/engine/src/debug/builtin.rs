@@ -0,0 +1,150 @@
+use super::{Args, Command, Console, Cvar, Value, ValueKind};
+use crate::{
+    core::engine::Engine,
+    math::{Transform, Vec3},
+};
+
+const NON_FINITE_SCALE: &str = "The time scale must be a finite number";
+
+/// Adds the commands and cvars every engine has.
+pub(crate) fn register(console: &mut Console) {
+    console.register(
+        Command::new(
+            "help",
+            "Lists the commands and cvars, or describes one",
+            help,
+        )
+        .with_optional("name", ValueKind::String),
+    );
+    console.register(
+        Command::new("echo", "Prints the text", |_, args| {
+            Ok(args.string("text").unwrap_or_default().to_string())
+        })
+        .with_param("text", ValueKind::String),
+    );
+    console.register(Command::new(
+        "clear",
+        "Empties the output",
+        |engine, _| {
+            engine.console_mut().clear();
+            Ok(String::new())
+        },
+    ));
+    console.register(
+        Command::new("exec", "Runs every line of a script", |engine, args| {
+            engine
+                .exec_script(args.string("path").unwrap_or_default())
+                .map(|_| String::new())
+                .map_err(|err| err.to_string())
+        })
+        .with_param("path", ValueKind::String),
+    );
+    console.register(Command::new("quit", "Stops the engine", |engine, _| {
+        engine
+            .shutdown()
+            .map(|_| String::new())
+            .map_err(|err| err.to_string())
+    }));
+    console.register(
+        Command::new(
+            "timescale",
+            "Shows or changes the speed of the game time",
+            |engine, args| {
+                if let Some(scale) = args.float("scale") {
+                    if !engine.time_mut().set_scale(scale) {
+                        return Err(NON_FINITE_SCALE.to_string());
+                    }
+                }
+                Ok(format!("Time scale is {}", engine.time().scale()))
+            },
+        )
+        .with_optional("scale", ValueKind::Float),
+    );
+    console.register(
+        Command::new(
+            "spawn",
+            "Spawns an entity with a transform",
+            |engine, args| {
+                let position = Vec3::new(
+                    args.float("x").unwrap_or_default(),
+                    args.float("y").unwrap_or_default(),
+                    0.0,
+                );
+                let entity = engine
+                    .world_mut()
+                    .spawn_with(Transform::from_translation(position));
+                Ok(format!("Spawned {entity}"))
+            },
+        )
+        .with_optional("x", ValueKind::Float)
+        .with_optional("y", ValueKind::Float),
+    );
+
+    console.register_cvar(Cvar::bound(
+        "time.scale",
+        "Speed of the game time",
+        ValueKind::Float,
+        |engine| Value::Float(engine.time().scale()),
+        |engine, value| match value {
+            Value::Float(scale) => match engine.time_mut().set_scale(scale) {
+                true => Ok(()),
+                false => Err(NON_FINITE_SCALE.to_string()),
+            },
+            _ => Err("Expected a float".to_string()),
+        },
+    ));
+    console.register_cvar(Cvar::bound(
+        "time.paused",
+        "Stops the game time",
+        ValueKind::Bool,
+        |engine| Value::Bool(engine.time().is_paused()),
+        |engine, value| match value {
+            Value::Bool(paused) => {
+                match paused {
+                    true => engine.time_mut().pause(),
+                    false => engine.time_mut().resume(),
+                }
+                Ok(())
+            }
+            _ => Err("Expected a bool".to_string()),
+        },
+    ));
+    // Records dropped by the filters of the `LogConfig` stay dropped
+    console.register_cvar(Cvar::bound(
+        "log.level",
+        "Most verbose level that is logged",
+        ValueKind::String,
+        |_| Value::String(log::max_level().to_string().to_lowercase()),
+        |_, value| {
+            let level = value.to_string();
+            level
+                .parse()
+                .map(log::set_max_level)
+                .map_err(|_| format!("Unknown level '{level}'"))
+        },
+    ));
+}
+
+fn help(engine: &mut Engine, args: &Args) -> Result<String, String> {
+    let console = engine.console();
+    let describe_cvar = |cvar: &Cvar| {
+        format!("{} [{}] - {}", cvar.name(), cvar.kind(), cvar.description())
+    };
+    let describe_command = |command: &Command| {
+        format!("{} - {}", command.usage(), command.description())
+    };
+
+    match args.string("name") {
+        Some(name) => console
+            .command(name)
+            .map(describe_command)
+            .or_else(|| console.cvar(name).map(describe_cvar))
+            .ok_or_else(|| format!("Unknown command or cvar '{name}'")),
+        None => Ok(console
+            .commands()
+            .map(describe_command)
+            .chain(console.cvars().map(describe_cvar))
+            .collect::<Vec<_>>()
+            .join("\n")),
+    }
+}
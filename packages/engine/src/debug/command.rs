@@ -0,0 +1,274 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use super::{Error, Value, ValueKind};
+use crate::core::engine::Engine;
+
+/// Runs a command, the returned text is printed to the console.
+pub type Handler =
+    dyn Fn(&mut Engine, &Args) -> Result<String, String> + Send + Sync;
+
+/// Parameter of a `Command`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub kind: ValueKind,
+    pub optional: bool,
+}
+
+/// Arguments passed to a command, already parsed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    values: BTreeMap<String, Value>,
+}
+
+impl Args {
+    /// Returns `None` for optional parameters that were not passed.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            Value::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, name: &str) -> Option<f32> {
+        match self.get(name)? {
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Named action run from the console.
+///
+/// Arguments are checked against the parameters before the handler runs, a
+/// string as the last parameter takes the rest of the line.
+#[derive(Clone)]
+pub struct Command {
+    name: String,
+    description: String,
+    params: Vec<Param>,
+    handler: Arc<Handler>,
+}
+
+impl Command {
+    pub fn new<F>(name: &str, description: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Engine, &Args) -> Result<String, String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            params: Vec::new(),
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Adds a required parameter, they must come before the optional ones.
+    pub fn with_param(mut self, name: &str, kind: ValueKind) -> Self {
+        debug_assert!(
+            self.params.iter().all(|param| !param.optional),
+            "required parameters must come first"
+        );
+        self.params.push(Param {
+            name: name.to_string(),
+            kind,
+            optional: false,
+        });
+        self
+    }
+
+    pub fn with_optional(mut self, name: &str, kind: ValueKind) -> Self {
+        self.params.push(Param {
+            name: name.to_string(),
+            kind,
+            optional: true,
+        });
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn params(&self) -> &[Param] {
+        &self.params
+    }
+
+    /// Name and parameters, like `spawn <x:float> [count:int]`.
+    pub fn usage(&self) -> String {
+        self.params.iter().fold(self.name.clone(), |usage, param| {
+            let (open, close) = match param.optional {
+                true => ('[', ']'),
+                false => ('<', '>'),
+            };
+            format!("{usage} {open}{}:{}{close}", param.name, param.kind)
+        })
+    }
+
+    /// Checks the amount and types of the arguments.
+    pub fn parse(&self, tokens: &[String]) -> Result<Args, Error> {
+        let required = self.params.iter().filter(|param| !param.optional);
+        let takes_rest = self
+            .params
+            .last()
+            .is_some_and(|param| param.kind == ValueKind::String);
+        if tokens.len() < required.count()
+            || (tokens.len() > self.params.len() && !takes_rest)
+        {
+            return Err(Error::Usage(self.usage()));
+        }
+
+        let mut values = BTreeMap::new();
+        for (index, (param, token)) in
+            self.params.iter().zip(tokens).enumerate()
+        {
+            let text = match index + 1 == self.params.len() && takes_rest {
+                true => tokens[index..].join(" "),
+                false => token.clone(),
+            };
+            let value = param.kind.parse(&text).ok_or_else(|| {
+                Error::InvalidArgument(param.name.clone(), param.kind, text)
+            })?;
+            values.insert(param.name.clone(), value);
+        }
+        Ok(Args { values })
+    }
+
+    pub(crate) fn handler(&self) -> Arc<Handler> {
+        self.handler.clone()
+    }
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+/// Splits a line into statements separated by `;`, each one made of tokens
+/// separated by whitespace. Double quotes group words into a single token.
+pub fn tokenize(line: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut statements = vec![Vec::new()];
+    let mut token: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                // Keeps empty quotes as an empty token
+                token.get_or_insert_with(String::new);
+            }
+            '\\' if quoted => {
+                if let Some(escaped) = chars.next() {
+                    token.get_or_insert_with(String::new).push(escaped);
+                }
+            }
+            ';' if !quoted => {
+                statements.last_mut().unwrap().extend(token.take());
+                statements.push(Vec::new());
+            }
+            c if c.is_whitespace() && !quoted => {
+                statements.last_mut().unwrap().extend(token.take());
+            }
+            c => token.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err(Error::UnclosedQuote);
+    }
+    statements.last_mut().unwrap().extend(token);
+
+    statements.retain(|statement| !statement.is_empty());
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|token| token.to_string()).collect()
+    }
+
+    #[test]
+    fn tokenizes_statements() {
+        assert_eq!(
+            tokenize(r#"echo "a; \"b\""  c; spawn 1 ;; "" "#).unwrap(),
+            vec![
+                strings(&["echo", "a; \"b\"", "c"]),
+                strings(&["spawn", "1"]),
+                strings(&[""]),
+            ]
+        );
+        assert_eq!(tokenize("echo \"open"), Err(Error::UnclosedQuote));
+    }
+
+    #[test]
+    fn parses_typed_arguments() {
+        let command = Command::new("spawn", "", |_, _| Ok(String::new()))
+            .with_param("x", ValueKind::Float)
+            .with_param("visible", ValueKind::Bool)
+            .with_optional("count", ValueKind::Int);
+        assert_eq!(
+            command.usage(),
+            "spawn <x:float> <visible:bool> [count:int]"
+        );
+
+        let args = command.parse(&strings(&["1.5", "on"])).unwrap();
+        assert_eq!(args.float("x"), Some(1.5));
+        assert_eq!(args.bool("visible"), Some(true));
+        assert_eq!(args.int("count"), None);
+        assert_eq!(
+            command.parse(&strings(&["1", "no", "x"])),
+            Err(Error::InvalidArgument(
+                "count".to_string(),
+                ValueKind::Int,
+                "x".to_string()
+            ))
+        );
+        assert_eq!(
+            command.parse(&strings(&["1"])),
+            Err(Error::Usage(command.usage()))
+        );
+        assert_eq!(
+            command.parse(&strings(&["1", "no", "2", "3"])),
+            Err(Error::Usage(command.usage()))
+        );
+
+        // A trailing string takes the rest of the line
+        let echo = Command::new("echo", "", |_, _| Ok(String::new()))
+            .with_param("text", ValueKind::String);
+        let args = echo.parse(&strings(&["hello", "there"])).unwrap();
+        assert_eq!(args.string("text"), Some("hello there"));
+    }
+}
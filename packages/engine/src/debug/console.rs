@@ -0,0 +1,501 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use log::Level;
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use super::{Command, Cvar};
+use crate::{
+    core::{
+        event::{Event, KeyboardEvent},
+        logging::LogBuffer,
+        window::WindowId,
+    },
+    math::{Rect, Vec2},
+    render::{Color, Renderer, TextRenderer},
+    ui::{self, DrawCommand},
+};
+
+/// Lines kept in the output, older ones are dropped.
+const MAX_OUTPUT: usize = 512;
+/// Entries kept in the history.
+const MAX_HISTORY: usize = 100;
+const FONT_SIZE: f32 = 14.0;
+/// Part of the window covered when open.
+const HEIGHT: f32 = 0.4;
+
+/// What produced a line of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// Typed by the user.
+    Input,
+    Output,
+    Error,
+    /// Mirrored from the `log` crate.
+    Log(Level),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleLine {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+/// Drop-down console holding the registered commands and cvars.
+///
+/// Typed lines are queued and run by the `Engine` on its next step, use
+/// `Engine::execute()` to run them right away.
+#[derive(Debug)]
+pub struct Console {
+    commands: BTreeMap<String, Command>,
+    cvars: BTreeMap<String, Cvar>,
+    output: VecDeque<ConsoleLine>,
+    history: Vec<String>,
+    /// History entry recalled with the arrow keys.
+    history_index: Option<usize>,
+    input: String,
+    pending: VecDeque<String>,
+    open: bool,
+    toggle_key: KeyCode,
+    /// The text typed by the toggle key is ignored.
+    skip_text: bool,
+    /// Next log entry to mirror.
+    log_sequence: u64,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+            cvars: BTreeMap::new(),
+            output: VecDeque::new(),
+            history: Vec::new(),
+            history_index: None,
+            input: String::new(),
+            pending: VecDeque::new(),
+            open: false,
+            toggle_key: KeyCode::Backquote,
+            skip_text: false,
+            log_sequence: 0,
+        }
+    }
+}
+
+impl Console {
+    /// Creates a closed console without commands, toggled with the key
+    /// below `Esc`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_toggle_key(mut self, key: KeyCode) -> Self {
+        self.toggle_key = key;
+        self
+    }
+
+    /// Adds a command, replacing the one with the same name.
+    pub fn register(&mut self, command: Command) {
+        self.commands.insert(command.name().to_string(), command);
+    }
+
+    /// Adds a cvar, replacing the one with the same name.
+    pub fn register_cvar(&mut self, cvar: Cvar) {
+        self.cvars.insert(cvar.name().to_string(), cvar);
+    }
+
+    pub fn command(&self, name: &str) -> Option<&Command> {
+        self.commands.get(name)
+    }
+
+    /// Values of bound cvars are read with `Engine::cvar()`.
+    pub fn cvar(&self, name: &str) -> Option<&Cvar> {
+        self.cvars.get(name)
+    }
+
+    pub(crate) fn cvar_mut(&mut self, name: &str) -> Option<&mut Cvar> {
+        self.cvars.get_mut(name)
+    }
+
+    /// Sorted by name.
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.values()
+    }
+
+    /// Sorted by name.
+    pub fn cvars(&self) -> impl Iterator<Item = &Cvar> {
+        self.cvars.values()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Line being typed.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn set_input(&mut self, input: &str) {
+        self.input = input.to_string();
+    }
+
+    /// Oldest first.
+    pub fn output(&self) -> impl Iterator<Item = &ConsoleLine> {
+        self.output.iter()
+    }
+
+    pub fn print(&mut self, kind: LineKind, text: &str) {
+        for line in text.lines() {
+            if self.output.len() == MAX_OUTPUT {
+                self.output.pop_front();
+            }
+            self.output.push_back(ConsoleLine {
+                kind,
+                text: line.to_string(),
+            });
+        }
+    }
+
+    /// Empties the output.
+    pub fn clear(&mut self) {
+        self.output.clear();
+    }
+
+    /// Submitted lines, oldest first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Queues a line as if it was typed, it runs on the next engine step.
+    pub fn submit(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        self.print(LineKind::Input, &format!("> {line}"));
+        if self.history.last().map(String::as_str) != Some(line) {
+            if self.history.len() == MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.history.push(line.to_string());
+        }
+        self.history_index = None;
+        self.pending.push_back(line.to_string());
+    }
+
+    pub(crate) fn take_pending(&mut self) -> Option<String> {
+        self.pending.pop_front()
+    }
+
+    /// Names of the commands and cvars starting with the prefix.
+    pub fn completions(&self, prefix: &str) -> Vec<&str> {
+        self.commands
+            .keys()
+            .chain(self.cvars.keys())
+            .filter(|name| name.starts_with(prefix))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Completes the name being typed as far as it is unambiguous, the
+    /// candidates are printed when there are many.
+    pub fn complete(&mut self) {
+        // Only the name is completed, not the arguments
+        if self.input.contains(char::is_whitespace) {
+            return;
+        }
+        let candidates = self.completions(&self.input);
+        let Some(first) = candidates.first() else {
+            return;
+        };
+        if candidates.len() == 1 {
+            self.input = format!("{first} ");
+            return;
+        }
+
+        let common = candidates.iter().fold(first.len(), |common, name| {
+            first
+                .chars()
+                .zip(name.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a.len_utf8())
+                .sum::<usize>()
+                .min(common)
+        });
+        let (input, listed) =
+            (first[..common].to_string(), candidates.join("  "));
+        self.input = input;
+        self.print(LineKind::Output, &listed);
+    }
+
+    /// Recalls the previous entry of the history.
+    pub fn history_previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.history_index = Some(index);
+        self.input = self.history[index].clone();
+    }
+
+    /// Recalls the next entry of the history, past the last one the input
+    /// is emptied.
+    pub fn history_next(&mut self) {
+        let Some(index) = self.history_index else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.history_index = Some(index + 1);
+            self.input = self.history[index + 1].clone();
+        } else {
+            self.history_index = None;
+            self.input.clear();
+        }
+    }
+
+    /// Copies the log entries recorded since the last call into the output.
+    pub fn mirror_log(&mut self, buffer: &LogBuffer) {
+        for entry in buffer.entries_since(self.log_sequence) {
+            self.print(
+                LineKind::Log(entry.level),
+                &format!("[{}] {}", entry.target, entry.message),
+            );
+            self.log_sequence = entry.sequence + 1;
+        }
+    }
+
+    /// Handles the keyboard of the primary window, returns `true` if the
+    /// console used the event and the game should ignore it.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let Event::Keyboard(WindowId::PRIMARY, event) = event else {
+            return false;
+        };
+        match event {
+            KeyboardEvent::Pressed(PhysicalKey::Code(key))
+                if *key == self.toggle_key =>
+            {
+                self.toggle();
+                self.skip_text = true;
+                true
+            }
+            _ if !self.open => false,
+            KeyboardEvent::Text(text) => {
+                if !std::mem::take(&mut self.skip_text) {
+                    self.input.push_str(text);
+                }
+                true
+            }
+            KeyboardEvent::Pressed(key) => {
+                self.skip_text = false;
+                match key {
+                    PhysicalKey::Code(
+                        KeyCode::Enter | KeyCode::NumpadEnter,
+                    ) => {
+                        let input = std::mem::take(&mut self.input);
+                        self.submit(&input);
+                    }
+                    PhysicalKey::Code(KeyCode::Backspace) => {
+                        self.input.pop();
+                    }
+                    PhysicalKey::Code(KeyCode::ArrowUp) => {
+                        self.history_previous()
+                    }
+                    PhysicalKey::Code(KeyCode::ArrowDown) => {
+                        self.history_next()
+                    }
+                    PhysicalKey::Code(KeyCode::Tab) => self.complete(),
+                    PhysicalKey::Code(KeyCode::Escape) => self.open = false,
+                    _ => {}
+                }
+                true
+            }
+            KeyboardEvent::Released(_) => {
+                self.skip_text = false;
+                true
+            }
+        }
+    }
+
+    /// Draws the console over the top of the window when open, into the
+    /// frame that is being recorded.
+    pub fn draw(
+        &self,
+        renderer: &mut dyn Renderer,
+        surface_size: Vec2,
+        text: &mut TextRenderer,
+    ) -> Result<(), ui::Error> {
+        if !self.open {
+            return Ok(());
+        }
+
+        let line_height = (FONT_SIZE * 1.25).round();
+        let height = (surface_size.y * HEIGHT).round();
+        let text_rect = |top: f32| {
+            Rect::new(
+                Vec2::new(4.0, top),
+                Vec2::new(surface_size.x - 4.0, top + line_height),
+            )
+        };
+        let input_top = height - line_height - 4.0;
+        let mut commands = vec![
+            DrawCommand::Rect {
+                rect: Rect::new(Vec2::ZERO, Vec2::new(surface_size.x, height)),
+                color: Color::rgba(0.0, 0.0, 0.0, 0.85),
+            },
+            DrawCommand::Rect {
+                rect: Rect::new(
+                    Vec2::new(0.0, input_top - 2.0),
+                    Vec2::new(surface_size.x, height),
+                ),
+                color: Color::rgba(0.15, 0.15, 0.15, 0.9),
+            },
+            DrawCommand::Text {
+                rect: text_rect(input_top),
+                text: format!("> {}_", self.input),
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+            },
+        ];
+
+        // Newest lines right above the input
+        let tops = (1..).map(|row| input_top - 4.0 - row as f32 * line_height);
+        commands.extend(
+            self.output
+                .iter()
+                .rev()
+                .zip(tops.take_while(|top| *top >= 0.0))
+                .map(|(line, top)| DrawCommand::Text {
+                    rect: text_rect(top),
+                    text: line.text.clone(),
+                    font_size: FONT_SIZE,
+                    color: line_color(line.kind),
+                }),
+        );
+        ui::paint(renderer, surface_size, &commands, Some(text))
+    }
+}
+
+fn line_color(kind: LineKind) -> Color {
+    match kind {
+        LineKind::Input => Color::rgb(0.6, 0.8, 1.0),
+        LineKind::Output | LineKind::Log(Level::Info) => Color::WHITE,
+        LineKind::Error | LineKind::Log(Level::Error) => {
+            Color::rgb(1.0, 0.4, 0.4)
+        }
+        LineKind::Log(Level::Warn) => Color::rgb(1.0, 0.8, 0.3),
+        LineKind::Log(_) => Color::rgb(0.6, 0.6, 0.6),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: KeyCode) -> Event {
+        Event::Keyboard(
+            WindowId::PRIMARY,
+            KeyboardEvent::Pressed(PhysicalKey::Code(key)),
+        )
+    }
+
+    fn text(text: &str) -> Event {
+        Event::Keyboard(WindowId::PRIMARY, KeyboardEvent::Text(text.into()))
+    }
+
+    fn console() -> Console {
+        let mut console = Console::new();
+        for name in ["timescale", "time.scale", "time.paused", "quit"] {
+            console.register(Command::new(name, "", |_, _| Ok(String::new())));
+        }
+        console
+    }
+
+    #[test]
+    fn typing_completing_and_history() {
+        let mut console = console();
+        assert!(!console.handle_event(&text("a")));
+        // The text of the toggle key is not typed
+        assert!(console.handle_event(&key(KeyCode::Backquote)));
+        assert!(console.handle_event(&text("`")));
+        assert!(console.is_open());
+        assert_eq!(console.input(), "");
+
+        console.handle_event(&text("ti"));
+        console.handle_event(&key(KeyCode::Tab));
+        assert_eq!(console.input(), "time");
+        assert_eq!(
+            console.output().last().unwrap().text,
+            "time.paused  time.scale  timescale"
+        );
+        console.handle_event(&text(".s"));
+        console.handle_event(&key(KeyCode::Tab));
+        assert_eq!(console.input(), "time.scale ");
+
+        console.handle_event(&text("2"));
+        console.handle_event(&key(KeyCode::Enter));
+        console.submit("quit");
+        assert_eq!(console.take_pending().as_deref(), Some("time.scale 2"));
+        assert_eq!(console.take_pending().as_deref(), Some("quit"));
+        assert_eq!(console.history(), ["time.scale 2", "quit"]);
+
+        console.handle_event(&key(KeyCode::ArrowUp));
+        console.handle_event(&key(KeyCode::ArrowUp));
+        assert_eq!(console.input(), "time.scale 2");
+        console.handle_event(&key(KeyCode::Backspace));
+        console.handle_event(&key(KeyCode::ArrowDown));
+        assert_eq!(console.input(), "quit");
+        console.handle_event(&key(KeyCode::ArrowDown));
+        assert_eq!(console.input(), "");
+
+        console.handle_event(&key(KeyCode::Escape));
+        assert!(!console.is_open());
+    }
+
+    #[test]
+    fn completes_non_ascii_names() {
+        let mut console = Console::new();
+        for name in ["é1", "é2", "ça"] {
+            console.register(Command::new(name, "", |_, _| Ok(String::new())));
+        }
+
+        console.set_input("é");
+        console.complete();
+        assert_eq!(console.input(), "é");
+        assert_eq!(console.output().last().unwrap().text, "é1  é2");
+        console.set_input("ç");
+        console.complete();
+        assert_eq!(console.input(), "ça ");
+    }
+
+    #[test]
+    fn mirrors_new_log_entries() {
+        let mut console = Console::new();
+        let buffer = LogBuffer::new(8);
+        buffer.push(Level::Warn, "game", "low health".to_string());
+        console.mirror_log(&buffer);
+        buffer.push(Level::Info, "game", "healed".to_string());
+        console.mirror_log(&buffer);
+
+        assert_eq!(
+            console.output().cloned().collect::<Vec<_>>(),
+            vec![
+                ConsoleLine {
+                    kind: LineKind::Log(Level::Warn),
+                    text: "[game] low health".to_string(),
+                },
+                ConsoleLine {
+                    kind: LineKind::Log(Level::Info),
+                    text: "[game] healed".to_string(),
+                },
+            ]
+        );
+    }
+}
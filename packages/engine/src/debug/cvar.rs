@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use strum::Display;
+
+use crate::core::engine::Engine;
+
+/// Type of a `Value`, used to parse command arguments and cvars.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum ValueKind {
+    Bool,
+    Int,
+    Float,
+    String,
+}
+
+impl ValueKind {
+    /// Accepts `true`/`false`, `on`/`off`, `yes`/`no` and `1`/`0` as bools.
+    pub fn parse(self, text: &str) -> Option<Value> {
+        match self {
+            Self::Bool => match text.to_lowercase().as_str() {
+                "true" | "on" | "yes" | "1" => Some(Value::Bool(true)),
+                "false" | "off" | "no" | "0" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            Self::Int => text.parse().ok().map(Value::Int),
+            Self::Float => text.parse().ok().map(Value::Float),
+            Self::String => Some(Value::String(text.to_string())),
+        }
+    }
+}
+
+/// Argument of a command or value of a cvar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    String(String),
+}
+
+impl Value {
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Self::Bool(_) => ValueKind::Bool,
+            Self::Int(_) => ValueKind::Int,
+            Self::Float(_) => ValueKind::Float,
+            Self::String(_) => ValueKind::String,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Float(value) => write!(f, "{value}"),
+            Self::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+type Getter = dyn Fn(&Engine) -> Value + Send + Sync;
+type Setter = dyn Fn(&mut Engine, Value) -> Result<(), String> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) enum Storage {
+    /// Owned by the console.
+    Value(Value),
+    /// Reads and writes an engine setting.
+    Bound {
+        kind: ValueKind,
+        get: Arc<Getter>,
+        set: Arc<Setter>,
+    },
+}
+
+/// Console variable, typing its name prints the value and typing it followed
+/// by a value changes it.
+#[derive(Clone)]
+pub struct Cvar {
+    name: String,
+    description: String,
+    pub(crate) storage: Storage,
+}
+
+impl Cvar {
+    /// Creates a cvar holding its own value.
+    pub fn new(name: &str, description: &str, value: impl Into<Value>) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            storage: Storage::Value(value.into()),
+        }
+    }
+
+    /// Creates a cvar bound to an engine setting, the setter receives values
+    /// of the passed kind only.
+    pub fn bound<G, S>(
+        name: &str,
+        description: &str,
+        kind: ValueKind,
+        get: G,
+        set: S,
+    ) -> Self
+    where
+        G: Fn(&Engine) -> Value + Send + Sync + 'static,
+        S: Fn(&mut Engine, Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            storage: Storage::Bound {
+                kind,
+                get: Arc::new(get),
+                set: Arc::new(set),
+            },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        match &self.storage {
+            Storage::Value(value) => value.kind(),
+            Storage::Bound { kind, .. } => *kind,
+        }
+    }
+}
+
+impl std::fmt::Debug for Cvar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cvar")
+            .field("name", &self.name)
+            .field("kind", &self.kind())
+            .finish()
+    }
+}
@@ -0,0 +1,226 @@
+use winit::{
+    event::MouseButton,
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::core::{
+    event::{Event, KeyboardEvent, MouseEvent, WindowEvent},
+    window::WindowId,
+};
+
+/// Collects the input of the primary window for `egui`.
+#[derive(Default)]
+pub(crate) struct Input {
+    pointer: egui::Pos2,
+    modifiers: egui::Modifiers,
+    focused: bool,
+    /// Waiting for the next frame.
+    events: Vec<egui::Event>,
+}
+
+impl Input {
+    /// Translates the passed event, modifiers and the pointer position are
+    /// tracked even when `forward` is `false`.
+    pub(crate) fn handle(
+        &mut self,
+        event: &Event,
+        pixels_per_point: f32,
+        forward: bool,
+    ) {
+        let translated = match event {
+            Event::Keyboard(WindowId::PRIMARY, event) => self.keyboard(event),
+            Event::Mouse(WindowId::PRIMARY, event) => {
+                self.mouse(event, pixels_per_point)
+            }
+            Event::Window(WindowId::PRIMARY, WindowEvent::Focused(focused)) => {
+                self.focused = *focused;
+                Some(egui::Event::WindowFocused(*focused))
+            }
+            _ => None,
+        };
+
+        if let Some(event) = translated.filter(|_| forward) {
+            self.events.push(event);
+        }
+    }
+
+    /// Takes the events collected since the last frame.
+    pub(crate) fn take(
+        &mut self,
+        screen_size: egui::Vec2,
+        time: f64,
+    ) -> egui::RawInput {
+        egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                screen_size,
+            )),
+            time: Some(time),
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.events),
+            focused: self.focused,
+            ..Default::default()
+        }
+    }
+
+    fn keyboard(&mut self, event: &KeyboardEvent) -> Option<egui::Event> {
+        let (key, pressed) = match event {
+            KeyboardEvent::Pressed(PhysicalKey::Code(key)) => (*key, true),
+            KeyboardEvent::Released(PhysicalKey::Code(key)) => (*key, false),
+            KeyboardEvent::Text(text) => {
+                return Some(egui::Event::Text(text.clone()));
+            }
+            _ => return None,
+        };
+
+        match key {
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => {
+                self.modifiers.shift = pressed;
+            }
+            KeyCode::ControlLeft | KeyCode::ControlRight => {
+                self.modifiers.ctrl = pressed;
+                self.modifiers.command = pressed;
+            }
+            KeyCode::AltLeft | KeyCode::AltRight => {
+                self.modifiers.alt = pressed
+            }
+            KeyCode::SuperLeft | KeyCode::SuperRight => {
+                self.modifiers.mac_cmd = pressed;
+            }
+            _ => {}
+        }
+
+        Some(egui::Event::Key {
+            key: translate_key(key)?,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers: self.modifiers,
+        })
+    }
+
+    fn mouse(
+        &mut self,
+        event: &MouseEvent,
+        pixels_per_point: f32,
+    ) -> Option<egui::Event> {
+        let (button, pressed) = match event {
+            MouseEvent::Moved(position) => {
+                self.pointer =
+                    egui::pos2(position.x, position.y) / pixels_per_point;
+                return Some(egui::Event::PointerMoved(self.pointer));
+            }
+            MouseEvent::Scrolled(delta) => {
+                return Some(egui::Event::MouseWheel {
+                    unit: egui::MouseWheelUnit::Point,
+                    delta: egui::vec2(delta.x, delta.y) / pixels_per_point,
+                    modifiers: self.modifiers,
+                });
+            }
+            MouseEvent::Pressed(button) => (button, true),
+            MouseEvent::Released(button) => (button, false),
+        };
+
+        let button = match button {
+            MouseButton::Left => egui::PointerButton::Primary,
+            MouseButton::Right => egui::PointerButton::Secondary,
+            MouseButton::Middle => egui::PointerButton::Middle,
+            MouseButton::Back => egui::PointerButton::Extra1,
+            MouseButton::Forward => egui::PointerButton::Extra2,
+            MouseButton::Other(_) => return None,
+        };
+        Some(egui::Event::PointerButton {
+            pos: self.pointer,
+            button,
+            pressed,
+            modifiers: self.modifiers,
+        })
+    }
+}
+
+/// `egui` understands the names used by `winit`, except for the letters.
+fn translate_key(key: KeyCode) -> Option<egui::Key> {
+    let name = format!("{key:?}");
+    egui::Key::from_name(name.strip_prefix("Key").unwrap_or(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+
+    #[test]
+    fn events_are_translated() {
+        let mut input = Input::default();
+        let primary = |event| Event::Mouse(WindowId::PRIMARY, event);
+        input.handle(
+            &primary(MouseEvent::Moved(Vec2::new(20.0, 10.0))),
+            2.0,
+            true,
+        );
+        input.handle(
+            &primary(MouseEvent::Pressed(MouseButton::Left)),
+            2.0,
+            true,
+        );
+        input.handle(
+            &Event::Keyboard(
+                WindowId::PRIMARY,
+                KeyboardEvent::Pressed(PhysicalKey::Code(KeyCode::ShiftLeft)),
+            ),
+            2.0,
+            false,
+        );
+        input.handle(
+            &Event::Keyboard(
+                WindowId::PRIMARY,
+                KeyboardEvent::Pressed(PhysicalKey::Code(KeyCode::KeyA)),
+            ),
+            2.0,
+            true,
+        );
+        input.handle(
+            &Event::Keyboard(
+                WindowId::PRIMARY,
+                KeyboardEvent::Text("A".into()),
+            ),
+            2.0,
+            true,
+        );
+        // Other windows are ignored
+        input.handle(
+            &Event::Mouse(WindowId::new(), MouseEvent::Moved(Vec2::ZERO)),
+            2.0,
+            true,
+        );
+
+        let raw = input.take(egui::vec2(100.0, 50.0), 1.0);
+        assert!(raw.modifiers.shift);
+        let shift = egui::Modifiers::SHIFT;
+        assert_eq!(
+            raw.events,
+            [
+                egui::Event::PointerMoved(egui::pos2(10.0, 5.0)),
+                egui::Event::PointerButton {
+                    pos: egui::pos2(10.0, 5.0),
+                    button: egui::PointerButton::Primary,
+                    pressed: true,
+                    modifiers: egui::Modifiers::NONE,
+                },
+                egui::Event::Key {
+                    key: egui::Key::A,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: shift,
+                },
+                egui::Event::Text("A".to_string()),
+            ]
+        );
+        assert!(input.take(egui::vec2(100.0, 50.0), 2.0).events.is_empty());
+
+        assert_eq!(translate_key(KeyCode::Digit4), Some(egui::Key::Num4));
+        assert_eq!(translate_key(KeyCode::ArrowUp), Some(egui::Key::ArrowUp));
+        assert_eq!(translate_key(KeyCode::CapsLock), None);
+    }
+}
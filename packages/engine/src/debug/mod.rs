@@ -0,0 +1,68 @@
+//! Development helpers.
+//!
+//! `Tweakables` are always available, so the code reading them does not
+//! depend on features. The `DebugOverlay` showing and editing them requires
+//! the `debug-overlay` feature, without it nothing of the overlay is compiled.
+//!
+//! The `Console` is toggled with a key and runs typed `Command`s, changes
+//! `Cvar`s bound to engine settings and mirrors the log. Every engine has a
+//! few built-in commands (`help`, `exec`, `timescale`, `spawn`, `quit`, ...),
+//! the `startup_script` of the `EngineConfig` runs when the engine starts.
+//!
+//! ```
+//! # use unen_engine::{core::engine::Engine, debug::{Command, ValueKind}};
+//! let mut engine = Engine::default();
+//! engine.console_mut().register(
+//!     Command::new("greet", "Says hello", |_, args| {
+//!         Ok(format!("Hello {}!", args.string("name").unwrap()))
+//!     })
+//!     .with_param("name", ValueKind::String),
+//! );
+//!
+//! assert_eq!(engine.execute("greet world").unwrap(), "Hello world!");
+//! engine.execute("time.scale 0.5").unwrap();
+//! assert_eq!(engine.time().scale(), 0.5);
+//! ```
+
+use thiserror::Error;
+
+mod builtin;
+pub mod command;
+pub mod console;
+pub mod cvar;
+#[cfg(feature = "debug-overlay")]
+mod input;
+#[cfg(feature = "debug-overlay")]
+pub mod overlay;
+#[cfg(feature = "debug-overlay")]
+mod painter;
+pub mod tweak;
+
+pub(crate) use builtin::register as register_builtins;
+pub use command::{Args, Command, Param};
+pub use console::{Console, ConsoleLine, LineKind};
+pub use cvar::{Cvar, Value, ValueKind};
+#[cfg(feature = "debug-overlay")]
+pub use overlay::{DebugOverlay, DebugStatus};
+pub use tweak::{Tweakable, Tweakables};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("Unknown command or cvar '{0}'")]
+    UnknownCommand(String),
+    /// Wrong amount of arguments, contains the usage of the command.
+    #[error("Usage: {0}")]
+    Usage(String),
+    #[error("Invalid {1} for '{0}': {2}")]
+    InvalidArgument(String, ValueKind, String),
+    #[error("Unclosed quote")]
+    UnclosedQuote,
+    /// The command reported an error.
+    #[error("{0}")]
+    Failed(String),
+    #[error("Failed to read script: {0}")]
+    Io(String),
+    /// Scripts running each other, most likely in a loop.
+    #[error("Scripts nested more than {0} levels deep")]
+    ExecTooDeep(usize),
+}
@@ -0,0 +1,370 @@
+use std::{collections::VecDeque, time::Duration};
+
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::{
+    core::{
+        event::{Event, KeyboardEvent},
+        scheduler::worker::{WorkerKind, WorkerState},
+        window::WindowId,
+    },
+    render::{Error, Renderer, SurfaceId},
+};
+
+use super::{input::Input, painter::Painter, Tweakable, Tweakables};
+
+/// Frames kept for the statistics and the graph.
+const FRAME_HISTORY: usize = 240;
+/// Frame time drawn as a line in the graph.
+const TARGET_FRAME_TIME: f32 = 1000.0 / 60.0;
+
+/// Engine state shown by the overlay.
+#[derive(Debug, Clone, Default)]
+pub struct DebugStatus {
+    pub workers: Vec<(WorkerKind, WorkerState)>,
+    pub entities: usize,
+    pub fixed_ticks: u64,
+    pub paused: bool,
+}
+
+/// In-game overlay showing frame statistics, the workers and the
+/// tweakables, toggled with a hotkey.
+///
+/// _Input is only taken from the primary window._
+pub struct DebugOverlay {
+    context: egui::Context,
+    input: Input,
+    painter: Painter,
+    visible: bool,
+    hotkey: KeyCode,
+    /// Milliseconds, oldest first.
+    frame_times: VecDeque<f32>,
+    /// Seconds since the first frame.
+    elapsed: f64,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            context: egui::Context::default(),
+            input: Input::default(),
+            painter: Painter::default(),
+            visible: false,
+            hotkey: KeyCode::F3,
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl DebugOverlay {
+    /// Creates a hidden overlay toggled with `F3`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_hotkey(mut self, hotkey: KeyCode) -> Self {
+        self.hotkey = hotkey;
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Scale of the overlay, usually the scale factor of the window.
+    pub fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+        self.context.set_pixels_per_point(pixels_per_point);
+    }
+
+    /// Whether the pointer is over the overlay, the game should ignore it.
+    pub fn wants_pointer(&self) -> bool {
+        self.visible && self.context.wants_pointer_input()
+    }
+
+    /// Whether a text field has focus, the game should ignore the keyboard.
+    pub fn wants_keyboard(&self) -> bool {
+        self.visible && self.context.wants_keyboard_input()
+    }
+
+    /// Feeds an input event, toggling the overlay on the hotkey.
+    ///
+    /// _The `Engine` does this for every event it handles._
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::Keyboard(
+            WindowId::PRIMARY,
+            KeyboardEvent::Pressed(PhysicalKey::Code(key)),
+        ) = event
+        {
+            if *key == self.hotkey {
+                self.visible = !self.visible;
+                return;
+            }
+        }
+
+        let pixels_per_point = self.context.pixels_per_point();
+        self.input.handle(event, pixels_per_point, self.visible);
+    }
+
+    /// Records the duration of a frame.
+    pub fn record_frame(&mut self, delta: Duration) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta.as_secs_f32() * 1000.0);
+        self.elapsed += delta.as_secs_f64();
+    }
+
+    /// Average frames per second over the recorded frames.
+    pub fn fps(&self) -> f32 {
+        let total = self.frame_times.iter().sum::<f32>();
+        if total > 0.0 {
+            self.frame_times.len() as f32 * 1000.0 / total
+        } else {
+            0.0
+        }
+    }
+
+    /// Draws the overlay into the active frame, nothing is drawn while
+    /// hidden.
+    pub fn render(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        surface: SurfaceId,
+        status: &DebugStatus,
+        tweakables: &mut Tweakables,
+    ) -> Result<(), Error> {
+        if !self.visible {
+            return Ok(());
+        }
+        crate::profile_scope!("DebugOverlay::render", "debug");
+
+        let size = renderer.surface_size(surface)?;
+        let pixels_per_point = self.context.pixels_per_point();
+        let input = self.input.take(
+            egui::vec2(size.0 as f32, size.1 as f32) / pixels_per_point,
+            self.elapsed,
+        );
+
+        let context = self.context.clone();
+        let output = context.run(input, |context| {
+            egui::Window::new("Debug")
+                .default_pos([8.0, 8.0])
+                .resizable(false)
+                .show(context, |ui| {
+                    self.frame_ui(ui, status);
+                    workers_ui(ui, status);
+                    if !tweakables.is_empty() {
+                        egui::CollapsingHeader::new("Tweakables")
+                            .default_open(true)
+                            .show(ui, |ui| tweakables_ui(ui, tweakables));
+                    }
+                });
+        });
+        let primitives =
+            context.tessellate(output.shapes, output.pixels_per_point);
+
+        self.painter.paint(
+            renderer,
+            size,
+            output.pixels_per_point,
+            &output.textures_delta,
+            &primitives,
+        )
+    }
+
+    fn frame_ui(&self, ui: &mut egui::Ui, status: &DebugStatus) {
+        let last = self.frame_times.back().copied().unwrap_or_default();
+        let worst = self.frame_times.iter().copied().fold(0.0, f32::max);
+        ui.label(format!("{:.0} FPS", self.fps()));
+        ui.label(format!("Frame: {last:.2} ms (worst {worst:.2} ms)"));
+        ui.label(format!(
+            "Entities: {}, fixed ticks: {}{}",
+            status.entities,
+            status.fixed_ticks,
+            if status.paused { " (paused)" } else { "" }
+        ));
+
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width().max(200.0), 60.0),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(120));
+
+        // Always fits the target, grows with the slowest frame
+        let scale = worst.max(TARGET_FRAME_TIME * 2.0);
+        let y = |time: f32| rect.bottom() - rect.height() * time / scale;
+        painter.hline(
+            rect.x_range(),
+            y(TARGET_FRAME_TIME),
+            egui::Stroke::new(1.0, egui::Color32::DARK_GREEN),
+        );
+
+        let step = rect.width() / (FRAME_HISTORY - 1) as f32;
+        let offset = FRAME_HISTORY - self.frame_times.len();
+        let points = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(index, time)| {
+                egui::pos2(
+                    rect.left() + (offset + index) as f32 * step,
+                    y(*time),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.0, egui::Color32::LIGHT_YELLOW),
+        ));
+    }
+}
+
+fn workers_ui(ui: &mut egui::Ui, status: &DebugStatus) {
+    egui::CollapsingHeader::new(format!("Workers ({})", status.workers.len()))
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("workers").striped(true).show(ui, |ui| {
+                for (kind, state) in &status.workers {
+                    ui.label(kind.to_string());
+                    ui.label(state.to_string());
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+fn tweakables_ui(ui: &mut egui::Ui, tweakables: &mut Tweakables) {
+    for (name, tweakable) in tweakables.iter_mut() {
+        match tweakable {
+            Tweakable::Bool(value) => {
+                ui.checkbox(value, name);
+            }
+            Tweakable::Int { value, range } => {
+                ui.add(egui::Slider::new(value, range.clone()).text(name));
+            }
+            Tweakable::Float { value, range } => {
+                ui.add(egui::Slider::new(value, range.clone()).text(name));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{Color, SoftwareRenderer};
+
+    fn press(key: KeyCode) -> Event {
+        Event::Keyboard(
+            WindowId::PRIMARY,
+            KeyboardEvent::Pressed(PhysicalKey::Code(key)),
+        )
+    }
+
+    fn frame(
+        overlay: &mut DebugOverlay,
+        renderer: &mut SoftwareRenderer,
+        surface: SurfaceId,
+        tweakables: &mut Tweakables,
+    ) -> usize {
+        renderer.begin_frame(surface).unwrap();
+        renderer.clear(Color::BLACK).unwrap();
+        let status = DebugStatus {
+            workers: vec![(WorkerKind::Generic(0), WorkerState::Idle)],
+            ..Default::default()
+        };
+        overlay
+            .render(renderer, surface, &status, tweakables)
+            .unwrap();
+        renderer.end_frame().unwrap().draw_calls
+    }
+
+    #[test]
+    fn hotkey_toggles_the_overlay() {
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(320, 240).unwrap();
+        let mut tweakables = Tweakables::new();
+        let mut overlay = DebugOverlay::new().with_hotkey(KeyCode::F1);
+        for _ in 0..10 {
+            overlay.record_frame(Duration::from_millis(20));
+        }
+        assert!((overlay.fps() - 50.0).abs() < 0.01);
+
+        assert_eq!(
+            frame(&mut overlay, &mut renderer, surface, &mut tweakables),
+            0
+        );
+
+        overlay.handle_event(&press(KeyCode::F3));
+        assert!(!overlay.is_visible());
+        overlay.handle_event(&press(KeyCode::F1));
+        assert!(overlay.is_visible());
+        // The first frame only measures the window
+        frame(&mut overlay, &mut renderer, surface, &mut tweakables);
+        assert!(
+            frame(&mut overlay, &mut renderer, surface, &mut tweakables) > 0
+        );
+
+        // Something was drawn at the top-left corner
+        let image = renderer.surface(surface).unwrap();
+        let drawn = (0..100)
+            .flat_map(|x| (0..100).map(move |y| (x, y)))
+            .filter(|&(x, y)| image.pixel(x, y) != Color::BLACK.to_rgba8())
+            .count();
+        assert!(drawn > 1000, "only {drawn} pixels drawn");
+
+        overlay.handle_event(&press(KeyCode::F1));
+        assert_eq!(
+            frame(&mut overlay, &mut renderer, surface, &mut tweakables),
+            0
+        );
+    }
+
+    #[test]
+    fn tweakables_are_editable() {
+        let mut tweakables = Tweakables::new();
+        tweakables.register("wireframe", Tweakable::Bool(false));
+
+        let context = egui::Context::default();
+        let mut run = |events| {
+            let input = egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(200.0, 200.0),
+                )),
+                events,
+                ..Default::default()
+            };
+            let _ = context.run(input, |context| {
+                egui::CentralPanel::default()
+                    .show(context, |ui| tweakables_ui(ui, &mut tweakables));
+            });
+        };
+
+        // The checkbox is the first row of the panel
+        let click = |pressed| egui::Event::PointerButton {
+            pos: egui::pos2(20.0, 16.0),
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        run(vec![]);
+        run(vec![
+            egui::Event::PointerMoved(egui::pos2(20.0, 16.0)),
+            click(true),
+        ]);
+        run(vec![click(false)]);
+        assert_eq!(tweakables.bool("wireframe"), Some(true));
+    }
+}
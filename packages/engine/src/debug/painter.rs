@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use egui::{epaint::Primitive, ClippedPrimitive, ImageData, TexturesDelta};
+
+use crate::{
+    math::Vec2,
+    render::{
+        Color, DrawCall, Error, FilterMode, Renderer, ScissorRect,
+        TextureDescriptor, TextureId, Vertex,
+    },
+};
+
+/// Draws the output of `egui` with a `Renderer`.
+#[derive(Default)]
+pub(crate) struct Painter {
+    textures: HashMap<egui::TextureId, TextureId>,
+}
+
+impl Painter {
+    /// Draws into the active frame, `size` is the size of its surface in
+    /// pixels.
+    pub(crate) fn paint(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        size: (u32, u32),
+        pixels_per_point: f32,
+        textures: &TexturesDelta,
+        primitives: &[ClippedPrimitive],
+    ) -> Result<(), Error> {
+        for (id, delta) in &textures.set {
+            self.set_texture(renderer, *id, delta)?;
+        }
+
+        let mut vertices = Vec::new();
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in primitives
+        {
+            // Custom painting callbacks are not supported
+            let Primitive::Mesh(mesh) = primitive else {
+                continue;
+            };
+            let Some(&texture) = self.textures.get(&mesh.texture_id) else {
+                continue;
+            };
+            let Some(scissor) = scissor(*clip_rect * pixels_per_point, size)
+            else {
+                continue;
+            };
+
+            let scale = Vec2::new(
+                2.0 * pixels_per_point / size.0 as f32,
+                -2.0 * pixels_per_point / size.1 as f32,
+            );
+            vertices.clear();
+            vertices.extend(mesh.vertices.iter().map(|vertex| {
+                Vertex::new(
+                    Vec2::new(vertex.pos.x, vertex.pos.y) * scale
+                        + Vec2::new(-1.0, 1.0),
+                    Vec2::new(vertex.uv.x, vertex.uv.y),
+                    Color::from_rgba8(vertex.color.to_srgba_unmultiplied()),
+                )
+            }));
+            renderer.draw(
+                &DrawCall::new(&vertices, &mesh.indices)
+                    .with_texture(texture)
+                    .with_scissor(scissor),
+            )?;
+        }
+
+        for id in &textures.free {
+            if let Some(texture) = self.textures.remove(id) {
+                renderer.destroy_texture(texture)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_texture(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        id: egui::TextureId,
+        delta: &egui::epaint::ImageDelta,
+    ) -> Result<(), Error> {
+        // The renderer expects colors without premultiplied alpha
+        let data = match &delta.image {
+            ImageData::Color(image) => image
+                .pixels
+                .iter()
+                .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+                .collect::<Vec<_>>(),
+            ImageData::Font(image) => image
+                .srgba_pixels(None)
+                .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+                .collect(),
+        };
+        let [width, height] = delta.image.size().map(|side| side as u32);
+
+        match (delta.pos, self.textures.get(&id)) {
+            (Some([x, y]), Some(&texture)) => {
+                let region = ScissorRect {
+                    x: x as u32,
+                    y: y as u32,
+                    width,
+                    height,
+                };
+                renderer.update_texture(texture, region, &data)
+            }
+            (_, previous) => {
+                if let Some(&previous) = previous {
+                    renderer.destroy_texture(previous)?;
+                }
+                let filter = match delta.options.magnification {
+                    egui::TextureFilter::Nearest => FilterMode::Nearest,
+                    egui::TextureFilter::Linear => FilterMode::Linear,
+                };
+                let texture = renderer.create_texture(
+                    &TextureDescriptor::new(width, height).with_filter(filter),
+                    &data,
+                )?;
+                self.textures.insert(id, texture);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Converts a clip rectangle in pixels, `None` if nothing is visible.
+fn scissor(rect: egui::Rect, size: (u32, u32)) -> Option<ScissorRect> {
+    let clamp =
+        |value: f32, max: u32| value.round().clamp(0.0, max as f32) as u32;
+    let (left, top) = (clamp(rect.min.x, size.0), clamp(rect.min.y, size.1));
+    let (right, bottom) =
+        (clamp(rect.max.x, size.0), clamp(rect.max.y, size.1));
+    (right > left && bottom > top).then(|| ScissorRect {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+/// Value that can be edited while the game runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tweakable {
+    Bool(bool),
+    Int {
+        value: i64,
+        range: RangeInclusive<i64>,
+    },
+    Float {
+        value: f32,
+        range: RangeInclusive<f32>,
+    },
+}
+
+/// Named values edited from the debug overlay, read them every frame to
+/// pick up the changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tweakables {
+    values: BTreeMap<String, Tweakable>,
+}
+
+impl Tweakables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a tweakable, keeping the current value if it already exists with
+    /// the same type.
+    pub fn register(&mut self, name: &str, tweakable: Tweakable) {
+        match self.values.get_mut(name) {
+            Some(current)
+                if std::mem::discriminant(current)
+                    == std::mem::discriminant(&tweakable) =>
+            {
+                let value = current.clone();
+                *current = tweakable;
+                current.set_from(&value);
+            }
+            _ => {
+                self.values.insert(name.to_string(), tweakable);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Tweakable> {
+        self.values.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tweakable> {
+        self.values.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tweakable> {
+        self.values.get_mut(name)
+    }
+
+    /// Returns `None` if missing or not a bool.
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.values.get(name)? {
+            Tweakable::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` if missing or not an int.
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.values.get(name)? {
+            Tweakable::Int { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` if missing or not a float.
+    pub fn float(&self, name: &str) -> Option<f32> {
+        match self.values.get(name)? {
+            Tweakable::Float { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Tweakable)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Sorted by name.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Tweakable)> {
+        self.values
+            .iter_mut()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl Tweakable {
+    /// Copies the value of another tweakable of the same type, clamped to
+    /// the range of this one.
+    fn set_from(&mut self, other: &Tweakable) {
+        match (self, other) {
+            (Self::Bool(value), Self::Bool(other)) => *value = *other,
+            (Self::Int { value, range }, Self::Int { value: other, .. }) => {
+                *value = (*other).clamp(*range.start(), *range.end());
+            }
+            (
+                Self::Float { value, range },
+                Self::Float { value: other, .. },
+            ) => {
+                *value = other.clamp(*range.start(), *range.end());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registering_keeps_edited_values() {
+        let mut tweakables = Tweakables::new();
+        tweakables.register("god_mode", Tweakable::Bool(false));
+        tweakables.register(
+            "speed",
+            Tweakable::Float {
+                value: 1.0,
+                range: 0.0..=10.0,
+            },
+        );
+        assert_eq!(tweakables.bool("god_mode"), Some(false));
+        assert_eq!(tweakables.int("speed"), None);
+
+        if let Some(Tweakable::Float { value, .. }) =
+            tweakables.get_mut("speed")
+        {
+            *value = 8.0;
+        }
+        // Registering again, e.g. after a hot reload, keeps the edits
+        tweakables.register(
+            "speed",
+            Tweakable::Float {
+                value: 1.0,
+                range: 0.0..=5.0,
+            },
+        );
+        assert_eq!(tweakables.float("speed"), Some(5.0));
+
+        tweakables.register(
+            "speed",
+            Tweakable::Int {
+                value: 3,
+                range: 0..=5,
+            },
+        );
+        assert_eq!(tweakables.int("speed"), Some(3));
+        assert_eq!(
+            tweakables.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["god_mode", "speed"]
+        );
+    }
+}
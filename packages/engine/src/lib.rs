@@ -1 +1,13 @@
+pub mod animation;
+pub mod asset;
+pub mod audio;
 pub mod core;
+pub mod debug;
+pub mod math;
+pub mod net;
+pub mod physics;
+pub mod render;
+pub mod scene;
+pub mod script;
+pub mod spatial;
+pub mod ui;
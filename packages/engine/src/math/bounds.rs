@@ -0,0 +1,383 @@
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use super::Transform;
+
+/// Axis aligned bounding box in 3D.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Creates a box containing both corners, in any order.
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn from_center_size(center: Vec3, size: Vec3) -> Self {
+        let half = size.abs() * 0.5;
+        Self {
+            min: center - half,
+            max: center + half,
+        }
+    }
+
+    /// Smallest box containing every point, `None` if there are no points.
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(
+        points: I,
+    ) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(
+            points.fold(Self::new(first, first), |aabb, point| {
+                aabb.expand_to(point)
+            }),
+        )
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn half_size(&self) -> Vec3 {
+        self.size() * 0.5
+    }
+
+    /// Surface area, used by bounding volume hierarchies as a cost metric.
+    pub fn surface_area(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Returns `true` if `other` is completely inside this box.
+    pub fn contains(&self, other: &Self) -> bool {
+        other.min.cmpge(self.min).all() && other.max.cmple(self.max).all()
+    }
+
+    /// Returns `true` if both boxes overlap, touching counts as overlapping.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.distance_squared(sphere.center) <= sphere.radius * sphere.radius
+    }
+
+    /// Overlapping region of both boxes, `None` if they do not intersect.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.intersects(other).then(|| Self {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        })
+    }
+
+    /// Smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Grows the box to contain the passed point.
+    pub fn expand_to(&self, point: Vec3) -> Self {
+        Self {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    /// Grows the box by `margin` on every side.
+    pub fn inflate(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - Vec3::splat(margin),
+            max: self.max + Vec3::splat(margin),
+        }
+    }
+
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        point.clamp(self.min, self.max)
+    }
+
+    /// Squared distance to the passed point, zero if it is inside.
+    pub fn distance_squared(&self, point: Vec3) -> f32 {
+        self.closest_point(point).distance_squared(point)
+    }
+
+    /// Box containing this one after being transformed.
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        let corners = (0..8).map(|i| {
+            let corner = Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            transform.transform_point(corner)
+        });
+        // There are always 8 corners
+        Self::from_points(corners).unwrap_or(*self)
+    }
+}
+
+/// Axis aligned rectangle in 2D.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Rect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Rect {
+    /// Creates a rectangle containing both corners, in any order.
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Self {
+        let half = size.abs() * 0.5;
+        Self {
+            min: center - half,
+            max: center + half,
+        }
+    }
+
+    /// Smallest rectangle containing every point, `None` if there are no
+    /// points.
+    pub fn from_points<I: IntoIterator<Item = Vec2>>(
+        points: I,
+    ) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(
+            points.fold(Self::new(first, first), |rect, point| {
+                rect.expand_to(point)
+            }),
+        )
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn half_size(&self) -> Vec2 {
+        self.size() * 0.5
+    }
+
+    pub fn area(&self) -> f32 {
+        let size = self.size();
+        size.x * size.y
+    }
+
+    /// Perimeter, used by bounding volume hierarchies as a cost metric.
+    pub fn perimeter(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x + size.y)
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Returns `true` if `other` is completely inside this rectangle.
+    pub fn contains(&self, other: &Self) -> bool {
+        other.min.cmpge(self.min).all() && other.max.cmple(self.max).all()
+    }
+
+    /// Returns `true` if both rectangles overlap, touching counts as
+    /// overlapping.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+
+    pub fn intersects_circle(&self, circle: &Circle) -> bool {
+        self.distance_squared(circle.center) <= circle.radius * circle.radius
+    }
+
+    /// Overlapping region of both rectangles, `None` if they do not
+    /// intersect.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.intersects(other).then(|| Self {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        })
+    }
+
+    /// Smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Grows the rectangle to contain the passed point.
+    pub fn expand_to(&self, point: Vec2) -> Self {
+        Self {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    /// Grows the rectangle by `margin` on every side.
+    pub fn inflate(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - Vec2::splat(margin),
+            max: self.max + Vec2::splat(margin),
+        }
+    }
+
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        point.clamp(self.min, self.max)
+    }
+
+    /// Squared distance to the passed point, zero if it is inside.
+    pub fn distance_squared(&self, point: Vec2) -> f32 {
+        self.closest_point(point).distance_squared(point)
+    }
+}
+
+/// Bounding sphere.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        let radius = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radius * radius
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_center_size(self.center, Vec3::splat(self.radius * 2.0))
+    }
+}
+
+/// Bounding circle.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        let radius = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radius * radius
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::from_center_size(self.center, Vec2::splat(self.radius * 2.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Quat;
+
+    use super::*;
+
+    #[test]
+    fn aabb_queries() {
+        let a = Aabb::new(Vec3::ONE, Vec3::ZERO);
+        assert_eq!(a.min, Vec3::ZERO);
+        assert!(a.contains_point(Vec3::splat(0.5)));
+        assert!(!a.contains_point(Vec3::new(0.5, 1.5, 0.5)));
+
+        let b = Aabb::from_center_size(Vec3::ONE, Vec3::ONE);
+        assert!(a.intersects(&b));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Aabb::new(Vec3::splat(0.5), Vec3::ONE))
+        );
+        assert_eq!(a.union(&b), Aabb::new(Vec3::ZERO, Vec3::splat(1.5)));
+
+        let far = Aabb::from_center_size(Vec3::splat(5.0), Vec3::ONE);
+        assert!(!a.intersects(&far));
+        assert_eq!(a.intersection(&far), None);
+        assert!(a.union(&far).contains(&far));
+
+        assert!(
+            a.intersects_sphere(&Sphere::new(Vec3::new(2.0, 0.5, 0.5), 1.0))
+        );
+        assert!(!a.intersects_sphere(&Sphere::new(Vec3::splat(2.0), 1.0)));
+    }
+
+    #[test]
+    fn transformed_aabb() {
+        let aabb = Aabb::new(Vec3::NEG_ONE, Vec3::ONE);
+        let transform = Transform::from_translation(Vec3::X * 10.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4));
+
+        let transformed = aabb.transformed(&transform);
+        let extent = 2.0_f32.sqrt();
+        assert!(transformed
+            .min
+            .abs_diff_eq(Vec3::new(10.0 - extent, -extent, -1.0), 1e-5));
+        assert!(transformed
+            .max
+            .abs_diff_eq(Vec3::new(10.0 + extent, extent, 1.0), 1e-5));
+    }
+
+    #[test]
+    fn rect_queries() {
+        let rect = Rect::from_center_size(Vec2::ZERO, Vec2::new(4.0, 2.0));
+        assert_eq!(rect.area(), 8.0);
+        assert_eq!(rect.perimeter(), 12.0);
+        assert!(rect.contains_point(Vec2::new(2.0, 1.0)));
+        assert!(rect.intersects_circle(&Circle::new(Vec2::new(3.0, 0.0), 1.0)));
+        assert!(!rect.intersects_circle(&Circle::new(Vec2::new(3.0, 2.0), 1.0)));
+        assert_eq!(
+            Rect::from_points([Vec2::ONE, Vec2::NEG_ONE, Vec2::X * 3.0]),
+            Some(Rect::new(Vec2::NEG_ONE, Vec2::new(3.0, 1.0)))
+        );
+    }
+
+    #[test]
+    fn spheres() {
+        let a = Sphere::new(Vec3::ZERO, 1.0);
+        assert!(a.intersects(&Sphere::new(Vec3::X * 2.0, 1.0)));
+        assert!(!a.intersects(&Sphere::new(Vec3::X * 2.1, 1.0)));
+        assert_eq!(a.aabb(), Aabb::new(Vec3::NEG_ONE, Vec3::ONE));
+
+        let circle = Circle::new(Vec2::ZERO, 2.0);
+        assert!(circle.contains_point(Vec2::new(1.0, 1.0)));
+        assert!(!circle.intersects(&Circle::new(Vec2::X * 5.0, 2.0)));
+    }
+}
@@ -0,0 +1,28 @@
+//! Math types shared by every engine API.
+//!
+//! Vectors, matrices and quaternions come from `glam` (re-exported here, so
+//! games do not need to depend on it directly), everything built on top of them
+//! lives in this module:
+//!
+//! - `Transform`: translation, rotation and scale that can be composed and
+//!   inverted.
+//! - `Aabb` / `Rect`: axis aligned bounding boxes in 3D and 2D.
+//! - `Sphere` / `Circle`: bounding spheres in 3D and 2D.
+//! - `Ray` / `Ray2d`: rays with intersection tests against the shapes above.
+//!
+//! Every type can be (de)serialized with serde.
+
+pub mod bounds;
+pub mod ray;
+pub mod transform;
+
+pub use bounds::{Aabb, Circle, Rect, Sphere};
+pub use glam::{
+    Affine2, Affine3A, EulerRot, IVec2, IVec3, Mat2, Mat3, Mat4, Quat, UVec2,
+    UVec3, Vec2, Vec3, Vec4,
+};
+pub use ray::{Ray, Ray2d};
+pub use transform::Transform;
+
+/// Tolerance used when comparing floats.
+pub const EPSILON: f32 = 1e-5;
@@ -0,0 +1,242 @@
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Circle, Rect, Sphere, EPSILON};
+
+/// Half-line in 3D.
+///
+/// Intersection tests return the distance along the ray of the first hit,
+/// rays starting inside a shape hit it at `0.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Always normalized.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Creates a new ray, the direction gets normalized.
+    ///
+    /// Returns `None` if the direction has no length.
+    pub fn new(origin: Vec3, direction: Vec3) -> Option<Self> {
+        Some(Self {
+            origin,
+            direction: direction.try_normalize()?,
+        })
+    }
+
+    /// Returns the point at the passed distance along the ray.
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Slab test.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inverse = self.direction.recip();
+        let t1 = (aabb.min - self.origin) * inverse;
+        let t2 = (aabb.max - self.origin) * inverse;
+
+        // NaN appears when the origin lies on a slab parallel to the ray,
+        // `min` and `max` ignore it
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element();
+
+        (near <= far).then_some(near)
+    }
+
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let offset = self.origin - sphere.center;
+        let b = offset.dot(self.direction);
+        let c = offset.length_squared() - sphere.radius * sphere.radius;
+        // Outside and pointing away
+        if c > 0.0 && b > 0.0 {
+            return None;
+        }
+
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        Some((-b - discriminant.sqrt()).max(0.0))
+    }
+
+    /// Intersection with the plane going through `point` with the passed
+    /// `normal`.
+    pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+        let denominator = normal.dot(self.direction);
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+
+        let distance = (point - self.origin).dot(normal) / denominator;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Möller–Trumbore intersection, both faces are hit.
+    pub fn intersect_triangle(&self, triangle: [Vec3; 3]) -> Option<f32> {
+        let edge1 = triangle[1] - triangle[0];
+        let edge2 = triangle[2] - triangle[0];
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let t = self.origin - triangle[0];
+        let u = t.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = t.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge2.dot(q) * inverse;
+        (distance >= 0.0).then_some(distance)
+    }
+}
+
+/// Half-line in 2D, see `Ray`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Ray2d {
+    pub origin: Vec2,
+    /// Always normalized.
+    pub direction: Vec2,
+}
+
+impl Ray2d {
+    /// Creates a new ray, the direction gets normalized.
+    ///
+    /// Returns `None` if the direction has no length.
+    pub fn new(origin: Vec2, direction: Vec2) -> Option<Self> {
+        Some(Self {
+            origin,
+            direction: direction.try_normalize()?,
+        })
+    }
+
+    /// Returns the point at the passed distance along the ray.
+    pub fn at(&self, distance: f32) -> Vec2 {
+        self.origin + self.direction * distance
+    }
+
+    /// Slab test.
+    pub fn intersect_rect(&self, rect: &Rect) -> Option<f32> {
+        let inverse = self.direction.recip();
+        let t1 = (rect.min - self.origin) * inverse;
+        let t2 = (rect.max - self.origin) * inverse;
+
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element();
+
+        (near <= far).then_some(near)
+    }
+
+    pub fn intersect_circle(&self, circle: &Circle) -> Option<f32> {
+        let offset = self.origin - circle.center;
+        let b = offset.dot(self.direction);
+        let c = offset.length_squared() - circle.radius * circle.radius;
+        if c > 0.0 && b > 0.0 {
+            return None;
+        }
+
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        Some((-b - discriminant.sqrt()).max(0.0))
+    }
+
+    /// Intersection with the segment going from `a` to `b`.
+    pub fn intersect_segment(&self, a: Vec2, b: Vec2) -> Option<f32> {
+        let segment = b - a;
+        let denominator = self.direction.perp_dot(segment);
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+
+        let offset = a - self.origin;
+        let distance = offset.perp_dot(segment) / denominator;
+        let along = offset.perp_dot(self.direction) / denominator;
+
+        (distance >= 0.0 && (0.0..=1.0).contains(&along)).then_some(distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_aabb() {
+        let aabb = Aabb::new(Vec3::NEG_ONE, Vec3::ONE);
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::X).unwrap();
+        assert_eq!(ray.intersect_aabb(&aabb), Some(4.0));
+
+        // Pointing away
+        let ray = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::NEG_X).unwrap();
+        assert_eq!(ray.intersect_aabb(&aabb), None);
+
+        // Starting inside
+        let ray = Ray::new(Vec3::ZERO, Vec3::Y).unwrap();
+        assert_eq!(ray.intersect_aabb(&aabb), Some(0.0));
+
+        // Parallel and outside
+        let ray = Ray::new(Vec3::new(-5.0, 2.0, 0.0), Vec3::X).unwrap();
+        assert_eq!(ray.intersect_aabb(&aabb), None);
+
+        assert!(Ray::new(Vec3::ZERO, Vec3::ZERO).is_none());
+    }
+
+    #[test]
+    fn ray_sphere_plane_triangle() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::NEG_Z).unwrap();
+
+        let sphere = Sphere::new(Vec3::ZERO, 2.0);
+        assert_eq!(ray.intersect_sphere(&sphere), Some(8.0));
+        let missed = Sphere::new(Vec3::X * 3.0, 2.0);
+        assert_eq!(ray.intersect_sphere(&missed), None);
+
+        assert_eq!(ray.intersect_plane(Vec3::ZERO, Vec3::Z), Some(10.0));
+        assert_eq!(ray.intersect_plane(Vec3::ZERO, Vec3::X), None);
+
+        let triangle = [
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(1.0, -1.0, 1.0),
+            Vec3::new(0.0, 1.0, 1.0),
+        ];
+        let distance = ray.intersect_triangle(triangle).unwrap();
+        assert!((distance - 9.0).abs() < EPSILON);
+        assert_eq!(ray.at(distance).z, 1.0);
+
+        let shifted = triangle.map(|vertex| vertex + Vec3::X * 5.0);
+        assert_eq!(ray.intersect_triangle(shifted), None);
+    }
+
+    #[test]
+    fn ray_2d() {
+        let ray = Ray2d::new(Vec2::ZERO, Vec2::new(1.0, 1.0)).unwrap();
+        let rect = Rect::new(Vec2::splat(2.0), Vec2::splat(3.0));
+        let distance = ray.intersect_rect(&rect).unwrap();
+        assert!(ray.at(distance).abs_diff_eq(Vec2::splat(2.0), EPSILON));
+
+        let circle = Circle::new(Vec2::new(5.0, 0.0), 1.0);
+        let ray = Ray2d::new(Vec2::ZERO, Vec2::X).unwrap();
+        assert_eq!(ray.intersect_circle(&circle), Some(4.0));
+
+        let distance = ray
+            .intersect_segment(Vec2::new(3.0, -1.0), Vec2::new(3.0, 1.0))
+            .unwrap();
+        assert!((distance - 3.0).abs() < EPSILON);
+        assert_eq!(
+            ray.intersect_segment(Vec2::new(3.0, 1.0), Vec2::new(3.0, 2.0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::ops::Mul;
+
+use glam::{Mat4, Quat, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// Position, rotation and scale of an object.
+///
+/// Applied in the scale, rotate, translate order, like `Mat4` built from
+/// `Mat4::from_scale_rotation_translation()`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    /// Does not change anything.
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Creates a 2D transform, rotating around the z axis.
+    pub fn from_2d(translation: Vec2, rotation: f32, scale: Vec2) -> Self {
+        Self {
+            translation: translation.extend(0.0),
+            rotation: Quat::from_rotation_z(rotation),
+            scale: scale.extend(1.0),
+        }
+    }
+
+    /// Decomposes the passed matrix, which must not contain shear or
+    /// projection.
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let (scale, rotation, translation) =
+            matrix.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    /// Sets the translation.
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    /// Sets the rotation.
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the scale.
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn to_matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(
+            self.scale,
+            self.rotation,
+            self.translation,
+        )
+    }
+
+    /// Returns the rotation around the z axis, useful for 2D.
+    pub fn rotation_2d(&self) -> f32 {
+        let forward = self.rotation * Vec3::X;
+        forward.y.atan2(forward.x)
+    }
+
+    /// Transforms a point.
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation * (self.scale * point) + self.translation
+    }
+
+    /// Transforms a direction, translation is ignored.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotation * (self.scale * vector)
+    }
+
+    /// Returns the transform that applies `other` first and then `self`, e.g.
+    /// `parent.mul_transform(child)` gives the world transform of the child.
+    ///
+    /// _The result is exact as long as `self` has a uniform scale, otherwise
+    /// the shear that a matrix would produce is lost._
+    pub fn mul_transform(&self, other: &Self) -> Self {
+        Self {
+            translation: self.transform_point(other.translation),
+            rotation: self.rotation * other.rotation,
+            scale: self.scale * other.scale,
+        }
+    }
+
+    /// Returns the transform that undoes this one.
+    ///
+    /// _Exact for uniform scales, see `::mul_transform()`. A zero scale
+    /// produces non finite values._
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let scale = self.scale.recip();
+        Self {
+            translation: -(scale * (rotation * self.translation)),
+            rotation,
+            scale,
+        }
+    }
+
+    /// Rotates the transform so its forward direction (`-Z`) points at
+    /// `target`.
+    pub fn looking_at(mut self, target: Vec3, up: Vec3) -> Self {
+        let forward = (target - self.translation).normalize_or_zero();
+        if forward == Vec3::ZERO {
+            return self;
+        }
+        let right = up.cross(-forward).normalize_or(Vec3::X);
+        let up = (-forward).cross(right);
+        self.rotation =
+            Quat::from_mat3(&glam::Mat3::from_cols(right, up, -forward));
+        self
+    }
+
+    /// Returns `true` if both transforms are equal within `epsilon`.
+    pub fn abs_diff_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.translation.abs_diff_eq(other.translation, epsilon)
+            && self.scale.abs_diff_eq(other.scale, epsilon)
+            // `q` and `-q` represent the same rotation
+            && (self.rotation.abs_diff_eq(other.rotation, epsilon)
+                || self.rotation.abs_diff_eq(-other.rotation, epsilon))
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Transform {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.mul_transform(&other)
+    }
+}
+
+impl Mul<Vec3> for Transform {
+    type Output = Vec3;
+
+    fn mul(self, point: Vec3) -> Vec3 {
+        self.transform_point(point)
+    }
+}
+
+impl From<Transform> for Mat4 {
+    fn from(transform: Transform) -> Self {
+        transform.to_matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::math::EPSILON;
+
+    fn sample() -> Transform {
+        Transform::from_translation(Vec3::new(1.0, 2.0, 3.0))
+            .with_rotation(Quat::from_euler(glam::EulerRot::XYZ, 0.3, 0.5, 1.1))
+            .with_scale(Vec3::splat(2.0))
+    }
+
+    #[test]
+    fn composition_matches_matrices() {
+        let parent = sample();
+        let child = Transform::from_translation(Vec3::new(-4.0, 0.5, 2.0))
+            .with_rotation(Quat::from_rotation_y(0.7))
+            .with_scale(Vec3::new(1.0, 3.0, 0.5));
+
+        let composed = parent * child;
+        let expected = parent.to_matrix() * child.to_matrix();
+        assert!(composed.to_matrix().abs_diff_eq(expected, 1e-4));
+
+        let point = Vec3::new(0.5, -1.0, 2.0);
+        assert!((composed * point).abs_diff_eq(parent * (child * point), 1e-4));
+    }
+
+    #[test]
+    fn inverse() {
+        let transform = sample();
+        let identity = transform * transform.inverse();
+        assert!(identity.abs_diff_eq(&Transform::IDENTITY, 1e-4));
+
+        let point = Vec3::new(7.0, -3.0, 0.25);
+        let back = transform.inverse() * (transform * point);
+        assert!(back.abs_diff_eq(point, 1e-4));
+    }
+
+    #[test]
+    fn two_dimensional() {
+        let transform =
+            Transform::from_2d(Vec2::new(10.0, 0.0), FRAC_PI_2, Vec2::ONE);
+        assert!((transform.rotation_2d() - FRAC_PI_2).abs() < EPSILON);
+        assert!((transform * Vec3::X)
+            .abs_diff_eq(Vec3::new(10.0, 1.0, 0.0), EPSILON));
+    }
+
+    #[test]
+    fn looking_at() {
+        let transform = Transform::from_translation(Vec3::new(0.0, 0.0, 5.0))
+            .looking_at(Vec3::ZERO, Vec3::Y);
+        let forward = transform.transform_vector(Vec3::NEG_Z);
+        assert!(forward.abs_diff_eq(Vec3::NEG_Z, EPSILON));
+
+        let transform = Transform::IDENTITY.looking_at(Vec3::X, Vec3::Y);
+        let forward = transform.transform_vector(Vec3::NEG_Z);
+        assert!(forward.abs_diff_eq(Vec3::X, EPSILON));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let transform = sample();
+        let json = serde_json::to_string(&transform).unwrap();
+        let parsed: Transform = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, transform);
+
+        // Missing fields use the identity
+        let parsed: Transform =
+            serde_json::from_str(r#"{"translation":[1.0,2.0,3.0]}"#).unwrap();
+        assert_eq!(
+            parsed,
+            Transform::from_translation(Vec3::new(1.0, 2.0, 3.0))
+        );
+    }
+}
@@ -0,0 +1,627 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use super::{
+    packet::{sequence_greater, Message, Packet, Payload, PAYLOAD_HEADER_SIZE},
+    Channel, NetConfig,
+};
+
+/// Sent packets remembered to match their acks.
+const SENT_PACKETS: usize = 256;
+/// Reliable messages further ahead than this are dropped, they get resent
+/// once the ones before them are delivered.
+const RECEIVE_WINDOW: u16 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum State {
+    /// Waiting for the other side to accept.
+    Connecting {
+        started: Instant,
+        last_request: Option<Instant>,
+    },
+    Connected,
+}
+
+struct SentPacket {
+    sequence: u16,
+    sent_at: Instant,
+    /// Reliable fragments in the packet, by message id and fragment.
+    fragments: Vec<(u16, u8)>,
+}
+
+/// Reliable fragment waiting for an ack.
+struct Pending {
+    message: Message,
+    last_sent: Option<Instant>,
+}
+
+/// Fragments of a message received so far.
+struct Assembly {
+    fragments: Vec<Option<Vec<u8>>>,
+    missing: usize,
+    /// Bytes of the received fragments.
+    size: usize,
+    started: Instant,
+}
+
+impl Assembly {
+    fn new(fragments: u8, now: Instant) -> Self {
+        Self {
+            fragments: vec![None; fragments.into()],
+            missing: fragments.into(),
+            size: 0,
+            started: now,
+        }
+    }
+
+    /// Memory taken by an assembly of the passed number of fragments.
+    fn slots_size(fragments: u8) -> usize {
+        usize::from(fragments) * size_of::<Option<Vec<u8>>>()
+    }
+
+    /// Memory taken by the assembly, counted against the receive buffer.
+    fn buffered(&self) -> usize {
+        self.fragments.len() * size_of::<Option<Vec<u8>>>() + self.size
+    }
+
+    fn insert(&mut self, message: Message) {
+        // Fragments of a different message with a reused id
+        if usize::from(message.fragments) != self.fragments.len() {
+            return;
+        }
+        let Some(slot) = self.fragments.get_mut(usize::from(message.fragment))
+        else {
+            return;
+        };
+        if slot.is_none() {
+            self.size += message.bytes.len();
+            *slot = Some(message.bytes);
+            self.missing -= 1;
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.missing == 0
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.fragments.into_iter().flatten().flatten().collect()
+    }
+}
+
+/// Reliability, ordering and fragmentation of the messages exchanged with a
+/// single peer.
+pub(crate) struct Connection {
+    pub address: SocketAddr,
+    pub salt: u64,
+    pub state: State,
+    /// Flags connections accepted by this host.
+    pub incoming: bool,
+    pub last_received: Instant,
+    last_sent: Option<Instant>,
+    rtt: Option<Duration>,
+    local_sequence: u16,
+    remote_sequence: Option<u16>,
+    /// Bit `n` is set if `remote_sequence - n - 1` was received.
+    received_bits: u32,
+    /// Received messages that were not acknowledged yet.
+    ack_pending: bool,
+    sent: Vec<Option<SentPacket>>,
+    next_reliable: u16,
+    next_unreliable: u16,
+    pending: Vec<Pending>,
+    unreliable: Vec<Message>,
+    /// Next reliable message to deliver.
+    next_expected: u16,
+    reliable_assemblies: HashMap<u16, Assembly>,
+    unreliable_assemblies: HashMap<u16, Assembly>,
+    /// Memory taken by both assemblies, see `NetConfig::max_receive_buffer`.
+    buffered: usize,
+}
+
+impl Connection {
+    pub fn new(
+        address: SocketAddr,
+        salt: u64,
+        state: State,
+        now: Instant,
+    ) -> Self {
+        Self {
+            address,
+            salt,
+            state,
+            incoming: false,
+            last_received: now,
+            last_sent: None,
+            rtt: None,
+            local_sequence: 0,
+            remote_sequence: None,
+            received_bits: 0,
+            ack_pending: false,
+            sent: (0..SENT_PACKETS).map(|_| None).collect(),
+            next_reliable: 0,
+            next_unreliable: 0,
+            pending: Vec::new(),
+            unreliable: Vec::new(),
+            next_expected: 0,
+            reliable_assemblies: HashMap::new(),
+            unreliable_assemblies: HashMap::new(),
+            buffered: 0,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state == State::Connected
+    }
+
+    /// Smoothed round trip time, `None` until a packet is acknowledged.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Splits the message in fragments and queues them.
+    ///
+    /// _The caller checks that the message fits in 255 fragments._
+    pub fn queue(
+        &mut self,
+        channel: Channel,
+        bytes: &[u8],
+        config: &NetConfig,
+    ) {
+        let chunks: Vec<&[u8]> = if bytes.is_empty() {
+            vec![bytes]
+        } else {
+            bytes.chunks(config.fragment_size()).collect()
+        };
+        let next = match channel {
+            Channel::Reliable => &mut self.next_reliable,
+            Channel::Unreliable => &mut self.next_unreliable,
+        };
+        let id = *next;
+        *next = next.wrapping_add(1);
+
+        for (fragment, chunk) in chunks.iter().enumerate() {
+            let message = Message {
+                channel,
+                id,
+                fragment: fragment as u8,
+                fragments: chunks.len() as u8,
+                bytes: chunk.to_vec(),
+            };
+            match channel {
+                Channel::Reliable => self.pending.push(Pending {
+                    message,
+                    last_sent: None,
+                }),
+                Channel::Unreliable => self.unreliable.push(message),
+            }
+        }
+    }
+
+    /// Processes the acks and returns the messages ready to be delivered.
+    pub fn receive(
+        &mut self,
+        payload: Payload,
+        now: Instant,
+        config: &NetConfig,
+    ) -> Vec<(Channel, Vec<u8>)> {
+        self.last_received = now;
+        if !self.is_fresh(payload.sequence) {
+            return Vec::new();
+        }
+        self.acknowledge(payload.ack, now);
+        for bit in 0..32 {
+            if payload.ack_bits & (1 << bit) != 0 {
+                self.acknowledge(payload.ack.wrapping_sub(bit + 1), now);
+            }
+        }
+
+        // Packets with only acks are not acknowledged, or both sides would
+        // keep answering each other
+        let has_messages = !payload.messages.is_empty();
+        let buffered = &mut self.buffered;
+        self.unreliable_assemblies.retain(|_, assembly| {
+            let keep = now - assembly.started < config.timeout;
+            if !keep {
+                *buffered -= assembly.buffered();
+            }
+            keep
+        });
+
+        let mut refused = false;
+        let mut delivered = Vec::new();
+        for message in payload.messages {
+            let id = message.id;
+            match message.channel {
+                Channel::Reliable => {
+                    let ahead = id.wrapping_sub(self.next_expected);
+                    // Already delivered
+                    if ahead > u16::MAX / 2 {
+                        continue;
+                    }
+                    // The next message is always kept, so the ones after it
+                    // can be delivered and free the buffer
+                    if ahead >= RECEIVE_WINDOW
+                        || (ahead > 0 && !self.fits(&message, config))
+                    {
+                        refused = true;
+                        continue;
+                    }
+                    Self::store(
+                        &mut self.reliable_assemblies,
+                        &mut self.buffered,
+                        message,
+                        now,
+                    );
+                }
+                Channel::Unreliable => {
+                    if !self.fits(&message, config) {
+                        continue;
+                    }
+                    Self::store(
+                        &mut self.unreliable_assemblies,
+                        &mut self.buffered,
+                        message,
+                        now,
+                    );
+                    if self.unreliable_assemblies[&id].is_complete() {
+                        if let Some(assembly) =
+                            self.unreliable_assemblies.remove(&id)
+                        {
+                            self.buffered -= assembly.buffered();
+                            delivered
+                                .push((Channel::Unreliable, assembly.finish()));
+                        }
+                    }
+                }
+            }
+        }
+
+        while self
+            .reliable_assemblies
+            .get(&self.next_expected)
+            .is_some_and(Assembly::is_complete)
+        {
+            if let Some(assembly) =
+                self.reliable_assemblies.remove(&self.next_expected)
+            {
+                self.buffered -= assembly.buffered();
+                delivered.push((Channel::Reliable, assembly.finish()));
+            }
+            self.next_expected = self.next_expected.wrapping_add(1);
+        }
+
+        // Not acknowledging the packet gets the refused fragments resent
+        if !refused {
+            self.track(payload.sequence);
+            self.ack_pending |= has_messages;
+        }
+
+        delivered
+    }
+
+    /// Checks if the fragment fits in the receive buffer.
+    fn fits(&self, message: &Message, config: &NetConfig) -> bool {
+        let assemblies = match message.channel {
+            Channel::Reliable => &self.reliable_assemblies,
+            Channel::Unreliable => &self.unreliable_assemblies,
+        };
+        let slots = if assemblies.contains_key(&message.id) {
+            0
+        } else {
+            Assembly::slots_size(message.fragments)
+        };
+        self.buffered + slots + message.bytes.len() <= config.max_receive_buffer
+    }
+
+    /// Adds the fragment to its assembly, starting it if needed.
+    fn store(
+        assemblies: &mut HashMap<u16, Assembly>,
+        buffered: &mut usize,
+        message: Message,
+        now: Instant,
+    ) {
+        let before = assemblies.get(&message.id).map_or(0, Assembly::buffered);
+        let assembly = assemblies
+            .entry(message.id)
+            .or_insert_with(|| Assembly::new(message.fragments, now));
+        assembly.insert(message);
+        *buffered += assembly.buffered() - before;
+    }
+
+    /// Returns the packets to send: the queued messages, the reliable ones
+    /// due to be resent, and an empty packet when acks are pending or the
+    /// connection has been quiet for too long.
+    pub fn flush(&mut self, now: Instant, config: &NetConfig) -> Vec<Packet> {
+        let due = |last_sent: Option<Instant>, interval: Duration| {
+            last_sent.is_none_or(|sent| now - sent >= interval)
+        };
+
+        let mut outgoing = Vec::new();
+        for pending in &mut self.pending {
+            if due(pending.last_sent, config.resend_interval) {
+                pending.last_sent = Some(now);
+                outgoing.push(pending.message.clone());
+            }
+        }
+        outgoing.append(&mut self.unreliable);
+
+        let mut packets = Vec::new();
+        let mut messages = Vec::new();
+        let mut size = PAYLOAD_HEADER_SIZE;
+        for message in outgoing {
+            if !messages.is_empty()
+                && (size + message.size() > config.max_packet_size
+                    || messages.len() == usize::from(u8::MAX))
+            {
+                packets.push(self.packet(std::mem::take(&mut messages), now));
+                size = PAYLOAD_HEADER_SIZE;
+            }
+            size += message.size();
+            messages.push(message);
+        }
+
+        if !messages.is_empty()
+            || (packets.is_empty()
+                && (self.ack_pending
+                    || due(self.last_sent, config.heartbeat_interval)))
+        {
+            packets.push(self.packet(messages, now));
+        }
+
+        packets
+    }
+
+    fn packet(&mut self, messages: Vec<Message>, now: Instant) -> Packet {
+        let sequence = self.local_sequence;
+        self.local_sequence = sequence.wrapping_add(1);
+        self.sent[usize::from(sequence) % SENT_PACKETS] = Some(SentPacket {
+            sequence,
+            sent_at: now,
+            fragments: messages
+                .iter()
+                .filter(|message| message.channel == Channel::Reliable)
+                .map(|message| (message.id, message.fragment))
+                .collect(),
+        });
+        self.last_sent = Some(now);
+        self.ack_pending = false;
+
+        Packet::Payload(
+            self.salt,
+            Payload {
+                sequence,
+                // Nothing is sent with this sequence until the peer sends
+                // plenty of packets, so it can not acknowledge anything
+                ack: self.remote_sequence.unwrap_or(u16::MAX),
+                ack_bits: self.received_bits,
+                messages,
+            },
+        )
+    }
+
+    /// Returns `false` for sequences already received.
+    fn is_fresh(&self, sequence: u16) -> bool {
+        let Some(remote) = self.remote_sequence else {
+            return true;
+        };
+        if sequence_greater(sequence, remote) {
+            return true;
+        }
+
+        let age = u32::from(remote.wrapping_sub(sequence));
+        match age {
+            0 => false,
+            // Too old to be tracked
+            33.. => true,
+            _ => self.received_bits & (1 << (age - 1)) == 0,
+        }
+    }
+
+    /// Records the received sequence, to be acknowledged.
+    fn track(&mut self, sequence: u16) {
+        let Some(remote) = self.remote_sequence else {
+            self.remote_sequence = Some(sequence);
+            return;
+        };
+
+        if sequence_greater(sequence, remote) {
+            let shift = u32::from(sequence.wrapping_sub(remote));
+            self.received_bits = if shift > 32 {
+                0
+            } else {
+                ((u64::from(self.received_bits) << shift) | (1 << (shift - 1)))
+                    as u32
+            };
+            self.remote_sequence = Some(sequence);
+            return;
+        }
+
+        let age = u32::from(remote.wrapping_sub(sequence));
+        if (1..=32).contains(&age) {
+            self.received_bits |= 1 << (age - 1);
+        }
+    }
+
+    /// Stops resending the reliable fragments in the acknowledged packet.
+    fn acknowledge(&mut self, sequence: u16, now: Instant) {
+        let slot = &mut self.sent[usize::from(sequence) % SENT_PACKETS];
+        if !matches!(slot, Some(packet) if packet.sequence == sequence) {
+            return;
+        }
+        let Some(packet) = slot.take() else {
+            return;
+        };
+
+        let sample = now - packet.sent_at;
+        self.rtt = Some(match self.rtt {
+            Some(rtt) => rtt.mul_f32(0.875) + sample.mul_f32(0.125),
+            None => sample,
+        });
+        if !packet.fragments.is_empty() {
+            self.pending.retain(|pending| {
+                let key = (pending.message.id, pending.message.fragment);
+                !packet.fragments.contains(&key)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(now: Instant) -> (Connection, Connection) {
+        let address = "127.0.0.1:1".parse().unwrap();
+        (
+            Connection::new(address, 1, State::Connected, now),
+            Connection::new(address, 1, State::Connected, now),
+        )
+    }
+
+    /// Sends the flushed packets, except the ones `lose` returns `true` for.
+    fn deliver(
+        from: &mut Connection,
+        to: &mut Connection,
+        now: Instant,
+        lose: &mut impl FnMut() -> bool,
+    ) -> Vec<(Channel, Vec<u8>)> {
+        let config = NetConfig::default();
+        let mut delivered = Vec::new();
+        for packet in from.flush(now, &config) {
+            let Packet::Payload(_, payload) = packet else {
+                panic!("expected a payload, got {packet:?}");
+            };
+            if !lose() {
+                delivered.extend(to.receive(payload, now, &config));
+            }
+        }
+        delivered
+    }
+
+    #[test]
+    fn reliable_messages_survive_loss() {
+        let config = NetConfig::default();
+        let mut now = Instant::now();
+        let (mut a, mut b) = pair(now);
+
+        let big: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+        let mut sent = Vec::new();
+        for i in 0..20 {
+            let message = format!("message {i}").into_bytes();
+            a.queue(Channel::Reliable, &message, &config);
+            sent.push(message);
+        }
+        a.queue(Channel::Reliable, &big, &config);
+        sent.push(big);
+
+        // Two packets out of three are lost, in both directions
+        let mut count = 0;
+        let mut lose = || {
+            count += 1;
+            count % 3 != 0
+        };
+        let mut received = Vec::new();
+        for _ in 0..100 {
+            received.extend(deliver(&mut a, &mut b, now, &mut lose));
+            deliver(&mut b, &mut a, now, &mut lose);
+            now += Duration::from_millis(50);
+        }
+
+        assert_eq!(
+            received,
+            sent.into_iter()
+                .map(|message| (Channel::Reliable, message))
+                .collect::<Vec<_>>()
+        );
+        assert!(a.pending.is_empty());
+        assert!(a.rtt().is_some());
+    }
+
+    #[test]
+    fn unreliable_messages_are_sent_once() {
+        let config = NetConfig::default();
+        let now = Instant::now();
+        let (mut a, mut b) = pair(now);
+
+        a.queue(Channel::Unreliable, b"lost", &config);
+        assert_eq!(deliver(&mut a, &mut b, now, &mut || true), Vec::new());
+
+        let big = vec![7; config.fragment_size() * 2 + 1];
+        a.queue(Channel::Unreliable, b"", &config);
+        a.queue(Channel::Unreliable, &big, &config);
+        assert_eq!(
+            deliver(&mut a, &mut b, now, &mut || false),
+            vec![
+                (Channel::Unreliable, Vec::new()),
+                (Channel::Unreliable, big)
+            ]
+        );
+        assert_eq!(deliver(&mut a, &mut b, now, &mut || false), Vec::new());
+    }
+
+    #[test]
+    fn receive_buffer_is_bounded() {
+        let config = NetConfig::default();
+        let now = Instant::now();
+        let (mut a, mut b) = pair(now);
+        let payload =
+            |sequence: u16, channel, ids: std::ops::Range<u16>| Payload {
+                sequence,
+                ack: u16::MAX,
+                ack_bits: 0,
+                messages: ids
+                    .map(|id| Message {
+                        channel,
+                        id,
+                        fragment: 0,
+                        fragments: u8::MAX,
+                        bytes: vec![0; 1000],
+                    })
+                    .collect(),
+            };
+
+        // Every id claims the most fragments but only sends one, the
+        // sequences are behind the ones of `a`
+        for packet in 0..256 {
+            let ids = packet * 256..packet * 256 + 255;
+            let flood = payload(60000 + packet, Channel::Unreliable, ids);
+            b.receive(flood, now, &config);
+        }
+        assert!(b.buffered <= config.max_receive_buffer);
+        let refused = payload(60256, Channel::Reliable, 1..RECEIVE_WINDOW);
+        b.receive(refused, now, &config);
+        assert!(b.buffered <= config.max_receive_buffer);
+        assert!(!b.reliable_assemblies.contains_key(&(RECEIVE_WINDOW - 1)));
+        assert_eq!(b.remote_sequence, Some(60255));
+
+        // Refused reliable fragments are resent, the next message always
+        // fits and frees the buffer
+        let big = vec![7; config.max_message_size()];
+        a.queue(Channel::Reliable, &big, &config);
+        let mut received = Vec::new();
+        for _ in 0..10 {
+            received.extend(deliver(&mut a, &mut b, now, &mut || false));
+        }
+        assert_eq!(received, vec![(Channel::Reliable, big)]);
+        assert!(b.reliable_assemblies.is_empty());
+    }
+
+    #[test]
+    fn duplicated_packets_are_ignored() {
+        let config = NetConfig::default();
+        let now = Instant::now();
+        let (mut a, mut b) = pair(now);
+
+        a.queue(Channel::Unreliable, b"once", &config);
+        let Packet::Payload(_, payload) = a.flush(now, &config).remove(0)
+        else {
+            panic!("expected a payload");
+        };
+        assert_eq!(b.receive(payload.clone(), now, &config).len(), 1);
+        assert_eq!(b.receive(payload, now, &config), Vec::new());
+    }
+}
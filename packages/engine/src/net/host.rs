@@ -0,0 +1,646 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{unbounded, Receiver, Sender};
+
+use super::{
+    connection::{Connection, State},
+    packet::Packet,
+    socket, Channel, ConnectionId, DisconnectReason, Error, NetConfig,
+};
+use crate::core::{
+    event::{Dispatcher, Event, NetworkEvent},
+    scheduler::Job,
+};
+
+/// Times a `Packet::Disconnect` is sent, it is not acknowledged so it is
+/// repeated in case some are lost.
+const DISCONNECT_REPEATS: usize = 3;
+
+/// A UDP socket and every connection made through it.
+///
+/// _Nothing is sent or received until the job returned by `take_job()` is
+/// running, `Engine::start_network()` takes care of it._
+pub struct NetworkHost {
+    config: NetConfig,
+    local_addr: SocketAddr,
+    /// Reads and writes the socket, until taken.
+    job: Option<Job>,
+    outgoing: Sender<(SocketAddr, Vec<u8>)>,
+    incoming: Receiver<(SocketAddr, Vec<u8>)>,
+    connections: HashMap<ConnectionId, Connection>,
+    addresses: HashMap<SocketAddr, ConnectionId>,
+    next_id: u64,
+    /// Events waiting for the next `update()`.
+    events: Vec<NetworkEvent>,
+}
+
+impl NetworkHost {
+    /// Binds the socket, use port `0` to let the system pick one.
+    pub fn bind(
+        address: impl ToSocketAddrs,
+        config: NetConfig,
+    ) -> Result<Self, Error> {
+        config.validate()?;
+        let socket =
+            UdpSocket::bind(address).map_err(|e| Error::Io(e.to_string()))?;
+        let local_addr =
+            socket.local_addr().map_err(|e| Error::Io(e.to_string()))?;
+
+        let (outgoing, outgoing_receiver) = unbounded();
+        let (incoming_sender, incoming) = unbounded();
+        let conditioner = config.conditioner;
+        let job: Job = Box::new(move || {
+            socket::run(socket, conditioner, outgoing_receiver, incoming_sender)
+        });
+
+        Ok(Self {
+            config,
+            local_addr,
+            job: Some(job),
+            outgoing,
+            incoming,
+            connections: HashMap::new(),
+            addresses: HashMap::new(),
+            next_id: 0,
+            events: Vec::new(),
+        })
+    }
+
+    /// Takes the job that reads and writes the socket, it runs until the
+    /// host is dropped.
+    pub fn take_job(&mut self) -> Option<Job> {
+        self.job.take()
+    }
+
+    pub fn config(&self) -> &NetConfig {
+        &self.config
+    }
+
+    /// Returns the address the socket is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns `true` if the handshake with the connection completed.
+    pub fn is_connected(&self, id: ConnectionId) -> bool {
+        self.connections
+            .get(&id)
+            .is_some_and(Connection::is_connected)
+    }
+
+    /// Returns the address of the other side of the connection.
+    pub fn address(&self, id: ConnectionId) -> Option<SocketAddr> {
+        self.connections
+            .get(&id)
+            .map(|connection| connection.address)
+    }
+
+    /// Returns the smoothed round trip time of the connection, `None` until
+    /// one of its packets is acknowledged.
+    pub fn rtt(&self, id: ConnectionId) -> Option<Duration> {
+        self.connections.get(&id).and_then(Connection::rtt)
+    }
+
+    /// Returns every connection that completed its handshake.
+    pub fn connections(&self) -> impl Iterator<Item = ConnectionId> + '_ {
+        self.connections
+            .iter()
+            .filter(|(_, connection)| connection.is_connected())
+            .map(|(id, _)| *id)
+    }
+
+    /// Starts connecting to another host, returns the existing connection
+    /// if there is one to the same address.
+    ///
+    /// _The result is reported with either `NetworkEvent::Connected` or
+    /// `NetworkEvent::Disconnected`._
+    pub fn connect(&mut self, address: SocketAddr) -> ConnectionId {
+        if let Some(id) = self.addresses.get(&address) {
+            return *id;
+        }
+
+        let salt = RandomState::new().hash_one(self.next_id);
+        let state = State::Connecting {
+            started: Instant::now(),
+            last_request: None,
+        };
+        let connection = Connection::new(address, salt, state, Instant::now());
+        self.insert(connection)
+    }
+
+    /// Closes the connection, the other side is notified.
+    pub fn disconnect(&mut self, id: ConnectionId) -> Result<(), Error> {
+        let connection = self.remove(id, DisconnectReason::Closed)?;
+        for _ in 0..DISCONNECT_REPEATS {
+            self.send_packet(
+                connection.address,
+                &Packet::Disconnect(connection.salt),
+            );
+        }
+        Ok(())
+    }
+
+    /// Queues a message, the ones sent before the handshake completes wait
+    /// for it.
+    pub fn send(
+        &mut self,
+        id: ConnectionId,
+        channel: Channel,
+        bytes: &[u8],
+    ) -> Result<(), Error> {
+        self.check_size(bytes)?;
+        let connection = self
+            .connections
+            .get_mut(&id)
+            .ok_or(Error::NoSuchConnection(id))?;
+        connection.queue(channel, bytes, &self.config);
+        Ok(())
+    }
+
+    /// Queues a message for every connection that completed its handshake.
+    pub fn broadcast(
+        &mut self,
+        channel: Channel,
+        bytes: &[u8],
+    ) -> Result<(), Error> {
+        self.check_size(bytes)?;
+        for connection in self.connections.values_mut() {
+            if connection.is_connected() {
+                connection.queue(channel, bytes, &self.config);
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes the received packets, sends the queued messages and
+    /// dispatches the resulting `NetworkEvent`s.
+    pub fn update(&mut self, now: Instant, dispatcher: &Dispatcher) {
+        crate::profile_scope!("NetworkHost::update");
+        while let Ok((address, bytes)) = self.incoming.try_recv() {
+            // Anything else is noise or a different game
+            if let Some(packet) =
+                Packet::decode(self.config.protocol_id, &bytes)
+            {
+                self.receive(address, packet, now);
+            }
+        }
+
+        let ids: Vec<ConnectionId> = self.connections.keys().copied().collect();
+        for id in ids {
+            self.update_connection(id, now);
+        }
+
+        for event in self.events.drain(..) {
+            dispatcher.send(Event::Network(event));
+        }
+    }
+
+    fn receive(&mut self, address: SocketAddr, packet: Packet, now: Instant) {
+        let existing = self.addresses.get(&address).copied();
+
+        if let Packet::ConnectRequest(salt) = packet {
+            if let Some(id) = existing {
+                let connection = &self.connections[&id];
+                let connecting =
+                    matches!(connection.state, State::Connecting { .. });
+                match (connection.incoming, connection.salt == salt) {
+                    // The accept was lost
+                    (true, true) => {}
+                    // The other side restarted, the old connection is dead
+                    (true, false) => {
+                        let _ = self.remove(id, DisconnectReason::ClosedByPeer);
+                        return self.accept(address, salt, now);
+                    }
+                    // Both sides connecting to each other, the lowest salt
+                    // wins so only one side accepts
+                    (false, _) if !connecting || connection.salt < salt => {
+                        return
+                    }
+                    // Theirs wins, our connection takes it over to keep its
+                    // id and queued messages
+                    (false, _) => {
+                        if let Some(connection) = self.connections.get_mut(&id)
+                        {
+                            connection.salt = salt;
+                            connection.state = State::Connected;
+                            connection.incoming = true;
+                            connection.last_received = now;
+                        }
+                        self.events.push(NetworkEvent::Connected(id));
+                    }
+                }
+                self.send_packet(address, &Packet::ConnectAccept(salt));
+            } else {
+                self.accept(address, salt, now);
+            }
+            return;
+        }
+
+        let Some(id) = existing else {
+            return;
+        };
+        let Some(connection) = self.connections.get_mut(&id) else {
+            return;
+        };
+        // Left over from a previous connection
+        if connection.salt != packet.salt() {
+            return;
+        }
+
+        let connecting = matches!(connection.state, State::Connecting { .. });
+        match packet {
+            Packet::ConnectRequest(_) => {}
+            Packet::ConnectAccept(_) => {
+                if connecting {
+                    connection.state = State::Connected;
+                    connection.last_received = now;
+                    self.events.push(NetworkEvent::Connected(id));
+                }
+            }
+            Packet::ConnectDeny(_) => {
+                if connecting {
+                    let _ = self.remove(id, DisconnectReason::Denied);
+                }
+            }
+            Packet::Disconnect(_) => {
+                let _ = self.remove(id, DisconnectReason::ClosedByPeer);
+            }
+            Packet::Payload(_, payload) => {
+                // The accept was lost, but the other side already sends
+                if connecting {
+                    connection.state = State::Connected;
+                    self.events.push(NetworkEvent::Connected(id));
+                }
+                for (channel, bytes) in
+                    connection.receive(payload, now, &self.config)
+                {
+                    self.events
+                        .push(NetworkEvent::Received(id, channel, bytes));
+                }
+            }
+        }
+    }
+
+    /// Accepts the connection request, or denies it when full.
+    fn accept(&mut self, address: SocketAddr, salt: u64, now: Instant) {
+        let incoming = self
+            .connections
+            .values()
+            .filter(|connection| connection.incoming)
+            .count();
+        if incoming >= self.config.max_connections {
+            self.send_packet(address, &Packet::ConnectDeny(salt));
+            return;
+        }
+
+        let mut connection =
+            Connection::new(address, salt, State::Connected, now);
+        connection.incoming = true;
+        let id = self.insert(connection);
+        self.send_packet(address, &Packet::ConnectAccept(salt));
+        self.events.push(NetworkEvent::Connected(id));
+    }
+
+    fn update_connection(&mut self, id: ConnectionId, now: Instant) {
+        let Some(connection) = self.connections.get_mut(&id) else {
+            return;
+        };
+
+        match connection.state {
+            State::Connecting {
+                started,
+                last_request,
+            } => {
+                if now - started >= self.config.connect_timeout {
+                    let _ = self.remove(id, DisconnectReason::TimedOut);
+                    return;
+                }
+                if last_request.is_none_or(|sent| {
+                    now - sent >= self.config.resend_interval
+                }) {
+                    connection.state = State::Connecting {
+                        started,
+                        last_request: Some(now),
+                    };
+                    let packet = Packet::ConnectRequest(connection.salt);
+                    let address = connection.address;
+                    self.send_packet(address, &packet);
+                }
+            }
+            State::Connected => {
+                if now - connection.last_received >= self.config.timeout {
+                    let _ = self.remove(id, DisconnectReason::TimedOut);
+                    return;
+                }
+                let address = connection.address;
+                for packet in connection.flush(now, &self.config) {
+                    self.send_packet(address, &packet);
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, connection: Connection) -> ConnectionId {
+        let id = ConnectionId(self.next_id);
+        self.next_id += 1;
+        self.addresses.insert(connection.address, id);
+        self.connections.insert(id, connection);
+        id
+    }
+
+    /// Removes the connection and queues its `NetworkEvent::Disconnected`.
+    fn remove(
+        &mut self,
+        id: ConnectionId,
+        reason: DisconnectReason,
+    ) -> Result<Connection, Error> {
+        let connection = self
+            .connections
+            .remove(&id)
+            .ok_or(Error::NoSuchConnection(id))?;
+        self.addresses.remove(&connection.address);
+        self.events.push(NetworkEvent::Disconnected(id, reason));
+        Ok(connection)
+    }
+
+    fn check_size(&self, bytes: &[u8]) -> Result<(), Error> {
+        let max = self.config.max_message_size();
+        if bytes.len() > max {
+            return Err(Error::MessageTooLarge(bytes.len(), max));
+        }
+        Ok(())
+    }
+
+    fn send_packet(&self, address: SocketAddr, packet: &Packet) {
+        let bytes = packet.encode(self.config.protocol_id);
+        // Only fails once the job stopped, the connections will time out
+        let _ = self.outgoing.send((address, bytes));
+    }
+}
+
+impl Drop for NetworkHost {
+    fn drop(&mut self) {
+        for connection in self.connections.values() {
+            let packet = Packet::Disconnect(connection.salt);
+            for _ in 0..DISCONNECT_REPEATS {
+                self.send_packet(connection.address, &packet);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{
+        core::event::{self, Consumer},
+        net::LinkConditioner,
+    };
+
+    /// Binds a host on localhost and runs its socket on a new thread.
+    fn start(config: NetConfig) -> (NetworkHost, Dispatcher, Consumer) {
+        let mut host = NetworkHost::bind("127.0.0.1:0", config).unwrap();
+        thread::spawn(host.take_job().unwrap());
+        let (dispatcher, consumer) = event::create_handler();
+        (host, dispatcher, consumer)
+    }
+
+    fn network_events(consumer: &Consumer) -> Vec<NetworkEvent> {
+        std::iter::from_fn(|| consumer.poll())
+            .filter_map(|event| match event {
+                Event::Network(event) => Some(event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Updates the hosts until `done` returns `true`, panics after a while.
+    fn run_until(
+        hosts: &mut [(&mut NetworkHost, &Dispatcher)],
+        mut done: impl FnMut() -> bool,
+    ) {
+        let started = Instant::now();
+        while !done() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "took too long"
+            );
+            for (host, dispatcher) in hosts.iter_mut() {
+                host.update(Instant::now(), dispatcher);
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+    }
+
+    #[test]
+    fn messages_survive_loss_and_latency() {
+        let conditioner = LinkConditioner::default()
+            .with_loss(0.3)
+            .with_latency(Duration::from_millis(20))
+            .with_jitter(Duration::from_millis(10));
+        let config = NetConfig {
+            resend_interval: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let (mut server, server_dispatcher, server_events) =
+            start(config.clone().with_conditioner(conditioner.with_seed(1)));
+        let (mut client, client_dispatcher, client_events) =
+            start(config.with_conditioner(conditioner.with_seed(2)));
+
+        let connection = client.connect(server.local_addr());
+        let mut sent = Vec::new();
+        for i in 0..30 {
+            let message = format!("message {i}").into_bytes();
+            client
+                .send(connection, Channel::Reliable, &message)
+                .unwrap();
+            sent.push(message);
+        }
+        let big: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+        client.send(connection, Channel::Reliable, &big).unwrap();
+        sent.push(big);
+
+        let mut received = Vec::new();
+        let mut client_connected = false;
+        run_until(
+            &mut [
+                (&mut server, &server_dispatcher),
+                (&mut client, &client_dispatcher),
+            ],
+            || {
+                for event in network_events(&server_events) {
+                    if let NetworkEvent::Received(_, channel, bytes) = event {
+                        assert_eq!(channel, Channel::Reliable);
+                        received.push(bytes);
+                    }
+                }
+                client_connected |= network_events(&client_events)
+                    .contains(&NetworkEvent::Connected(connection));
+                client_connected && received.len() == sent.len()
+            },
+        );
+
+        assert_eq!(received, sent);
+        assert!(client.is_connected(connection));
+        assert_eq!(server.connections().count(), 1);
+    }
+
+    #[test]
+    fn hosts_connect_to_each_other() {
+        let config = NetConfig {
+            connect_timeout: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let (mut left, left_dispatcher, left_events) = start(config.clone());
+        let (mut right, right_dispatcher, right_events) = start(config);
+
+        let to_right = left.connect(right.local_addr());
+        let to_left = right.connect(left.local_addr());
+        left.send(to_right, Channel::Reliable, b"from left")
+            .unwrap();
+        right
+            .send(to_left, Channel::Reliable, b"from right")
+            .unwrap();
+
+        let mut events = (Vec::new(), Vec::new());
+        run_until(
+            &mut [
+                (&mut left, &left_dispatcher),
+                (&mut right, &right_dispatcher),
+            ],
+            || {
+                events.0.extend(network_events(&left_events));
+                events.1.extend(network_events(&right_events));
+                events.0.len() == 2 && events.1.len() == 2
+            },
+        );
+
+        assert_eq!(
+            events.0,
+            vec![
+                NetworkEvent::Connected(to_right),
+                NetworkEvent::Received(
+                    to_right,
+                    Channel::Reliable,
+                    b"from right".to_vec()
+                ),
+            ]
+        );
+        assert_eq!(
+            events.1,
+            vec![
+                NetworkEvent::Connected(to_left),
+                NetworkEvent::Received(
+                    to_left,
+                    Channel::Reliable,
+                    b"from left".to_vec()
+                ),
+            ]
+        );
+        assert_eq!(left.connections().count(), 1);
+        assert_eq!(right.connections().count(), 1);
+    }
+
+    #[test]
+    fn handshake_times_out() {
+        // Bound but never read, so nothing answers
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (mut client, dispatcher, consumer) = start(NetConfig {
+            connect_timeout: Duration::from_millis(200),
+            ..Default::default()
+        });
+
+        let connection = client.connect(silent.local_addr().unwrap());
+        assert!(!client.is_connected(connection));
+        let mut events = Vec::new();
+        run_until(&mut [(&mut client, &dispatcher)], || {
+            events.extend(network_events(&consumer));
+            !events.is_empty()
+        });
+
+        assert_eq!(
+            events,
+            vec![NetworkEvent::Disconnected(
+                connection,
+                DisconnectReason::TimedOut
+            )]
+        );
+        assert_eq!(client.address(connection), None);
+        assert_eq!(
+            client.send(connection, Channel::Reliable, b"late"),
+            Err(Error::NoSuchConnection(connection))
+        );
+    }
+
+    #[test]
+    fn full_host_denies_connections() {
+        let (mut server, server_dispatcher, server_events) = start(NetConfig {
+            max_connections: 1,
+            ..Default::default()
+        });
+        let (mut first, first_dispatcher, first_events) =
+            start(NetConfig::default());
+        let (mut second, second_dispatcher, second_events) =
+            start(NetConfig::default());
+
+        let accepted = first.connect(server.local_addr());
+        let mut first_connected = false;
+        run_until(
+            &mut [
+                (&mut server, &server_dispatcher),
+                (&mut first, &first_dispatcher),
+            ],
+            || {
+                first_connected |= network_events(&first_events)
+                    .contains(&NetworkEvent::Connected(accepted));
+                first_connected
+            },
+        );
+
+        let denied = second.connect(server.local_addr());
+        let mut events = Vec::new();
+        run_until(
+            &mut [
+                (&mut server, &server_dispatcher),
+                (&mut second, &second_dispatcher),
+            ],
+            || {
+                events.extend(network_events(&second_events));
+                !events.is_empty()
+            },
+        );
+        assert_eq!(
+            events,
+            vec![NetworkEvent::Disconnected(denied, DisconnectReason::Denied)]
+        );
+
+        first.disconnect(accepted).unwrap();
+        let mut events = Vec::new();
+        run_until(
+            &mut [
+                (&mut server, &server_dispatcher),
+                (&mut first, &first_dispatcher),
+            ],
+            || {
+                events.extend(network_events(&server_events));
+                events.iter().any(|event| {
+                    matches!(
+                        event,
+                        NetworkEvent::Disconnected(
+                            _,
+                            DisconnectReason::ClosedByPeer
+                        )
+                    )
+                })
+            },
+        );
+        assert_eq!(server.connections().count(), 0);
+    }
+}
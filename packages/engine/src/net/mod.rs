@@ -0,0 +1,203 @@
+//! Client-server networking over UDP.
+//!
+//! A `NetworkHost` owns a UDP socket and the connections made through it:
+//! it connects to other hosts and, up to `NetConfig::max_connections`,
+//! accepts the ones connecting to it. Connections start with a handshake
+//! and end when either side disconnects or stops answering for
+//! `NetConfig::timeout`.
+//!
+//! Messages are sent on a `Channel`:
+//!
+//! - `Channel::Reliable` messages are resent until acknowledged and are
+//!   delivered once, in the order they were sent.
+//! - `Channel::Unreliable` messages are sent once, they can be lost or
+//!   arrive out of order.
+//!
+//! Messages bigger than a packet are split in fragments and reassembled on
+//! the other side. Every packet carries a sequence number and acknowledges
+//! the last 33 packets received from the peer, so acks survive lost
+//! packets.
+//!
+//! The socket is read and written by a job (see `NetworkHost::take_job()`)
+//! that the `Engine` runs on a `WorkerKind::Networking` worker, while the
+//! protocol itself is updated on every `Engine::step()`. Connections,
+//! disconnections and received messages are reported with `NetworkEvent`s.
+//!
+//! ```no_run
+//! # use unen_engine::{core::engine::Engine, net::{Channel, NetConfig, NetworkHost}};
+//! let mut server = Engine::default();
+//! let host = NetworkHost::bind("127.0.0.1:7777", NetConfig::default()).unwrap();
+//! server.start_network(host).unwrap();
+//!
+//! let mut client = Engine::default();
+//! let host = NetworkHost::bind("127.0.0.1:0", NetConfig::default()).unwrap();
+//! client.start_network(host).unwrap();
+//! let network = client.network_mut().unwrap();
+//! let connection = network.connect("127.0.0.1:7777".parse().unwrap());
+//! // Queued until the handshake completes
+//! network.send(connection, Channel::Reliable, b"hello").unwrap();
+//! ```
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use thiserror::Error;
+
+mod connection;
+pub mod host;
+mod packet;
+pub mod socket;
+
+pub use host::NetworkHost;
+pub use socket::LinkConditioner;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// Failed to create or configure the socket.
+    #[error("Socket error: {0}")]
+    Io(String),
+    /// The configuration can not be used.
+    #[error("Invalid network config: {0}")]
+    InvalidConfig(String),
+    /// The connection was closed or never existed.
+    #[error("No such connection '{0}'")]
+    NoSuchConnection(ConnectionId),
+    /// The message does not fit in the maximum amount of fragments.
+    #[error("Message of {0} bytes is bigger than the maximum of {1} bytes")]
+    MessageTooLarge(usize, usize),
+    /// Only one `NetworkHost` can run at a time.
+    #[error("Network already running at '{0}'")]
+    AlreadyRunning(std::net::SocketAddr),
+}
+
+/// Identifies a connection, unique for the lifetime of a `NetworkHost`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct ConnectionId(pub(crate) u64);
+
+impl std::fmt::Display for ConnectionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Connection({})", self.0)
+    }
+}
+
+/// Delivery guarantees of a message.
+#[derive(
+    Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+pub enum Channel {
+    /// Delivered once and in order, resent until acknowledged.
+    Reliable,
+    /// Sent once, can be lost or arrive out of order.
+    Unreliable,
+}
+
+/// Why a connection ended.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// Closed with `NetworkHost::disconnect()`.
+    Closed,
+    /// Closed by the other side.
+    ClosedByPeer,
+    /// Nothing was received for too long, or the handshake did not complete
+    /// in time.
+    TimedOut,
+    /// The other side refused the connection, it is full.
+    Denied,
+}
+
+/// Everything needed to configure a `NetworkHost`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetConfig {
+    /// Identifies the game and protocol version, packets with a different
+    /// one are ignored.
+    pub protocol_id: u32,
+    /// Incoming connections accepted at the same time, `0` only allows
+    /// connecting to other hosts.
+    pub max_connections: usize,
+    /// Biggest datagram sent, bigger messages are fragmented.
+    pub max_packet_size: usize,
+    /// Time given to the handshake to complete.
+    pub connect_timeout: Duration,
+    /// Time without receiving anything after which a connection is dropped.
+    pub timeout: Duration,
+    /// Time after which unacknowledged reliable messages are sent again,
+    /// also used to repeat the connection requests.
+    pub resend_interval: Duration,
+    /// Time after which an empty packet is sent to keep the connection
+    /// alive.
+    pub heartbeat_interval: Duration,
+    /// Bytes kept per connection for the messages still missing fragments,
+    /// fragments past it are dropped.
+    ///
+    /// _The next reliable message is always kept, so a message of
+    /// `::max_message_size()` can still be received._
+    pub max_receive_buffer: usize,
+    /// Simulated packet loss and latency, meant for testing.
+    pub conditioner: Option<LinkConditioner>,
+}
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            protocol_id: u32::from_be_bytes(*b"UNEN"),
+            max_connections: 32,
+            max_packet_size: 1200,
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+            resend_interval: Duration::from_millis(100),
+            heartbeat_interval: Duration::from_millis(250),
+            max_receive_buffer: 1024 * 1024,
+            conditioner: None,
+        }
+    }
+}
+
+impl NetConfig {
+    /// Sets the simulated packet loss and latency.
+    pub fn with_conditioner(mut self, conditioner: LinkConditioner) -> Self {
+        self.conditioner = Some(conditioner);
+        self
+    }
+
+    /// Checks if every value is usable.
+    pub fn validate(&self) -> Result<(), Error> {
+        let min = packet::PAYLOAD_HEADER_SIZE + packet::MESSAGE_HEADER_SIZE + 1;
+        if !(min..=socket::MAX_DATAGRAM_SIZE).contains(&self.max_packet_size) {
+            return Err(Error::InvalidConfig(format!(
+                "packet size must be between {min} and {}",
+                socket::MAX_DATAGRAM_SIZE
+            )));
+        }
+        if self.resend_interval.is_zero() {
+            return Err(Error::InvalidConfig(
+                "resend interval can not be zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Biggest piece of a message that fits in a single packet.
+    pub(crate) fn fragment_size(&self) -> usize {
+        self.max_packet_size
+            - packet::PAYLOAD_HEADER_SIZE
+            - packet::MESSAGE_HEADER_SIZE
+    }
+
+    /// Biggest message that can be sent.
+    pub fn max_message_size(&self) -> usize {
+        self.fragment_size() * usize::from(u8::MAX)
+    }
+}
@@ -0,0 +1,267 @@
+//! Wire format, every value is big-endian.
+//!
+//! ```text
+//! packet:  protocol id (u32) | kind (u8) | salt (u64) | payload?
+//! payload: sequence (u16) | ack (u16) | ack bits (u32) | count (u8)
+//!          | message * count
+//! message: channel (u8) | id (u16) | fragment (u8) | fragments (u8)
+//!          | length (u16) | bytes
+//! ```
+
+use super::Channel;
+
+/// Size of the header shared by every packet.
+const HEADER_SIZE: usize = 4 + 1 + 8;
+/// Size of the header of a payload packet, without its messages.
+pub(crate) const PAYLOAD_HEADER_SIZE: usize = HEADER_SIZE + 2 + 2 + 4 + 1;
+/// Size of the header of a message, without its bytes.
+pub(crate) const MESSAGE_HEADER_SIZE: usize = 1 + 2 + 1 + 1 + 2;
+
+/// A piece of a message, or the whole message when it fits in a packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Message {
+    pub channel: Channel,
+    /// Sequence of the message in its channel.
+    pub id: u16,
+    pub fragment: u8,
+    pub fragments: u8,
+    pub bytes: Vec<u8>,
+}
+
+impl Message {
+    pub fn size(&self) -> usize {
+        MESSAGE_HEADER_SIZE + self.bytes.len()
+    }
+}
+
+/// The salt is chosen by the connecting side, it tells apart the packets of
+/// a new connection from the ones of a previous one with the same address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Packet {
+    ConnectRequest(u64),
+    ConnectAccept(u64),
+    ConnectDeny(u64),
+    Disconnect(u64),
+    Payload(u64, Payload),
+}
+
+/// Packet carrying messages, or only acks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Payload {
+    pub sequence: u16,
+    /// Most recent sequence received from the peer.
+    pub ack: u16,
+    /// Bit `n` acknowledges the sequence `ack - n - 1`.
+    pub ack_bits: u32,
+    pub messages: Vec<Message>,
+}
+
+impl Packet {
+    pub fn salt(&self) -> u64 {
+        match self {
+            Packet::ConnectRequest(salt)
+            | Packet::ConnectAccept(salt)
+            | Packet::ConnectDeny(salt)
+            | Packet::Disconnect(salt)
+            | Packet::Payload(salt, _) => *salt,
+        }
+    }
+
+    pub fn encode(&self, protocol_id: u32) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PAYLOAD_HEADER_SIZE);
+        bytes.extend_from_slice(&protocol_id.to_be_bytes());
+        let kind = match self {
+            Packet::ConnectRequest(_) => 0,
+            Packet::ConnectAccept(_) => 1,
+            Packet::ConnectDeny(_) => 2,
+            Packet::Disconnect(_) => 3,
+            Packet::Payload(..) => 4,
+        };
+        bytes.push(kind);
+        bytes.extend_from_slice(&self.salt().to_be_bytes());
+
+        if let Packet::Payload(_, payload) = self {
+            let Payload {
+                sequence,
+                ack,
+                ack_bits,
+                messages,
+            } = payload;
+            bytes.extend_from_slice(&sequence.to_be_bytes());
+            bytes.extend_from_slice(&ack.to_be_bytes());
+            bytes.extend_from_slice(&ack_bits.to_be_bytes());
+            bytes.push(messages.len() as u8);
+            for message in messages {
+                bytes.push(match message.channel {
+                    Channel::Reliable => 0,
+                    Channel::Unreliable => 1,
+                });
+                bytes.extend_from_slice(&message.id.to_be_bytes());
+                bytes.push(message.fragment);
+                bytes.push(message.fragments);
+                bytes.extend_from_slice(
+                    &(message.bytes.len() as u16).to_be_bytes(),
+                );
+                bytes.extend_from_slice(&message.bytes);
+            }
+        }
+
+        bytes
+    }
+
+    /// Returns `None` for truncated packets, unknown values or a different
+    /// protocol.
+    pub fn decode(protocol_id: u32, bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.u32()? != protocol_id {
+            return None;
+        }
+        let kind = reader.u8()?;
+        let salt = reader.u64()?;
+
+        let packet = match kind {
+            0 => Packet::ConnectRequest(salt),
+            1 => Packet::ConnectAccept(salt),
+            2 => Packet::ConnectDeny(salt),
+            3 => Packet::Disconnect(salt),
+            4 => {
+                let sequence = reader.u16()?;
+                let ack = reader.u16()?;
+                let ack_bits = reader.u32()?;
+                let count = reader.u8()?;
+                let messages = (0..count)
+                    .map(|_| reader.message())
+                    .collect::<Option<_>>()?;
+                Packet::Payload(
+                    salt,
+                    Payload {
+                        sequence,
+                        ack,
+                        ack_bits,
+                        messages,
+                    },
+                )
+            }
+            _ => return None,
+        };
+
+        reader.0.is_empty().then_some(packet)
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (taken, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|[byte]| byte)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take().map(u16::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_be_bytes)
+    }
+
+    fn message(&mut self) -> Option<Message> {
+        let channel = match self.u8()? {
+            0 => Channel::Reliable,
+            1 => Channel::Unreliable,
+            _ => return None,
+        };
+        let id = self.u16()?;
+        let fragment = self.u8()?;
+        let fragments = self.u8()?;
+        if fragment >= fragments {
+            return None;
+        }
+        let length = usize::from(self.u16()?);
+        if self.0.len() < length {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+
+        Some(Message {
+            channel,
+            id,
+            fragment,
+            fragments,
+            bytes: bytes.to_vec(),
+        })
+    }
+}
+
+/// Returns `true` if `a` comes after `b`, handling the wrap around.
+pub(crate) fn sequence_greater(a: u16, b: u16) -> bool {
+    a != b && a.wrapping_sub(b) < 0x8000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROTOCOL: u32 = 7;
+
+    #[test]
+    fn round_trip() {
+        let packets = [
+            Packet::ConnectRequest(1),
+            Packet::ConnectDeny(u64::MAX),
+            Packet::Payload(
+                3,
+                Payload {
+                    sequence: 65535,
+                    ack: 12,
+                    ack_bits: 0b101,
+                    messages: vec![
+                        Message {
+                            channel: Channel::Reliable,
+                            id: 4,
+                            fragment: 1,
+                            fragments: 2,
+                            bytes: b"hello".to_vec(),
+                        },
+                        Message {
+                            channel: Channel::Unreliable,
+                            id: 0,
+                            fragment: 0,
+                            fragments: 1,
+                            bytes: Vec::new(),
+                        },
+                    ],
+                },
+            ),
+        ];
+
+        for packet in packets {
+            let bytes = packet.encode(PROTOCOL);
+            assert_eq!(Packet::decode(PROTOCOL, &bytes), Some(packet));
+            assert_eq!(Packet::decode(PROTOCOL + 1, &bytes), None);
+            assert_eq!(
+                Packet::decode(PROTOCOL, &bytes[..bytes.len() - 1]),
+                None
+            );
+        }
+        assert_eq!(Packet::decode(PROTOCOL, b"junk"), None);
+    }
+
+    #[test]
+    fn sequences_wrap_around() {
+        assert!(sequence_greater(1, 0));
+        assert!(sequence_greater(0, 65535));
+        assert!(!sequence_greater(65535, 0));
+        assert!(!sequence_greater(5, 5));
+    }
+}
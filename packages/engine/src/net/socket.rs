@@ -0,0 +1,211 @@
+//! The UDP socket and the job that reads and writes it.
+
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
+use serde::{Deserialize, Serialize};
+
+/// Biggest payload of an IPv4 UDP datagram.
+pub const MAX_DATAGRAM_SIZE: usize = 65507;
+/// How long the job blocks on the socket before checking for datagrams to
+/// send.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Drops and delays the outgoing datagrams to simulate a bad link.
+///
+/// _Only the datagrams sent by the host using it are affected, so both
+/// sides need one to degrade both directions._
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkConditioner {
+    /// Chance of dropping a datagram, between `0.0` and `1.0`.
+    pub loss: f32,
+    /// Delay added to every datagram.
+    pub latency: Duration,
+    /// Random delay added on top of `latency`, up to this value. Datagrams
+    /// can arrive out of order.
+    pub jitter: Duration,
+    /// Seed of the random generator, the same seed drops the same
+    /// datagrams.
+    pub seed: u64,
+}
+
+impl Default for LinkConditioner {
+    fn default() -> Self {
+        Self {
+            loss: 0.0,
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            seed: 0,
+        }
+    }
+}
+
+impl LinkConditioner {
+    pub fn with_loss(mut self, loss: f32) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// SplitMix64, good enough to simulate packet loss.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value between `0.0` and `1.0`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Applies the `LinkConditioner` to the outgoing datagrams.
+struct Conditioner {
+    config: LinkConditioner,
+    rng: Rng,
+    /// Datagrams waiting for their delay, by time to send.
+    delayed: VecDeque<(Instant, SocketAddr, Vec<u8>)>,
+}
+
+impl Conditioner {
+    fn new(config: LinkConditioner) -> Self {
+        Self {
+            config,
+            rng: Rng(config.seed),
+            delayed: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, address: SocketAddr, bytes: Vec<u8>, now: Instant) {
+        if self.rng.next_f32() < self.config.loss {
+            return;
+        }
+        let jitter = self.config.jitter.mul_f32(self.rng.next_f32());
+        let at = now + self.config.latency + jitter;
+        let index = self.delayed.partition_point(|(other, ..)| *other <= at);
+        self.delayed.insert(index, (at, address, bytes));
+    }
+
+    fn pop(&mut self, now: Instant) -> Option<(SocketAddr, Vec<u8>)> {
+        if self.delayed.front().is_some_and(|(at, ..)| *at <= now) {
+            self.delayed
+                .pop_front()
+                .map(|(_, address, bytes)| (address, bytes))
+        } else {
+            None
+        }
+    }
+}
+
+/// Reads and writes the socket until `outgoing` is disconnected, received
+/// datagrams are sent through `incoming`.
+pub(crate) fn run(
+    socket: UdpSocket,
+    conditioner: Option<LinkConditioner>,
+    outgoing: Receiver<(SocketAddr, Vec<u8>)>,
+    incoming: Sender<(SocketAddr, Vec<u8>)>,
+) {
+    if let Err(err) = socket.set_read_timeout(Some(POLL_INTERVAL)) {
+        log::error!("Failed to configure network socket: {err}");
+        return;
+    }
+    let mut conditioner = conditioner.map(Conditioner::new);
+    let send = |address: SocketAddr, bytes: &[u8]| {
+        if let Err(err) = socket.send_to(bytes, address) {
+            log::debug!("Failed to send datagram to '{address}': {err}");
+        }
+    };
+
+    let mut buffer = vec![0; MAX_DATAGRAM_SIZE];
+    loop {
+        let now = Instant::now();
+        loop {
+            match outgoing.try_recv() {
+                Ok((address, bytes)) => match &mut conditioner {
+                    Some(conditioner) => conditioner.push(address, bytes, now),
+                    None => send(address, &bytes),
+                },
+                Err(TryRecvError::Empty) => break,
+                // The host was dropped
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+        if let Some(conditioner) = &mut conditioner {
+            while let Some((address, bytes)) = conditioner.pop(now) {
+                send(address, &bytes);
+            }
+        }
+
+        match socket.recv_from(&mut buffer) {
+            Ok((size, address)) => {
+                let _ = incoming.send((address, buffer[..size].to_vec()));
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut
+                ) => {}
+            // Windows reports the previous datagram was refused by the peer
+            Err(err) if err.kind() == ErrorKind::ConnectionReset => {}
+            Err(err) => log::debug!("Failed to receive datagram: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditioner_drops_and_delays() {
+        let address = "127.0.0.1:1".parse().unwrap();
+        let now = Instant::now();
+        let latency = Duration::from_millis(50);
+        let mut conditioner = Conditioner::new(
+            LinkConditioner::default()
+                .with_loss(0.5)
+                .with_latency(latency)
+                .with_seed(3),
+        );
+
+        for i in 0..1000u16 {
+            conditioner.push(address, i.to_be_bytes().to_vec(), now);
+        }
+        let kept = conditioner.delayed.len();
+        assert!((400..600).contains(&kept), "kept {kept} datagrams");
+
+        assert_eq!(conditioner.pop(now), None);
+        let mut sent = 0;
+        while conditioner.pop(now + latency).is_some() {
+            sent += 1;
+        }
+        assert_eq!(sent, kept);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::math::Vec2;
+
+use super::Collider;
+
+/// How a body is moved by the simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyType {
+    /// Moved by forces and collisions.
+    #[default]
+    Dynamic,
+    /// Never moves.
+    Static,
+    /// Moves with its velocity, unaffected by forces and collisions.
+    Kinematic,
+}
+
+/// Body simulated by a `PhysicsWorld`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RigidBody {
+    pub position: Vec2,
+    /// Radians, counter-clockwise.
+    pub rotation: f32,
+    pub linear_velocity: Vec2,
+    pub angular_velocity: f32,
+    /// Fraction of the velocity lost every second.
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    /// Multiplies the gravity of the world.
+    pub gravity_scale: f32,
+    body_type: BodyType,
+    collider: Collider,
+    /// Accumulated until the next step.
+    force: Vec2,
+    torque: f32,
+    inverse_mass: f32,
+    inverse_inertia: f32,
+}
+
+impl RigidBody {
+    pub fn new(body_type: BodyType, collider: Collider) -> Self {
+        let mut body = Self {
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            linear_velocity: Vec2::ZERO,
+            angular_velocity: 0.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            gravity_scale: 1.0,
+            body_type,
+            collider,
+            force: Vec2::ZERO,
+            torque: 0.0,
+            inverse_mass: 0.0,
+            inverse_inertia: 0.0,
+        };
+        body.update_mass();
+        body
+    }
+
+    pub fn dynamic(collider: Collider) -> Self {
+        Self::new(BodyType::Dynamic, collider)
+    }
+
+    pub fn fixed(collider: Collider) -> Self {
+        Self::new(BodyType::Static, collider)
+    }
+
+    pub fn kinematic(collider: Collider) -> Self {
+        Self::new(BodyType::Kinematic, collider)
+    }
+
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_linear_velocity(mut self, velocity: Vec2) -> Self {
+        self.linear_velocity = velocity;
+        self
+    }
+
+    pub fn with_angular_velocity(mut self, velocity: f32) -> Self {
+        self.angular_velocity = velocity;
+        self
+    }
+
+    pub fn with_damping(mut self, linear: f32, angular: f32) -> Self {
+        self.linear_damping = linear;
+        self.angular_damping = angular;
+        self
+    }
+
+    pub fn with_gravity_scale(mut self, scale: f32) -> Self {
+        self.gravity_scale = scale;
+        self
+    }
+
+    pub fn body_type(&self) -> BodyType {
+        self.body_type
+    }
+
+    pub fn set_body_type(&mut self, body_type: BodyType) {
+        self.body_type = body_type;
+        self.update_mass();
+    }
+
+    pub fn collider(&self) -> &Collider {
+        &self.collider
+    }
+
+    /// Replaces the collider, recomputing the mass.
+    pub fn set_collider(&mut self, collider: Collider) {
+        self.collider = collider;
+        self.update_mass();
+    }
+
+    /// Mass from the collider, infinite for static and kinematic bodies.
+    pub fn mass(&self) -> f32 {
+        if self.inverse_mass > 0.0 {
+            1.0 / self.inverse_mass
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    pub(crate) fn inverse_mass(&self) -> f32 {
+        self.inverse_mass
+    }
+
+    pub(crate) fn inverse_inertia(&self) -> f32 {
+        self.inverse_inertia
+    }
+
+    /// Pushes the center of mass during the next step.
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque += torque;
+    }
+
+    /// Changes the velocity immediately, the impulse is applied at a point
+    /// in world space.
+    pub fn apply_impulse(&mut self, impulse: Vec2, point: Vec2) {
+        self.linear_velocity += impulse * self.inverse_mass;
+        self.angular_velocity +=
+            (point - self.position).perp_dot(impulse) * self.inverse_inertia;
+    }
+
+    /// Integrates gravity and the accumulated forces, then clears them.
+    pub(crate) fn integrate_forces(&mut self, gravity: Vec2, delta: f32) {
+        if self.body_type == BodyType::Dynamic {
+            self.linear_velocity += (gravity * self.gravity_scale
+                + self.force * self.inverse_mass)
+                * delta;
+            self.angular_velocity += self.torque * self.inverse_inertia * delta;
+            self.linear_velocity *= 1.0 / (1.0 + delta * self.linear_damping);
+            self.angular_velocity *= 1.0 / (1.0 + delta * self.angular_damping);
+        }
+        self.force = Vec2::ZERO;
+        self.torque = 0.0;
+    }
+
+    pub(crate) fn integrate_velocity(&mut self, delta: f32) {
+        if self.body_type != BodyType::Static {
+            self.position += self.linear_velocity * delta;
+            self.rotation += self.angular_velocity * delta;
+        }
+    }
+
+    fn update_mass(&mut self) {
+        let mass = self.collider.mass();
+        if self.body_type == BodyType::Dynamic && mass > 0.0 {
+            self.inverse_mass = 1.0 / mass;
+            let inertia = mass * self.collider.shape.unit_inertia();
+            self.inverse_inertia =
+                if inertia > 0.0 { 1.0 / inertia } else { 0.0 };
+        } else {
+            self.inverse_mass = 0.0;
+            self.inverse_inertia = 0.0;
+        }
+
+        if self.body_type == BodyType::Static {
+            self.linear_velocity = Vec2::ZERO;
+            self.angular_velocity = 0.0;
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::math::{IVec2, Rect};
+
+/// Bounds covering more cells than this are tested against everything
+/// instead of being inserted in each cell.
+const MAX_CELLS: i64 = 64;
+
+/// Uniform grid finding the pairs of bounds that overlap.
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<usize>>,
+    /// Bounds too big for the grid.
+    large: Vec<usize>,
+    rects: Vec<Rect>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(2.0)
+    }
+}
+
+impl SpatialHash {
+    /// Cells should be about the size of the most common bodies.
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            large: Vec::new(),
+            rects: Vec::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.large.clear();
+        self.rects.clear();
+    }
+
+    /// Inserts bounds, identified by the order of insertion starting from
+    /// `0`.
+    pub fn insert(&mut self, rect: Rect) -> usize {
+        let index = self.rects.len();
+        self.rects.push(rect);
+
+        let (min, max) = (
+            self.cell(rect.min.x, rect.min.y),
+            self.cell(rect.max.x, rect.max.y),
+        );
+        let cells = (max.x as i64 - min.x as i64 + 1)
+            * (max.y as i64 - min.y as i64 + 1);
+        if cells > MAX_CELLS {
+            self.large.push(index);
+            return index;
+        }
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+        index
+    }
+
+    /// Every pair of overlapping bounds, sorted and with the lowest index
+    /// first.
+    pub fn pairs(&self) -> BTreeSet<(usize, usize)> {
+        let mut pairs = BTreeSet::new();
+        let mut push = |a: usize, b: usize| {
+            if a != b && self.rects[a].intersects(&self.rects[b]) {
+                pairs.insert((a.min(b), a.max(b)));
+            }
+        };
+
+        for cell in self.cells.values() {
+            for (i, a) in cell.iter().enumerate() {
+                for b in &cell[i + 1..] {
+                    push(*a, *b);
+                }
+            }
+        }
+        for large in &self.large {
+            for other in 0..self.rects.len() {
+                push(*large, other);
+            }
+        }
+
+        pairs
+    }
+
+    fn cell(&self, x: f32, y: f32) -> IVec2 {
+        IVec2::new(
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+
+    #[test]
+    fn finds_overlapping_pairs() {
+        let mut grid = SpatialHash::new(1.0);
+        let square = |x: f32, y: f32, size: f32| {
+            Rect::from_center_size(Vec2::new(x, y), Vec2::splat(size))
+        };
+        grid.insert(square(0.0, 0.0, 1.0));
+        grid.insert(square(0.8, 0.0, 1.0));
+        grid.insert(square(5.0, 5.0, 1.0));
+        // Too big for the grid
+        grid.insert(square(5.0, 0.0, 20.0));
+        grid.insert(square(30.0, 0.0, 1.0));
+
+        let pairs: Vec<_> = grid.pairs().into_iter().collect();
+        assert_eq!(pairs, [(0, 1), (0, 3), (1, 3), (2, 3)]);
+
+        grid.clear();
+        assert!(grid.pairs().is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+use crate::math::{Rect, Vec2};
+
+use super::Error;
+
+/// Convex polygon with counter-clockwise vertices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvexPolygon {
+    vertices: Vec<Vec2>,
+    /// Outward normal of the edge starting at the vertex with the same index.
+    normals: Vec<Vec2>,
+}
+
+impl ConvexPolygon {
+    /// Creates the convex hull of the passed points, moved so that its
+    /// centroid is at the origin.
+    ///
+    /// _Points inside the hull or on its edges are dropped._
+    pub fn new(points: &[Vec2]) -> Result<Self, Error> {
+        let mut hull = convex_hull(points);
+        if hull.len() < 3 {
+            return Err(Error::InvalidPolygon(format!(
+                "{} points do not enclose an area",
+                points.len()
+            )));
+        }
+
+        let centroid = centroid(&hull);
+        hull.iter_mut().for_each(|vertex| *vertex -= centroid);
+        Ok(Self::from_hull(hull))
+    }
+
+    /// Creates a rectangle centered at the origin.
+    pub fn rectangle(half_extents: Vec2) -> Self {
+        let Vec2 { x, y } = half_extents.abs();
+        Self::from_hull(vec![
+            Vec2::new(-x, -y),
+            Vec2::new(x, -y),
+            Vec2::new(x, y),
+            Vec2::new(-x, y),
+        ])
+    }
+
+    fn from_hull(vertices: Vec<Vec2>) -> Self {
+        let normals = (0..vertices.len())
+            .map(|i| {
+                let edge = vertices[(i + 1) % vertices.len()] - vertices[i];
+                Vec2::new(edge.y, -edge.x).normalize_or_zero()
+            })
+            .collect();
+
+        Self { vertices, normals }
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Vec2] {
+        &self.normals
+    }
+
+    pub fn area(&self) -> f32 {
+        let n = self.vertices.len();
+        (0..n)
+            .map(|i| self.vertices[i].perp_dot(self.vertices[(i + 1) % n]))
+            .sum::<f32>()
+            * 0.5
+    }
+
+    /// Moment of inertia around the origin for a unit mass.
+    fn unit_inertia(&self) -> f32 {
+        let n = self.vertices.len();
+        let (mut numerator, mut denominator) = (0.0, 0.0);
+        for i in 0..n {
+            let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+            let cross = a.perp_dot(b).abs();
+            numerator += cross * (a.dot(a) + a.dot(b) + b.dot(b));
+            denominator += cross;
+        }
+
+        if denominator > 0.0 {
+            numerator / (6.0 * denominator)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the polygon placed in the world.
+    pub(crate) fn transformed(&self, position: Vec2, rotation: f32) -> Self {
+        let rotation = Vec2::from_angle(rotation);
+        Self {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|vertex| position + rotation.rotate(*vertex))
+                .collect(),
+            normals: self
+                .normals
+                .iter()
+                .map(|normal| rotation.rotate(*normal))
+                .collect(),
+        }
+    }
+}
+
+/// Geometry of a collider, centered at the position of its body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Circle { radius: f32 },
+    Box { half_extents: Vec2 },
+    Polygon(ConvexPolygon),
+}
+
+impl Shape {
+    pub fn area(&self) -> f32 {
+        match self {
+            Self::Circle { radius } => std::f32::consts::PI * radius * radius,
+            Self::Box { half_extents } => 4.0 * half_extents.x * half_extents.y,
+            Self::Polygon(polygon) => polygon.area(),
+        }
+    }
+
+    /// Moment of inertia around the center for a unit mass.
+    pub(crate) fn unit_inertia(&self) -> f32 {
+        match self {
+            Self::Circle { radius } => radius * radius * 0.5,
+            Self::Box { half_extents } => half_extents.length_squared() / 3.0,
+            Self::Polygon(polygon) => polygon.unit_inertia(),
+        }
+    }
+
+    /// Bounds of the shape placed in the world.
+    pub fn rect(&self, position: Vec2, rotation: f32) -> Rect {
+        match self {
+            Self::Circle { radius } => {
+                Rect::from_center_size(position, Vec2::splat(radius * 2.0))
+            }
+            Self::Box { half_extents } => {
+                let rotation = Vec2::from_angle(rotation);
+                let extents = Vec2::new(
+                    rotation.x.abs() * half_extents.x
+                        + rotation.y.abs() * half_extents.y,
+                    rotation.y.abs() * half_extents.x
+                        + rotation.x.abs() * half_extents.y,
+                );
+                Rect::from_center_size(position, extents * 2.0)
+            }
+            Self::Polygon(polygon) => Rect::from_points(
+                polygon.transformed(position, rotation).vertices,
+            )
+            .unwrap_or(Rect::new(position, position)),
+        }
+    }
+}
+
+/// Shape and material of a body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collider {
+    pub shape: Shape,
+    /// Mass per unit of area.
+    pub density: f32,
+    /// Resistance to sliding, combined with the geometric mean.
+    pub friction: f32,
+    /// Bounciness from `0.0` to `1.0`, the highest of the two is used.
+    pub restitution: f32,
+    /// Sensors report overlaps instead of colliding.
+    pub sensor: bool,
+}
+
+impl Collider {
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            density: 1.0,
+            friction: 0.5,
+            restitution: 0.0,
+            sensor: false,
+        }
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        Self::new(Shape::Circle {
+            radius: radius.abs(),
+        })
+    }
+
+    pub fn rectangle(width: f32, height: f32) -> Self {
+        Self::new(Shape::Box {
+            half_extents: Vec2::new(width, height).abs() * 0.5,
+        })
+    }
+
+    /// Creates a collider from the convex hull of the points, see
+    /// `ConvexPolygon::new()`.
+    pub fn polygon(points: &[Vec2]) -> Result<Self, Error> {
+        Ok(Self::new(Shape::Polygon(ConvexPolygon::new(points)?)))
+    }
+
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_sensor(mut self, sensor: bool) -> Self {
+        self.sensor = sensor;
+        self
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.shape.area() * self.density
+    }
+}
+
+/// Andrew's monotone chain, returns the hull counter-clockwise.
+fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() * 2);
+    for pass in [
+        &points[..],
+        &points.iter().rev().copied().collect::<Vec<_>>(),
+    ] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if (b - a).perp_dot(*point - a) > f32::EPSILON {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        // The last point is the first one of the other pass
+        hull.pop();
+    }
+
+    hull
+}
+
+fn centroid(vertices: &[Vec2]) -> Vec2 {
+    let n = vertices.len();
+    let (mut center, mut area) = (Vec2::ZERO, 0.0);
+    for i in 0..n {
+        let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+        let cross = a.perp_dot(b);
+        center += (a + b) * cross;
+        area += cross;
+    }
+
+    center / (3.0 * area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EPSILON;
+
+    #[test]
+    fn polygons_are_convex_hulls() {
+        let polygon = ConvexPolygon::new(&[
+            Vec2::new(2.0, 2.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(1.0, 0.0),
+        ])
+        .unwrap();
+
+        // Centered, counter-clockwise and without the inner points
+        assert_eq!(
+            polygon.vertices(),
+            [
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(1.0, -1.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(-1.0, 1.0),
+            ]
+        );
+        assert_eq!(polygon.normals()[0], Vec2::NEG_Y);
+        assert!((polygon.area() - 4.0).abs() < EPSILON);
+
+        assert!(matches!(
+            ConvexPolygon::new(&[Vec2::ZERO, Vec2::X, Vec2::X * 2.0]),
+            Err(Error::InvalidPolygon(_))
+        ));
+    }
+
+    #[test]
+    fn mass_properties() {
+        let square = Collider::rectangle(2.0, 2.0).with_density(2.0);
+        assert!((square.mass() - 8.0).abs() < EPSILON);
+
+        // A box and the same polygon must agree
+        let polygon =
+            Shape::Polygon(ConvexPolygon::rectangle(Vec2::new(1.0, 0.5)));
+        let rectangle = Collider::rectangle(2.0, 1.0).shape;
+        assert!((polygon.area() - rectangle.area()).abs() < EPSILON);
+        assert!(
+            (polygon.unit_inertia() - rectangle.unit_inertia()).abs() < EPSILON
+        );
+
+        let rect = rectangle.rect(Vec2::ZERO, std::f32::consts::FRAC_PI_2);
+        assert!((rect.half_size() - Vec2::new(0.5, 1.0)).length() < EPSILON);
+    }
+}
@@ -0,0 +1,84 @@
+//! 2D rigid body physics.
+//!
+//! Bodies live in a `PhysicsWorld`, each one with a single `Collider` shaped
+//! as a circle, a box or a convex polygon. On every `PhysicsWorld::step()`:
+//!
+//! 1. Forces and gravity are integrated into the velocities.
+//! 2. The broadphase (a spatial hash) finds the pairs whose bounds overlap.
+//! 3. The narrowphase builds a contact `Manifold` for each touching pair.
+//! 4. The solver applies impulses to resolve the contacts, then velocities
+//!    are integrated into the positions.
+//! 5. `PhysicsEvent`s report the collisions and sensor overlaps that started
+//!    or ended.
+//!
+//! The `Engine` steps its world on every fixed tick and copies the positions
+//! to the `Transform` of the entities with a `BodyId` component. Bodies are
+//! always processed in the same order, identical inputs produce identical
+//! results.
+//!
+//! ```
+//! # use unen_engine::{core::event::create_handler, math::Vec2};
+//! # use unen_engine::physics::{Collider, PhysicsWorld, RigidBody};
+//! let (dispatcher, _) = create_handler();
+//! let mut physics = PhysicsWorld::new();
+//! physics.add(RigidBody::fixed(Collider::rectangle(20.0, 1.0)));
+//! let ball = physics.add(
+//!     RigidBody::dynamic(Collider::circle(0.5))
+//!         .with_position(Vec2::new(0.0, 5.0)),
+//! );
+//!
+//! for _ in 0..120 {
+//!     physics.step(1.0 / 60.0, &dispatcher);
+//! }
+//! assert!(physics.get(ball).unwrap().position.y < 1.5);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod body;
+pub mod broadphase;
+pub mod collider;
+pub mod narrowphase;
+mod solver;
+pub mod world;
+
+pub use body::{BodyType, RigidBody};
+pub use broadphase::SpatialHash;
+pub use collider::{Collider, ConvexPolygon, Shape};
+pub use narrowphase::{ContactPoint, Manifold};
+pub use world::PhysicsWorld;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The points do not make a polygon with an area.
+    #[error("Invalid polygon: {0}")]
+    InvalidPolygon(String),
+    /// The body was removed or never existed.
+    #[error("No such body '{0}'")]
+    NoSuchBody(BodyId),
+}
+
+/// Identifies a body, unique for the lifetime of a `PhysicsWorld`.
+///
+/// It can be added as a component to an entity to make the `Engine` move it
+/// with the body.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct BodyId(pub(crate) u32);
+
+impl std::fmt::Display for BodyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Body({})", self.0)
+    }
+}
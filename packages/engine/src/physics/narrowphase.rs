@@ -0,0 +1,294 @@
+use crate::math::Vec2;
+
+use super::{BodyId, ConvexPolygon, RigidBody, Shape};
+
+/// Allowed penetration, keeps contacts stable between steps.
+pub(crate) const LINEAR_SLOP: f32 = 0.005;
+
+/// Point where two bodies touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactPoint {
+    /// World space.
+    pub point: Vec2,
+    /// How far the bodies overlap along the normal.
+    pub depth: f32,
+}
+
+/// Contact between two touching bodies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifold {
+    pub a: BodyId,
+    pub b: BodyId,
+    /// Points from `a` to `b`.
+    pub normal: Vec2,
+    /// One or two points.
+    pub points: Vec<ContactPoint>,
+}
+
+/// Shape placed in the world.
+enum Placed {
+    Circle(Vec2, f32),
+    Polygon(ConvexPolygon),
+}
+
+impl Placed {
+    fn new(body: &RigidBody) -> Self {
+        match &body.collider().shape {
+            Shape::Circle { radius } => Self::Circle(body.position, *radius),
+            Shape::Box { half_extents } => Self::Polygon(
+                ConvexPolygon::rectangle(*half_extents)
+                    .transformed(body.position, body.rotation),
+            ),
+            Shape::Polygon(polygon) => {
+                Self::Polygon(polygon.transformed(body.position, body.rotation))
+            }
+        }
+    }
+}
+
+/// Returns the normal from `a` to `b` and the contact points, `None` if the
+/// bodies do not touch.
+pub(crate) fn collide(
+    a: &RigidBody,
+    b: &RigidBody,
+) -> Option<(Vec2, Vec<ContactPoint>)> {
+    match (Placed::new(a), Placed::new(b)) {
+        (Placed::Circle(a, ra), Placed::Circle(b, rb)) => {
+            collide_circles(a, ra, b, rb)
+        }
+        (Placed::Polygon(a), Placed::Circle(b, rb)) => {
+            collide_polygon_circle(&a, b, rb)
+        }
+        (Placed::Circle(a, ra), Placed::Polygon(b)) => {
+            collide_polygon_circle(&b, a, ra)
+                .map(|(normal, points)| (-normal, points))
+        }
+        (Placed::Polygon(a), Placed::Polygon(b)) => collide_polygons(&a, &b),
+    }
+}
+
+fn collide_circles(
+    a: Vec2,
+    ra: f32,
+    b: Vec2,
+    rb: f32,
+) -> Option<(Vec2, Vec<ContactPoint>)> {
+    let offset = b - a;
+    let distance = offset.length();
+    if distance > ra + rb {
+        return None;
+    }
+
+    // Concentric circles get pushed apart upwards
+    let normal = offset.try_normalize().unwrap_or(Vec2::Y);
+    let depth = ra + rb - distance;
+    let point = a + normal * (ra - depth * 0.5);
+    Some((normal, vec![ContactPoint { point, depth }]))
+}
+
+fn collide_polygon_circle(
+    polygon: &ConvexPolygon,
+    center: Vec2,
+    radius: f32,
+) -> Option<(Vec2, Vec<ContactPoint>)> {
+    let vertices = polygon.vertices();
+    let normals = polygon.normals();
+
+    // Face closest to the center
+    let (face, separation) = normals
+        .iter()
+        .zip(vertices)
+        .map(|(normal, vertex)| normal.dot(center - *vertex))
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    if separation > radius {
+        return None;
+    }
+
+    let v1 = vertices[face];
+    let v2 = vertices[(face + 1) % vertices.len()];
+    let (normal, distance) = if separation <= 0.0 {
+        // The center is inside
+        (normals[face], separation)
+    } else if (center - v1).dot(v2 - v1) <= 0.0 {
+        let offset = center - v1;
+        (offset.try_normalize()?, offset.length())
+    } else if (center - v2).dot(v1 - v2) <= 0.0 {
+        let offset = center - v2;
+        (offset.try_normalize()?, offset.length())
+    } else {
+        (normals[face], separation)
+    };
+    if distance > radius {
+        return None;
+    }
+
+    let depth = radius - distance;
+    let point = center - normal * (radius - depth * 0.5);
+    Some((normal, vec![ContactPoint { point, depth }]))
+}
+
+/// Edge of `a` along which `b` is the most separated.
+fn max_separation(a: &ConvexPolygon, b: &ConvexPolygon) -> (usize, f32) {
+    a.normals()
+        .iter()
+        .zip(a.vertices())
+        .map(|(normal, vertex)| {
+            b.vertices()
+                .iter()
+                .map(|point| normal.dot(*point - *vertex))
+                .fold(f32::INFINITY, f32::min)
+        })
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (edge, separation)| {
+            if separation > best.1 {
+                (edge, separation)
+            } else {
+                best
+            }
+        })
+}
+
+/// Separating axis test, then the incident edge is clipped against the
+/// reference face.
+fn collide_polygons(
+    a: &ConvexPolygon,
+    b: &ConvexPolygon,
+) -> Option<(Vec2, Vec<ContactPoint>)> {
+    let (edge_a, separation_a) = max_separation(a, b);
+    if separation_a > 0.0 {
+        return None;
+    }
+    let (edge_b, separation_b) = max_separation(b, a);
+    if separation_b > 0.0 {
+        return None;
+    }
+
+    // Prefer `a` to avoid switching between steps
+    let (reference, incident, edge, flip) =
+        if separation_b > separation_a + 0.1 * LINEAR_SLOP {
+            (b, a, edge_b, true)
+        } else {
+            (a, b, edge_a, false)
+        };
+
+    let normal = reference.normals()[edge];
+    let count = reference.vertices().len();
+    let v1 = reference.vertices()[edge];
+    let v2 = reference.vertices()[(edge + 1) % count];
+
+    // Edge of the incident polygon facing the reference face the most
+    let incident_edge = incident
+        .normals()
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.dot(normal).total_cmp(&b.dot(normal)))
+        .map(|(edge, _)| edge)?;
+    let count = incident.vertices().len();
+    let mut points = [
+        incident.vertices()[incident_edge],
+        incident.vertices()[(incident_edge + 1) % count],
+    ];
+
+    // Clip to the sides of the reference face
+    let tangent = (v2 - v1).normalize_or_zero();
+    points = clip(points, -tangent, -tangent.dot(v1))?;
+    points = clip(points, tangent, tangent.dot(v2))?;
+
+    let points: Vec<ContactPoint> = points
+        .into_iter()
+        .filter_map(|point| {
+            let separation = normal.dot(point - v1);
+            (separation <= 0.0).then_some(ContactPoint {
+                point: point - normal * (separation * 0.5),
+                depth: -separation,
+            })
+        })
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+
+    Some((if flip { -normal } else { normal }, points))
+}
+
+/// Keeps the part of the segment where `normal.dot(point) <= offset`.
+fn clip(points: [Vec2; 2], normal: Vec2, offset: f32) -> Option<[Vec2; 2]> {
+    let distance = points.map(|point| normal.dot(point) - offset);
+    match (distance[0] <= 0.0, distance[1] <= 0.0) {
+        (true, true) => Some(points),
+        (false, false) => None,
+        (inside_first, _) => {
+            let t = distance[0] / (distance[0] - distance[1]);
+            let intersection = points[0] + (points[1] - points[0]) * t;
+            Some(if inside_first {
+                [points[0], intersection]
+            } else {
+                [intersection, points[1]]
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::EPSILON, physics::Collider};
+
+    fn body(collider: Collider, x: f32, y: f32) -> RigidBody {
+        RigidBody::dynamic(collider).with_position(Vec2::new(x, y))
+    }
+
+    #[test]
+    fn circles() {
+        let a = body(Collider::circle(1.0), 0.0, 0.0);
+        let b = body(Collider::circle(1.0), 1.5, 0.0);
+        let (normal, points) = collide(&a, &b).unwrap();
+        assert_eq!(normal, Vec2::X);
+        assert!((points[0].depth - 0.5).abs() < EPSILON);
+        assert!((points[0].point - Vec2::new(0.75, 0.0)).length() < EPSILON);
+
+        assert!(collide(&a, &body(Collider::circle(1.0), 2.5, 0.0)).is_none());
+    }
+
+    #[test]
+    fn polygon_and_circle() {
+        let square = body(Collider::rectangle(2.0, 2.0), 0.0, 0.0);
+        let above = body(Collider::circle(0.5), 0.0, 1.25);
+        let (normal, points) = collide(&square, &above).unwrap();
+        assert!((normal - Vec2::Y).length() < EPSILON);
+        assert!((points[0].depth - 0.25).abs() < EPSILON);
+
+        // Swapped, the normal still points from the first body
+        let (normal, _) = collide(&above, &square).unwrap();
+        assert!((normal + Vec2::Y).length() < EPSILON);
+
+        // Near a corner but outside of its radius
+        let corner = body(Collider::circle(0.5), 1.4, 1.4);
+        assert!(collide(&square, &corner).is_none());
+        let corner = body(Collider::circle(0.5), 1.3, 1.3);
+        let (normal, _) = collide(&square, &corner).unwrap();
+        assert!((normal - Vec2::ONE.normalize()).length() < EPSILON);
+    }
+
+    #[test]
+    fn stacked_boxes_touch_in_two_points() {
+        let ground = body(Collider::rectangle(10.0, 1.0), 0.0, 0.0);
+        let crate_ = body(Collider::rectangle(1.0, 1.0), 0.0, 0.9);
+        let (normal, points) = collide(&ground, &crate_).unwrap();
+
+        assert!((normal - Vec2::Y).length() < EPSILON);
+        assert_eq!(points.len(), 2);
+        for point in &points {
+            assert!((point.depth - 0.1).abs() < EPSILON);
+            assert!((point.point.y - 0.45).abs() < EPSILON);
+            assert!((point.point.x.abs() - 0.5).abs() < EPSILON);
+        }
+
+        let (normal, _) = collide(&crate_, &ground).unwrap();
+        assert!((normal + Vec2::Y).length() < EPSILON);
+
+        let apart = body(Collider::rectangle(1.0, 1.0), 0.0, 1.1);
+        assert!(collide(&ground, &apart).is_none());
+    }
+}
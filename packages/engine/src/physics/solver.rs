@@ -0,0 +1,159 @@
+//! Sequential impulses solver.
+
+use crate::math::Vec2;
+
+use super::{narrowphase::LINEAR_SLOP, Manifold, RigidBody};
+
+/// Fraction of the penetration resolved every step.
+const BAUMGARTE: f32 = 0.2;
+/// Slower impacts do not bounce, avoids jitter of resting bodies.
+const RESTITUTION_THRESHOLD: f32 = 1.0;
+
+/// Copy of the data of a body needed while solving.
+pub(crate) struct SolverBody {
+    pub position: Vec2,
+    pub linear_velocity: Vec2,
+    pub angular_velocity: f32,
+    inverse_mass: f32,
+    inverse_inertia: f32,
+}
+
+impl SolverBody {
+    pub fn new(body: &RigidBody) -> Self {
+        Self {
+            position: body.position,
+            linear_velocity: body.linear_velocity,
+            angular_velocity: body.angular_velocity,
+            inverse_mass: body.inverse_mass(),
+            inverse_inertia: body.inverse_inertia(),
+        }
+    }
+
+    fn velocity_at(&self, offset: Vec2) -> Vec2 {
+        self.linear_velocity + offset.perp() * self.angular_velocity
+    }
+
+    fn apply(&mut self, impulse: Vec2, offset: Vec2) {
+        self.linear_velocity += impulse * self.inverse_mass;
+        self.angular_velocity +=
+            offset.perp_dot(impulse) * self.inverse_inertia;
+    }
+}
+
+struct PointConstraint {
+    offset_a: Vec2,
+    offset_b: Vec2,
+    normal_mass: f32,
+    tangent_mass: f32,
+    bias: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+}
+
+/// Contact between the bodies at the attached indices.
+pub(crate) struct ContactConstraint {
+    a: usize,
+    b: usize,
+    normal: Vec2,
+    friction: f32,
+    points: Vec<PointConstraint>,
+}
+
+impl ContactConstraint {
+    pub fn new(
+        manifold: &Manifold,
+        (a, body_a): (usize, &RigidBody),
+        (b, body_b): (usize, &RigidBody),
+        bodies: &[SolverBody],
+        delta: f32,
+    ) -> Self {
+        let (solver_a, solver_b) = (&bodies[a], &bodies[b]);
+        let normal = manifold.normal;
+        let tangent = normal.perp();
+        let friction =
+            (body_a.collider().friction * body_b.collider().friction).sqrt();
+        let restitution = body_a
+            .collider()
+            .restitution
+            .max(body_b.collider().restitution);
+
+        let effective_mass = |offset_a: Vec2, offset_b: Vec2, axis: Vec2| {
+            let (ra, rb) = (offset_a.perp_dot(axis), offset_b.perp_dot(axis));
+            let mass = solver_a.inverse_mass
+                + solver_b.inverse_mass
+                + solver_a.inverse_inertia * ra * ra
+                + solver_b.inverse_inertia * rb * rb;
+            if mass > 0.0 {
+                1.0 / mass
+            } else {
+                0.0
+            }
+        };
+
+        let points = manifold
+            .points
+            .iter()
+            .map(|contact| {
+                let offset_a = contact.point - solver_a.position;
+                let offset_b = contact.point - solver_b.position;
+                let approach = (solver_b.velocity_at(offset_b)
+                    - solver_a.velocity_at(offset_a))
+                .dot(normal);
+
+                let mut bias =
+                    BAUMGARTE / delta * (contact.depth - LINEAR_SLOP).max(0.0);
+                if approach < -RESTITUTION_THRESHOLD {
+                    bias = bias.max(-restitution * approach);
+                }
+
+                PointConstraint {
+                    offset_a,
+                    offset_b,
+                    normal_mass: effective_mass(offset_a, offset_b, normal),
+                    tangent_mass: effective_mass(offset_a, offset_b, tangent),
+                    bias,
+                    normal_impulse: 0.0,
+                    tangent_impulse: 0.0,
+                }
+            })
+            .collect();
+
+        Self {
+            a,
+            b,
+            normal,
+            friction,
+            points,
+        }
+    }
+
+    pub fn solve(&mut self, bodies: &mut [SolverBody]) {
+        let tangent = self.normal.perp();
+        for point in &mut self.points {
+            let (a, b) = (&bodies[self.a], &bodies[self.b]);
+            let relative =
+                b.velocity_at(point.offset_b) - a.velocity_at(point.offset_a);
+
+            // Friction is bounded by the current normal impulse
+            let impulse = -relative.dot(tangent) * point.tangent_mass;
+            let limit = self.friction * point.normal_impulse;
+            let total = (point.tangent_impulse + impulse).clamp(-limit, limit);
+            let impulse = tangent * (total - point.tangent_impulse);
+            point.tangent_impulse = total;
+            bodies[self.a].apply(-impulse, point.offset_a);
+            bodies[self.b].apply(impulse, point.offset_b);
+
+            let (a, b) = (&bodies[self.a], &bodies[self.b]);
+            let relative =
+                b.velocity_at(point.offset_b) - a.velocity_at(point.offset_a);
+            let impulse =
+                (point.bias - relative.dot(self.normal)) * point.normal_mass;
+            // Bodies can only be pushed apart
+            let total = (point.normal_impulse + impulse).max(0.0);
+            let impulse = self.normal * (total - point.normal_impulse);
+            point.normal_impulse = total;
+            bodies[self.a].apply(-impulse, point.offset_a);
+            bodies[self.b].apply(impulse, point.offset_b);
+        }
+    }
+}
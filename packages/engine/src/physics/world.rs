@@ -0,0 +1,424 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    core::event::{Dispatcher, Event, PhysicsEvent},
+    math::{Quat, Transform, Vec2},
+    scene::{Entity, World},
+};
+
+use super::{
+    broadphase::SpatialHash,
+    narrowphase::{self, Manifold},
+    solver::{ContactConstraint, SolverBody},
+    BodyId, BodyType, Error, RigidBody,
+};
+
+/// Iterations of the solver on every step, more makes stacks more stable.
+const DEFAULT_ITERATIONS: usize = 8;
+
+/// Simulates a set of bodies.
+#[derive(Debug, Clone)]
+pub struct PhysicsWorld {
+    gravity: Vec2,
+    iterations: usize,
+    /// Ordered to keep the simulation deterministic.
+    bodies: BTreeMap<BodyId, RigidBody>,
+    next_id: u32,
+    broadphase: SpatialHash,
+    /// Bodies touching since the last step.
+    contacts: BTreeMap<(BodyId, BodyId), Manifold>,
+    /// Sensor and the body overlapping it since the last step.
+    overlaps: BTreeSet<(BodyId, BodyId)>,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self {
+            gravity: Vec2::new(0.0, -9.81),
+            iterations: DEFAULT_ITERATIONS,
+            bodies: BTreeMap::new(),
+            next_id: 0,
+            broadphase: SpatialHash::default(),
+            contacts: BTreeMap::new(),
+            overlaps: BTreeSet::new(),
+        }
+    }
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_gravity(mut self, gravity: Vec2) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+
+    pub fn with_broadphase(mut self, broadphase: SpatialHash) -> Self {
+        self.broadphase = broadphase;
+        self
+    }
+
+    pub fn gravity(&self) -> Vec2 {
+        self.gravity
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vec2) {
+        self.gravity = gravity;
+    }
+
+    pub fn add(&mut self, body: RigidBody) -> BodyId {
+        let id = BodyId(self.next_id);
+        self.next_id += 1;
+        self.bodies.insert(id, body);
+        id
+    }
+
+    /// Removes the body, its contacts end on the next step.
+    pub fn remove(&mut self, id: BodyId) -> Result<RigidBody, Error> {
+        self.bodies.remove(&id).ok_or(Error::NoSuchBody(id))
+    }
+
+    pub fn get(&self, id: BodyId) -> Option<&RigidBody> {
+        self.bodies.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: BodyId) -> Option<&mut RigidBody> {
+        self.bodies.get_mut(&id)
+    }
+
+    /// Every body, ordered by id.
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyId, &RigidBody)> {
+        self.bodies.iter().map(|(id, body)| (*id, body))
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    /// Contacts found during the last step.
+    pub fn contacts(&self) -> impl Iterator<Item = &Manifold> {
+        self.contacts.values()
+    }
+
+    /// Returns `true` if the bodies touched during the last step.
+    pub fn in_contact(&self, a: BodyId, b: BodyId) -> bool {
+        self.contacts.contains_key(&(a.min(b), a.max(b)))
+    }
+
+    /// Returns `true` if the body overlapped the sensor during the last
+    /// step.
+    pub fn in_sensor(&self, sensor: BodyId, body: BodyId) -> bool {
+        self.overlaps.contains(&(sensor, body))
+    }
+
+    /// Advances the simulation by `delta` seconds, sending the collisions
+    /// and sensor overlaps that started or ended.
+    ///
+    /// _Use a fixed `delta`, the results of variable steps are not
+    /// reproducible._
+    pub fn step(&mut self, delta: f32, dispatcher: &Dispatcher) {
+        crate::profile_scope!("PhysicsWorld::step", "physics");
+        if delta <= 0.0 {
+            return;
+        }
+
+        for body in self.bodies.values_mut() {
+            body.integrate_forces(self.gravity, delta);
+        }
+
+        let ids: Vec<BodyId> = self.bodies.keys().copied().collect();
+        let bodies: Vec<&RigidBody> = self.bodies.values().collect();
+
+        self.broadphase.clear();
+        for body in &bodies {
+            let shape = &body.collider().shape;
+            self.broadphase
+                .insert(shape.rect(body.position, body.rotation));
+        }
+
+        let mut contacts = BTreeMap::new();
+        let mut overlaps = BTreeSet::new();
+        for (a, b) in self.broadphase.pairs() {
+            let (body_a, body_b) = (bodies[a], bodies[b]);
+            let sensor_a = body_a.collider().sensor;
+            let sensor_b = body_b.collider().sensor;
+            let dynamic = body_a.body_type() == BodyType::Dynamic
+                || body_b.body_type() == BodyType::Dynamic;
+            if !dynamic && !sensor_a && !sensor_b {
+                continue;
+            }
+
+            let Some((normal, points)) = narrowphase::collide(body_a, body_b)
+            else {
+                continue;
+            };
+            if sensor_a || sensor_b {
+                if sensor_a {
+                    overlaps.insert((ids[a], ids[b]));
+                }
+                if sensor_b {
+                    overlaps.insert((ids[b], ids[a]));
+                }
+                continue;
+            }
+
+            let manifold = Manifold {
+                a: ids[a],
+                b: ids[b],
+                normal,
+                points,
+            };
+            contacts.insert((ids[a], ids[b]), (a, b, manifold));
+        }
+
+        let mut solver_bodies: Vec<SolverBody> =
+            bodies.iter().map(|body| SolverBody::new(body)).collect();
+        let mut constraints: Vec<ContactConstraint> = contacts
+            .values()
+            .map(|(a, b, manifold)| {
+                ContactConstraint::new(
+                    manifold,
+                    (*a, bodies[*a]),
+                    (*b, bodies[*b]),
+                    &solver_bodies,
+                    delta,
+                )
+            })
+            .collect();
+        for _ in 0..self.iterations {
+            for constraint in &mut constraints {
+                constraint.solve(&mut solver_bodies);
+            }
+        }
+
+        for (body, solved) in self.bodies.values_mut().zip(solver_bodies) {
+            if body.body_type() == BodyType::Dynamic {
+                body.linear_velocity = solved.linear_velocity;
+                body.angular_velocity = solved.angular_velocity;
+            }
+            body.integrate_velocity(delta);
+        }
+
+        let contacts: BTreeMap<_, _> = contacts
+            .into_iter()
+            .map(|(key, (_, _, manifold))| (key, manifold))
+            .collect();
+        self.dispatch_changes(&contacts, &overlaps, dispatcher);
+        self.contacts = contacts;
+        self.overlaps = overlaps;
+    }
+
+    /// Internal function that sends the events of the contacts and overlaps
+    /// that changed.
+    fn dispatch_changes(
+        &self,
+        contacts: &BTreeMap<(BodyId, BodyId), Manifold>,
+        overlaps: &BTreeSet<(BodyId, BodyId)>,
+        dispatcher: &Dispatcher,
+    ) {
+        let send = |event| dispatcher.send(Event::Physics(event));
+
+        for (a, b) in self.contacts.keys() {
+            if !contacts.contains_key(&(*a, *b)) {
+                send(PhysicsEvent::CollisionEnded(*a, *b));
+            }
+        }
+        for (a, b) in contacts.keys() {
+            if !self.contacts.contains_key(&(*a, *b)) {
+                send(PhysicsEvent::CollisionStarted(*a, *b));
+            }
+        }
+        for (sensor, body) in self.overlaps.difference(overlaps) {
+            send(PhysicsEvent::SensorExited(*sensor, *body));
+        }
+        for (sensor, body) in overlaps.difference(&self.overlaps) {
+            send(PhysicsEvent::SensorEntered(*sensor, *body));
+        }
+    }
+
+    /// Copies the position and rotation of the bodies to the `Transform` of
+    /// the entities with their `BodyId`, keeping the depth and scale.
+    pub fn sync_transforms(&self, world: &mut World) {
+        let linked: Vec<(Entity, BodyId)> =
+            world.query::<BodyId>().map(|(e, id)| (e, *id)).collect();
+        for (entity, id) in linked {
+            let (Some(body), Some(transform)) =
+                (self.bodies.get(&id), world.get_mut::<Transform>(entity))
+            else {
+                continue;
+            };
+            transform.translation.x = body.position.x;
+            transform.translation.y = body.position.y;
+            transform.rotation = Quat::from_rotation_z(body.rotation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::event::{create_handler, Consumer},
+        math::{Vec3, EPSILON},
+        physics::Collider,
+    };
+
+    const DELTA: f32 = 1.0 / 60.0;
+
+    fn events(consumer: &Consumer) -> Vec<PhysicsEvent> {
+        std::iter::from_fn(|| consumer.poll())
+            .filter_map(|event| match event {
+                Event::Physics(event) => Some(event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn stack() -> (PhysicsWorld, Vec<BodyId>) {
+        let mut physics = PhysicsWorld::new();
+        let mut ids =
+            vec![physics.add(RigidBody::fixed(Collider::rectangle(20.0, 1.0)))];
+        for i in 0..3 {
+            ids.push(
+                physics.add(
+                    RigidBody::dynamic(Collider::rectangle(1.0, 1.0))
+                        .with_position(Vec2::new(
+                            0.1 * i as f32,
+                            1.0 + i as f32 * 1.2,
+                        )),
+                ),
+            );
+        }
+        ids.push(
+            physics.add(
+                RigidBody::dynamic(Collider::circle(0.5).with_restitution(0.5))
+                    .with_position(Vec2::new(3.0, 4.0))
+                    .with_angular_velocity(2.0),
+            ),
+        );
+        (physics, ids)
+    }
+
+    #[test]
+    fn bodies_come_to_rest_on_the_ground() {
+        let (dispatcher, consumer) = create_handler();
+        let (mut physics, ids) = stack();
+        for _ in 0..240 {
+            physics.step(DELTA, &dispatcher);
+        }
+
+        // The ground is at 0.5, every box is 1 tall
+        for (i, id) in ids[1..4].iter().enumerate() {
+            let body = physics.get(*id).unwrap();
+            let expected = 1.0 + i as f32;
+            assert!(
+                (body.position.y - expected).abs() < 0.05,
+                "box {i} at {}",
+                body.position.y
+            );
+            assert!(body.linear_velocity.length() < 0.1);
+        }
+        let ball = physics.get(ids[4]).unwrap();
+        assert!((ball.position.y - 1.0).abs() < 0.05);
+        assert!(physics.in_contact(ids[0], ids[1]));
+        assert!(physics.in_contact(ids[2], ids[1]));
+        assert_eq!(physics.get(ids[0]).unwrap().position, Vec2::ZERO);
+
+        let events = events(&consumer);
+        assert!(
+            events.contains(&PhysicsEvent::CollisionStarted(ids[0], ids[4]))
+        );
+    }
+
+    #[test]
+    fn identical_inputs_give_identical_results() {
+        let (dispatcher, _consumer) = create_handler();
+        let (mut first, _) = stack();
+        let (mut second, _) = stack();
+        for _ in 0..120 {
+            first.step(DELTA, &dispatcher);
+            second.step(DELTA, &dispatcher);
+        }
+
+        for ((_, a), (_, b)) in first.bodies().zip(second.bodies()) {
+            assert_eq!(a.position.to_array(), b.position.to_array());
+            assert_eq!(a.rotation.to_bits(), b.rotation.to_bits());
+        }
+    }
+
+    #[test]
+    fn sensors_report_enter_and_exit() {
+        let (dispatcher, consumer) = create_handler();
+        let mut physics = PhysicsWorld::new().with_gravity(Vec2::ZERO);
+        let sensor = physics.add(RigidBody::fixed(
+            Collider::rectangle(2.0, 2.0).with_sensor(true),
+        ));
+        let ball = physics.add(
+            RigidBody::dynamic(Collider::circle(0.5))
+                .with_position(Vec2::new(-3.0, 0.0))
+                .with_linear_velocity(Vec2::new(6.0, 0.0)),
+        );
+
+        let mut all = Vec::new();
+        for _ in 0..60 {
+            physics.step(DELTA, &dispatcher);
+            all.extend(events(&consumer));
+            if physics.in_sensor(sensor, ball) {
+                // Sensors do not slow bodies down
+                assert_eq!(physics.get(ball).unwrap().linear_velocity.x, 6.0);
+            }
+        }
+        assert_eq!(
+            all,
+            [
+                PhysicsEvent::SensorEntered(sensor, ball),
+                PhysicsEvent::SensorExited(sensor, ball)
+            ]
+        );
+
+        // Removing a body ends its contacts
+        physics.get_mut(ball).unwrap().position = Vec2::ZERO;
+        physics.step(DELTA, &dispatcher);
+        assert!(physics.in_sensor(sensor, ball));
+        physics.remove(ball).unwrap();
+        physics.step(DELTA, &dispatcher);
+        assert_eq!(
+            events(&consumer).last(),
+            Some(&PhysicsEvent::SensorExited(sensor, ball))
+        );
+        assert_eq!(physics.remove(ball), Err(Error::NoSuchBody(ball)));
+    }
+
+    #[test]
+    fn transforms_follow_bodies() {
+        let mut world = World::new();
+        let mut physics = PhysicsWorld::new();
+        let id = physics.add(
+            RigidBody::kinematic(Collider::circle(1.0))
+                .with_position(Vec2::new(1.0, 2.0))
+                .with_rotation(std::f32::consts::FRAC_PI_2),
+        );
+        let entity = world
+            .spawn_with(Transform::from_translation(Vec3::new(0.0, 0.0, 5.0)));
+        world.insert(entity, id).unwrap();
+
+        physics.sync_transforms(&mut world);
+        let transform = world.get::<Transform>(entity).unwrap();
+        assert_eq!(transform.translation, Vec3::new(1.0, 2.0, 5.0));
+        assert!(
+            (transform.rotation_2d() - std::f32::consts::FRAC_PI_2).abs()
+                < EPSILON
+        );
+    }
+}
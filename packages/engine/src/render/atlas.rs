@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{sprite::Sprite, sprite::UvRect, TextureId};
+use crate::math::Vec2;
+
+/// Region of an atlas in pixels, starting at the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasRegion {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// A texture containing many images, each one referenced by an index and
+/// optionally a name.
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    texture: TextureId,
+    size: (u32, u32),
+    regions: Vec<AtlasRegion>,
+    names: HashMap<String, usize>,
+}
+
+impl TextureAtlas {
+    /// Creates an empty atlas for the passed texture and its size in pixels.
+    pub fn new(texture: TextureId, width: u32, height: u32) -> Self {
+        Self {
+            texture,
+            size: (width, height),
+            regions: Vec::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Creates an atlas made of equally sized cells, indexed row by row.
+    pub fn from_grid(
+        texture: TextureId,
+        cell_width: u32,
+        cell_height: u32,
+        columns: u32,
+        rows: u32,
+    ) -> Self {
+        let mut atlas =
+            Self::new(texture, cell_width * columns, cell_height * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                atlas.add(AtlasRegion::new(
+                    column * cell_width,
+                    row * cell_height,
+                    cell_width,
+                    cell_height,
+                ));
+            }
+        }
+
+        atlas
+    }
+
+    /// Adds a region, returning its index.
+    pub fn add(&mut self, region: AtlasRegion) -> usize {
+        self.regions.push(region);
+        self.regions.len() - 1
+    }
+
+    /// Adds a named region, returning its index.
+    pub fn add_named(&mut self, name: &str, region: AtlasRegion) -> usize {
+        let index = self.add(region);
+        self.names.insert(name.to_string(), index);
+        index
+    }
+
+    /// Returns the index of the region with the passed name.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    pub fn texture(&self) -> TextureId {
+        self.texture
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn region(&self, index: usize) -> Option<AtlasRegion> {
+        self.regions.get(index).copied()
+    }
+
+    /// Returns the texture coordinates of the passed region.
+    pub fn uv(&self, index: usize) -> Option<UvRect> {
+        let region = self.regions.get(index)?;
+        let (width, height) = (self.size.0 as f32, self.size.1 as f32);
+
+        Some(UvRect {
+            min: Vec2::new(region.x as f32 / width, region.y as f32 / height),
+            max: Vec2::new(
+                (region.x + region.width) as f32 / width,
+                (region.y + region.height) as f32 / height,
+            ),
+        })
+    }
+
+    /// Creates a sprite showing the passed region at its size in pixels.
+    pub fn sprite(&self, index: usize) -> Option<Sprite> {
+        let region = self.regions.get(index)?;
+        let uv = self.uv(index)?;
+
+        Some(
+            Sprite::new(
+                self.texture,
+                Vec2::new(region.width as f32, region.height as f32),
+            )
+            .with_uv(uv),
+        )
+    }
+
+    /// Same as `::sprite()` but looking up the region by name.
+    pub fn named_sprite(&self, name: &str) -> Option<Sprite> {
+        self.sprite(self.index_of(name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_regions() {
+        let atlas = TextureAtlas::from_grid(TextureId(0), 16, 8, 4, 2);
+        assert_eq!(atlas.len(), 8);
+        assert_eq!(atlas.size(), (64, 16));
+        assert_eq!(atlas.region(5), Some(AtlasRegion::new(16, 8, 16, 8)));
+        assert_eq!(
+            atlas.uv(5),
+            Some(UvRect {
+                min: Vec2::new(0.25, 0.5),
+                max: Vec2::new(0.5, 1.0),
+            })
+        );
+        assert_eq!(atlas.uv(8), None);
+    }
+
+    #[test]
+    fn named_regions() {
+        let mut atlas = TextureAtlas::new(TextureId(0), 32, 32);
+        atlas.add(AtlasRegion::new(0, 0, 16, 16));
+        let index = atlas.add_named("player", AtlasRegion::new(16, 0, 8, 4));
+
+        assert_eq!(atlas.index_of("player"), Some(index));
+        let sprite = atlas.named_sprite("player").unwrap();
+        assert_eq!(sprite.size, Vec2::new(8.0, 4.0));
+        assert_eq!(sprite.uv.min, Vec2::new(0.5, 0.0));
+    }
+}
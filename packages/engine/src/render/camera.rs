@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use crate::math::Vec2;
+
+/// 2D camera with an orthographic projection.
+///
+/// World coordinates are in pixels (at `zoom == 1.0`) with the y axis pointing
+/// up, the camera `position` is shown at the center of the viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OrthographicCamera {
+    /// World position shown at the center of the viewport.
+    pub position: Vec2,
+    /// Rotation in radians, counter-clockwise.
+    pub rotation: f32,
+    /// Values above `1.0` zoom in.
+    pub zoom: f32,
+    /// Size of the target surface in pixels.
+    pub viewport: Vec2,
+}
+
+impl OrthographicCamera {
+    /// Creates a new camera centered at the origin.
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            zoom: 1.0,
+            viewport: Vec2::new(width, height),
+        }
+    }
+
+    /// Sets the position.
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets the rotation.
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the zoom.
+    pub fn with_zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Changes the viewport, should be called whenever the surface is
+    /// resized.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.viewport = Vec2::new(width, height);
+    }
+
+    /// Converts a world position into clip space.
+    pub fn world_to_clip(&self, point: Vec2) -> Vec2 {
+        self.world_to_view(point) * 2.0 / self.viewport
+    }
+
+    /// Converts a world position into surface pixels, starting at the
+    /// top-left corner.
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        let view = self.world_to_view(point);
+        Vec2::new(view.x, -view.y) + self.viewport * 0.5
+    }
+
+    /// Converts surface pixels, starting at the top-left corner, into a world
+    /// position. Useful to find what is under the cursor.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let centered = point - self.viewport * 0.5;
+        let view = Vec2::new(centered.x, -centered.y) / self.zoom;
+        Vec2::from_angle(self.rotation).rotate(view) + self.position
+    }
+
+    /// Position relative to the camera, in pixels.
+    fn world_to_view(&self, point: Vec2) -> Vec2 {
+        Vec2::from_angle(-self.rotation).rotate(point - self.position)
+            * self.zoom
+    }
+}
+
+impl Default for OrthographicCamera {
+    fn default() -> Self {
+        Self::new(1.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.abs_diff_eq(b, 1e-4), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn projection() {
+        let camera = OrthographicCamera::new(200.0, 100.0);
+        assert_near(camera.world_to_clip(Vec2::ZERO), Vec2::ZERO);
+        assert_near(
+            camera.world_to_clip(Vec2::new(100.0, 50.0)),
+            Vec2::new(1.0, 1.0),
+        );
+        assert_near(
+            camera.world_to_screen(Vec2::new(-100.0, 50.0)),
+            Vec2::ZERO,
+        );
+
+        let camera = camera.with_position(Vec2::new(100.0, 0.0)).with_zoom(2.0);
+        assert_near(
+            camera.world_to_clip(Vec2::new(150.0, 0.0)),
+            Vec2::new(1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn screen_round_trip() {
+        let camera = OrthographicCamera::new(320.0, 240.0)
+            .with_position(Vec2::new(12.0, -7.0))
+            .with_rotation(0.7)
+            .with_zoom(1.5);
+
+        let world = Vec2::new(33.0, 18.0);
+        let screen = camera.world_to_screen(world);
+        assert_near(camera.screen_to_world(screen), world);
+    }
+}
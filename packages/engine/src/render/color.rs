@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+use crate::math::Vec4;
+
+/// RGBA color with every channel in the `0.0..=1.0` range.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const TRANSPARENT: Self = Self::rgba(0.0, 0.0, 0.0, 0.0);
+    pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::rgb(1.0, 1.0, 1.0);
+    pub const RED: Self = Self::rgb(1.0, 0.0, 0.0);
+    pub const GREEN: Self = Self::rgb(0.0, 1.0, 0.0);
+    pub const BLUE: Self = Self::rgb(0.0, 0.0, 1.0);
+
+    /// Creates a new opaque color.
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::rgba(r, g, b, 1.0)
+    }
+
+    /// Creates a new color.
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Creates a new color from 8 bit channels.
+    pub fn from_rgba8(rgba: [u8; 4]) -> Self {
+        Self::rgba(
+            rgba[0] as f32 / 255.0,
+            rgba[1] as f32 / 255.0,
+            rgba[2] as f32 / 255.0,
+            rgba[3] as f32 / 255.0,
+        )
+    }
+
+    /// Converts the color into 8 bit channels.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        let convert =
+            |channel: f32| (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [
+            convert(self.r),
+            convert(self.g),
+            convert(self.b),
+            convert(self.a),
+        ]
+    }
+
+    /// Returns the same color with the passed alpha.
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Returns the channels as an array.
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+/// Multiplies every channel.
+impl std::ops::Mul for Color {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::rgba(
+            self.r * other.r,
+            self.g * other.g,
+            self.b * other.b,
+            self.a * other.a,
+        )
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from(value: [f32; 4]) -> Self {
+        Self::rgba(value[0], value[1], value[2], value[3])
+    }
+}
+
+impl From<Vec4> for Color {
+    fn from(value: Vec4) -> Self {
+        Self::rgba(value.x, value.y, value.z, value.w)
+    }
+}
+
+impl From<Color> for Vec4 {
+    fn from(color: Color) -> Self {
+        Vec4::from_array(color.to_array())
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use super::{Color, Error};
+
+/// RGBA8 image stored in memory, rows go from top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// Result of comparing two images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// Amount of pixels with at least one channel above the tolerance.
+    pub mismatched: usize,
+    /// Biggest difference found in a single channel.
+    pub max_delta: u8,
+}
+
+impl ImageDiff {
+    /// Returns `true` if every pixel is within the tolerance.
+    pub fn is_match(&self) -> bool {
+        self.mismatched == 0
+    }
+}
+
+impl Image {
+    /// Creates a new image filled with the passed color.
+    pub fn new(width: u32, height: u32, color: Color) -> Self {
+        let pixels = color
+            .to_rgba8()
+            .iter()
+            .copied()
+            .cycle()
+            .take(width as usize * height as usize * 4)
+            .collect();
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Creates a new image from raw RGBA8 pixels.
+    pub fn from_rgba8(
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> Result<Self, Error> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(Error::InvalidTextureData(expected, pixels.len()));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decodes an image file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let image = image::open(path)
+            .map_err(|err| Error::Image(err.to_string()))?
+            .into_rgba8();
+        let (width, height) = image.dimensions();
+
+        Self::from_rgba8(width, height, image.into_raw())
+    }
+
+    /// Decodes an image from encoded bytes (e.g. the content of a PNG file).
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|err| Error::Image(err.to_string()))?
+            .into_rgba8();
+        let (width, height) = image.dimensions();
+
+        Self::from_rgba8(width, height, image.into_raw())
+    }
+
+    /// Encodes the image as PNG and writes it to the passed path.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        image::save_buffer(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgba8,
+        )
+        .map_err(|err| Error::Image(err.to_string()))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the raw RGBA8 pixels.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the raw RGBA8 pixels.
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    /// Returns the pixel at the passed position.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = self.index(x, y);
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    /// Changes the pixel at the passed position.
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let index = self.index(x, y);
+        self.pixels[index..index + 4].copy_from_slice(&rgba);
+    }
+
+    /// Fills the whole image with the passed color.
+    pub fn fill(&mut self, color: Color) {
+        let rgba = color.to_rgba8();
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+
+    /// Compares every channel of both images. Images with different sizes
+    /// mismatch every pixel.
+    pub fn diff(&self, other: &Self, tolerance: u8) -> ImageDiff {
+        if self.width != other.width || self.height != other.height {
+            return ImageDiff {
+                mismatched: (self.width * self.height)
+                    .max(other.width * other.height)
+                    as usize,
+                max_delta: u8::MAX,
+            };
+        }
+
+        let mut diff = ImageDiff {
+            mismatched: 0,
+            max_delta: 0,
+        };
+        for (a, b) in self
+            .pixels
+            .chunks_exact(4)
+            .zip(other.pixels.chunks_exact(4))
+        {
+            let delta = a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0);
+            diff.max_delta = diff.max_delta.max(delta);
+            if delta > tolerance {
+                diff.mismatched += 1;
+            }
+        }
+
+        diff
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+}
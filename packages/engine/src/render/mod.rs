@@ -0,0 +1,290 @@
+//! Rendering abstraction.
+//!
+//! Every backend implements the `Renderer` trait, which works with surfaces
+//! (things that can be drawn into), textures and indexed triangle lists.
+//!
+//! Vertex positions are given in clip space, `(-1, -1)` is the bottom-left
+//! corner of the surface and `(1, 1)` the top-right one. Texture coordinates
+//! start at the top-left corner of the texture.
+//!
+//! 2D games usually do not draw triangles directly, but use a `SpriteBatch`
+//! together with an `OrthographicCamera`, and a `TextRenderer` for text.
+//!
+//! Available backends:
+//!
+//! - `SoftwareRenderer`: renders on the CPU into in-memory framebuffers, useful
+//!   for headless tests.
+//! - `WgpuRenderer`: renders on the GPU, requires the `wgpu` feature.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::math::Vec2;
+
+pub mod atlas;
+pub mod camera;
+pub mod color;
+pub mod image;
+pub mod software;
+pub mod sprite;
+pub mod text;
+#[cfg(feature = "wgpu")]
+pub mod wgpu;
+
+#[cfg(feature = "wgpu")]
+pub use self::wgpu::WgpuRenderer;
+pub use atlas::{AtlasRegion, TextureAtlas};
+pub use camera::OrthographicCamera;
+pub use color::Color;
+pub use image::{Image, ImageDiff};
+pub use software::SoftwareRenderer;
+pub use sprite::{Sprite, SpriteBatch, UvRect};
+pub use text::{Font, Fonts, TextRenderer, TextStyle};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The passed surface does not exist.
+    #[error("Unknown surface `{0:?}`")]
+    UnknownSurface(SurfaceId),
+    /// The passed texture does not exist.
+    #[error("Unknown texture `{0:?}`")]
+    UnknownTexture(TextureId),
+    /// A frame was started while another one was still active.
+    #[error("A frame is already active")]
+    FrameAlreadyActive,
+    /// Drawing requires an active frame.
+    #[error("No active frame")]
+    NoActiveFrame,
+    /// Surfaces and textures can not have a zero size.
+    #[error("Invalid size {0}x{1}")]
+    InvalidSize(u32, u32),
+    /// The passed data does not match the expected size.
+    #[error("Invalid texture data: expected {0} bytes got {1}")]
+    InvalidTextureData(usize, usize),
+    /// The updated region does not fit inside the texture.
+    #[error("Region is out of the texture bounds")]
+    RegionOutOfBounds,
+    /// An index points outside of the vertex list.
+    #[error("Index {0} is out of bounds for {1} vertices")]
+    IndexOutOfBounds(u32, usize),
+    /// The backend does not support the operation.
+    #[error("Unsupported operation: {0}")]
+    Unsupported(&'static str),
+    /// Failed to decode or encode an image.
+    #[error("Image error: {0}")]
+    Image(String),
+    /// Error reported by the backend.
+    #[error("Backend error: {0}")]
+    Backend(String),
+}
+
+/// Identifies a surface owned by a `Renderer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SurfaceId(pub(crate) u32);
+
+/// Identifies a texture owned by a `Renderer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureId(pub(crate) u32);
+
+/// How a texture is sampled.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize,
+)]
+pub enum FilterMode {
+    /// Nearest texel, keeps pixel art crisp.
+    #[default]
+    Nearest,
+    /// Bilinear interpolation.
+    Linear,
+}
+
+/// Describes a texture, the data is always RGBA8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureDescriptor {
+    pub width: u32,
+    pub height: u32,
+    pub filter: FilterMode,
+}
+
+impl TextureDescriptor {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            filter: FilterMode::default(),
+        }
+    }
+
+    /// Sets the filter.
+    pub fn with_filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+/// A single vertex of a triangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct Vertex {
+    /// Position in clip space.
+    pub position: [f32; 2],
+    /// Texture coordinates.
+    pub uv: [f32; 2],
+    /// Color multiplied with the sampled texel.
+    pub color: [f32; 4],
+}
+
+impl Vertex {
+    pub fn new(position: Vec2, uv: Vec2, color: Color) -> Self {
+        Self {
+            position: position.to_array(),
+            uv: uv.to_array(),
+            color: color.to_array(),
+        }
+    }
+}
+
+/// Region of a surface in pixels, starting at the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A list of indexed triangles sharing the same texture.
+#[derive(Debug, Clone, Copy)]
+pub struct DrawCall<'a> {
+    pub vertices: &'a [Vertex],
+    /// Every three indices form a triangle.
+    pub indices: &'a [u32],
+    /// Sampled texture, `None` draws plain colors.
+    pub texture: Option<TextureId>,
+    /// Only pixels inside this region are touched.
+    pub scissor: Option<ScissorRect>,
+}
+
+impl<'a> DrawCall<'a> {
+    pub fn new(vertices: &'a [Vertex], indices: &'a [u32]) -> Self {
+        Self {
+            vertices,
+            indices,
+            texture: None,
+            scissor: None,
+        }
+    }
+
+    /// Sets the sampled texture.
+    pub fn with_texture(mut self, texture: TextureId) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    /// Sets the scissor region.
+    pub fn with_scissor(mut self, scissor: ScissorRect) -> Self {
+        self.scissor = Some(scissor);
+        self
+    }
+
+    /// Checks that every index points to an existing vertex.
+    pub fn validate(&self) -> Result<(), Error> {
+        match self
+            .indices
+            .iter()
+            .find(|index| **index as usize >= self.vertices.len())
+        {
+            Some(index) => {
+                Err(Error::IndexOutOfBounds(*index, self.vertices.len()))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Per frame statistics, useful to verify batching.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub draw_calls: usize,
+    pub triangles: usize,
+}
+
+/// Common interface of every rendering backend.
+///
+/// Drawing happens between `::begin_frame()` and `::end_frame()`, only one
+/// frame can be active at a time.
+pub trait Renderer {
+    /// Creates a surface that is not presented anywhere.
+    fn create_offscreen_surface(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> Result<SurfaceId, Error>;
+
+    /// Resizes the passed surface, its content is discarded.
+    fn resize_surface(
+        &mut self,
+        surface: SurfaceId,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error>;
+
+    /// Returns the size of the passed surface.
+    fn surface_size(&self, surface: SurfaceId) -> Result<(u32, u32), Error>;
+
+    /// Destroys the passed surface.
+    fn destroy_surface(&mut self, surface: SurfaceId) -> Result<(), Error>;
+
+    /// Creates a texture from RGBA8 data.
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureId, Error>;
+
+    /// Replaces a region of the passed texture with RGBA8 data.
+    fn update_texture(
+        &mut self,
+        texture: TextureId,
+        region: ScissorRect,
+        data: &[u8],
+    ) -> Result<(), Error>;
+
+    /// Destroys the passed texture.
+    fn destroy_texture(&mut self, texture: TextureId) -> Result<(), Error>;
+
+    /// Starts drawing into the passed surface.
+    fn begin_frame(&mut self, surface: SurfaceId) -> Result<(), Error>;
+
+    /// Fills the whole surface with the passed color.
+    fn clear(&mut self, color: Color) -> Result<(), Error>;
+
+    /// Draws the passed triangles.
+    fn draw(&mut self, call: &DrawCall) -> Result<(), Error>;
+
+    /// Finishes the active frame, presenting it if the surface is a window.
+    ///
+    /// Returns the statistics of the finished frame.
+    fn end_frame(&mut self) -> Result<FrameStats, Error>;
+
+    /// Reads back the content of the passed surface.
+    fn read_surface(&mut self, surface: SurfaceId) -> Result<Image, Error>;
+}
+
+/// Checks that `data` has the exact size of a RGBA8 region.
+pub(crate) fn validate_data(
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<(), Error> {
+    if width == 0 || height == 0 {
+        return Err(Error::InvalidSize(width, height));
+    }
+
+    let expected = width as usize * height as usize * 4;
+    if data.len() != expected {
+        return Err(Error::InvalidTextureData(expected, data.len()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,571 @@
+//! CPU renderer that draws into in-memory framebuffers.
+//!
+//! It is not meant to be fast, but to produce deterministic images that can be
+//! compared in tests running on machines without a GPU.
+
+use std::collections::HashMap;
+
+use super::{
+    validate_data, Color, DrawCall, Error, FilterMode, FrameStats, Image,
+    Renderer, ScissorRect, SurfaceId, TextureDescriptor, TextureId, Vertex,
+};
+
+struct Texture {
+    image: Image,
+    filter: FilterMode,
+}
+
+/// The frame that is currently being drawn.
+struct Frame {
+    surface: SurfaceId,
+    stats: FrameStats,
+}
+
+/// Renderer that draws on the CPU.
+#[derive(Default)]
+pub struct SoftwareRenderer {
+    surfaces: HashMap<SurfaceId, Image>,
+    textures: HashMap<TextureId, Texture>,
+    next_id: u32,
+    frame: Option<Frame>,
+}
+
+impl SoftwareRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the framebuffer of the passed surface without copying it.
+    pub fn surface(&self, surface: SurfaceId) -> Result<&Image, Error> {
+        self.surfaces
+            .get(&surface)
+            .ok_or(Error::UnknownSurface(surface))
+    }
+
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn target(&mut self) -> Result<(&mut Image, &mut FrameStats), Error> {
+        let frame = self.frame.as_mut().ok_or(Error::NoActiveFrame)?;
+        let image = self
+            .surfaces
+            .get_mut(&frame.surface)
+            .ok_or(Error::UnknownSurface(frame.surface))?;
+        Ok((image, &mut frame.stats))
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn create_offscreen_surface(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> Result<SurfaceId, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidSize(width, height));
+        }
+
+        let id = SurfaceId(self.next_id());
+        self.surfaces
+            .insert(id, Image::new(width, height, Color::TRANSPARENT));
+
+        Ok(id)
+    }
+
+    fn resize_surface(
+        &mut self,
+        surface: SurfaceId,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidSize(width, height));
+        }
+
+        let image = self
+            .surfaces
+            .get_mut(&surface)
+            .ok_or(Error::UnknownSurface(surface))?;
+        *image = Image::new(width, height, Color::TRANSPARENT);
+
+        Ok(())
+    }
+
+    fn surface_size(&self, surface: SurfaceId) -> Result<(u32, u32), Error> {
+        let image = self.surface(surface)?;
+        Ok((image.width(), image.height()))
+    }
+
+    fn destroy_surface(&mut self, surface: SurfaceId) -> Result<(), Error> {
+        if self
+            .frame
+            .as_ref()
+            .is_some_and(|frame| frame.surface == surface)
+        {
+            self.frame = None;
+        }
+
+        self.surfaces
+            .remove(&surface)
+            .map(|_| ())
+            .ok_or(Error::UnknownSurface(surface))
+    }
+
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureId, Error> {
+        validate_data(descriptor.width, descriptor.height, data)?;
+
+        let id = TextureId(self.next_id());
+        let image = Image::from_rgba8(
+            descriptor.width,
+            descriptor.height,
+            data.to_vec(),
+        )?;
+        self.textures.insert(
+            id,
+            Texture {
+                image,
+                filter: descriptor.filter,
+            },
+        );
+
+        Ok(id)
+    }
+
+    fn update_texture(
+        &mut self,
+        texture: TextureId,
+        region: ScissorRect,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        validate_data(region.width, region.height, data)?;
+
+        let image = &mut self
+            .textures
+            .get_mut(&texture)
+            .ok_or(Error::UnknownTexture(texture))?
+            .image;
+        let right = region.x.checked_add(region.width);
+        let bottom = region.y.checked_add(region.height);
+        if right.is_none_or(|right| right > image.width())
+            || bottom.is_none_or(|bottom| bottom > image.height())
+        {
+            return Err(Error::RegionOutOfBounds);
+        }
+
+        let row_size = region.width as usize * 4;
+        let texture_width = image.width() as usize;
+        let pixels = image.pixels_mut();
+        for (row, source) in data.chunks_exact(row_size).enumerate() {
+            let start = ((region.y as usize + row) * texture_width
+                + region.x as usize)
+                * 4;
+            pixels[start..start + row_size].copy_from_slice(source);
+        }
+
+        Ok(())
+    }
+
+    fn destroy_texture(&mut self, texture: TextureId) -> Result<(), Error> {
+        self.textures
+            .remove(&texture)
+            .map(|_| ())
+            .ok_or(Error::UnknownTexture(texture))
+    }
+
+    fn begin_frame(&mut self, surface: SurfaceId) -> Result<(), Error> {
+        if self.frame.is_some() {
+            return Err(Error::FrameAlreadyActive);
+        }
+        if !self.surfaces.contains_key(&surface) {
+            return Err(Error::UnknownSurface(surface));
+        }
+
+        self.frame = Some(Frame {
+            surface,
+            stats: FrameStats::default(),
+        });
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Color) -> Result<(), Error> {
+        let (image, _) = self.target()?;
+        image.fill(color);
+        Ok(())
+    }
+
+    fn draw(&mut self, call: &DrawCall) -> Result<(), Error> {
+        call.validate()?;
+
+        let texture = match call.texture {
+            Some(id) => Some(
+                self.textures.remove(&id).ok_or(Error::UnknownTexture(id))?,
+            ),
+            None => None,
+        };
+
+        let result = self.target().map(|(image, stats)| {
+            stats.draw_calls += 1;
+            stats.triangles += call.indices.len() / 3;
+
+            let scissor = clip_scissor(image, call.scissor);
+            for triangle in call.indices.chunks_exact(3) {
+                rasterize(
+                    image,
+                    scissor,
+                    [
+                        &call.vertices[triangle[0] as usize],
+                        &call.vertices[triangle[1] as usize],
+                        &call.vertices[triangle[2] as usize],
+                    ],
+                    texture.as_ref(),
+                );
+            }
+        });
+
+        // Give the texture back
+        if let (Some(id), Some(texture)) = (call.texture, texture) {
+            self.textures.insert(id, texture);
+        }
+
+        result
+    }
+
+    fn end_frame(&mut self) -> Result<FrameStats, Error> {
+        self.frame
+            .take()
+            .map(|frame| frame.stats)
+            .ok_or(Error::NoActiveFrame)
+    }
+
+    fn read_surface(&mut self, surface: SurfaceId) -> Result<Image, Error> {
+        self.surface(surface).cloned()
+    }
+}
+
+/// Pixel bounds `(min_x, min_y, max_x, max_y)`, max is exclusive.
+type Bounds = (u32, u32, u32, u32);
+
+fn clip_scissor(image: &Image, scissor: Option<ScissorRect>) -> Bounds {
+    let (width, height) = (image.width(), image.height());
+    match scissor {
+        Some(rect) => (
+            rect.x.min(width),
+            rect.y.min(height),
+            rect.x.saturating_add(rect.width).min(width),
+            rect.y.saturating_add(rect.height).min(height),
+        ),
+        None => (0, 0, width, height),
+    }
+}
+
+/// Signed area of the parallelogram formed by `a->b` and `a->c`.
+fn edge(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Top-left fill rule, pixels exactly on a shared edge are only drawn by one
+/// of the triangles.
+fn is_top_left(a: [f32; 2], b: [f32; 2]) -> bool {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    (dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+fn rasterize(
+    image: &mut Image,
+    bounds: Bounds,
+    vertices: [&Vertex; 3],
+    texture: Option<&Texture>,
+) {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let to_pixels = |vertex: &Vertex| {
+        [
+            (vertex.position[0] + 1.0) * 0.5 * width,
+            (1.0 - vertex.position[1]) * 0.5 * height,
+        ]
+    };
+
+    let mut points = vertices.map(to_pixels);
+    let mut vertices = vertices;
+    let mut area = edge(points[0], points[1], points[2]);
+    if area == 0.0 || !area.is_finite() {
+        return;
+    }
+    // Keep a consistent winding so the fill rule works for both orientations
+    if area < 0.0 {
+        points.swap(1, 2);
+        vertices.swap(1, 2);
+        area = -area;
+    }
+
+    let min_x = points.iter().map(|p| p[0]).fold(f32::MAX, f32::min);
+    let max_x = points.iter().map(|p| p[0]).fold(f32::MIN, f32::max);
+    let min_y = points.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+    let max_y = points.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+
+    let start_x = (min_x.floor().max(0.0) as u32).max(bounds.0);
+    let start_y = (min_y.floor().max(0.0) as u32).max(bounds.1);
+    let end_x = (max_x.ceil().max(0.0) as u32).min(bounds.2);
+    let end_y = (max_y.ceil().max(0.0) as u32).min(bounds.3);
+
+    let edges = [
+        (points[1], points[2], is_top_left(points[1], points[2])),
+        (points[2], points[0], is_top_left(points[2], points[0])),
+        (points[0], points[1], is_top_left(points[0], points[1])),
+    ];
+
+    for y in start_y..end_y {
+        for x in start_x..end_x {
+            let sample = [x as f32 + 0.5, y as f32 + 0.5];
+            let mut weights = [0.0; 3];
+            let mut inside = true;
+            for (index, (a, b, top_left)) in edges.iter().enumerate() {
+                let weight = edge(*a, *b, sample);
+                if weight < 0.0 || (weight == 0.0 && !top_left) {
+                    inside = false;
+                    break;
+                }
+                weights[index] = weight / area;
+            }
+            if !inside {
+                continue;
+            }
+
+            let interpolate = |attribute: &dyn Fn(&Vertex) -> f32| {
+                weights[0] * attribute(vertices[0])
+                    + weights[1] * attribute(vertices[1])
+                    + weights[2] * attribute(vertices[2])
+            };
+
+            let mut color = Color::rgba(
+                interpolate(&|v| v.color[0]),
+                interpolate(&|v| v.color[1]),
+                interpolate(&|v| v.color[2]),
+                interpolate(&|v| v.color[3]),
+            );
+            if let Some(texture) = texture {
+                let uv = [interpolate(&|v| v.uv[0]), interpolate(&|v| v.uv[1])];
+                color = color * sample_texture(texture, uv);
+            }
+
+            blend(image, x, y, color);
+        }
+    }
+}
+
+fn sample_texture(texture: &Texture, uv: [f32; 2]) -> Color {
+    let image = &texture.image;
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let texel = |x: f32, y: f32| {
+        let x = (x.max(0.0) as u32).min(image.width() - 1);
+        let y = (y.max(0.0) as u32).min(image.height() - 1);
+        Color::from_rgba8(image.pixel(x, y))
+    };
+
+    match texture.filter {
+        FilterMode::Nearest => {
+            texel((uv[0] * width).floor(), (uv[1] * height).floor())
+        }
+        FilterMode::Linear => {
+            let x = uv[0] * width - 0.5;
+            let y = uv[1] * height - 0.5;
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+
+            let lerp = |a: Color, b: Color, t: f32| {
+                Color::rgba(
+                    a.r + (b.r - a.r) * t,
+                    a.g + (b.g - a.g) * t,
+                    a.b + (b.b - a.b) * t,
+                    a.a + (b.a - a.a) * t,
+                )
+            };
+            let top = lerp(texel(x0, y0), texel(x0 + 1.0, y0), fx);
+            let bottom =
+                lerp(texel(x0, y0 + 1.0), texel(x0 + 1.0, y0 + 1.0), fx);
+            lerp(top, bottom, fy)
+        }
+    }
+}
+
+/// Source-over alpha blending.
+fn blend(image: &mut Image, x: u32, y: u32, source: Color) {
+    let destination = Color::from_rgba8(image.pixel(x, y));
+    let alpha = source.a.clamp(0.0, 1.0);
+    let inverse = 1.0 - alpha;
+
+    let result = Color::rgba(
+        source.r * alpha + destination.r * inverse,
+        source.g * alpha + destination.g * inverse,
+        source.b * alpha + destination.b * inverse,
+        alpha + destination.a * inverse,
+    );
+    image.set_pixel(x, y, result.to_rgba8());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+
+    /// Two triangles covering the rectangle between the passed clip space
+    /// corners.
+    fn quad(min: Vec2, max: Vec2, color: Color) -> ([Vertex; 4], [u32; 6]) {
+        (
+            [
+                Vertex::new(Vec2::new(min.x, max.y), Vec2::ZERO, color),
+                Vertex::new(max, Vec2::X, color),
+                Vertex::new(Vec2::new(max.x, min.y), Vec2::ONE, color),
+                Vertex::new(min, Vec2::Y, color),
+            ],
+            [0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    fn render<F>(width: u32, height: u32, draw: F) -> Image
+    where
+        F: FnOnce(&mut SoftwareRenderer),
+    {
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(width, height).unwrap();
+        renderer.begin_frame(surface).unwrap();
+        renderer.clear(Color::BLACK).unwrap();
+        draw(&mut renderer);
+        renderer.end_frame().unwrap();
+        renderer.read_surface(surface).unwrap()
+    }
+
+    #[test]
+    fn clear_fills_surface() {
+        let image = render(4, 4, |_| {});
+        assert_eq!(image, Image::new(4, 4, Color::BLACK));
+    }
+
+    #[test]
+    fn quad_covers_exact_pixels() {
+        // Covers the top-left quarter of a 4x4 surface
+        let (vertices, indices) =
+            quad(Vec2::new(-1.0, 0.0), Vec2::new(0.0, 1.0), Color::RED);
+        let image = render(4, 4, |renderer| {
+            renderer.draw(&DrawCall::new(&vertices, &indices)).unwrap();
+        });
+
+        let mut expected = Image::new(4, 4, Color::BLACK);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            expected.set_pixel(x, y, Color::RED.to_rgba8());
+        }
+        assert!(image.diff(&expected, 0).is_match());
+    }
+
+    #[test]
+    fn shared_edges_are_blended_once() {
+        let color = Color::WHITE.with_alpha(0.5);
+        let (vertices, indices) = quad(Vec2::NEG_ONE, Vec2::ONE, color);
+        let image = render(8, 8, |renderer| {
+            renderer.draw(&DrawCall::new(&vertices, &indices)).unwrap();
+        });
+
+        let expected = Image::new(8, 8, Color::rgb(0.5, 0.5, 0.5));
+        let diff = image.diff(&expected, 1);
+        assert!(diff.is_match(), "{diff:?}");
+    }
+
+    #[test]
+    fn textured_quad_and_scissor() {
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(2, 2).unwrap();
+        let texture = renderer
+            .create_texture(
+                &TextureDescriptor::new(2, 2),
+                &[
+                    255, 0, 0, 255, 0, 255, 0, 255, //
+                    0, 0, 255, 255, 255, 255, 255, 255,
+                ],
+            )
+            .unwrap();
+        let (vertices, indices) = quad(Vec2::NEG_ONE, Vec2::ONE, Color::WHITE);
+
+        renderer.begin_frame(surface).unwrap();
+        renderer.clear(Color::BLACK).unwrap();
+        renderer
+            .draw(
+                &DrawCall::new(&vertices, &indices)
+                    .with_texture(texture)
+                    .with_scissor(ScissorRect {
+                        x: 0,
+                        y: 0,
+                        width: 2,
+                        height: 1,
+                    }),
+            )
+            .unwrap();
+        let stats = renderer.end_frame().unwrap();
+        let image = renderer.read_surface(surface).unwrap();
+
+        assert_eq!(stats.draw_calls, 1);
+        assert_eq!(stats.triangles, 2);
+        assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(1, 0), [0, 255, 0, 255]);
+        // Outside of the scissor
+        assert_eq!(image.pixel(0, 1), [0, 0, 0, 255]);
+        assert_eq!(image.pixel(1, 1), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn update_texture_region() {
+        let mut renderer = SoftwareRenderer::new();
+        let texture = renderer
+            .create_texture(&TextureDescriptor::new(2, 2), &[0; 16])
+            .unwrap();
+        let region = ScissorRect {
+            x: 1,
+            y: 1,
+            width: 1,
+            height: 1,
+        };
+        renderer
+            .update_texture(texture, region, &[1, 2, 3, 4])
+            .unwrap();
+        assert_eq!(renderer.textures[&texture].image.pixel(1, 1), [1, 2, 3, 4]);
+
+        let region = ScissorRect { x: 2, ..region };
+        assert_eq!(
+            renderer.update_texture(texture, region, &[1, 2, 3, 4]),
+            Err(Error::RegionOutOfBounds),
+        );
+        let region = ScissorRect {
+            x: u32::MAX,
+            ..region
+        };
+        assert_eq!(
+            renderer.update_texture(texture, region, &[1, 2, 3, 4]),
+            Err(Error::RegionOutOfBounds),
+        );
+    }
+
+    #[test]
+    fn frame_errors() {
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(1, 1).unwrap();
+
+        assert_eq!(renderer.clear(Color::BLACK), Err(Error::NoActiveFrame));
+        renderer.begin_frame(surface).unwrap();
+        assert_eq!(
+            renderer.begin_frame(surface),
+            Err(Error::FrameAlreadyActive)
+        );
+
+        let vertices = [Vertex::new(Vec2::ZERO, Vec2::ZERO, Color::WHITE)];
+        assert_eq!(
+            renderer.draw(&DrawCall::new(&vertices, &[0, 1, 2])),
+            Err(Error::IndexOutOfBounds(1, 1)),
+        );
+    }
+}
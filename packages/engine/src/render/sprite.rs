@@ -0,0 +1,442 @@
+//! Batched 2D sprite rendering.
+//!
+//! Sprites are pushed into a `SpriteBatch` every frame, drawing the batch
+//! sorts them by `z` and merges consecutive sprites sharing a texture into a
+//! single draw call.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    camera::OrthographicCamera, Color, DrawCall, Error, Renderer, TextureId,
+    Vertex,
+};
+use crate::math::Vec2;
+
+/// Limits the size of the buffers of a single draw call.
+const MAX_SPRITES_PER_DRAW: usize = 16384;
+
+/// Texture coordinates of a sprite, `(0, 0)` is the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UvRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl UvRect {
+    /// The whole texture.
+    pub const FULL: Self = Self {
+        min: Vec2::ZERO,
+        max: Vec2::ONE,
+    };
+}
+
+impl Default for UvRect {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// A textured quad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub texture: Option<TextureId>,
+    /// Part of the texture that is shown.
+    pub uv: UvRect,
+    /// World position of the anchor.
+    pub position: Vec2,
+    /// Rotation around the anchor in radians, counter-clockwise.
+    pub rotation: f32,
+    pub scale: Vec2,
+    /// Size in world units before scaling.
+    pub size: Vec2,
+    /// Point the sprite is positioned and rotated around, `(0, 0)` is the
+    /// bottom-left corner and `(1, 1)` the top-right one.
+    pub anchor: Vec2,
+    /// Multiplied with the texture color.
+    pub tint: Color,
+    /// Sprites with a higher `z` are drawn on top.
+    pub z: f32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Sprite {
+    /// Creates a sprite showing the whole texture.
+    pub fn new(texture: TextureId, size: Vec2) -> Self {
+        Self {
+            texture: Some(texture),
+            ..Self::colored(Color::WHITE, size)
+        }
+    }
+
+    /// Creates an untextured sprite.
+    pub fn colored(color: Color, size: Vec2) -> Self {
+        Self {
+            texture: None,
+            uv: UvRect::FULL,
+            position: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            size,
+            anchor: Vec2::splat(0.5),
+            tint: color,
+            z: 0.0,
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+
+    /// Sets the texture coordinates.
+    pub fn with_uv(mut self, uv: UvRect) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    /// Sets the position.
+    pub fn with_position(mut self, position: Vec2) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets the rotation.
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the scale.
+    pub fn with_scale(mut self, scale: Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the anchor.
+    pub fn with_anchor(mut self, anchor: Vec2) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Sets the tint.
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Sets the z order.
+    pub fn with_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// Mirrors the texture.
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+
+    /// Returns the world position of the corners, in counter-clockwise order
+    /// starting at the top-left one.
+    pub fn corners(&self) -> [Vec2; 4] {
+        let size = self.size * self.scale;
+        let min = -self.anchor * size;
+        let max = min + size;
+        let rotation = Vec2::from_angle(self.rotation);
+
+        [Vec2::new(min.x, max.y), min, Vec2::new(max.x, min.y), max]
+            .map(|corner| rotation.rotate(corner) + self.position)
+    }
+
+    fn vertices(&self, camera: &OrthographicCamera) -> [Vertex; 4] {
+        let (mut left, mut right) = (self.uv.min.x, self.uv.max.x);
+        let (mut top, mut bottom) = (self.uv.min.y, self.uv.max.y);
+        if self.flip_x {
+            std::mem::swap(&mut left, &mut right);
+        }
+        if self.flip_y {
+            std::mem::swap(&mut top, &mut bottom);
+        }
+        let uvs = [
+            Vec2::new(left, top),
+            Vec2::new(left, bottom),
+            Vec2::new(right, bottom),
+            Vec2::new(right, top),
+        ];
+
+        let corners = self.corners();
+        std::array::from_fn(|i| {
+            Vertex::new(camera.world_to_clip(corners[i]), uvs[i], self.tint)
+        })
+    }
+}
+
+/// Collects sprites and draws them with as few draw calls as possible.
+///
+/// Sprites sharing the same `z` are grouped by texture, so their submission
+/// order is only kept between sprites using the same texture.
+#[derive(Debug, Default)]
+pub struct SpriteBatch {
+    sprites: Vec<Sprite>,
+    // Reused between frames
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a sprite to be drawn.
+    pub fn push(&mut self, sprite: Sprite) {
+        self.sprites.push(sprite);
+    }
+
+    /// Amount of queued sprites.
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Removes every queued sprite without drawing them.
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    /// Draws and removes every queued sprite, a frame must be active.
+    ///
+    /// Returns the amount of draw calls used.
+    pub fn draw(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        camera: &OrthographicCamera,
+    ) -> Result<usize, Error> {
+        crate::profile_scope!("SpriteBatch::draw", "render");
+
+        // Stable, so sprites with the same key keep their submission order
+        self.sprites.sort_by(|a, b| {
+            a.z.total_cmp(&b.z).then_with(|| a.texture.cmp(&b.texture))
+        });
+
+        let mut draw_calls = 0;
+        let mut start = 0;
+        while start < self.sprites.len() {
+            // Thanks to the sorting, consecutive sprites sharing a texture can
+            // be drawn together even if they are on different `z` layers
+            let texture = self.sprites[start].texture;
+            let end = self.sprites[start..]
+                .iter()
+                .take(MAX_SPRITES_PER_DRAW)
+                .position(|sprite| sprite.texture != texture)
+                .map_or(
+                    (start + MAX_SPRITES_PER_DRAW).min(self.sprites.len()),
+                    |offset| start + offset,
+                );
+
+            self.vertices.clear();
+            self.indices.clear();
+            for sprite in &self.sprites[start..end] {
+                let base = self.vertices.len() as u32;
+                self.vertices.extend(sprite.vertices(camera));
+                self.indices.extend([
+                    base,
+                    base + 1,
+                    base + 2,
+                    base,
+                    base + 2,
+                    base + 3,
+                ]);
+            }
+
+            let mut call = DrawCall::new(&self.vertices, &self.indices);
+            call.texture = texture;
+            renderer.draw(&call)?;
+
+            draw_calls += 1;
+            start = end;
+        }
+
+        self.sprites.clear();
+
+        Ok(draw_calls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{
+        atlas::TextureAtlas, Image, SoftwareRenderer, SurfaceId,
+        TextureDescriptor,
+    };
+
+    fn setup(width: u32, height: u32) -> (SoftwareRenderer, SurfaceId) {
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(width, height).unwrap();
+        (renderer, surface)
+    }
+
+    fn texture(renderer: &mut SoftwareRenderer, color: Color) -> TextureId {
+        renderer
+            .create_texture(&TextureDescriptor::new(1, 1), &color.to_rgba8())
+            .unwrap()
+    }
+
+    fn render(
+        renderer: &mut SoftwareRenderer,
+        surface: SurfaceId,
+        batch: &mut SpriteBatch,
+    ) -> (usize, Image) {
+        let (width, height) = renderer.surface_size(surface).unwrap();
+        let camera = OrthographicCamera::new(width as f32, height as f32);
+
+        renderer.begin_frame(surface).unwrap();
+        renderer.clear(Color::BLACK).unwrap();
+        let draw_calls = batch.draw(renderer, &camera).unwrap();
+        let stats = renderer.end_frame().unwrap();
+        assert_eq!(stats.draw_calls, draw_calls);
+
+        (draw_calls, renderer.read_surface(surface).unwrap())
+    }
+
+    #[test]
+    fn sprite_position_and_anchor() {
+        let (mut renderer, surface) = setup(8, 8);
+        let mut batch = SpriteBatch::new();
+        // Bottom-left quarter of the surface
+        batch.push(
+            Sprite::colored(Color::RED, Vec2::new(4.0, 4.0))
+                .with_position(Vec2::new(-4.0, -4.0))
+                .with_anchor(Vec2::ZERO),
+        );
+
+        let (_, image) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(image.pixel(0, 7), Color::RED.to_rgba8());
+        assert_eq!(image.pixel(3, 4), Color::RED.to_rgba8());
+        assert_eq!(image.pixel(4, 4), Color::BLACK.to_rgba8());
+        assert_eq!(image.pixel(3, 3), Color::BLACK.to_rgba8());
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn rotation_and_scale() {
+        let (mut renderer, surface) = setup(8, 8);
+        let mut batch = SpriteBatch::new();
+        // A 1x4 vertical bar turned into a 8x2 horizontal one
+        batch.push(
+            Sprite::colored(Color::GREEN, Vec2::new(1.0, 4.0))
+                .with_scale(Vec2::new(2.0, 2.0))
+                .with_rotation(std::f32::consts::FRAC_PI_2),
+        );
+
+        let (_, image) = render(&mut renderer, surface, &mut batch);
+        for x in 0..8 {
+            assert_eq!(image.pixel(x, 3), Color::GREEN.to_rgba8());
+            assert_eq!(image.pixel(x, 4), Color::GREEN.to_rgba8());
+            assert_eq!(image.pixel(x, 2), Color::BLACK.to_rgba8());
+        }
+    }
+
+    #[test]
+    fn z_order() {
+        let (mut renderer, surface) = setup(4, 4);
+        let red = texture(&mut renderer, Color::RED);
+        let blue = texture(&mut renderer, Color::BLUE);
+
+        let mut batch = SpriteBatch::new();
+        batch.push(Sprite::new(red, Vec2::new(4.0, 4.0)).with_z(1.0));
+        batch.push(Sprite::new(blue, Vec2::new(4.0, 4.0)).with_z(0.0));
+
+        let (_, image) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(image.pixel(1, 1), Color::RED.to_rgba8());
+    }
+
+    #[test]
+    fn batching_by_texture() {
+        let (mut renderer, surface) = setup(4, 4);
+        let red = texture(&mut renderer, Color::RED);
+        let blue = texture(&mut renderer, Color::BLUE);
+
+        // Same z, interleaved textures are merged
+        let mut batch = SpriteBatch::new();
+        for i in 0..10 {
+            let texture = if i % 2 == 0 { red } else { blue };
+            batch.push(Sprite::new(texture, Vec2::ONE));
+        }
+        let (draw_calls, _) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(draw_calls, 2);
+
+        // Interleaved z layers have to keep their order
+        for i in 0..4 {
+            let texture = if i % 2 == 0 { red } else { blue };
+            batch.push(Sprite::new(texture, Vec2::ONE).with_z(i as f32));
+        }
+        let (draw_calls, _) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(draw_calls, 4);
+
+        // Layers using a single texture are merged
+        for i in 0..4 {
+            batch.push(Sprite::new(red, Vec2::ONE).with_z(i as f32));
+        }
+        let (draw_calls, _) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(draw_calls, 1);
+    }
+
+    #[test]
+    fn atlas_sprites() {
+        let (mut renderer, surface) = setup(2, 1);
+        let texture = red_green(&mut renderer);
+        let atlas = TextureAtlas::from_grid(texture, 1, 1, 2, 1);
+
+        // Swap both cells
+        let mut batch = SpriteBatch::new();
+        for (index, x) in [(1, -1.0), (0, 0.0)] {
+            batch.push(
+                atlas
+                    .sprite(index)
+                    .unwrap()
+                    .with_anchor(Vec2::ZERO)
+                    .with_position(Vec2::new(x, -0.5)),
+            );
+        }
+
+        let (draw_calls, image) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(draw_calls, 1);
+        assert_eq!(image.pixel(0, 0), Color::GREEN.to_rgba8());
+        assert_eq!(image.pixel(1, 0), Color::RED.to_rgba8());
+    }
+
+    #[test]
+    fn flip_and_tint() {
+        let (mut renderer, surface) = setup(2, 1);
+        let texture = red_green(&mut renderer);
+
+        let mut batch = SpriteBatch::new();
+        batch.push(
+            Sprite::new(texture, Vec2::new(2.0, 1.0)).with_flip(true, false),
+        );
+        let (_, image) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(image.pixel(0, 0), Color::GREEN.to_rgba8());
+        assert_eq!(image.pixel(1, 0), Color::RED.to_rgba8());
+
+        batch.push(
+            Sprite::new(texture, Vec2::new(2.0, 1.0))
+                .with_tint(Color::rgb(0.0, 1.0, 1.0)),
+        );
+        let (_, image) = render(&mut renderer, surface, &mut batch);
+        assert_eq!(image.pixel(0, 0), Color::BLACK.to_rgba8());
+        assert_eq!(image.pixel(1, 0), Color::GREEN.to_rgba8());
+    }
+
+    /// A 2x1 texture with a red and a green pixel.
+    fn red_green(renderer: &mut SoftwareRenderer) -> TextureId {
+        let mut data = Color::RED.to_rgba8().to_vec();
+        data.extend(Color::GREEN.to_rgba8());
+        renderer
+            .create_texture(&TextureDescriptor::new(2, 1), &data)
+            .unwrap()
+    }
+}
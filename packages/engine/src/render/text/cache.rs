@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use ab_glyph::{point, Font as _, GlyphId};
+
+use super::{FontId, Fonts};
+use crate::{
+    math::Vec2,
+    render::{
+        self, Renderer, ScissorRect, TextureDescriptor, TextureId, UvRect,
+    },
+};
+
+/// Empty pixels between glyphs, so they do not bleed into each other.
+const PADDING: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct GlyphKey {
+    pub font: FontId,
+    pub glyph: u16,
+    /// Bits of the size in pixels.
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CachedGlyph {
+    /// Top-left corner relative to the start of the glyph on the baseline.
+    pub offset: Vec2,
+    pub size: Vec2,
+    pub uv: UvRect,
+}
+
+/// Rasterized glyphs packed in rows of a square texture.
+///
+/// Glyphs are white, their coverage is stored in the alpha channel so the
+/// vertex color gives the text its color.
+#[derive(Debug)]
+pub(crate) struct GlyphCache {
+    size: u32,
+    max_size: u32,
+    pixels: Vec<u8>,
+    texture: Option<TextureId>,
+    /// The texture does not match the pixels anymore.
+    stale: bool,
+    /// `None` for glyphs without an outline, like spaces.
+    glyphs: HashMap<GlyphKey, Option<CachedGlyph>>,
+    /// Where the next glyph goes.
+    cursor: (u32, u32),
+    row_height: u32,
+    /// Rows changed since the last upload.
+    dirty: Option<(u32, u32)>,
+}
+
+impl GlyphCache {
+    pub fn new(size: u32, max_size: u32) -> Self {
+        Self {
+            size,
+            max_size: max_size.max(size),
+            pixels: vec![0; (size * size * 4) as usize],
+            texture: None,
+            stale: true,
+            glyphs: HashMap::new(),
+            cursor: (0, 0),
+            row_height: 0,
+            dirty: None,
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Rasterizes the glyph if needed, returns `None` when it does not fit.
+    pub fn get(
+        &mut self,
+        fonts: &Fonts,
+        key: GlyphKey,
+    ) -> Option<Option<CachedGlyph>> {
+        if let Some(cached) = self.glyphs.get(&key) {
+            return Some(*cached);
+        }
+        let outlined = fonts.get(key.font).and_then(|font| {
+            font.0
+                .outline_glyph(GlyphId(key.glyph).with_scale_and_position(
+                    f32::from_bits(key.size),
+                    point(0.0, 0.0),
+                ))
+        });
+        let Some(outlined) = outlined else {
+            self.glyphs.insert(key, None);
+            return Some(None);
+        };
+
+        let bounds = outlined.px_bounds();
+        let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+        let (x, y) = self.allocate(width, height)?;
+        let stride = self.size;
+        let pixels = &mut self.pixels;
+        outlined.draw(|column, row, coverage| {
+            let index = (((y + row) * stride + x + column) * 4) as usize;
+            let alpha = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+            pixels[index..index + 4].copy_from_slice(&[255, 255, 255, alpha]);
+        });
+        self.dirty = Some(match self.dirty {
+            Some((top, bottom)) => (top.min(y), bottom.max(y + height)),
+            None => (y, y + height),
+        });
+
+        let size = self.size as f32;
+        let cached = CachedGlyph {
+            offset: Vec2::new(bounds.min.x, bounds.min.y),
+            size: Vec2::new(width as f32, height as f32),
+            uv: UvRect {
+                min: Vec2::new(x as f32, y as f32) / size,
+                max: Vec2::new((x + width) as f32, (y + height) as f32) / size,
+            },
+        };
+        self.glyphs.insert(key, Some(cached));
+        Some(Some(cached))
+    }
+
+    /// Doubles the size, dropping every glyph. Returns `false` if the
+    /// maximum size was already reached.
+    pub fn grow(&mut self) -> bool {
+        if self.size >= self.max_size {
+            return false;
+        }
+        self.size = (self.size * 2).min(self.max_size);
+        self.clear();
+        true
+    }
+
+    /// Drops every glyph.
+    pub fn clear(&mut self) {
+        self.pixels = vec![0; (self.size * self.size * 4) as usize];
+        self.glyphs.clear();
+        self.cursor = (0, 0);
+        self.row_height = 0;
+        self.dirty = None;
+        self.stale = true;
+    }
+
+    /// Sends the new glyphs to the renderer, returns the texture to draw
+    /// with.
+    pub fn upload(
+        &mut self,
+        renderer: &mut dyn Renderer,
+    ) -> Result<TextureId, render::Error> {
+        match self.texture {
+            Some(texture) if !self.stale => {
+                if let Some((top, bottom)) = self.dirty.take() {
+                    let row = (self.size * 4) as usize;
+                    renderer.update_texture(
+                        texture,
+                        ScissorRect {
+                            x: 0,
+                            y: top,
+                            width: self.size,
+                            height: bottom - top,
+                        },
+                        &self.pixels[top as usize * row..bottom as usize * row],
+                    )?;
+                }
+                Ok(texture)
+            }
+            previous => {
+                if let Some(previous) = previous {
+                    renderer.destroy_texture(previous)?;
+                }
+                let texture = renderer.create_texture(
+                    &TextureDescriptor::new(self.size, self.size),
+                    &self.pixels,
+                )?;
+                self.texture = Some(texture);
+                self.stale = false;
+                self.dirty = None;
+                Ok(texture)
+            }
+        }
+    }
+
+    /// Finds room for a glyph, starting a new row when the current one is
+    /// full.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width + PADDING > self.size {
+            return None;
+        }
+        if self.cursor.0 + width + PADDING > self.size {
+            self.cursor = (0, self.cursor.1 + self.row_height);
+            self.row_height = 0;
+        }
+        if self.cursor.1 + height + PADDING > self.size {
+            return None;
+        }
+
+        let position = self.cursor;
+        self.cursor.0 += width + PADDING;
+        self.row_height = self.row_height.max(height + PADDING);
+        Some(position)
+    }
+}
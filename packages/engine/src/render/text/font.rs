@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use ab_glyph::{Font as _, FontArc};
+use serde::{Deserialize, Serialize};
+
+use super::Error;
+use crate::asset::{AssetLoader, LoadContext};
+
+/// A TrueType or OpenType font, cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Font(pub(crate) FontArc);
+
+impl Font {
+    /// Parses the content of a TTF or OTF file.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        FontArc::try_from_vec(bytes)
+            .map(Self)
+            .map_err(|err| Error::InvalidFont(err.to_string()))
+    }
+
+    /// Reads a TTF or OTF file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let bytes =
+            std::fs::read(path).map_err(|err| Error::Io(err.to_string()))?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Returns `true` if the font can draw the character.
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.0.glyph_id(c).0 != 0
+    }
+}
+
+/// Identifies a font added to `Fonts`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct FontId(pub(crate) u32);
+
+impl FontId {
+    /// The first added font.
+    pub const DEFAULT: Self = Self(0);
+}
+
+impl std::fmt::Display for FontId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Font({})", self.0)
+    }
+}
+
+/// Every font text can be drawn with, cheap to clone.
+///
+/// Characters missing from the font picked by a `TextStyle` are drawn with
+/// the first other font that has them, in the order they were added.
+#[derive(Debug, Clone, Default)]
+pub struct Fonts {
+    fonts: Vec<Font>,
+}
+
+impl Fonts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_font(mut self, font: Font) -> Self {
+        self.add(font);
+        self
+    }
+
+    pub fn add(&mut self, font: Font) -> FontId {
+        self.fonts.push(font);
+        FontId(self.fonts.len() as u32 - 1)
+    }
+
+    pub fn get(&self, font: FontId) -> Option<&Font> {
+        self.fonts.get(font.0 as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.fonts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    /// Font drawing the character, falls back to `font` if none has it.
+    pub(crate) fn resolve(&self, font: FontId, c: char) -> FontId {
+        if self.get(font).is_some_and(|primary| primary.has_glyph(c)) {
+            return font;
+        }
+        self.fonts
+            .iter()
+            .position(|fallback| fallback.has_glyph(c))
+            .map_or(font, |index| FontId(index as u32))
+    }
+}
+
+/// Loads TTF and OTF files as a `Font`.
+#[derive(Debug, Default)]
+pub struct FontLoader;
+
+impl AssetLoader for FontLoader {
+    type Asset = Font;
+
+    fn extensions(&self) -> &[&str] {
+        &["ttf", "otf"]
+    }
+
+    fn load(&self, bytes: &[u8], _: LoadContext) -> Result<Font, String> {
+        Font::from_bytes(bytes.to_vec()).map_err(|err| err.to_string())
+    }
+}
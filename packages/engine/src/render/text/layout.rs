@@ -0,0 +1,294 @@
+use ab_glyph::{Font as _, GlyphId, ScaleFont};
+
+use super::{Error, FontId, Fonts};
+use crate::{math::Vec2, render::Color};
+
+/// Horizontal alignment of the lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    fn factor(self) -> f32 {
+        match self {
+            Self::Left => 0.0,
+            Self::Center => 0.5,
+            Self::Right => 1.0,
+        }
+    }
+}
+
+/// How text is laid out and drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    pub font: FontId,
+    /// Pixels.
+    pub size: f32,
+    pub color: Color,
+    /// Aligned inside `max_width`, or the widest line without it.
+    pub align: Align,
+    /// Lines are wrapped between words to fit, in pixels.
+    pub max_width: Option<f32>,
+    /// Multiplies the line height of the font.
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(FontId::DEFAULT, 16.0)
+    }
+}
+
+impl TextStyle {
+    pub fn new(font: FontId, size: f32) -> Self {
+        Self {
+            font,
+            size,
+            color: Color::WHITE,
+            align: Align::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+}
+
+/// A glyph placed by the layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    /// Differs from the style font for fallback characters.
+    pub font: FontId,
+    pub glyph: u16,
+    /// Start of the glyph on the baseline, in pixels from the top-left corner
+    /// of the text.
+    pub position: Vec2,
+}
+
+/// Text broken into lines and positioned glyphs, see `Fonts::layout()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    /// Widest line and height of every line.
+    pub size: Vec2,
+    pub lines: usize,
+    pub font_size: f32,
+}
+
+/// Line being laid out.
+#[derive(Default)]
+struct Line {
+    glyphs: Vec<PositionedGlyph>,
+    /// Pen position.
+    x: f32,
+    /// Without the trailing whitespace.
+    width: f32,
+    /// Kerned with the next glyph.
+    previous: Option<(FontId, GlyphId)>,
+}
+
+impl Fonts {
+    /// Breaks the text into lines and positions every glyph, kerning is
+    /// applied between glyphs of the same font.
+    ///
+    /// _Words longer than `max_width` overflow, they are never broken._
+    pub fn layout(
+        &self,
+        text: &str,
+        style: &TextStyle,
+    ) -> Result<TextLayout, Error> {
+        let font = self.get(style.font).ok_or(Error::NoSuchFont(style.font))?;
+        let scaled = font.0.as_scaled(style.size);
+        let line_height =
+            (scaled.height() + scaled.line_gap()) * style.line_spacing;
+
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut line = Line::default();
+            for (spaces, word) in words(paragraph) {
+                let (start, width) = (line.glyphs.len(), line.width);
+                self.shape(spaces, style, &mut line);
+                self.shape(word, style, &mut line);
+
+                let overflows =
+                    style.max_width.is_some_and(|max_width| line.x > max_width);
+                if overflows && width > 0.0 && !word.is_empty() {
+                    // Moves the word to a new line, dropping the spaces
+                    line.glyphs.truncate(start);
+                    lines.push(std::mem::take(&mut line));
+                    self.shape(word, style, &mut line);
+                }
+                if !word.is_empty() {
+                    line.width = line.x;
+                }
+            }
+            lines.push(line);
+        }
+
+        let widest = lines.iter().map(|line| line.width).fold(0.0, f32::max);
+        let width = style.max_width.unwrap_or(widest);
+        let glyphs = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(index, line)| {
+                let offset = Vec2::new(
+                    (width - line.width) * style.align.factor(),
+                    index as f32 * line_height + scaled.ascent(),
+                );
+                line.glyphs.iter().map(move |glyph| PositionedGlyph {
+                    position: glyph.position + offset,
+                    ..*glyph
+                })
+            })
+            .collect();
+
+        Ok(TextLayout {
+            glyphs,
+            size: Vec2::new(widest, lines.len() as f32 * line_height),
+            lines: lines.len(),
+            font_size: style.size,
+        })
+    }
+
+    /// Appends the glyphs of the text to the line.
+    fn shape(&self, text: &str, style: &TextStyle, line: &mut Line) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            let font = self.resolve(style.font, c);
+            let Some(scaled) =
+                self.get(font).map(|font| font.0.as_scaled(style.size))
+            else {
+                continue;
+            };
+            let glyph = scaled.glyph_id(c);
+            if let Some((previous_font, previous)) = line.previous {
+                if previous_font == font {
+                    line.x += scaled.kern(previous, glyph);
+                }
+            }
+            line.glyphs.push(PositionedGlyph {
+                font,
+                glyph: glyph.0,
+                position: Vec2::new(line.x, 0.0),
+            });
+            line.x += scaled.h_advance(glyph);
+            line.previous = Some((font, glyph));
+        }
+    }
+}
+
+/// Splits the text in words, each one with the whitespace preceding it.
+fn words(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let start = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        let end = rest[start..]
+            .find(char::is_whitespace)
+            .map_or(rest.len(), |end| start + end);
+        let (spaces, word) = (&rest[..start], &rest[start..end]);
+        rest = &rest[end..];
+        Some((spaces, word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::text::Font;
+
+    fn fonts() -> Fonts {
+        let font = |bytes: &[u8]| Font::from_bytes(bytes.to_vec()).unwrap();
+        Fonts::new()
+            .with_font(font(epaint_default_fonts::HACK_REGULAR))
+            .with_font(font(epaint_default_fonts::NOTO_EMOJI_REGULAR))
+    }
+
+    #[test]
+    fn wraps_and_aligns_lines() {
+        let fonts = fonts();
+        let style = TextStyle::new(FontId::DEFAULT, 20.0);
+        let single = fonts.layout("a", &style).unwrap();
+        // Monospaced
+        let advance = single.size.x;
+        let line_height = single.size.y;
+
+        let layout = fonts.layout("aaa bb\ncccc", &style).unwrap();
+        assert_eq!(layout.lines, 2);
+        assert_eq!(layout.size, Vec2::new(advance * 6.0, line_height * 2.0));
+
+        let style =
+            style.with_max_width(advance * 5.0).with_align(Align::Right);
+        let layout = fonts.layout("aaa bb cccc  ", &style).unwrap();
+        assert_eq!(layout.lines, 3);
+        // The spaces before wrapped words are dropped
+        assert_eq!(layout.glyphs.len(), 3 + 2 + 4 + 2);
+        let starts = [0, 3, 5].map(|index| layout.glyphs[index].position);
+        assert!((starts[0].x - advance * 2.0).abs() < 1e-3);
+        assert!((starts[1].x - advance * 3.0).abs() < 1e-3);
+        assert!((starts[2].y - starts[1].y - line_height).abs() < 1e-3);
+        assert_eq!(layout.size.x, advance * 4.0);
+
+        assert_eq!(
+            fonts.layout("a", &TextStyle::new(FontId(5), 20.0)),
+            Err(Error::NoSuchFont(FontId(5)))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_other_fonts() {
+        let fonts = fonts();
+        let layout = fonts
+            .layout("a\u{1F600}", &TextStyle::new(FontId::DEFAULT, 20.0))
+            .unwrap();
+        let used = layout.glyphs.iter().map(|glyph| glyph.font);
+        assert_eq!(used.collect::<Vec<_>>(), vec![FontId(0), FontId(1)]);
+        assert!(layout.glyphs.iter().all(|glyph| glyph.glyph != 0));
+
+        assert!(matches!(
+            Font::from_bytes(b"not a font".to_vec()),
+            Err(Error::InvalidFont(_))
+        ));
+    }
+
+    #[test]
+    fn kerning_pulls_pairs_closer() {
+        // Only has "A" and "V", the fonts embedded for tests have no
+        // kerning table
+        let bytes = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/kerning.ttf"
+        ));
+        let font = Font::from_bytes(bytes.to_vec()).unwrap();
+        let fonts = Fonts::new().with_font(font);
+        let style = TextStyle::new(FontId::DEFAULT, 32.0);
+        let width = |text| fonts.layout(text, &style).unwrap().size.x;
+
+        assert!(width("AV") < width("A") + width("V") - 1.0);
+    }
+}
@@ -0,0 +1,60 @@
+//! Text rendering.
+//!
+//! TrueType and OpenType `Font`s are loaded from files, or from assets with
+//! the `FontLoader`, and collected in `Fonts`. `Fonts::layout()` breaks text
+//! into lines (wrapping between words and aligning them) and positions the
+//! glyphs with kerning, characters missing from a font are taken from the
+//! other ones.
+//!
+//! The `TextRenderer` rasterizes the glyphs on demand into an atlas texture
+//! and draws them with a single draw call per text.
+//!
+//! ```no_run
+//! # use unen_engine::{math::Vec2, render::{Renderer, SoftwareRenderer}};
+//! # use unen_engine::render::text::{Align, Font, Fonts, TextRenderer, TextStyle};
+//! let fonts = Fonts::new()
+//!     .with_font(Font::load("assets/fonts/Regular.ttf").unwrap())
+//!     .with_font(Font::load("assets/fonts/Emoji.ttf").unwrap());
+//! let mut text = TextRenderer::new(fonts);
+//!
+//! let mut renderer = SoftwareRenderer::new();
+//! let surface = renderer.create_offscreen_surface(320, 240).unwrap();
+//! renderer.begin_frame(surface).unwrap();
+//! let style = TextStyle::default()
+//!     .with_max_width(300.0)
+//!     .with_align(Align::Center);
+//! text.draw(
+//!     &mut renderer,
+//!     Vec2::new(320.0, 240.0),
+//!     Vec2::new(10.0, 10.0),
+//!     "Hello world",
+//!     &style,
+//! )
+//! .unwrap();
+//! renderer.end_frame().unwrap();
+//! ```
+
+use thiserror::Error;
+
+mod cache;
+pub mod font;
+pub mod layout;
+pub mod renderer;
+
+pub use font::{Font, FontId, FontLoader, Fonts};
+pub use layout::{Align, PositionedGlyph, TextLayout, TextStyle};
+pub use renderer::TextRenderer;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The bytes are not a TrueType or OpenType font.
+    #[error("Invalid font: {0}")]
+    InvalidFont(String),
+    #[error("Failed to read font: {0}")]
+    Io(String),
+    /// The font was never added to the `Fonts`.
+    #[error("No such font '{0}'")]
+    NoSuchFont(FontId),
+    #[error(transparent)]
+    Render(#[from] super::Error),
+}
@@ -0,0 +1,230 @@
+use super::{
+    cache::{CachedGlyph, GlyphCache, GlyphKey},
+    Error, Fonts, TextLayout, TextStyle,
+};
+use crate::{
+    math::Vec2,
+    render::{Color, DrawCall, Renderer, Vertex},
+};
+
+/// Draws text with the glyphs cached in a texture atlas.
+///
+/// The atlas starts small and grows when glyphs do not fit anymore. It
+/// belongs to the first `Renderer` passed to `::draw()`, a `TextRenderer`
+/// can not be shared between renderers.
+#[derive(Debug)]
+pub struct TextRenderer {
+    fonts: Fonts,
+    cache: GlyphCache,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl TextRenderer {
+    /// Initial size of the atlas in pixels.
+    pub const ATLAS_SIZE: u32 = 256;
+    /// The atlas is cleared instead of growing past this size.
+    pub const MAX_ATLAS_SIZE: u32 = 4096;
+
+    pub fn new(fonts: Fonts) -> Self {
+        Self {
+            fonts,
+            cache: GlyphCache::new(Self::ATLAS_SIZE, Self::MAX_ATLAS_SIZE),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Sets the initial size of the atlas, before anything is drawn.
+    pub fn with_atlas_size(mut self, size: u32) -> Self {
+        self.cache = GlyphCache::new(size, Self::MAX_ATLAS_SIZE);
+        self
+    }
+
+    pub fn fonts(&self) -> &Fonts {
+        &self.fonts
+    }
+
+    pub fn fonts_mut(&mut self) -> &mut Fonts {
+        &mut self.fonts
+    }
+
+    /// Current size of the atlas in pixels.
+    pub fn atlas_size(&self) -> u32 {
+        self.cache.size()
+    }
+
+    /// Lays out and draws text into the frame that is being recorded.
+    ///
+    /// `position` is the top-left corner of the text in pixels from the
+    /// top-left corner of the surface.
+    pub fn draw(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        surface_size: Vec2,
+        position: Vec2,
+        text: &str,
+        style: &TextStyle,
+    ) -> Result<TextLayout, Error> {
+        let layout = self.fonts.layout(text, style)?;
+        self.draw_layout(
+            renderer,
+            surface_size,
+            position,
+            &layout,
+            style.color,
+        )?;
+        Ok(layout)
+    }
+
+    /// Draws text that was already laid out, see `::draw()`.
+    pub fn draw_layout(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        surface_size: Vec2,
+        position: Vec2,
+        layout: &TextLayout,
+        color: Color,
+    ) -> Result<(), Error> {
+        crate::profile_scope!("TextRenderer::draw_layout", "render");
+
+        let placed = loop {
+            if let Some(placed) = self.place(layout, false) {
+                break placed;
+            }
+            if !self.cache.grow() {
+                // Draws what fits in an empty atlas
+                self.cache.clear();
+                break self.place(layout, true).unwrap_or_default();
+            }
+        };
+        let texture = self.cache.upload(renderer)?;
+        if placed.is_empty() {
+            return Ok(());
+        }
+
+        let scale = Vec2::new(2.0, -2.0) / surface_size;
+        let to_clip = |point: Vec2| point * scale + Vec2::new(-1.0, 1.0);
+        self.vertices.clear();
+        self.indices.clear();
+        for (pen, glyph) in placed {
+            // Whole pixels keep the glyphs sharp
+            let min = (position + pen).round() + glyph.offset;
+            let max = min + glyph.size;
+            let (uv_min, uv_max) = (glyph.uv.min, glyph.uv.max);
+
+            let first = self.vertices.len() as u32;
+            self.vertices.extend([
+                Vertex::new(to_clip(min), uv_min, color),
+                Vertex::new(
+                    to_clip(Vec2::new(max.x, min.y)),
+                    Vec2::new(uv_max.x, uv_min.y),
+                    color,
+                ),
+                Vertex::new(to_clip(max), uv_max, color),
+                Vertex::new(
+                    to_clip(Vec2::new(min.x, max.y)),
+                    Vec2::new(uv_min.x, uv_max.y),
+                    color,
+                ),
+            ]);
+            self.indices.extend(
+                [0, 1, 2, 0, 2, 3].into_iter().map(|index| first + index),
+            );
+        }
+        renderer.draw(
+            &DrawCall::new(&self.vertices, &self.indices).with_texture(texture),
+        )?;
+        Ok(())
+    }
+
+    /// Makes sure every glyph is in the atlas, returns `None` if one does not
+    /// fit unless `lossy` is set, then it is skipped.
+    fn place(
+        &mut self,
+        layout: &TextLayout,
+        lossy: bool,
+    ) -> Option<Vec<(Vec2, CachedGlyph)>> {
+        let mut placed = Vec::with_capacity(layout.glyphs.len());
+        for glyph in &layout.glyphs {
+            let key = GlyphKey {
+                font: glyph.font,
+                glyph: glyph.glyph,
+                size: layout.font_size.to_bits(),
+            };
+            match self.cache.get(&self.fonts, key) {
+                Some(Some(cached)) => placed.push((glyph.position, cached)),
+                Some(None) => {}
+                None if lossy => {}
+                None => return None,
+            }
+        }
+        Some(placed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{text::Font, SoftwareRenderer};
+
+    fn text_renderer() -> TextRenderer {
+        let font =
+            Font::from_bytes(epaint_default_fonts::HACK_REGULAR.to_vec());
+        TextRenderer::new(Fonts::new().with_font(font.unwrap()))
+    }
+
+    #[test]
+    fn draws_into_offscreen_surface() {
+        let mut text = text_renderer();
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(200, 60).unwrap();
+        let size = Vec2::new(200.0, 60.0);
+        let style = TextStyle::default().with_color(Color::RED);
+
+        renderer.begin_frame(surface).unwrap();
+        renderer.clear(Color::BLACK).unwrap();
+        let layout = text
+            .draw(&mut renderer, size, Vec2::new(10.0, 20.0), "Hello", &style)
+            .unwrap();
+        assert_eq!(renderer.end_frame().unwrap().draw_calls, 1);
+
+        let image = renderer.read_surface(surface).unwrap();
+        let mut inked = 0;
+        for y in 0..60 {
+            for x in 0..200 {
+                let [r, g, b, _] = image.pixel(x, y);
+                if r == 0 {
+                    continue;
+                }
+                inked += 1;
+                assert_eq!((g, b), (0, 0));
+                // Everything lands inside the text box
+                let (x, y) = (x as f32, y as f32);
+                assert!((10.0..10.0 + layout.size.x + 1.0).contains(&x));
+                assert!((20.0..20.0 + layout.size.y).contains(&y));
+            }
+        }
+        assert!(inked > 50, "only {inked} pixels drawn");
+    }
+
+    #[test]
+    fn atlas_grows_when_full() {
+        let mut text = text_renderer().with_atlas_size(32);
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(64, 64).unwrap();
+        let alphabet = ('A'..='Z').chain('a'..='z').collect::<String>();
+
+        renderer.begin_frame(surface).unwrap();
+        text.draw(
+            &mut renderer,
+            Vec2::new(64.0, 64.0),
+            Vec2::ZERO,
+            &alphabet,
+            &TextStyle::default().with_max_width(64.0),
+        )
+        .unwrap();
+        renderer.end_frame().unwrap();
+        assert!(text.atlas_size() > 32);
+    }
+}
@@ -0,0 +1,876 @@
+//! GPU renderer built on top of `wgpu`.
+//!
+//! Draw calls are recorded while the frame is active and submitted in a single
+//! render pass by `::end_frame()`.
+
+use std::{collections::HashMap, sync::Arc};
+
+use ::wgpu::util::DeviceExt;
+use winit::window::Window;
+
+use super::{
+    validate_data, Color, DrawCall, Error, FilterMode, FrameStats, Image,
+    Renderer, ScissorRect, SurfaceId, TextureDescriptor, TextureId, Vertex,
+};
+
+/// Format used by offscreen surfaces, matches the `Image` layout.
+const OFFSCREEN_FORMAT: ::wgpu::TextureFormat =
+    ::wgpu::TextureFormat::Rgba8Unorm;
+
+enum Target {
+    Window {
+        surface: ::wgpu::Surface<'static>,
+        config: ::wgpu::SurfaceConfiguration,
+    },
+    Offscreen {
+        texture: ::wgpu::Texture,
+    },
+}
+
+impl Target {
+    fn size(&self) -> (u32, u32) {
+        match self {
+            Target::Window { config, .. } => (config.width, config.height),
+            Target::Offscreen { texture } => {
+                (texture.width(), texture.height())
+            }
+        }
+    }
+
+    fn format(&self) -> ::wgpu::TextureFormat {
+        match self {
+            Target::Window { config, .. } => config.format,
+            Target::Offscreen { texture } => texture.format(),
+        }
+    }
+}
+
+struct Texture {
+    texture: ::wgpu::Texture,
+    bind_group: ::wgpu::BindGroup,
+}
+
+/// A recorded draw call, indices point into the frame buffers.
+struct Command {
+    indices: std::ops::Range<u32>,
+    base_vertex: i32,
+    texture: TextureId,
+    scissor: Option<ScissorRect>,
+}
+
+/// The frame that is currently being recorded.
+struct Frame {
+    surface: SurfaceId,
+    clear: Option<Color>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    commands: Vec<Command>,
+    stats: FrameStats,
+}
+
+/// Renderer that draws on the GPU.
+pub struct WgpuRenderer {
+    instance: ::wgpu::Instance,
+    adapter: ::wgpu::Adapter,
+    device: ::wgpu::Device,
+    queue: ::wgpu::Queue,
+    shader: ::wgpu::ShaderModule,
+    bind_group_layout: ::wgpu::BindGroupLayout,
+    pipeline_layout: ::wgpu::PipelineLayout,
+    /// One pipeline per target format.
+    pipelines: HashMap<::wgpu::TextureFormat, ::wgpu::RenderPipeline>,
+    nearest_sampler: ::wgpu::Sampler,
+    linear_sampler: ::wgpu::Sampler,
+    /// Sampled by draw calls without a texture.
+    white: TextureId,
+    surfaces: HashMap<SurfaceId, Target>,
+    textures: HashMap<TextureId, Texture>,
+    next_id: u32,
+    frame: Option<Frame>,
+}
+
+impl WgpuRenderer {
+    /// Creates a renderer without any window, only offscreen surfaces can be
+    /// used.
+    pub fn new() -> Result<Self, Error> {
+        let instance =
+            ::wgpu::Instance::new(&::wgpu::InstanceDescriptor::default());
+        Self::with_instance(instance, None)
+    }
+
+    /// Creates a renderer able to present into the passed window, waiting
+    /// for the vertical blank if `vsync` is set.
+    ///
+    /// Returns the renderer and the surface of the window.
+    pub fn with_window(
+        window: Arc<Window>,
+        vsync: bool,
+    ) -> Result<(Self, SurfaceId), Error> {
+        let instance =
+            ::wgpu::Instance::new(&::wgpu::InstanceDescriptor::default());
+        let size = window.inner_size();
+        let surface = instance
+            .create_surface(window)
+            .map_err(|err| Error::Backend(err.to_string()))?;
+
+        let mut renderer = Self::with_instance(instance, Some(&surface))?;
+        let id = renderer.add_window_surface(
+            surface,
+            size.width,
+            size.height,
+            vsync,
+        )?;
+
+        Ok((renderer, id))
+    }
+
+    /// Creates a surface that presents into the passed window, waiting for
+    /// the vertical blank if `vsync` is set.
+    pub fn create_window_surface(
+        &mut self,
+        window: Arc<Window>,
+        vsync: bool,
+    ) -> Result<SurfaceId, Error> {
+        let size = window.inner_size();
+        let surface = self
+            .instance
+            .create_surface(window)
+            .map_err(|err| Error::Backend(err.to_string()))?;
+
+        self.add_window_surface(surface, size.width, size.height, vsync)
+    }
+
+    fn with_instance(
+        instance: ::wgpu::Instance,
+        compatible_surface: Option<&::wgpu::Surface>,
+    ) -> Result<Self, Error> {
+        let adapter = pollster::block_on(instance.request_adapter(
+            &::wgpu::RequestAdapterOptions {
+                compatible_surface,
+                ..Default::default()
+            },
+        ))
+        .ok_or(Error::Backend("No compatible adapter found".to_string()))?;
+        let (device, queue) = pollster::block_on(
+            adapter.request_device(
+                &::wgpu::DeviceDescriptor {
+                    label: Some("unen-engine"),
+                    required_limits: ::wgpu::Limits::downlevel_webgl2_defaults(
+                    )
+                    .using_resolution(adapter.limits()),
+                    ..Default::default()
+                },
+                None,
+            ),
+        )
+        .map_err(|err| Error::Backend(err.to_string()))?;
+
+        let shader =
+            device.create_shader_module(::wgpu::include_wgsl!("shader.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(
+            &::wgpu::BindGroupLayoutDescriptor {
+                label: Some("texture"),
+                entries: &[
+                    ::wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ::wgpu::ShaderStages::FRAGMENT,
+                        ty: ::wgpu::BindingType::Texture {
+                            sample_type: ::wgpu::TextureSampleType::Float {
+                                filterable: true,
+                            },
+                            view_dimension: ::wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    ::wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ::wgpu::ShaderStages::FRAGMENT,
+                        ty: ::wgpu::BindingType::Sampler(
+                            ::wgpu::SamplerBindingType::Filtering,
+                        ),
+                        count: None,
+                    },
+                ],
+            },
+        );
+        let pipeline_layout =
+            device.create_pipeline_layout(&::wgpu::PipelineLayoutDescriptor {
+                label: Some("sprite"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let sampler = |filter| {
+            device.create_sampler(&::wgpu::SamplerDescriptor {
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+        let nearest_sampler = sampler(::wgpu::FilterMode::Nearest);
+        let linear_sampler = sampler(::wgpu::FilterMode::Linear);
+
+        let mut renderer = Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            shader,
+            bind_group_layout,
+            pipeline_layout,
+            pipelines: HashMap::new(),
+            nearest_sampler,
+            linear_sampler,
+            white: TextureId(0),
+            surfaces: HashMap::new(),
+            textures: HashMap::new(),
+            next_id: 0,
+            frame: None,
+        };
+        renderer.white = renderer
+            .create_texture(&TextureDescriptor::new(1, 1), &[255; 4])?;
+
+        Ok(renderer)
+    }
+
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn add_window_surface(
+        &mut self,
+        surface: ::wgpu::Surface<'static>,
+        width: u32,
+        height: u32,
+        vsync: bool,
+    ) -> Result<SurfaceId, Error> {
+        let capabilities = surface.get_capabilities(&self.adapter);
+        let config = surface_config(&capabilities, width, height, vsync)?;
+        surface.configure(&self.device, &config);
+
+        let id = SurfaceId(self.next_id());
+        self.surfaces.insert(id, Target::Window { surface, config });
+
+        Ok(id)
+    }
+
+    fn create_offscreen_texture(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> ::wgpu::Texture {
+        self.device.create_texture(&::wgpu::TextureDescriptor {
+            label: Some("offscreen surface"),
+            size: ::wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: ::wgpu::TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            usage: ::wgpu::TextureUsages::RENDER_ATTACHMENT
+                | ::wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    fn pipeline(
+        &mut self,
+        format: ::wgpu::TextureFormat,
+    ) -> &::wgpu::RenderPipeline {
+        self.pipelines.entry(format).or_insert_with(|| {
+            self.device.create_render_pipeline(
+                &::wgpu::RenderPipelineDescriptor {
+                    label: Some("sprite"),
+                    layout: Some(&self.pipeline_layout),
+                    vertex: ::wgpu::VertexState {
+                        module: &self.shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: Default::default(),
+                        buffers: &[::wgpu::VertexBufferLayout {
+                            array_stride: size_of::<Vertex>() as u64,
+                            step_mode: ::wgpu::VertexStepMode::Vertex,
+                            attributes: &::wgpu::vertex_attr_array![
+                                0 => Float32x2,
+                                1 => Float32x2,
+                                2 => Float32x4,
+                            ],
+                        }],
+                    },
+                    primitive: ::wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: ::wgpu::MultisampleState::default(),
+                    fragment: Some(::wgpu::FragmentState {
+                        module: &self.shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: Default::default(),
+                        targets: &[Some(::wgpu::ColorTargetState {
+                            format,
+                            blend: Some(::wgpu::BlendState::ALPHA_BLENDING),
+                            write_mask: ::wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    multiview: None,
+                    cache: None,
+                },
+            )
+        })
+    }
+
+    fn submit(&mut self, frame: Frame) -> Result<(), Error> {
+        let target = self
+            .surfaces
+            .get(&frame.surface)
+            .ok_or(Error::UnknownSurface(frame.surface))?;
+        let (width, height) = target.size();
+        let format = target.format();
+
+        let (view, output) = match target {
+            Target::Window { surface, .. } => {
+                let output = surface
+                    .get_current_texture()
+                    .map_err(|err| Error::Backend(err.to_string()))?;
+                let view = output.texture.create_view(&Default::default());
+                (view, Some(output))
+            }
+            Target::Offscreen { texture } => {
+                (texture.create_view(&Default::default()), None)
+            }
+        };
+
+        // Recorded before borrowing the device for the pass
+        self.pipeline(format);
+        let pipeline = &self.pipelines[&format];
+
+        let vertex_buffer = self.device.create_buffer_init(
+            &::wgpu::util::BufferInitDescriptor {
+                label: Some("vertices"),
+                contents: vertex_bytes(&frame.vertices),
+                usage: ::wgpu::BufferUsages::VERTEX,
+            },
+        );
+        let index_bytes: Vec<u8> = frame
+            .indices
+            .iter()
+            .flat_map(|index| index.to_ne_bytes())
+            .collect();
+        let index_buffer = self.device.create_buffer_init(
+            &::wgpu::util::BufferInitDescriptor {
+                label: Some("indices"),
+                contents: &index_bytes,
+                usage: ::wgpu::BufferUsages::INDEX,
+            },
+        );
+
+        let mut encoder = self.device.create_command_encoder(
+            &::wgpu::CommandEncoderDescriptor {
+                label: Some("frame"),
+            },
+        );
+        {
+            let load = match frame.clear {
+                Some(color) => ::wgpu::LoadOp::Clear(::wgpu::Color {
+                    r: color.r as f64,
+                    g: color.g as f64,
+                    b: color.b as f64,
+                    a: color.a as f64,
+                }),
+                None => ::wgpu::LoadOp::Load,
+            };
+            let mut pass =
+                encoder.begin_render_pass(&::wgpu::RenderPassDescriptor {
+                    label: Some("frame"),
+                    color_attachments: &[Some(
+                        ::wgpu::RenderPassColorAttachment {
+                            view: &view,
+                            resolve_target: None,
+                            ops: ::wgpu::Operations {
+                                load,
+                                store: ::wgpu::StoreOp::Store,
+                            },
+                        },
+                    )],
+                    ..Default::default()
+                });
+
+            if !frame.commands.is_empty() {
+                pass.set_pipeline(pipeline);
+                pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                pass.set_index_buffer(
+                    index_buffer.slice(..),
+                    ::wgpu::IndexFormat::Uint32,
+                );
+            }
+            for command in &frame.commands {
+                let (x, y, w, h) = match command.scissor {
+                    Some(rect) => {
+                        let x = rect.x.min(width);
+                        let y = rect.y.min(height);
+                        (
+                            x,
+                            y,
+                            rect.width.min(width - x),
+                            rect.height.min(height - y),
+                        )
+                    }
+                    None => (0, 0, width, height),
+                };
+                if w == 0 || h == 0 {
+                    continue;
+                }
+
+                let texture = self
+                    .textures
+                    .get(&command.texture)
+                    .ok_or(Error::UnknownTexture(command.texture))?;
+                pass.set_scissor_rect(x, y, w, h);
+                pass.set_bind_group(0, &texture.bind_group, &[]);
+                pass.draw_indexed(
+                    command.indices.clone(),
+                    command.base_vertex,
+                    0..1,
+                );
+            }
+        }
+        self.queue.submit([encoder.finish()]);
+
+        if let Some(output) = output {
+            output.present();
+        }
+
+        Ok(())
+    }
+}
+
+impl Renderer for WgpuRenderer {
+    fn create_offscreen_surface(
+        &mut self,
+        width: u32,
+        height: u32,
+    ) -> Result<SurfaceId, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidSize(width, height));
+        }
+
+        let texture = self.create_offscreen_texture(width, height);
+        let id = SurfaceId(self.next_id());
+        self.surfaces.insert(id, Target::Offscreen { texture });
+
+        Ok(id)
+    }
+
+    fn resize_surface(
+        &mut self,
+        surface: SurfaceId,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidSize(width, height));
+        }
+
+        let texture = match self.surfaces.get(&surface) {
+            Some(Target::Offscreen { .. }) => {
+                Some(self.create_offscreen_texture(width, height))
+            }
+            Some(Target::Window { .. }) => None,
+            None => return Err(Error::UnknownSurface(surface)),
+        };
+
+        match self.surfaces.get_mut(&surface) {
+            Some(Target::Window { surface, config }) => {
+                config.width = width;
+                config.height = height;
+                surface.configure(&self.device, config);
+            }
+            Some(Target::Offscreen { texture: current }) => {
+                if let Some(texture) = texture {
+                    *current = texture;
+                }
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    fn surface_size(&self, surface: SurfaceId) -> Result<(u32, u32), Error> {
+        self.surfaces
+            .get(&surface)
+            .map(Target::size)
+            .ok_or(Error::UnknownSurface(surface))
+    }
+
+    fn destroy_surface(&mut self, surface: SurfaceId) -> Result<(), Error> {
+        if self
+            .frame
+            .as_ref()
+            .is_some_and(|frame| frame.surface == surface)
+        {
+            self.frame = None;
+        }
+
+        self.surfaces
+            .remove(&surface)
+            .map(|_| ())
+            .ok_or(Error::UnknownSurface(surface))
+    }
+
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureId, Error> {
+        validate_data(descriptor.width, descriptor.height, data)?;
+
+        let size = ::wgpu::Extent3d {
+            width: descriptor.width,
+            height: descriptor.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture_with_data(
+            &self.queue,
+            &::wgpu::TextureDescriptor {
+                label: None,
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: ::wgpu::TextureDimension::D2,
+                format: ::wgpu::TextureFormat::Rgba8Unorm,
+                usage: ::wgpu::TextureUsages::TEXTURE_BINDING
+                    | ::wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            ::wgpu::util::TextureDataOrder::LayerMajor,
+            data,
+        );
+        let view = texture.create_view(&Default::default());
+        let sampler = match descriptor.filter {
+            FilterMode::Nearest => &self.nearest_sampler,
+            FilterMode::Linear => &self.linear_sampler,
+        };
+        let bind_group =
+            self.device.create_bind_group(&::wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &self.bind_group_layout,
+                entries: &[
+                    ::wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: ::wgpu::BindingResource::TextureView(&view),
+                    },
+                    ::wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: ::wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            });
+
+        let id = TextureId(self.next_id());
+        self.textures.insert(
+            id,
+            Texture {
+                texture,
+                bind_group,
+            },
+        );
+
+        Ok(id)
+    }
+
+    fn update_texture(
+        &mut self,
+        texture: TextureId,
+        region: ScissorRect,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        validate_data(region.width, region.height, data)?;
+
+        let texture = &self
+            .textures
+            .get(&texture)
+            .ok_or(Error::UnknownTexture(texture))?
+            .texture;
+        let right = region.x.checked_add(region.width);
+        let bottom = region.y.checked_add(region.height);
+        if right.is_none_or(|right| right > texture.width())
+            || bottom.is_none_or(|bottom| bottom > texture.height())
+        {
+            return Err(Error::RegionOutOfBounds);
+        }
+
+        self.queue.write_texture(
+            ::wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: ::wgpu::Origin3d {
+                    x: region.x,
+                    y: region.y,
+                    z: 0,
+                },
+                aspect: ::wgpu::TextureAspect::All,
+            },
+            data,
+            ::wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(region.width * 4),
+                rows_per_image: Some(region.height),
+            },
+            ::wgpu::Extent3d {
+                width: region.width,
+                height: region.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn destroy_texture(&mut self, texture: TextureId) -> Result<(), Error> {
+        if texture == self.white {
+            return Err(Error::UnknownTexture(texture));
+        }
+
+        self.textures
+            .remove(&texture)
+            .map(|_| ())
+            .ok_or(Error::UnknownTexture(texture))
+    }
+
+    fn begin_frame(&mut self, surface: SurfaceId) -> Result<(), Error> {
+        if self.frame.is_some() {
+            return Err(Error::FrameAlreadyActive);
+        }
+        if !self.surfaces.contains_key(&surface) {
+            return Err(Error::UnknownSurface(surface));
+        }
+
+        self.frame = Some(Frame {
+            surface,
+            clear: None,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            commands: Vec::new(),
+            stats: FrameStats::default(),
+        });
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Color) -> Result<(), Error> {
+        let frame = self.frame.as_mut().ok_or(Error::NoActiveFrame)?;
+
+        // Everything recorded so far would be overwritten anyway
+        frame.clear = Some(color);
+        frame.vertices.clear();
+        frame.indices.clear();
+        frame.commands.clear();
+
+        Ok(())
+    }
+
+    fn draw(&mut self, call: &DrawCall) -> Result<(), Error> {
+        call.validate()?;
+
+        let texture = call.texture.unwrap_or(self.white);
+        if !self.textures.contains_key(&texture) {
+            return Err(Error::UnknownTexture(texture));
+        }
+
+        let frame = self.frame.as_mut().ok_or(Error::NoActiveFrame)?;
+        let start = frame.indices.len() as u32;
+        let command = Command {
+            indices: start..start + call.indices.len() as u32,
+            base_vertex: frame.vertices.len() as i32,
+            texture,
+            scissor: call.scissor,
+        };
+        frame.vertices.extend_from_slice(call.vertices);
+        frame.indices.extend_from_slice(call.indices);
+        frame.commands.push(command);
+        frame.stats.draw_calls += 1;
+        frame.stats.triangles += call.indices.len() / 3;
+
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<FrameStats, Error> {
+        let frame = self.frame.take().ok_or(Error::NoActiveFrame)?;
+        let stats = frame.stats;
+        self.submit(frame)?;
+
+        Ok(stats)
+    }
+
+    fn read_surface(&mut self, surface: SurfaceId) -> Result<Image, Error> {
+        let texture = match self.surfaces.get(&surface) {
+            Some(Target::Offscreen { texture }) => texture,
+            Some(Target::Window { .. }) => {
+                return Err(Error::Unsupported("reading a window surface"))
+            }
+            None => return Err(Error::UnknownSurface(surface)),
+        };
+        let (width, height) = (texture.width(), texture.height());
+
+        // Rows of the copy have to be aligned
+        let row_size = width * 4;
+        let padded_row_size = row_size
+            .div_ceil(::wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * ::wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.device.create_buffer(&::wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: padded_row_size as u64 * height as u64,
+            usage: ::wgpu::BufferUsages::COPY_DST
+                | ::wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(
+            &::wgpu::CommandEncoderDescriptor {
+                label: Some("readback"),
+            },
+        );
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            ::wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: ::wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer
+            .slice(..)
+            .map_async(::wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.device.poll(::wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|err| Error::Backend(err.to_string()))?
+            .map_err(|err| Error::Backend(err.to_string()))?;
+
+        let mapped = buffer.slice(..).get_mapped_range();
+        let mut pixels = Vec::with_capacity((row_size * height) as usize);
+        for row in mapped.chunks_exact(padded_row_size as usize) {
+            pixels.extend_from_slice(&row[..row_size as usize]);
+        }
+        drop(mapped);
+        buffer.unmap();
+
+        Image::from_rgba8(width, height, pixels)
+    }
+}
+
+fn vertex_bytes(vertices: &[Vertex]) -> &[u8] {
+    // SAFETY: `Vertex` is `#[repr(C)]` and only made of `f32`, so it has no
+    // padding and every byte is initialized.
+    unsafe {
+        std::slice::from_raw_parts(
+            vertices.as_ptr().cast::<u8>(),
+            std::mem::size_of_val(vertices),
+        )
+    }
+}
+
+/// Configuration of a window surface with the passed size.
+fn surface_config(
+    capabilities: &::wgpu::SurfaceCapabilities,
+    width: u32,
+    height: u32,
+    vsync: bool,
+) -> Result<::wgpu::SurfaceConfiguration, Error> {
+    // Prefer a linear format, so colors match the other backends
+    let format = capabilities
+        .formats
+        .iter()
+        .copied()
+        .find(|format| !format.is_srgb())
+        .or(capabilities.formats.first().copied())
+        .ok_or(Error::Unsupported("window surface"))?;
+    let present_mode = if vsync {
+        ::wgpu::PresentMode::AutoVsync
+    } else {
+        ::wgpu::PresentMode::AutoNoVsync
+    };
+    Ok(::wgpu::SurfaceConfiguration {
+        usage: ::wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format,
+        width: width.max(1),
+        height: height.max(1),
+        present_mode,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: capabilities.alpha_modes[0],
+        view_formats: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+    use crate::render::SoftwareRenderer;
+
+    fn draw(renderer: &mut dyn Renderer) -> Image {
+        let color = Color::RED.with_alpha(0.5);
+        let vertices = [
+            Vertex::new(Vec2::new(-1.0, 1.0), Vec2::ZERO, color),
+            Vertex::new(Vec2::new(0.0, 1.0), Vec2::new(1.0, 0.0), color),
+            Vertex::new(Vec2::ZERO, Vec2::ONE, color),
+            Vertex::new(Vec2::new(-1.0, 0.0), Vec2::new(0.0, 1.0), color),
+        ];
+        let indices = [0, 1, 2, 0, 2, 3];
+
+        let surface = renderer.create_offscreen_surface(8, 8).unwrap();
+        renderer.begin_frame(surface).unwrap();
+        renderer.clear(Color::BLUE).unwrap();
+        renderer.draw(&DrawCall::new(&vertices, &indices)).unwrap();
+        renderer.end_frame().unwrap();
+        renderer.read_surface(surface).unwrap()
+    }
+
+    #[test]
+    fn matches_software_renderer() {
+        // Headless machines usually have no adapter
+        let Ok(mut renderer) = WgpuRenderer::new() else {
+            return;
+        };
+
+        let expected = draw(&mut SoftwareRenderer::new());
+        let diff = draw(&mut renderer).diff(&expected, 2);
+        assert!(diff.is_match(), "{diff:?}");
+    }
+
+    #[test]
+    fn surface_follows_vsync() {
+        let capabilities = ::wgpu::SurfaceCapabilities {
+            formats: vec![
+                ::wgpu::TextureFormat::Bgra8UnormSrgb,
+                ::wgpu::TextureFormat::Bgra8Unorm,
+            ],
+            ..Default::default()
+        };
+
+        let config = surface_config(&capabilities, 0, 600, false).unwrap();
+        assert_eq!(config.present_mode, ::wgpu::PresentMode::AutoNoVsync);
+        assert_eq!(config.format, ::wgpu::TextureFormat::Bgra8Unorm);
+        assert_eq!((config.width, config.height), (1, 600));
+        let config = surface_config(&capabilities, 800, 600, true).unwrap();
+        assert_eq!(config.present_mode, ::wgpu::PresentMode::AutoVsync);
+
+        let incompatible = ::wgpu::SurfaceCapabilities::default();
+        assert!(matches!(
+            surface_config(&incompatible, 800, 600, true),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+/// Identifies an object living in a `World`.
+///
+/// Indices are reused after an entity is despawned, the generation makes sure
+/// old handles do not point to the new entity.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Creates an entity from its raw parts, mostly useful for tests and
+    /// serialization.
+    pub fn from_raw(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl std::fmt::Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Entity({}v{})", self.index, self.generation)
+    }
+}
+
+/// Hands out entities, reusing the indices of despawned ones.
+#[derive(Debug, Default)]
+pub(crate) struct Allocator {
+    /// Current generation of every index ever used.
+    generations: Vec<u32>,
+    /// Whether the index is currently in use.
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl Allocator {
+    pub fn allocate(&mut self) -> Entity {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity::from_raw(index, self.generations[index as usize])
+            }
+            None => {
+                let index = self.generations.len() as u32;
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity::from_raw(index, 0)
+            }
+        }
+    }
+
+    /// Returns `false` if the entity was not alive.
+    pub fn free(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        self.len -= 1;
+
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index).copied().unwrap_or(false)
+            && self.generations[index] == entity.generation
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Every alive entity, ordered by index.
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive)
+            .map(|(index, _)| {
+                Entity::from_raw(index as u32, self.generations[index])
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_are_reused_with_new_generation() {
+        let mut allocator = Allocator::default();
+        let first = allocator.allocate();
+        let second = allocator.allocate();
+        assert_eq!(allocator.len(), 2);
+
+        assert!(allocator.free(first));
+        assert!(!allocator.free(first));
+        assert!(!allocator.is_alive(first));
+
+        let third = allocator.allocate();
+        assert_eq!(third.index(), first.index());
+        assert_ne!(third, first);
+        assert!(allocator.is_alive(third));
+        assert_eq!(allocator.iter().collect::<Vec<_>>(), vec![third, second]);
+    }
+}
@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Entity, Error, World};
+use crate::math::Transform;
+
+/// Parent of an entity, managed by `World::set_parent()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parent(pub Entity);
+
+/// Children of an entity in insertion order, managed by `World::set_parent()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Children(pub Vec<Entity>);
+
+/// Transform relative to the world, computed from the local `Transform` of the
+/// entity and all its ancestors by `propagate_transforms()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GlobalTransform(pub Transform);
+
+impl World {
+    /// Changes the parent of `child`, `None` turns it into a root.
+    ///
+    /// The local transform is kept, so the child moves along with its new
+    /// parent. Refer to `::reparent()` to keep the world position instead.
+    pub fn set_parent(
+        &mut self,
+        child: Entity,
+        parent: Option<Entity>,
+    ) -> Result<(), Error> {
+        if !self.is_alive(child) {
+            return Err(Error::NoSuchEntity(child));
+        }
+        if let Some(parent) = parent {
+            if !self.is_alive(parent) {
+                return Err(Error::NoSuchEntity(parent));
+            }
+            if parent == child || self.ancestors(parent).any(|e| e == child) {
+                return Err(Error::HierarchyCycle(child, parent));
+            }
+        }
+
+        // Detach from the current parent
+        if let Some(Parent(old)) = self.remove::<Parent>(child) {
+            if let Some(children) = self.get_mut::<Children>(old) {
+                children.0.retain(|entity| *entity != child);
+                if children.0.is_empty() {
+                    self.remove::<Children>(old);
+                }
+            }
+        }
+
+        if let Some(parent) = parent {
+            self.insert(child, Parent(parent))?;
+            match self.get_mut::<Children>(parent) {
+                Some(children) => children.0.push(child),
+                None => {
+                    self.insert(parent, Children(vec![child]))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Same as `::set_parent()` but updates the local transform of `child` so
+    /// it keeps its current world position, rotation and scale.
+    pub fn reparent(
+        &mut self,
+        child: Entity,
+        parent: Option<Entity>,
+    ) -> Result<(), Error> {
+        let global = self.compute_global_transform(child);
+        self.set_parent(child, parent)?;
+
+        let parent_global = parent
+            .map(|parent| self.compute_global_transform(parent))
+            .unwrap_or_default();
+        let local = parent_global.inverse() * global;
+        self.insert(child, local)?;
+        if self.has::<GlobalTransform>(child) {
+            self.insert(child, GlobalTransform(global))?;
+        }
+
+        Ok(())
+    }
+
+    /// Despawns the entity together with all its descendants.
+    ///
+    /// Returns the amount of despawned entities.
+    pub fn despawn_recursive(&mut self, entity: Entity) -> usize {
+        if !self.is_alive(entity) {
+            return 0;
+        }
+
+        // Detach first so the parent does not keep a dead child
+        let _ = self.set_parent(entity, None);
+
+        let entities: Vec<Entity> = std::iter::once(entity)
+            .chain(self.descendants(entity))
+            .collect();
+        entities
+            .into_iter()
+            .filter(|entity| self.despawn(*entity))
+            .count()
+    }
+
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.get::<Parent>(entity).map(|parent| parent.0)
+    }
+
+    /// Direct children of the entity.
+    pub fn children(&self, entity: Entity) -> &[Entity] {
+        self.get::<Children>(entity)
+            .map(|children| children.0.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Entities without a parent.
+    pub fn roots(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities()
+            .filter(|entity| !self.has::<Parent>(*entity))
+    }
+
+    /// Parent, grandparent and so on up to the root.
+    pub fn ancestors(&self, entity: Entity) -> Ancestors<'_> {
+        Ancestors {
+            world: self,
+            current: self.parent(entity),
+        }
+    }
+
+    /// Every descendant in depth-first order, parents always come before
+    /// their children.
+    pub fn descendants(&self, entity: Entity) -> Descendants<'_> {
+        let mut stack = self.children(entity).to_vec();
+        stack.reverse();
+        Descendants { world: self, stack }
+    }
+
+    /// Computes the world transform by walking up the hierarchy, unlike
+    /// `GlobalTransform` it is always up to date.
+    pub fn compute_global_transform(&self, entity: Entity) -> Transform {
+        std::iter::once(entity)
+            .chain(self.ancestors(entity))
+            .map(|entity| {
+                self.get::<Transform>(entity).copied().unwrap_or_default()
+            })
+            .fold(Transform::IDENTITY, |global, local| local * global)
+    }
+}
+
+pub struct Ancestors<'a> {
+    world: &'a World,
+    current: Option<Entity>,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let current = self.current?;
+        self.current = self.world.parent(current);
+        Some(current)
+    }
+}
+
+pub struct Descendants<'a> {
+    world: &'a World,
+    stack: Vec<Entity>,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let entity = self.stack.pop()?;
+        self.stack
+            .extend(self.world.children(entity).iter().rev().copied());
+        Some(entity)
+    }
+}
+
+/// Updates the `GlobalTransform` of every entity with a `Transform`, parents
+/// are always updated before their children.
+///
+/// Entities without a `Transform` behave as an identity transform for their
+/// children.
+pub fn propagate_transforms(world: &mut World) {
+    crate::profile_scope!("scene::propagate_transforms", "scene");
+
+    let mut stack: Vec<(Entity, Transform)> = world
+        .roots()
+        .map(|root| (root, Transform::IDENTITY))
+        .collect();
+    stack.reverse();
+
+    while let Some((entity, parent)) = stack.pop() {
+        let global = match world.get::<Transform>(entity) {
+            Some(local) => {
+                let global = parent * *local;
+                // The entity is alive, it came from the hierarchy
+                let _ = world.insert(entity, GlobalTransform(global));
+                global
+            }
+            None => parent,
+        };
+
+        stack.extend(
+            world
+                .children(entity)
+                .iter()
+                .rev()
+                .map(|child| (*child, global)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Quat, Vec3, EPSILON};
+
+    fn translated(world: &mut World, x: f32) -> Entity {
+        world.spawn_with(Transform::from_translation(Vec3::X * x))
+    }
+
+    fn global(world: &World, entity: Entity) -> Transform {
+        world.get::<GlobalTransform>(entity).unwrap().0
+    }
+
+    #[test]
+    fn propagation() {
+        let mut world = World::new();
+        let root = world.spawn_with(
+            Transform::from_translation(Vec3::X * 10.0).with_rotation(
+                Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            ),
+        );
+        let child = translated(&mut world, 1.0);
+        let grandchild = translated(&mut world, 1.0);
+        // Spawned after its parent on purpose, order must not matter
+        world.set_parent(grandchild, Some(child)).unwrap();
+        world.set_parent(child, Some(root)).unwrap();
+
+        propagate_transforms(&mut world);
+
+        assert!(global(&world, child)
+            .translation
+            .abs_diff_eq(Vec3::new(10.0, 1.0, 0.0), EPSILON));
+        assert!(global(&world, grandchild)
+            .translation
+            .abs_diff_eq(Vec3::new(10.0, 2.0, 0.0), EPSILON));
+        assert!(global(&world, grandchild)
+            .abs_diff_eq(&world.compute_global_transform(grandchild), EPSILON));
+    }
+
+    #[test]
+    fn entities_without_transform_are_skipped() {
+        let mut world = World::new();
+        let root = translated(&mut world, 5.0);
+        let group = world.spawn();
+        let child = translated(&mut world, 1.0);
+        world.set_parent(group, Some(root)).unwrap();
+        world.set_parent(child, Some(group)).unwrap();
+
+        propagate_transforms(&mut world);
+        assert!(!world.has::<GlobalTransform>(group));
+        assert_eq!(global(&world, child).translation, Vec3::X * 6.0);
+    }
+
+    #[test]
+    fn reparent_keeps_world_position() {
+        let mut world = World::new();
+        let a = translated(&mut world, 5.0);
+        let b = world.spawn_with(
+            Transform::from_translation(Vec3::Y * 3.0)
+                .with_scale(Vec3::splat(2.0)),
+        );
+        let child = translated(&mut world, 1.0);
+        world.set_parent(child, Some(a)).unwrap();
+        propagate_transforms(&mut world);
+        let before = global(&world, child);
+
+        world.reparent(child, Some(b)).unwrap();
+        propagate_transforms(&mut world);
+        assert!(global(&world, child).abs_diff_eq(&before, EPSILON));
+        assert_eq!(world.children(a), &[]);
+        assert_eq!(world.children(b), &[child]);
+
+        world.reparent(child, None).unwrap();
+        propagate_transforms(&mut world);
+        assert!(global(&world, child).abs_diff_eq(&before, EPSILON));
+        assert_eq!(world.parent(child), None);
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        let c = world.spawn();
+        world.set_parent(b, Some(a)).unwrap();
+        world.set_parent(c, Some(b)).unwrap();
+
+        assert_eq!(
+            world.set_parent(a, Some(c)),
+            Err(Error::HierarchyCycle(a, c))
+        );
+        assert_eq!(
+            world.set_parent(a, Some(a)),
+            Err(Error::HierarchyCycle(a, a))
+        );
+    }
+
+    #[test]
+    fn iteration_and_recursive_despawn() {
+        let mut world = World::new();
+        let root = world.spawn();
+        let a = world.spawn();
+        let b = world.spawn();
+        let a1 = world.spawn();
+        let a2 = world.spawn();
+        let other = world.spawn();
+        world.set_parent(a, Some(root)).unwrap();
+        world.set_parent(b, Some(root)).unwrap();
+        world.set_parent(a1, Some(a)).unwrap();
+        world.set_parent(a2, Some(a)).unwrap();
+
+        assert_eq!(
+            world.descendants(root).collect::<Vec<_>>(),
+            vec![a, a1, a2, b]
+        );
+        assert_eq!(world.ancestors(a2).collect::<Vec<_>>(), vec![a, root]);
+        assert_eq!(world.roots().collect::<Vec<_>>(), vec![root, other]);
+
+        assert_eq!(world.despawn_recursive(a), 3);
+        assert_eq!(world.children(root), &[b]);
+        assert!(!world.is_alive(a1));
+        assert_eq!(world.despawn_recursive(root), 2);
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn despawn_unlinks_parent_and_children() {
+        let mut world = World::new();
+        let root = world.spawn();
+        let middle = world.spawn_with(Transform::from_translation(Vec3::X));
+        let leaf = world.spawn_with(Transform::from_translation(Vec3::Y));
+        world.set_parent(middle, Some(root)).unwrap();
+        world.set_parent(leaf, Some(middle)).unwrap();
+
+        assert!(world.despawn(middle));
+        assert_eq!(world.children(root), &[]);
+        assert!(!world.has::<Children>(root));
+        assert_eq!(world.parent(leaf), None);
+        assert_eq!(world.roots().collect::<Vec<_>>(), vec![root, leaf]);
+
+        // The orphan is propagated again, from its own transform
+        propagate_transforms(&mut world);
+        assert_eq!(
+            world.get::<GlobalTransform>(leaf),
+            Some(&GlobalTransform(Transform::from_translation(Vec3::Y)))
+        );
+    }
+}
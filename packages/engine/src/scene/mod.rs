@@ -0,0 +1,55 @@
+//! Entities, components and the scene graph.
+//!
+//! Entities live in a `World` and can be organized in a hierarchy with
+//! `World::set_parent()`. Every entity with a `Transform` gets a
+//! `GlobalTransform` that is recomputed on each `Engine::step()`.
+//!
+//! Worlds can be saved to and loaded from a `SceneSnapshot` with a
+//! `SceneRegistry`.
+
+use thiserror::Error;
+
+pub mod entity;
+pub mod hierarchy;
+pub mod serialize;
+pub mod world;
+
+pub use entity::Entity;
+pub use hierarchy::{propagate_transforms, Children, GlobalTransform, Parent};
+pub use serialize::{
+    EntityMap, EntitySnapshot, MapEntities, SceneRegistry, SceneSnapshot,
+};
+pub use world::World;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The entity was despawned or never existed.
+    #[error("No such entity '{0}'")]
+    NoSuchEntity(Entity),
+    /// Making the second entity the parent of the first one would create a
+    /// cycle.
+    #[error("'{1}' can not be the parent of '{0}', it would create a cycle")]
+    HierarchyCycle(Entity, Entity),
+    /// Failed to serialize the named component, resource or snapshot.
+    #[error("Failed to serialize '{0}': {1}")]
+    Serialize(String, String),
+    /// Failed to deserialize the named component or resource.
+    #[error("Failed to deserialize '{0}': {1}")]
+    Deserialize(String, String),
+    /// The snapshot could not be decoded or is malformed.
+    #[error("Invalid snapshot: {0}")]
+    InvalidSnapshot(String),
+    /// No component is registered with this name.
+    #[error("Unknown component '{0}'")]
+    UnknownComponent(String),
+    /// No resource is registered with this name.
+    #[error("Unknown resource '{0}'")]
+    UnknownResource(String),
+    /// The snapshot was saved by a newer version than the one supported by
+    /// the registry.
+    #[error("Snapshot version {0} is newer than the supported version {1}")]
+    UnsupportedVersion(u32, u32),
+    /// A migration from the attached version returned an error.
+    #[error("Migration from version {0} failed: {1}")]
+    MigrationFailed(u32, String),
+}
@@ -0,0 +1,680 @@
+//! Saving and loading worlds.
+//!
+//! Only components and resources registered in a `SceneRegistry` are saved,
+//! each under a stable name. Snapshots store their values as loosely typed
+//! `Value`s, so migrations can fix old snapshots before they are turned back
+//! into the registered types.
+//!
+//! ```
+//! # use unen_engine::{math::Transform, scene::{SceneRegistry, SceneSnapshot, World}};
+//! let mut world = World::new();
+//! world.spawn_with(Transform::IDENTITY);
+//!
+//! let registry = SceneRegistry::new();
+//! let ron = registry.save(&world).unwrap().to_ron().unwrap();
+//!
+//! let mut loaded = World::new();
+//! registry
+//!     .load(SceneSnapshot::from_ron(&ron).unwrap(), &mut loaded)
+//!     .unwrap();
+//! assert_eq!(loaded.len(), 1);
+//! ```
+
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap, HashSet},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+pub use serde_json::Value;
+
+use super::{Entity, Error, World};
+use crate::math::Transform;
+
+/// Components holding entities must implement this to be registered with
+/// `SceneRegistry::register_mapped_component()`, loaded entities get new ids.
+pub trait MapEntities {
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+/// Ids that entities had when saved, mapped to the ones they got when loaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntityMap {
+    map: HashMap<Entity, Entity>,
+}
+
+impl EntityMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, saved: Entity, loaded: Entity) {
+        self.map.insert(saved, loaded);
+    }
+
+    pub fn get(&self, saved: Entity) -> Option<Entity> {
+        self.map.get(&saved).copied()
+    }
+
+    /// Returns the new id of the entity, entities that were not part of the
+    /// snapshot are kept as they are.
+    pub fn map(&self, entity: Entity) -> Entity {
+        self.get(entity).unwrap_or(entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// Saved state of a `World`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneSnapshot {
+    /// Schema version of the registry that saved it.
+    pub version: u32,
+    /// Parents always come before their children.
+    #[serde(default)]
+    pub entities: Vec<EntitySnapshot>,
+    /// Resources by registered name.
+    #[serde(default)]
+    pub resources: BTreeMap<String, Value>,
+}
+
+/// Saved state of a single entity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    /// Id the entity had when saved.
+    pub entity: Entity,
+    #[serde(default)]
+    pub parent: Option<Entity>,
+    /// Components by registered name.
+    #[serde(default)]
+    pub components: BTreeMap<String, Value>,
+}
+
+impl SceneSnapshot {
+    /// Encodes as human readable RON.
+    pub fn to_ron(&self) -> Result<String, Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| Error::Serialize("snapshot".into(), err.to_string()))
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, Error> {
+        ron::from_str(text)
+            .map_err(|err| Error::InvalidSnapshot(err.to_string()))
+    }
+
+    /// Encodes as compact MessagePack, meant for save files.
+    pub fn to_binary(&self) -> Result<Vec<u8>, Error> {
+        rmp_serde::to_vec_named(self)
+            .map_err(|err| Error::Serialize("snapshot".into(), err.to_string()))
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, Error> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|err| Error::InvalidSnapshot(err.to_string()))
+    }
+
+    /// Renames a component on every entity, useful inside migrations.
+    pub fn rename_component(&mut self, from: &str, to: &str) {
+        for entity in &mut self.entities {
+            if let Some(value) = entity.components.remove(from) {
+                entity.components.insert(to.to_string(), value);
+            }
+        }
+    }
+
+    /// Every saved value of the named component, useful inside migrations.
+    pub fn components_mut<'a>(
+        &'a mut self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a mut Value> {
+        self.entities
+            .iter_mut()
+            .filter_map(move |entity| entity.components.get_mut(name))
+    }
+}
+
+struct ComponentRegistration {
+    save: fn(&World, Entity) -> Option<Result<Value, serde_json::Error>>,
+    deserialize: fn(Value) -> Result<Box<dyn Any>, serde_json::Error>,
+    insert: fn(Box<dyn Any>, &EntityMap, &mut World, Entity),
+}
+
+struct ResourceRegistration {
+    save: fn(&World) -> Option<Result<Value, serde_json::Error>>,
+    deserialize: fn(Value) -> Result<Box<dyn Any>, serde_json::Error>,
+    insert: fn(Box<dyn Any>, &mut World),
+}
+
+type Migration =
+    Box<dyn Fn(&mut SceneSnapshot) -> Result<(), String> + Send + Sync>;
+
+/// Knows how to save and load components and resources.
+///
+/// `Transform` is registered by default, the hierarchy is always saved.
+pub struct SceneRegistry {
+    version: u32,
+    components: BTreeMap<String, ComponentRegistration>,
+    resources: BTreeMap<String, ResourceRegistration>,
+    /// Migrations by the version they upgrade from.
+    migrations: BTreeMap<u32, Vec<Migration>>,
+}
+
+impl Default for SceneRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            version: 0,
+            components: BTreeMap::new(),
+            resources: BTreeMap::new(),
+            migrations: BTreeMap::new(),
+        };
+        registry.register_component::<Transform>("Transform");
+        registry
+    }
+}
+
+impl SceneRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the current schema version, bump it whenever the saved data
+    /// changes and add a migration from the previous one.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Registers a component under the passed name.
+    ///
+    /// _The name is stored in the snapshots, so it must not change between
+    /// versions unless a migration renames it._
+    pub fn register_component<T>(&mut self, name: &str)
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        self.add_component(
+            name,
+            ComponentRegistration {
+                save: save_component::<T>,
+                deserialize: deserialize::<T>,
+                insert: insert_component::<T>,
+            },
+        );
+    }
+
+    /// Same as `::register_component()` for components holding entities.
+    pub fn register_mapped_component<T>(&mut self, name: &str)
+    where
+        T: Serialize + DeserializeOwned + MapEntities + 'static,
+    {
+        self.add_component(
+            name,
+            ComponentRegistration {
+                save: save_component::<T>,
+                deserialize: deserialize::<T>,
+                insert: insert_mapped_component::<T>,
+            },
+        );
+    }
+
+    /// Registers a resource under the passed name.
+    pub fn register_resource<T>(&mut self, name: &str)
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        let registration = ResourceRegistration {
+            save: save_resource::<T>,
+            deserialize: deserialize::<T>,
+            insert: insert_resource::<T>,
+        };
+        if self
+            .resources
+            .insert(name.to_string(), registration)
+            .is_some()
+        {
+            log::warn!("Replaced scene resource registration '{name}'");
+        }
+    }
+
+    /// Adds a migration that upgrades snapshots saved with version `from` to
+    /// `from + 1`. Migrations added for the same version run in order.
+    pub fn add_migration<F>(&mut self, from: u32, migration: F)
+    where
+        F: Fn(&mut SceneSnapshot) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.migrations
+            .entry(from)
+            .or_default()
+            .push(Box::new(migration));
+    }
+
+    /// Saves every entity and the registered components and resources.
+    pub fn save(&self, world: &World) -> Result<SceneSnapshot, Error> {
+        crate::profile_scope!("SceneRegistry::save", "scene");
+
+        let entities = world
+            .roots()
+            .flat_map(|root| {
+                std::iter::once(root).chain(world.descendants(root))
+            })
+            .map(|entity| {
+                let mut components = BTreeMap::new();
+                for (name, registration) in &self.components {
+                    if let Some(value) = (registration.save)(world, entity) {
+                        let value = value.map_err(|err| {
+                            Error::Serialize(name.clone(), err.to_string())
+                        })?;
+                        components.insert(name.clone(), value);
+                    }
+                }
+
+                Ok(EntitySnapshot {
+                    entity,
+                    parent: world.parent(entity),
+                    components,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let mut resources = BTreeMap::new();
+        for (name, registration) in &self.resources {
+            if let Some(value) = (registration.save)(world) {
+                let value = value.map_err(|err| {
+                    Error::Serialize(name.clone(), err.to_string())
+                })?;
+                resources.insert(name.clone(), value);
+            }
+        }
+
+        Ok(SceneSnapshot {
+            version: self.version,
+            entities,
+            resources,
+        })
+    }
+
+    /// Runs the migrations needed to bring the snapshot to the current
+    /// version.
+    pub fn migrate(&self, snapshot: &mut SceneSnapshot) -> Result<(), Error> {
+        if snapshot.version > self.version {
+            return Err(Error::UnsupportedVersion(
+                snapshot.version,
+                self.version,
+            ));
+        }
+
+        while snapshot.version < self.version {
+            let version = snapshot.version;
+            for migration in self.migrations.get(&version).into_iter().flatten()
+            {
+                migration(snapshot)
+                    .map_err(|err| Error::MigrationFailed(version, err))?;
+            }
+            snapshot.version = version + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Spawns the saved entities into the world, which does not need to be
+    /// empty. Returns the ids the entities got.
+    ///
+    /// _Everything is validated first, on error the world is left as it
+    /// was._
+    pub fn load(
+        &self,
+        mut snapshot: SceneSnapshot,
+        world: &mut World,
+    ) -> Result<EntityMap, Error> {
+        crate::profile_scope!("SceneRegistry::load", "scene");
+
+        self.migrate(&mut snapshot)?;
+
+        let mut saved = HashSet::new();
+        for entity in &snapshot.entities {
+            if !saved.insert(entity.entity) {
+                return Err(Error::InvalidSnapshot(format!(
+                    "'{}' is duplicated",
+                    entity.entity
+                )));
+            }
+            if let Some(parent) = entity.parent {
+                if !saved.contains(&parent) {
+                    return Err(Error::InvalidSnapshot(format!(
+                        "'{}' must come after its parent '{parent}'",
+                        entity.entity
+                    )));
+                }
+            }
+        }
+
+        let mut entities = Vec::with_capacity(snapshot.entities.len());
+        for entity in snapshot.entities {
+            let mut components = Vec::with_capacity(entity.components.len());
+            for (name, value) in entity.components {
+                let registration = self
+                    .components
+                    .get(&name)
+                    .ok_or_else(|| Error::UnknownComponent(name.clone()))?;
+                let component = (registration.deserialize)(value)
+                    .map_err(|err| Error::Deserialize(name, err.to_string()))?;
+                components.push((registration, component));
+            }
+            entities.push((entity.entity, entity.parent, components));
+        }
+
+        let mut resources = Vec::with_capacity(snapshot.resources.len());
+        for (name, value) in snapshot.resources {
+            let registration = self
+                .resources
+                .get(&name)
+                .ok_or_else(|| Error::UnknownResource(name.clone()))?;
+            let resource = (registration.deserialize)(value)
+                .map_err(|err| Error::Deserialize(name, err.to_string()))?;
+            resources.push((registration, resource));
+        }
+
+        let mut map = EntityMap::new();
+        for (saved, ..) in &entities {
+            map.insert(*saved, world.spawn());
+        }
+        for (saved, parent, components) in entities {
+            let entity = map.map(saved);
+            for (registration, component) in components {
+                (registration.insert)(component, &map, world, entity);
+            }
+            if let Some(parent) = parent {
+                // Parents come first, so this can not create a cycle
+                world.set_parent(entity, Some(map.map(parent)))?;
+            }
+        }
+        for (registration, resource) in resources {
+            (registration.insert)(resource, world);
+        }
+
+        Ok(map)
+    }
+
+    fn add_component(
+        &mut self,
+        name: &str,
+        registration: ComponentRegistration,
+    ) {
+        if self
+            .components
+            .insert(name.to_string(), registration)
+            .is_some()
+        {
+            log::warn!("Replaced scene component registration '{name}'");
+        }
+    }
+}
+
+fn save_component<T: Serialize + 'static>(
+    world: &World,
+    entity: Entity,
+) -> Option<Result<Value, serde_json::Error>> {
+    world.get::<T>(entity).map(serde_json::to_value)
+}
+
+fn save_resource<T: Serialize + 'static>(
+    world: &World,
+) -> Option<Result<Value, serde_json::Error>> {
+    world.resource::<T>().map(serde_json::to_value)
+}
+
+fn deserialize<T: DeserializeOwned + 'static>(
+    value: Value,
+) -> Result<Box<dyn Any>, serde_json::Error> {
+    serde_json::from_value::<T>(value)
+        .map(|value| Box::new(value) as Box<dyn Any>)
+}
+
+fn insert_component<T: 'static>(
+    component: Box<dyn Any>,
+    _: &EntityMap,
+    world: &mut World,
+    entity: Entity,
+) {
+    if let Ok(component) = component.downcast::<T>() {
+        // The entity was just spawned
+        let _ = world.insert(entity, *component);
+    }
+}
+
+fn insert_mapped_component<T: MapEntities + 'static>(
+    component: Box<dyn Any>,
+    map: &EntityMap,
+    world: &mut World,
+    entity: Entity,
+) {
+    if let Ok(mut component) = component.downcast::<T>() {
+        component.map_entities(map);
+        let _ = world.insert(entity, *component);
+    }
+}
+
+fn insert_resource<T: 'static>(resource: Box<dyn Any>, world: &mut World) {
+    if let Ok(resource) = resource.downcast::<T>() {
+        world.insert_resource(*resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::Vec3,
+        scene::{Children, GlobalTransform},
+    };
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Health {
+        current: u32,
+        max: u32,
+    }
+
+    /// Entity being followed.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Follow(Entity);
+
+    impl MapEntities for Follow {
+        fn map_entities(&mut self, map: &EntityMap) {
+            self.0 = map.map(self.0);
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Score(u64);
+
+    fn registry() -> SceneRegistry {
+        let mut registry = SceneRegistry::new();
+        registry.register_component::<Health>("Health");
+        registry.register_mapped_component::<Follow>("Follow");
+        registry.register_resource::<Score>("Score");
+        registry
+    }
+
+    fn world() -> (World, Entity, Entity) {
+        let mut world = World::new();
+        let player = world.spawn_with(Health {
+            current: 7,
+            max: 10,
+        });
+        world
+            .insert(player, Transform::from_translation(Vec3::X))
+            .unwrap();
+        let pet = world.spawn_with(Follow(player));
+        world.set_parent(pet, Some(player)).unwrap();
+        // Derived and unregistered components are not saved
+        world.insert(pet, GlobalTransform::default()).unwrap();
+        world.insert(pet, "not registered").unwrap();
+        world.insert_resource(Score(42));
+        (world, player, pet)
+    }
+
+    #[test]
+    fn ron_round_trip_remaps_entities() {
+        let (world, player, pet) = world();
+        let registry = registry();
+        let snapshot = registry.save(&world).unwrap();
+        assert_eq!(snapshot.entities.len(), 2);
+        assert_eq!(snapshot.entities[1].parent, Some(player));
+        assert_eq!(
+            snapshot.entities[1].components.keys().collect::<Vec<_>>(),
+            ["Follow"]
+        );
+
+        let text = snapshot.to_ron().unwrap();
+        assert_eq!(SceneSnapshot::from_ron(&text).unwrap(), snapshot);
+
+        // Occupied ids force the loaded entities to get new ones
+        let mut loaded = World::new();
+        loaded.spawn();
+        loaded.spawn();
+        let map = registry
+            .load(SceneSnapshot::from_ron(&text).unwrap(), &mut loaded)
+            .unwrap();
+        let (new_player, new_pet) = (map.map(player), map.map(pet));
+        assert_ne!(new_player, player);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(Entity::from_raw(9, 0)), None);
+
+        assert_eq!(loaded.len(), 4);
+        assert_eq!(
+            loaded.get::<Health>(new_player),
+            Some(&Health {
+                current: 7,
+                max: 10
+            })
+        );
+        assert_eq!(
+            loaded.get::<Transform>(new_player),
+            Some(&Transform::from_translation(Vec3::X))
+        );
+        assert_eq!(loaded.get::<Follow>(new_pet), Some(&Follow(new_player)));
+        assert_eq!(loaded.parent(new_pet), Some(new_player));
+        assert_eq!(
+            loaded.get::<Children>(new_player),
+            Some(&Children(vec![new_pet]))
+        );
+        assert!(!loaded.has::<&str>(new_pet));
+        assert_eq!(loaded.resource::<Score>(), Some(&Score(42)));
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let (world, ..) = world();
+        let snapshot = registry().save(&world).unwrap();
+
+        let bytes = snapshot.to_binary().unwrap();
+        assert!(bytes.len() < snapshot.to_ron().unwrap().len());
+        assert_eq!(SceneSnapshot::from_binary(&bytes).unwrap(), snapshot);
+        assert!(matches!(
+            SceneSnapshot::from_binary(&bytes[..bytes.len() / 2]),
+            Err(Error::InvalidSnapshot(_))
+        ));
+    }
+
+    #[test]
+    fn old_versions_are_migrated() {
+        // Version 0 stored the health as a single number named "Hp"
+        let old = r#"(
+            version: 0,
+            entities: [(
+                entity: (index: 3, generation: 1),
+                components: {"Hp": 5},
+            )],
+        )"#;
+
+        let mut registry = registry().with_version(2);
+        registry.add_migration(0, |snapshot| {
+            snapshot.rename_component("Hp", "Health");
+            Ok(())
+        });
+        registry.add_migration(1, |snapshot| {
+            for health in snapshot.components_mut("Health") {
+                let current =
+                    health.as_u64().ok_or("Health is not a number")?;
+                *health = serde_json::json!({ "current": current, "max": 10 });
+            }
+            Ok(())
+        });
+
+        let mut world = World::new();
+        let map = registry
+            .load(SceneSnapshot::from_ron(old).unwrap(), &mut world)
+            .unwrap();
+        let entity = map.map(Entity::from_raw(3, 1));
+        assert_eq!(
+            world.get::<Health>(entity),
+            Some(&Health {
+                current: 5,
+                max: 10
+            })
+        );
+
+        let newer = SceneSnapshot {
+            version: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            registry.load(newer, &mut world),
+            Err(Error::UnsupportedVersion(3, 2))
+        );
+
+        let mut broken = SceneSnapshot::from_ron(old).unwrap();
+        for hp in broken.components_mut("Hp") {
+            *hp = Value::from("five");
+        }
+        assert_eq!(
+            registry.migrate(&mut broken),
+            Err(Error::MigrationFailed(1, "Health is not a number".into()))
+        );
+    }
+
+    #[test]
+    fn invalid_snapshots_leave_the_world_untouched() {
+        let (world, player, _) = world();
+        let registry = registry();
+        let mut world_copy = World::new();
+
+        let mut unknown = registry.save(&world).unwrap();
+        unknown.entities[1]
+            .components
+            .insert("Mana".into(), Value::from(3));
+        assert_eq!(
+            registry.load(unknown, &mut world_copy),
+            Err(Error::UnknownComponent("Mana".into()))
+        );
+
+        let mut wrong_type = registry.save(&world).unwrap();
+        wrong_type.entities[0]
+            .components
+            .insert("Health".into(), Value::from("full"));
+        assert!(matches!(
+            registry.load(wrong_type, &mut world_copy),
+            Err(Error::Deserialize(name, _)) if name == "Health"
+        ));
+
+        let mut orphan = registry.save(&world).unwrap();
+        orphan.entities.swap(0, 1);
+        assert!(matches!(
+            registry.load(orphan, &mut world_copy),
+            Err(Error::InvalidSnapshot(err)) if err.contains(&player.to_string())
+        ));
+
+        assert!(world_copy.is_empty());
+        assert!(!world_copy.has_resource::<Score>());
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+};
+
+use super::{
+    entity::{Allocator, Entity},
+    hierarchy::{Children, Parent},
+    Error,
+};
+
+/// Type erased component storage.
+trait Storage {
+    /// Returns `true` if the entity had a component.
+    fn remove(&mut self, entity: Entity) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Components are kept ordered by entity, so iteration is deterministic.
+type Components<T> = BTreeMap<Entity, T>;
+
+impl<T: 'static> Storage for Components<T> {
+    fn remove(&mut self, entity: Entity) -> bool {
+        BTreeMap::remove(self, &entity).is_some()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Container of entities and their components.
+///
+/// Any `'static` type can be used as a component, an entity holds at most one
+/// component of each type. Resources are global values not attached to any
+/// entity, at most one of each type.
+#[derive(Default)]
+pub struct World {
+    entities: Allocator,
+    storages: HashMap<TypeId, Box<dyn Storage>>,
+    resources: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new entity without components.
+    pub fn spawn(&mut self) -> Entity {
+        self.entities.allocate()
+    }
+
+    /// Creates a new entity with a single component.
+    pub fn spawn_with<T: 'static>(&mut self, component: T) -> Entity {
+        let entity = self.spawn();
+        self.storage_mut::<T>().insert(entity, component);
+        entity
+    }
+
+    /// Removes the entity and all its components, returns `false` if it was
+    /// not alive.
+    ///
+    /// _Children become roots, refer to `::despawn_recursive()`._
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        // Neither the parent nor the children keep a dead entity
+        let _ = self.set_parent(entity, None);
+        if let Some(Children(children)) = self.remove::<Children>(entity) {
+            for child in children {
+                self.remove::<Parent>(child);
+            }
+        }
+
+        self.entities.free(entity);
+
+        for storage in self.storages.values_mut() {
+            storage.remove(entity);
+        }
+
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.is_alive(entity)
+    }
+
+    /// Amount of alive entities.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every alive entity.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter()
+    }
+
+    /// Adds a component to the entity, returning the one it replaced.
+    pub fn insert<T: 'static>(
+        &mut self,
+        entity: Entity,
+        component: T,
+    ) -> Result<Option<T>, Error> {
+        if !self.is_alive(entity) {
+            return Err(Error::NoSuchEntity(entity));
+        }
+
+        Ok(self.storage_mut::<T>().insert(entity, component))
+    }
+
+    /// Removes a component from the entity.
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storage_mut::<T>().remove(&entity)
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        self.storage::<T>()?.get(&entity)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Components<T>>()?
+            .get_mut(&entity)
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    /// Every entity with a component of type `T`, ordered by entity.
+    pub fn query<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|components| components.iter())
+            .map(|(entity, component)| (*entity, component))
+    }
+
+    /// Same as `::query()` but with mutable access.
+    pub fn query_mut<T: 'static>(
+        &mut self,
+    ) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| {
+                storage.as_any_mut().downcast_mut::<Components<T>>()
+            })
+            .into_iter()
+            .flat_map(|components| components.iter_mut())
+            .map(|(entity, component)| (*entity, component))
+    }
+
+    /// Adds a resource, returning the one it replaced.
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) -> Option<T> {
+        self.resources
+            .insert(TypeId::of::<T>(), Box::new(resource))
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        self.resources
+            .remove(&TypeId::of::<T>())
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn resource<T: 'static>(&self) -> Option<&T> {
+        self.resources.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    pub fn resource_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.resources.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    pub fn has_resource<T: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&Components<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref::<Components<T>>()
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> &mut Components<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Components::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<Components<T>>()
+            // The key is the `TypeId` of the stored type
+            .expect("storage type mismatch")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[test]
+    fn components() {
+        let mut world = World::new();
+        let player = world.spawn_with(Health(10));
+        world.insert(player, Name("player")).unwrap();
+        let enemy = world.spawn_with(Health(5));
+
+        assert_eq!(world.get::<Name>(player), Some(&Name("player")));
+        assert!(!world.has::<Name>(enemy));
+
+        world.get_mut::<Health>(enemy).unwrap().0 -= 1;
+        assert_eq!(
+            world.query::<Health>().collect::<Vec<_>>(),
+            vec![(player, &Health(10)), (enemy, &Health(4))]
+        );
+
+        for (_, health) in world.query_mut::<Health>() {
+            health.0 = 0;
+        }
+        assert_eq!(world.get::<Health>(player), Some(&Health(0)));
+
+        assert_eq!(world.remove::<Name>(player), Some(Name("player")));
+        assert_eq!(world.query::<Name>().count(), 0);
+    }
+
+    #[test]
+    fn resources() {
+        let mut world = World::new();
+        assert_eq!(world.insert_resource(Health(3)), None);
+        assert_eq!(world.insert_resource(Health(4)), Some(Health(3)));
+
+        world.resource_mut::<Health>().unwrap().0 += 1;
+        assert_eq!(world.resource::<Health>(), Some(&Health(5)));
+        assert!(!world.has_resource::<Name>());
+        // Resources are not components
+        assert_eq!(world.query::<Health>().count(), 0);
+
+        assert_eq!(world.remove_resource::<Health>(), Some(Health(5)));
+        assert!(!world.has_resource::<Health>());
+    }
+
+    #[test]
+    fn despawn_removes_components() {
+        let mut world = World::new();
+        let entity = world.spawn_with(Health(1));
+        assert!(world.despawn(entity));
+        assert!(!world.despawn(entity));
+        assert!(world.is_empty());
+
+        // The index is reused, the old components must be gone
+        let reused = world.spawn();
+        assert_eq!(reused.index(), entity.index());
+        assert!(!world.has::<Health>(reused));
+        assert!(matches!(
+            world.insert(entity, Health(1)),
+            Err(Error::NoSuchEntity(_))
+        ));
+    }
+}
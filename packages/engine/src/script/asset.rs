@@ -0,0 +1,40 @@
+use crate::asset::{AssetLoader, LoadContext};
+
+/// Source code of a Rhai script.
+///
+/// _It is compiled by the `ScriptHost`, syntax errors are reported with a
+/// `ScriptEvent::Failed` once it runs._
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    source: String,
+}
+
+impl Script {
+    pub fn new<S: Into<String>>(source: S) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Loads `.rhai` files as a `Script`.
+#[derive(Debug, Default)]
+pub struct ScriptLoader;
+
+impl AssetLoader for ScriptLoader {
+    type Asset = Script;
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+
+    fn load(&self, bytes: &[u8], _: LoadContext) -> Result<Script, String> {
+        String::from_utf8(bytes.to_vec())
+            .map(Script::new)
+            .map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,303 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use rhai::{
+    serde::{from_dynamic, to_dynamic},
+    Array, Dynamic, EvalAltResult, FnPtr, INT,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::ScriptId;
+use crate::{
+    core::event::ScriptEvent,
+    scene::{self, Entity, World},
+};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Type erased access to a component registered by name.
+#[derive(Clone, Copy)]
+pub(crate) struct ComponentBinding {
+    get: fn(&World, Entity) -> Option<Result<Dynamic, String>>,
+    set: fn(&mut World, Entity, &Dynamic) -> Result<(), String>,
+    remove: fn(&mut World, Entity) -> bool,
+    query: fn(&World) -> Vec<Entity>,
+}
+
+impl ComponentBinding {
+    pub fn new<T>() -> Self
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        Self {
+            get: |world, entity| world.get::<T>(entity).map(convert),
+            set: |world, entity, value| {
+                let component = parse::<T>(value)?;
+                world
+                    .insert(entity, component)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            },
+            remove: |world, entity| world.remove::<T>(entity).is_some(),
+            query: |world| {
+                world.query::<T>().map(|(entity, _)| entity).collect()
+            },
+        }
+    }
+}
+
+/// Type erased access to a resource registered by name.
+#[derive(Clone, Copy)]
+pub(crate) struct ResourceBinding {
+    get: fn(&World) -> Option<Result<Dynamic, String>>,
+    set: fn(&mut World, &Dynamic) -> Result<(), String>,
+}
+
+impl ResourceBinding {
+    pub fn new<T>() -> Self
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        Self {
+            get: |world| world.resource::<T>().map(convert),
+            set: |world, value| {
+                world.insert_resource(parse::<T>(value)?);
+                Ok(())
+            },
+        }
+    }
+}
+
+fn convert<T: Serialize>(value: &T) -> Result<Dynamic, String> {
+    to_dynamic(value).map_err(|err| err.to_string())
+}
+
+/// Goes through JSON, Rhai only has `f64` floats and would refuse to turn
+/// them into `f32`.
+fn parse<T: DeserializeOwned>(value: &Dynamic) -> Result<T, String> {
+    from_dynamic::<serde_json::Value>(value)
+        .map_err(|err| err.to_string())
+        .and_then(|value| {
+            serde_json::from_value(value).map_err(|err| err.to_string())
+        })
+}
+
+/// Event callback registered by a script with `on()`.
+#[derive(Clone)]
+pub(crate) struct Callback {
+    pub script: ScriptId,
+    pub event: String,
+    pub function: FnPtr,
+}
+
+/// Everything the bound functions can reach.
+///
+/// _The `World` is moved in only while scripts run._
+#[derive(Default)]
+pub(crate) struct State {
+    pub world: World,
+    pub components: BTreeMap<String, ComponentBinding>,
+    pub resources: BTreeMap<String, ResourceBinding>,
+    pub callbacks: Vec<Callback>,
+    /// Most callbacks a single script can register.
+    pub max_callbacks: usize,
+    /// Script being run, owner of the callbacks registered with `on()`.
+    pub current: Option<ScriptId>,
+    /// Events emitted since the last dispatch.
+    pub emitted: Vec<ScriptEvent>,
+}
+
+impl State {
+    fn component(&self, name: &str) -> ScriptResult<ComponentBinding> {
+        self.components.get(name).copied().ok_or_else(|| {
+            scene::Error::UnknownComponent(name.to_string())
+                .to_string()
+                .into()
+        })
+    }
+
+    fn resource(&self, name: &str) -> ScriptResult<ResourceBinding> {
+        self.resources.get(name).copied().ok_or_else(|| {
+            scene::Error::UnknownResource(name.to_string())
+                .to_string()
+                .into()
+        })
+    }
+}
+
+pub(crate) type SharedState = Rc<RefCell<State>>;
+
+/// Registers the `Entity` type and every function of the scripting API.
+pub(crate) fn register(engine: &mut rhai::Engine, state: &SharedState) {
+    engine
+        .register_type_with_name::<Entity>("Entity")
+        .register_fn("to_string", |entity: &mut Entity| entity.to_string())
+        .register_fn("to_debug", |entity: &mut Entity| entity.to_string())
+        .register_fn("==", |a: Entity, b: Entity| a == b)
+        .register_fn("!=", |a: Entity, b: Entity| a != b)
+        .register_get("index", |entity: &mut Entity| entity.index() as INT);
+
+    register_entities(engine, state);
+    register_components(engine, state);
+    register_resources(engine, state);
+    register_events(engine, state);
+}
+
+fn register_entities(engine: &mut rhai::Engine, state: &SharedState) {
+    let shared = state.clone();
+    engine
+        .register_fn("spawn_entity", move || shared.borrow_mut().world.spawn());
+
+    let shared = state.clone();
+    engine.register_fn("despawn_entity", move |entity: Entity| {
+        shared.borrow_mut().world.despawn(entity)
+    });
+
+    let shared = state.clone();
+    engine.register_fn("is_alive", move |entity: Entity| {
+        shared.borrow().world.is_alive(entity)
+    });
+
+    let shared = state.clone();
+    engine.register_fn("entities", move || {
+        shared
+            .borrow()
+            .world
+            .entities()
+            .map(Dynamic::from)
+            .collect::<Array>()
+    });
+}
+
+fn register_components(engine: &mut rhai::Engine, state: &SharedState) {
+    let shared = state.clone();
+    engine.register_fn(
+        "component",
+        move |entity: Entity, name: &str| -> ScriptResult<Dynamic> {
+            let state = shared.borrow();
+            let binding = state.component(name)?;
+            match (binding.get)(&state.world, entity) {
+                Some(value) => Ok(value?),
+                None => Ok(Dynamic::UNIT),
+            }
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn(
+        "set_component",
+        move |entity: Entity, name: &str, value: Dynamic| -> ScriptResult<()> {
+            let mut state = shared.borrow_mut();
+            let binding = state.component(name)?;
+            (binding.set)(&mut state.world, entity, &value)
+                .map_err(|err| format!("Invalid '{name}': {err}").into())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn(
+        "has_component",
+        move |entity: Entity, name: &str| -> ScriptResult<bool> {
+            let state = shared.borrow();
+            let binding = state.component(name)?;
+            Ok((binding.get)(&state.world, entity).is_some())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn(
+        "remove_component",
+        move |entity: Entity, name: &str| -> ScriptResult<bool> {
+            let mut state = shared.borrow_mut();
+            let binding = state.component(name)?;
+            Ok((binding.remove)(&mut state.world, entity))
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn("query", move |name: &str| -> ScriptResult<Array> {
+        let state = shared.borrow();
+        let binding = state.component(name)?;
+        Ok((binding.query)(&state.world)
+            .into_iter()
+            .map(Dynamic::from)
+            .collect())
+    });
+}
+
+fn register_resources(engine: &mut rhai::Engine, state: &SharedState) {
+    let shared = state.clone();
+    engine.register_fn(
+        "resource",
+        move |name: &str| -> ScriptResult<Dynamic> {
+            let state = shared.borrow();
+            let binding = state.resource(name)?;
+            match (binding.get)(&state.world) {
+                Some(value) => Ok(value?),
+                None => Ok(Dynamic::UNIT),
+            }
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn(
+        "set_resource",
+        move |name: &str, value: Dynamic| -> ScriptResult<()> {
+            let mut state = shared.borrow_mut();
+            let binding = state.resource(name)?;
+            (binding.set)(&mut state.world, &value)
+                .map_err(|err| format!("Invalid '{name}': {err}").into())
+        },
+    );
+}
+
+fn register_events(engine: &mut rhai::Engine, state: &SharedState) {
+    let shared = state.clone();
+    engine.register_fn("emit", move |name: &str| {
+        shared.borrow_mut().emitted.push(ScriptEvent::Emitted(
+            name.to_string(),
+            serde_json::Value::Null,
+        ));
+    });
+
+    let shared = state.clone();
+    engine.register_fn(
+        "emit",
+        move |name: &str, data: Dynamic| -> ScriptResult<()> {
+            let data = from_dynamic::<serde_json::Value>(&data)?;
+            shared
+                .borrow_mut()
+                .emitted
+                .push(ScriptEvent::Emitted(name.to_string(), data));
+            Ok(())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn(
+        "on",
+        move |event: &str, function: FnPtr| -> ScriptResult<()> {
+            let mut state = shared.borrow_mut();
+            let script = state
+                .current
+                .ok_or("Callbacks can only be registered by a script")?;
+            let registered = state
+                .callbacks
+                .iter()
+                .filter(|callback| callback.script == script)
+                .count();
+            if registered >= state.max_callbacks {
+                return Err(format!(
+                    "Scripts can register at most {} callbacks",
+                    state.max_callbacks
+                )
+                .into());
+            }
+            state.callbacks.push(Callback {
+                script,
+                event: event.to_string(),
+                function,
+            });
+            Ok(())
+        },
+    );
+}
@@ -0,0 +1,613 @@
+use std::collections::BTreeMap;
+
+use rhai::{
+    module_resolvers::DummyModuleResolver, serde::to_dynamic, Array,
+    CallFnOptions, Dynamic, Scope, AST, FLOAT, INT,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use winit::keyboard::PhysicalKey;
+
+use super::{
+    bindings::{self, ComponentBinding, ResourceBinding, SharedState},
+    Error, Script, ScriptId, ScriptLimits,
+};
+use crate::{
+    asset::{AssetServer, Handle},
+    core::event::{
+        AnimationEvent, AssetEvent, Dispatcher, Event, KeyboardEvent,
+        MouseEvent, NetworkEvent, PhysicsEvent, ScriptEvent,
+    },
+    math::Transform,
+    scene::World,
+};
+
+struct Instance {
+    handle: Handle<Script>,
+    ast: Option<AST>,
+    /// Flags if the script defines `update(dt)`.
+    has_update: bool,
+    /// Compiles and runs the script again on the next update.
+    outdated: bool,
+}
+
+/// Runs scripts and delivers events to the callbacks they registered.
+///
+/// Scripts run once their asset is loaded, a reloaded asset replaces the
+/// callbacks of the previous version. Errors are reported with
+/// `ScriptEvent::Failed`, a script that fails to compile keeps running its
+/// previous version.
+pub struct ScriptHost {
+    engine: rhai::Engine,
+    limits: ScriptLimits,
+    state: SharedState,
+    scripts: BTreeMap<ScriptId, Instance>,
+    next_id: u64,
+    /// Events waiting for the callbacks, with their script name and
+    /// arguments.
+    events: Vec<(String, Array)>,
+}
+
+impl Default for ScriptHost {
+    fn default() -> Self {
+        Self::new(ScriptLimits::default())
+    }
+}
+
+impl ScriptHost {
+    /// Creates a new host, `Transform` is already registered.
+    pub fn new(limits: ScriptLimits) -> Self {
+        let state = SharedState::default();
+
+        let mut engine = rhai::Engine::new();
+        // Scripts can only reach what is registered below
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine.disable_symbol("eval");
+        engine.on_print(|text| log::info!(target: "script", "{text}"));
+        engine.on_debug(|text, source, position| {
+            log::debug!(
+                target: "script",
+                "{}:{position}: {text}",
+                source.unwrap_or("script")
+            )
+        });
+        bindings::register(&mut engine, &state);
+
+        let mut host = Self {
+            engine,
+            limits,
+            state,
+            scripts: BTreeMap::new(),
+            next_id: 0,
+            events: Vec::new(),
+        };
+        host.set_limits(limits);
+        host.register_component::<Transform>("Transform");
+
+        host
+    }
+
+    pub fn limits(&self) -> ScriptLimits {
+        self.limits
+    }
+
+    /// Changes the limits, they apply from the next call.
+    pub fn set_limits(&mut self, limits: ScriptLimits) {
+        self.engine
+            .set_max_operations(limits.max_operations)
+            .set_max_call_levels(limits.max_call_depth)
+            .set_max_string_size(limits.max_string_size)
+            .set_max_array_size(limits.max_array_size)
+            .set_max_map_size(limits.max_map_size);
+        self.state.borrow_mut().max_callbacks = limits.max_callbacks;
+        self.limits = limits;
+    }
+
+    /// Makes a component available to scripts under the passed name.
+    pub fn register_component<T>(&mut self, name: &str)
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        let binding = ComponentBinding::new::<T>();
+        let mut state = self.state.borrow_mut();
+        if state.components.insert(name.to_string(), binding).is_some() {
+            log::warn!("Replaced script component binding '{name}'");
+        }
+    }
+
+    /// Makes a resource available to scripts under the passed name.
+    pub fn register_resource<T>(&mut self, name: &str)
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        let binding = ResourceBinding::new::<T>();
+        let mut state = self.state.borrow_mut();
+        if state.resources.insert(name.to_string(), binding).is_some() {
+            log::warn!("Replaced script resource binding '{name}'");
+        }
+    }
+
+    /// Adds a script, it runs on the first update after it is loaded.
+    pub fn add(&mut self, script: Handle<Script>) -> ScriptId {
+        let id = ScriptId(self.next_id);
+        self.next_id += 1;
+        self.scripts.insert(
+            id,
+            Instance {
+                handle: script,
+                ast: None,
+                has_update: false,
+                outdated: true,
+            },
+        );
+        id
+    }
+
+    /// Stops running the script and drops its callbacks.
+    pub fn remove(&mut self, id: ScriptId) -> Result<(), Error> {
+        self.scripts.remove(&id).ok_or(Error::NoSuchScript(id))?;
+        self.state
+            .borrow_mut()
+            .callbacks
+            .retain(|callback| callback.script != id);
+        Ok(())
+    }
+
+    pub fn contains(&self, id: ScriptId) -> bool {
+        self.scripts.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.scripts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// Queues the event for the callbacks and reruns the reloaded scripts.
+    ///
+    /// _The `Engine` does this for every event._
+    pub fn queue_event(&mut self, event: &Event) {
+        if let Event::Asset(AssetEvent::Reloaded(id, _)) = event {
+            for instance in self.scripts.values_mut() {
+                if instance.handle.id() == *id {
+                    instance.outdated = true;
+                }
+            }
+        }
+
+        if self.state.borrow().callbacks.is_empty() {
+            return;
+        }
+        if let Some(event) = script_event(event) {
+            self.events.push(event);
+        }
+    }
+
+    /// Runs the loaded scripts, the queued callbacks and then the `update`
+    /// function of every script.
+    ///
+    /// _The `Engine` does this on every step._
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        assets: &AssetServer,
+        dispatcher: &Dispatcher,
+        delta: f32,
+    ) {
+        crate::profile_scope!("ScriptHost::update", "script");
+
+        std::mem::swap(world, &mut self.state.borrow_mut().world);
+        self.run_outdated(assets);
+        self.run_callbacks();
+        self.run_updates(delta);
+        std::mem::swap(world, &mut self.state.borrow_mut().world);
+
+        let mut state = self.state.borrow_mut();
+        state.current = None;
+        for event in state.emitted.drain(..) {
+            dispatcher.send(Event::Script(event));
+        }
+    }
+
+    /// Compiles the new and reloaded scripts and runs their top level.
+    fn run_outdated(&mut self, assets: &AssetServer) {
+        for (id, instance) in &mut self.scripts {
+            if !instance.outdated {
+                continue;
+            }
+            // Still loading
+            let Some(script) = assets.get(&instance.handle) else {
+                continue;
+            };
+            instance.outdated = false;
+
+            let ast = match self.engine.compile(script.source()) {
+                Ok(ast) => ast,
+                Err(err) => {
+                    report(&self.state, *id, Error::Compile(err.to_string()));
+                    continue;
+                }
+            };
+            {
+                let mut state = self.state.borrow_mut();
+                state.callbacks.retain(|callback| callback.script != *id);
+                state.current = Some(*id);
+            }
+            instance.has_update = ast
+                .iter_functions()
+                .any(|function| function.name == "update");
+            let result = self.engine.run_ast(&ast);
+            instance.ast = Some(ast);
+
+            if let Err(err) = result {
+                report(&self.state, *id, Error::Runtime(err.to_string()));
+            }
+        }
+    }
+
+    /// Calls the callbacks registered for the queued events.
+    fn run_callbacks(&mut self) {
+        for (name, args) in std::mem::take(&mut self.events) {
+            let callbacks: Vec<_> = self
+                .state
+                .borrow()
+                .callbacks
+                .iter()
+                .filter(|callback| callback.event == name)
+                .cloned()
+                .collect();
+
+            for callback in callbacks {
+                let Some(ast) = self
+                    .scripts
+                    .get(&callback.script)
+                    .and_then(|instance| instance.ast.as_ref())
+                else {
+                    continue;
+                };
+                self.state.borrow_mut().current = Some(callback.script);
+                if let Err(err) = callback.function.call::<Dynamic>(
+                    &self.engine,
+                    ast,
+                    args.clone(),
+                ) {
+                    let err = Error::Runtime(format!("'{name}': {err}"));
+                    report(&self.state, callback.script, err);
+                }
+            }
+        }
+    }
+
+    /// Calls `update(dt)` on every script that defines it.
+    fn run_updates(&mut self, delta: f32) {
+        for (id, instance) in &self.scripts {
+            let Some(ast) =
+                instance.ast.as_ref().filter(|_| instance.has_update)
+            else {
+                continue;
+            };
+            self.state.borrow_mut().current = Some(*id);
+            // The top level already ran when the script was loaded
+            let options = CallFnOptions::new().eval_ast(false);
+            if let Err(err) = self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                ast,
+                "update",
+                (delta as FLOAT,),
+            ) {
+                report(&self.state, *id, Error::Runtime(err.to_string()));
+            }
+        }
+    }
+}
+
+fn report(state: &SharedState, id: ScriptId, err: Error) {
+    state
+        .borrow_mut()
+        .emitted
+        .push(ScriptEvent::Failed(id, err.to_string()));
+}
+
+/// Returns the name of the event seen by scripts and the arguments passed to
+/// its callbacks, `None` for events not forwarded to scripts.
+fn script_event(event: &Event) -> Option<(String, Array)> {
+    let id = |id: u64| Dynamic::from(id as INT);
+    let text = |text: String| Dynamic::from(text);
+
+    let (kind, args) = match event {
+        Event::Engine(event) => (event.to_string(), Vec::new()),
+        Event::Keyboard(_, event) => match event {
+            KeyboardEvent::Pressed(PhysicalKey::Code(key))
+            | KeyboardEvent::Released(PhysicalKey::Code(key)) => {
+                (event.to_string(), vec![text(format!("{key:?}"))])
+            }
+            KeyboardEvent::Text(typed) => {
+                (event.to_string(), vec![text(typed.clone())])
+            }
+            _ => return None,
+        },
+        Event::Mouse(_, event) => match event {
+            MouseEvent::Pressed(button) | MouseEvent::Released(button) => {
+                (event.to_string(), vec![text(format!("{button:?}"))])
+            }
+            MouseEvent::Moved(delta) | MouseEvent::Scrolled(delta) => (
+                event.to_string(),
+                vec![(delta.x as FLOAT).into(), (delta.y as FLOAT).into()],
+            ),
+        },
+        Event::Asset(event) => match event {
+            AssetEvent::Loaded(asset, path)
+            | AssetEvent::Reloaded(asset, path) => (
+                event.to_string(),
+                vec![id(asset.0), text(path.display().to_string())],
+            ),
+            AssetEvent::LoadFailed(asset, path, err) => (
+                event.to_string(),
+                vec![
+                    id(asset.0),
+                    text(path.display().to_string()),
+                    text(err.clone()),
+                ],
+            ),
+            AssetEvent::Unloaded(asset) => {
+                (event.to_string(), vec![id(asset.0)])
+            }
+        },
+        Event::Physics(event) => match event {
+            PhysicsEvent::CollisionStarted(a, b)
+            | PhysicsEvent::CollisionEnded(a, b)
+            | PhysicsEvent::SensorEntered(a, b)
+            | PhysicsEvent::SensorExited(a, b) => {
+                (event.to_string(), vec![id(a.0.into()), id(b.0.into())])
+            }
+        },
+        Event::Animation(event) => match event {
+            AnimationEvent::Finished(animation) => {
+                (event.to_string(), vec![id(animation.0)])
+            }
+        },
+        // Emitted events keep their name
+        Event::Script(ScriptEvent::Emitted(name, data)) => {
+            let args = match data {
+                serde_json::Value::Null => Vec::new(),
+                data => vec![to_dynamic(data).ok()?],
+            };
+            return Some((name.clone(), args));
+        }
+        Event::Script(event @ ScriptEvent::Failed(script, err)) => {
+            (event.to_string(), vec![id(script.0), text(err.clone())])
+        }
+        Event::Network(event) => match event {
+            NetworkEvent::Connected(connection) => {
+                (event.to_string(), vec![id(connection.0)])
+            }
+            NetworkEvent::Disconnected(connection, reason) => (
+                event.to_string(),
+                vec![id(connection.0), text(reason.to_string())],
+            ),
+            NetworkEvent::Received(connection, channel, bytes) => (
+                event.to_string(),
+                vec![
+                    id(connection.0),
+                    text(channel.to_string()),
+                    Dynamic::from_blob(bytes.clone()),
+                ],
+            ),
+        },
+        Event::Window(..) => return None,
+        #[cfg(test)]
+        Event::Dummy => return None,
+    };
+
+    Some((format!("{event}.{kind}"), args))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        asset::FileSource,
+        core::event::{self, Consumer},
+        math::Vec3,
+        physics::BodyId,
+    };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Health(i32);
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Score {
+        points: i32,
+    }
+
+    struct Setup {
+        host: ScriptHost,
+        assets: AssetServer,
+        world: World,
+        dispatcher: Dispatcher,
+        consumer: Consumer,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let (dispatcher, consumer) = event::create_handler();
+            let mut host = ScriptHost::default();
+            host.register_component::<Health>("Health");
+            host.register_resource::<Score>("Score");
+            let mut world = World::new();
+            world.insert_resource(Score::default());
+
+            Self {
+                host,
+                assets: AssetServer::new(FileSource::new(".")),
+                world,
+                dispatcher,
+                consumer,
+            }
+        }
+
+        fn add(&mut self, source: &str) -> ScriptId {
+            let script = self.assets.add(Script::new(source));
+            self.host.add(script)
+        }
+
+        fn update(&mut self) {
+            self.host.update(
+                &mut self.world,
+                &self.assets,
+                &self.dispatcher,
+                0.5,
+            );
+        }
+
+        fn events(&self) -> Vec<ScriptEvent> {
+            std::iter::from_fn(|| self.consumer.poll())
+                .filter_map(|event| match event {
+                    Event::Script(event) => Some(event),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn scripts_modify_the_world() {
+        let mut setup = Setup::new();
+        let enemy = setup.world.spawn_with(Health(10));
+        setup.add(
+            r#"
+            let player = spawn_entity();
+            set_component(player, "Transform", #{ translation: [0.0, 1.0, 0.0] });
+
+            fn update(dt) {
+                for entity in query("Health") {
+                    let health = component(entity, "Health") - 8 * dt;
+                    if health > 0 {
+                        set_component(entity, "Health", health.to_int());
+                        continue;
+                    }
+                    despawn_entity(entity);
+                    let score = resource("Score");
+                    score.points += 1;
+                    set_resource("Score", score);
+                }
+            }
+            "#,
+        );
+
+        setup.update();
+        assert_eq!(setup.world.get::<Health>(enemy), Some(&Health(6)));
+        let (_, transform) = setup.world.query::<Transform>().next().unwrap();
+        assert_eq!(transform.translation, Vec3::Y);
+
+        setup.update();
+        setup.update();
+        assert!(!setup.world.is_alive(enemy));
+        assert_eq!(setup.world.len(), 1);
+        assert_eq!(setup.world.resource(), Some(&Score { points: 1 }));
+        assert_eq!(setup.events(), Vec::new());
+    }
+
+    #[test]
+    fn callbacks_receive_events() {
+        let mut setup = Setup::new();
+        setup.add(
+            r#"
+            on("Physics.CollisionStarted", |a, b| emit("hit", #{ bodies: [a, b] }));
+            on("damage", |data| {
+                let score = resource("Score");
+                score.points += data.amount;
+                set_resource("Score", score);
+            });
+            on("Engine.Started", || emit("ready"));
+            "#,
+        );
+        setup.update();
+
+        for event in [
+            Event::Physics(PhysicsEvent::CollisionStarted(
+                BodyId(1),
+                BodyId(2),
+            )),
+            Event::Script(ScriptEvent::Emitted(
+                "damage".to_string(),
+                json!({ "amount": 5 }),
+            )),
+            Event::Engine(event::EngineEvent::Started),
+        ] {
+            setup.host.queue_event(&event);
+        }
+        setup.update();
+
+        assert_eq!(setup.world.resource(), Some(&Score { points: 5 }));
+        assert_eq!(
+            setup.events(),
+            vec![
+                ScriptEvent::Emitted(
+                    "hit".to_string(),
+                    json!({ "bodies": [1, 2] })
+                ),
+                ScriptEvent::Emitted("ready".to_string(), json!(null)),
+            ]
+        );
+    }
+
+    #[test]
+    fn failures_are_sandboxed_and_reported() {
+        let mut setup = Setup::new();
+        setup.host.set_limits(ScriptLimits {
+            max_operations: 10_000,
+            max_string_size: 256,
+            ..Default::default()
+        });
+        let endless = setup.add("loop {}");
+        let greedy = setup.add(r#"let text = "a"; loop { text += text; }"#);
+        let import = setup.add(r#"import "other" as other;"#);
+        let broken = setup.add("let = 1;");
+        let unknown = setup.add(r#"query("Mana");"#);
+        let fine = setup.add(r#"emit("fine");"#);
+        setup.update();
+
+        let events = setup.events();
+        let failures: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                ScriptEvent::Failed(id, err) => Some((*id, err.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            failures.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![endless, greedy, import, broken, unknown]
+        );
+        assert!(failures[0].1.contains("Too many operations"));
+        assert!(failures[3].1.starts_with("Failed to compile"));
+        assert!(failures[4].1.contains("Unknown component 'Mana'"));
+        assert_eq!(
+            events.last(),
+            Some(&ScriptEvent::Emitted("fine".to_string(), json!(null)))
+        );
+
+        // Registering in a loop stops at the limit
+        setup.host.set_limits(ScriptLimits {
+            max_callbacks: 4,
+            ..Default::default()
+        });
+        let hungry = setup.add(r#"loop { on("damage", || ()); }"#);
+        setup.update();
+        assert!(matches!(
+            setup.events().last(),
+            Some(ScriptEvent::Failed(id, err))
+                if *id == hungry && err.contains("at most 4 callbacks")
+        ));
+        assert_eq!(setup.host.state.borrow().callbacks.len(), 4);
+
+        assert_eq!(setup.host.remove(fine), Ok(()));
+        assert_eq!(setup.host.remove(fine), Err(Error::NoSuchScript(fine)));
+    }
+}
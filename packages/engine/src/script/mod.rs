@@ -0,0 +1,135 @@
+//! Gameplay scripting with [Rhai](https://rhai.rs).
+//!
+//! Scripts are assets loaded from `.rhai` files and run by the `ScriptHost`
+//! during `Engine::step()`: the top level of a script runs once when it is
+//! loaded (and again after each reload), then its `update(dt)` function, if
+//! defined, runs on every step.
+//!
+//! Scripts reach the `World` through a small set of functions:
+//!
+//! | Function                          | Description                      |
+//! |-----------------------------------|----------------------------------|
+//! | `spawn_entity()`                  | Creates an empty entity          |
+//! | `despawn_entity(e)`               |                                  |
+//! | `is_alive(e)`, `entities()`       |                                  |
+//! | `component(e, name)`              | A component, `()` if missing     |
+//! | `set_component(e, name, value)`   | Adds or replaces a component     |
+//! | `has_component(e, name)`          |                                  |
+//! | `remove_component(e, name)`       |                                  |
+//! | `query(name)`                     | Entities with the component      |
+//! | `resource(name)`                  | A resource, `()` if missing      |
+//! | `set_resource(name, value)`       | Adds or replaces a resource      |
+//! | `emit(name)`, `emit(name, data)`  | Dispatches a `ScriptEvent`       |
+//! | `on(name, callback)`              | Calls back on matching events    |
+//!
+//! Components and resources are converted with `serde`, so only the types
+//! registered by name in the `ScriptHost` are visible (`Transform` is
+//! registered by default). Events are named `Category.Variant` (e.g.
+//! `Physics.CollisionStarted`), the ones emitted by scripts keep their name.
+//!
+//! Every call is sandboxed: it can not import modules or touch the
+//! filesystem, and the `ScriptLimits` stop it after too many operations or
+//! when strings, arrays and maps grow too large.
+//!
+//! ```
+//! # use unen_engine::{core::engine::Engine, math::Transform};
+//! # use unen_engine::script::Script;
+//! let mut engine = Engine::default();
+//! let script = engine.assets_mut().add(Script::new(
+//!     r#"
+//!     let player = spawn_entity();
+//!     set_component(player, "Transform", #{ translation: [1.0, 2.0, 0.0] });
+//!
+//!     fn update(dt) {
+//!         for entity in query("Transform") {
+//!             let transform = component(entity, "Transform");
+//!             transform.translation[0] += 10.0 * dt;
+//!             set_component(entity, "Transform", transform);
+//!         }
+//!     }
+//!     "#,
+//! ));
+//! engine.scripts_mut().add(script);
+//! engine.step();
+//!
+//! let (_, transform) = engine.world().query::<Transform>().next().unwrap();
+//! assert!(transform.translation.x >= 1.0);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod asset;
+mod bindings;
+pub mod host;
+
+pub use asset::{Script, ScriptLoader};
+pub use host::ScriptHost;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The script was removed or never existed.
+    #[error("No such script '{0}'")]
+    NoSuchScript(ScriptId),
+    /// The source has a syntax error.
+    #[error("Failed to compile script: {0}")]
+    Compile(String),
+    /// The script raised an error or hit one of the `ScriptLimits`.
+    #[error("Script error: {0}")]
+    Runtime(String),
+}
+
+/// Identifies a script added to a `ScriptHost`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct ScriptId(pub(crate) u64);
+
+impl std::fmt::Display for ScriptId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Script({})", self.0)
+    }
+}
+
+/// Sandboxing limits applied to every script call.
+///
+/// _Rhai does not track memory, the size limits are what keeps scripts from
+/// allocating without bounds._
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptLimits {
+    /// Operations a single call can run before being stopped.
+    pub max_operations: u64,
+    /// Deepest function call nesting.
+    pub max_call_depth: usize,
+    /// Longest string in bytes.
+    pub max_string_size: usize,
+    /// Most elements in an array.
+    pub max_array_size: usize,
+    /// Most properties in an object map.
+    pub max_map_size: usize,
+    /// Most callbacks a script can register with `on()`.
+    pub max_callbacks: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            max_call_depth: 64,
+            max_string_size: 64 * 1024,
+            max_array_size: 16 * 1024,
+            max_map_size: 1024,
+            max_callbacks: 256,
+        }
+    }
+}
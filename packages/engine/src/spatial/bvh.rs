@@ -0,0 +1,449 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{
+    math::{Aabb, Ray, Vec3},
+    scene::Entity,
+};
+
+use super::{push_nearest, sort_hits, SpatialIndex};
+
+const NULL: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct Node {
+    /// Padded bounds for leaves, union of the children otherwise.
+    aabb: Aabb,
+    parent: usize,
+    children: [usize; 2],
+    /// Exact bounds, only set on leaves.
+    leaf: Option<(Entity, Aabb)>,
+}
+
+/// Dynamic bounding volume hierarchy.
+///
+/// Leaves store padded bounds, moving an entity within them only updates
+/// its exact bounds. Otherwise the leaf is reinserted next to the sibling
+/// that grows the tree the least.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: usize,
+    leaves: HashMap<Entity, usize>,
+    margin: f32,
+}
+
+impl Default for Bvh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bvh {
+    pub fn new() -> Self {
+        Self::with_margin(0.1)
+    }
+
+    /// Bounds are padded by `margin` on every side, bigger margins mean
+    /// fewer reinsertions but looser queries.
+    pub fn with_margin(margin: f32) -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NULL,
+            leaves: HashMap::new(),
+            margin: margin.max(0.0),
+        }
+    }
+
+    /// Height of the tree, `0` when empty.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut stack = vec![(self.root, 1)];
+        while let Some((index, level)) = stack.pop() {
+            if index == NULL {
+                continue;
+            }
+            depth = depth.max(level);
+            stack.extend(
+                self.nodes[index].children.map(|child| (child, level + 1)),
+            );
+        }
+        depth
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL;
+            return;
+        }
+
+        // Walk down towards the cheapest sibling
+        let aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while self.nodes[index].leaf.is_none() {
+            let node = &self.nodes[index];
+            let area = node.aabb.surface_area();
+            let combined = node.aabb.union(&aabb).surface_area();
+            // Creating a parent here
+            let cost = 2.0 * combined;
+            // Every ancestor grows by this if descending
+            let inheritance = 2.0 * (combined - area);
+
+            let child_cost = |child: usize| {
+                let child = &self.nodes[child];
+                let grown = child.aabb.union(&aabb).surface_area();
+                match child.leaf {
+                    Some(_) => grown + inheritance,
+                    None => grown - child.aabb.surface_area() + inheritance,
+                }
+            };
+            let [left, right] = node.children;
+            let (cost_left, cost_right) = (child_cost(left), child_cost(right));
+
+            if cost < cost_left && cost < cost_right {
+                break;
+            }
+            index = if cost_left < cost_right { left } else { right };
+        }
+
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let parent = self.allocate(Node {
+            aabb: aabb.union(&self.nodes[sibling].aabb),
+            parent: old_parent,
+            children: [sibling, leaf],
+            leaf: None,
+        });
+        self.nodes[sibling].parent = parent;
+        self.nodes[leaf].parent = parent;
+
+        if old_parent == NULL {
+            self.root = parent;
+        } else {
+            let children = &mut self.nodes[old_parent].children;
+            let slot = (children[1] == sibling) as usize;
+            children[slot] = parent;
+        }
+
+        self.refit(old_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grandparent = self.nodes[parent].parent;
+        let [left, right] = self.nodes[parent].children;
+        let sibling = if left == leaf { right } else { left };
+
+        if grandparent == NULL {
+            self.root = sibling;
+            self.nodes[sibling].parent = NULL;
+        } else {
+            let children = &mut self.nodes[grandparent].children;
+            let slot = (children[1] == parent) as usize;
+            children[slot] = sibling;
+            self.nodes[sibling].parent = grandparent;
+            self.refit(grandparent);
+        }
+        self.free.push(parent);
+    }
+
+    /// Recomputes the bounds from the node up to the root.
+    fn refit(&mut self, mut index: usize) {
+        while index != NULL {
+            let [left, right] = self.nodes[index].children;
+            self.nodes[index].aabb =
+                self.nodes[left].aabb.union(&self.nodes[right].aabb);
+            index = self.nodes[index].parent;
+        }
+    }
+
+    /// Visits the leaves whose ancestors pass the test.
+    fn visit<T, L>(&self, mut test: T, mut leaf: L)
+    where
+        T: FnMut(&Aabb) -> bool,
+        L: FnMut(Entity, &Aabb),
+    {
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            if index == NULL {
+                continue;
+            }
+            let node = &self.nodes[index];
+            if !test(&node.aabb) {
+                continue;
+            }
+            match &node.leaf {
+                Some((entity, bounds)) => leaf(*entity, bounds),
+                None => stack.extend(node.children),
+            }
+        }
+    }
+}
+
+impl SpatialIndex for Bvh {
+    fn insert(&mut self, entity: Entity, bounds: Aabb) -> Option<Aabb> {
+        if let Some(&leaf) = self.leaves.get(&entity) {
+            let node = &mut self.nodes[leaf];
+            let previous = node.leaf.replace((entity, bounds)).map(|(_, b)| b);
+            // Still inside the padding
+            if node.aabb.contains(&bounds) {
+                return previous;
+            }
+
+            node.aabb = bounds.inflate(self.margin);
+            self.remove_leaf(leaf);
+            self.insert_leaf(leaf);
+            return previous;
+        }
+
+        let leaf = self.allocate(Node {
+            aabb: bounds.inflate(self.margin),
+            parent: NULL,
+            children: [NULL; 2],
+            leaf: Some((entity, bounds)),
+        });
+        self.insert_leaf(leaf);
+        self.leaves.insert(entity, leaf);
+        None
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<Aabb> {
+        let leaf = self.leaves.remove(&entity)?;
+        self.remove_leaf(leaf);
+        self.free.push(leaf);
+        self.nodes[leaf].leaf.take().map(|(_, bounds)| bounds)
+    }
+
+    fn bounds(&self, entity: Entity) -> Option<Aabb> {
+        let leaf = self.leaves.get(&entity)?;
+        self.nodes[*leaf].leaf.map(|(_, bounds)| bounds)
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        let mut entities: Vec<_> = self.leaves.keys().copied().collect();
+        entities.sort();
+        entities
+    }
+
+    fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.leaves.clear();
+        self.root = NULL;
+    }
+
+    fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut found = Vec::new();
+        self.visit(
+            |node| node.intersects(aabb),
+            |entity, bounds| {
+                if bounds.intersects(aabb) {
+                    found.push(entity);
+                }
+            },
+        );
+        found.sort();
+        found
+    }
+
+    fn query_radius(&self, center: Vec3, radius: f32) -> Vec<Entity> {
+        let radius = radius * radius;
+        let mut found = Vec::new();
+        self.visit(
+            |node| node.distance_squared(center) <= radius,
+            |entity, bounds| {
+                if bounds.distance_squared(center) <= radius {
+                    found.push(entity);
+                }
+            },
+        );
+        found.sort();
+        found
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(Entity, f32)> {
+        let mut hits = Vec::new();
+        let hit = |aabb: &Aabb| {
+            ray.intersect_aabb(aabb)
+                .filter(|distance| *distance <= max_distance)
+        };
+        self.visit(
+            |node| hit(node).is_some(),
+            |entity, bounds| {
+                if let Some(distance) = hit(bounds) {
+                    hits.push((entity, distance));
+                }
+            },
+        );
+        sort_hits(&mut hits);
+        hits
+    }
+
+    fn raycast_first(
+        &self,
+        ray: &Ray,
+        max_distance: f32,
+    ) -> Option<(Entity, f32)> {
+        let mut best: Option<(Entity, f32)> = None;
+        let mut queue = BinaryHeap::from([Closest(0.0, self.root)]);
+        while let Some(Closest(distance, index)) = queue.pop() {
+            if index == NULL {
+                continue;
+            }
+            // Everything left is further away
+            if best.is_some_and(|(_, best)| distance > best) {
+                break;
+            }
+
+            let node = &self.nodes[index];
+            match &node.leaf {
+                Some((entity, bounds)) => {
+                    let Some(hit) = ray.intersect_aabb(bounds) else {
+                        continue;
+                    };
+                    if hit <= max_distance
+                        && best.is_none_or(|best| {
+                            (hit, *entity) < (best.1, best.0)
+                        })
+                    {
+                        best = Some((*entity, hit));
+                    }
+                }
+                None => {
+                    for child in node.children {
+                        let aabb = &self.nodes[child].aabb;
+                        if let Some(hit) = ray.intersect_aabb(aabb) {
+                            if hit <= max_distance {
+                                queue.push(Closest(hit, child));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn nearest(&self, point: Vec3, k: usize) -> Vec<(Entity, f32)> {
+        let mut nearest = Vec::with_capacity(k + 1);
+        if k == 0 || self.root == NULL {
+            return nearest;
+        }
+
+        let mut queue = BinaryHeap::from([Closest(0.0, self.root)]);
+        while let Some(Closest(distance, index)) = queue.pop() {
+            if nearest.len() == k && distance > nearest[k - 1].1 {
+                break;
+            }
+
+            let node = &self.nodes[index];
+            match &node.leaf {
+                Some((entity, bounds)) => {
+                    let distance = bounds.distance_squared(point).sqrt();
+                    push_nearest(&mut nearest, k, *entity, distance);
+                }
+                None => {
+                    for child in node.children {
+                        let aabb = &self.nodes[child].aabb;
+                        queue.push(Closest(
+                            aabb.distance_squared(point).sqrt(),
+                            child,
+                        ));
+                    }
+                }
+            }
+        }
+        nearest
+    }
+}
+
+/// Node at a distance, the closest is popped first from a `BinaryHeap`.
+struct Closest(f32, usize);
+
+impl PartialEq for Closest {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Closest {}
+
+impl PartialOrd for Closest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Closest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::tests::{assert_matches_linear, random_boxes};
+
+    #[test]
+    fn matches_brute_force() {
+        assert_matches_linear(Bvh::new());
+        assert_matches_linear(Bvh::with_margin(0.0));
+    }
+
+    #[test]
+    fn small_moves_keep_the_tree() {
+        let mut bvh = Bvh::with_margin(0.5);
+        let boxes = random_boxes(200, 3);
+        for (entity, bounds) in &boxes {
+            bvh.insert(*entity, *bounds);
+        }
+        // A balanced tree of 200 leaves is 9 levels deep
+        assert!(bvh.depth() < 20, "depth {}", bvh.depth());
+
+        let (entity, bounds) = boxes[0];
+        let nodes = bvh.nodes.clone();
+        let nudged = Aabb::new(bounds.min + 0.25, bounds.max + 0.25);
+        assert_eq!(bvh.insert(entity, nudged), Some(bounds));
+        assert_eq!(bvh.bounds(entity), Some(nudged));
+        assert!(bvh
+            .nodes
+            .iter()
+            .zip(&nodes)
+            .all(|(a, b)| a.aabb == b.aabb && a.parent == b.parent));
+
+        let moved = Aabb::new(bounds.min + 10.0, bounds.max + 10.0);
+        assert!(bvh.update(entity, moved));
+        assert_eq!(bvh.query_aabb(&moved).first(), Some(&entity));
+        assert!(!bvh.update(Entity::from_raw(999, 0), moved));
+        // Freed nodes get reused
+        assert_eq!(bvh.nodes.len(), nodes.len());
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    math::{Aabb, IVec3, Ray, Vec3},
+    scene::Entity,
+};
+
+use super::{push_nearest, sort_hits, SpatialIndex};
+
+/// Hashes bounds into cubic cells of the same size.
+///
+/// _Entities are stored in every cell they touch, objects much bigger than
+/// the cells are better kept in a `Bvh`._
+#[derive(Debug, Clone)]
+pub struct UniformGrid {
+    cell_size: f32,
+    cells: HashMap<IVec3, Vec<Entity>>,
+    entries: HashMap<Entity, Aabb>,
+    /// Contains every bounds ever inserted, limits the raycasts.
+    extent: Option<Aabb>,
+}
+
+impl Default for UniformGrid {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl UniformGrid {
+    /// Cells should be about the size of the most common objects.
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+            extent: None,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Amount of cells containing at least an entity.
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn cell(&self, point: Vec3) -> IVec3 {
+        (point / self.cell_size).floor().as_ivec3()
+    }
+
+    fn cells_of(&self, aabb: &Aabb) -> (IVec3, IVec3) {
+        (self.cell(aabb.min), self.cell(aabb.max))
+    }
+
+    /// Entities in the cells overlapping the box, sorted and without
+    /// duplicates.
+    fn candidates(&self, aabb: &Aabb) -> Vec<Entity> {
+        let (min, max) = self.cells_of(aabb);
+        let size = (max - min + 1).as_i64vec3();
+        let mut candidates: Vec<Entity> = if size.x * size.y * size.z
+            > self.cells.len() as i64
+        {
+            // Cheaper to look at what is there
+            self.entries.keys().copied().collect()
+        } else {
+            let mut candidates = Vec::new();
+            for z in min.z..=max.z {
+                for y in min.y..=max.y {
+                    for x in min.x..=max.x {
+                        if let Some(cell) = self.cells.get(&IVec3::new(x, y, z))
+                        {
+                            candidates.extend(cell);
+                        }
+                    }
+                }
+            }
+            candidates
+        };
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Every entity closer than `radius`, with its distance.
+    fn within(&self, center: Vec3, radius: f32) -> Vec<(Entity, f32)> {
+        let area = Aabb::from_center_size(center, Vec3::splat(radius * 2.0));
+        self.candidates(&area)
+            .into_iter()
+            .filter_map(|entity| {
+                let distance = self.entries[&entity].distance_squared(center);
+                (distance <= radius * radius)
+                    .then_some((entity, distance.sqrt()))
+            })
+            .collect()
+    }
+
+    /// Walks the cells crossed by the ray in order, passing their entities
+    /// and the distance where the ray leaves them until `visit` returns
+    /// `false`.
+    fn traverse<F>(&self, ray: &Ray, max_distance: f32, mut visit: F)
+    where
+        F: FnMut(&[Entity], f32) -> bool,
+    {
+        let Some(extent) = self.extent else {
+            return;
+        };
+        let Some(start) = ray.intersect_aabb(&extent) else {
+            return;
+        };
+        let inverse = ray.direction.recip();
+        let t1 = (extent.min - ray.origin) * inverse;
+        let t2 = (extent.max - ray.origin) * inverse;
+        let end = t1.max(t2).min_element().min(max_distance);
+
+        let mut cell = self.cell(ray.at(start));
+        let step = ray.direction.signum().as_ivec3();
+        let boundary = |cell: IVec3| {
+            (cell + step.max(IVec3::ZERO)).as_vec3() * self.cell_size
+        };
+        let mut next = Vec3::select(
+            ray.direction.cmpeq(Vec3::ZERO),
+            Vec3::INFINITY,
+            (boundary(cell) - ray.origin) * inverse,
+        );
+        let delta = (self.cell_size * inverse).abs();
+
+        loop {
+            let exit = next.min_element();
+            let entities = self.cells.get(&cell).map_or(&[][..], Vec::as_slice);
+            if !visit(entities, exit) || exit > end {
+                break;
+            }
+
+            let axis = if next.x == exit {
+                0
+            } else if next.y == exit {
+                1
+            } else {
+                2
+            };
+            cell[axis] += step[axis];
+            next[axis] += delta[axis];
+        }
+    }
+}
+
+impl SpatialIndex for UniformGrid {
+    fn insert(&mut self, entity: Entity, bounds: Aabb) -> Option<Aabb> {
+        let previous = self.entries.insert(entity, bounds);
+        let cells = self.cells_of(&bounds);
+        if let Some(previous) = previous {
+            // Still in the same cells
+            if self.cells_of(&previous) == cells {
+                return Some(previous);
+            }
+            self.remove_from_cells(entity, &previous);
+        }
+
+        let (min, max) = cells;
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    self.cells
+                        .entry(IVec3::new(x, y, z))
+                        .or_default()
+                        .push(entity);
+                }
+            }
+        }
+        self.extent =
+            Some(self.extent.map_or(bounds, |extent| extent.union(&bounds)));
+        previous
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<Aabb> {
+        let bounds = self.entries.remove(&entity)?;
+        self.remove_from_cells(entity, &bounds);
+        Some(bounds)
+    }
+
+    fn bounds(&self, entity: Entity) -> Option<Aabb> {
+        self.entries.get(&entity).copied()
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        let mut entities: Vec<_> = self.entries.keys().copied().collect();
+        entities.sort();
+        entities
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+        self.extent = None;
+    }
+
+    fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut found = self.candidates(aabb);
+        found.retain(|entity| self.entries[entity].intersects(aabb));
+        found
+    }
+
+    fn query_radius(&self, center: Vec3, radius: f32) -> Vec<Entity> {
+        self.within(center, radius)
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(Entity, f32)> {
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+        self.traverse(ray, max_distance, |entities, _| {
+            for entity in entities {
+                if !seen.insert(*entity) {
+                    continue;
+                }
+                if let Some(distance) =
+                    ray.intersect_aabb(&self.entries[entity])
+                {
+                    if distance <= max_distance {
+                        hits.push((*entity, distance));
+                    }
+                }
+            }
+            true
+        });
+        sort_hits(&mut hits);
+        hits
+    }
+
+    fn raycast_first(
+        &self,
+        ray: &Ray,
+        max_distance: f32,
+    ) -> Option<(Entity, f32)> {
+        let mut best: Option<(Entity, f32)> = None;
+        self.traverse(ray, max_distance, |entities, exit| {
+            for entity in entities {
+                let Some(distance) = ray.intersect_aabb(&self.entries[entity])
+                else {
+                    continue;
+                };
+                if distance <= max_distance
+                    && best.is_none_or(|best| {
+                        (distance, *entity) < (best.1, best.0)
+                    })
+                {
+                    best = Some((*entity, distance));
+                }
+            }
+            // Hits in the next cells are further away
+            best.is_none_or(|(_, distance)| distance >= exit)
+        });
+        best
+    }
+
+    fn nearest(&self, point: Vec3, k: usize) -> Vec<(Entity, f32)> {
+        let mut nearest = Vec::with_capacity(k + 1);
+        let Some(extent) = self.extent.filter(|_| k > 0) else {
+            return nearest;
+        };
+
+        // Far enough to contain everything
+        let limit =
+            ((point - extent.center()).abs() + extent.half_size()).length();
+        let mut radius = self.cell_size;
+        loop {
+            let found = self.within(point, radius);
+            if found.len() >= k || radius >= limit {
+                for (entity, distance) in found {
+                    push_nearest(&mut nearest, k, entity, distance);
+                }
+                return nearest;
+            }
+            radius *= 2.0;
+        }
+    }
+}
+
+impl UniformGrid {
+    fn remove_from_cells(&mut self, entity: Entity, bounds: &Aabb) {
+        let (min, max) = self.cells_of(bounds);
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let key = IVec3::new(x, y, z);
+                    if let Some(cell) = self.cells.get_mut(&key) {
+                        cell.retain(|other| *other != entity);
+                        if cell.is_empty() {
+                            self.cells.remove(&key);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::tests::assert_matches_linear;
+
+    #[test]
+    fn matches_brute_force() {
+        assert_matches_linear(UniformGrid::new(4.0));
+        assert_matches_linear(UniformGrid::new(0.7));
+        assert_matches_linear(UniformGrid::new(50.0));
+    }
+
+    #[test]
+    fn cells_follow_the_bounds() {
+        let mut grid = UniformGrid::new(1.0);
+        let entity = Entity::from_raw(0, 0);
+        grid.insert(entity, Aabb::new(Vec3::splat(0.1), Vec3::splat(1.5)));
+        assert_eq!(grid.cell_count(), 8);
+
+        // Same cells, only the bounds change
+        grid.insert(entity, Aabb::new(Vec3::splat(0.2), Vec3::splat(1.6)));
+        assert_eq!(grid.cell_count(), 8);
+
+        grid.insert(entity, Aabb::new(Vec3::splat(5.2), Vec3::splat(5.6)));
+        assert_eq!(grid.cell_count(), 1);
+        let ray = Ray::new(Vec3::ZERO, Vec3::ONE).unwrap();
+        let (hit, distance) = grid.raycast_first(&ray, 100.0).unwrap();
+        assert_eq!(hit, entity);
+        assert!((distance - 5.2 * 3f32.sqrt()).abs() < 1e-4);
+        assert_eq!(grid.raycast_first(&ray, 5.0), None);
+
+        grid.remove(entity);
+        assert_eq!(grid.cell_count(), 0);
+    }
+}
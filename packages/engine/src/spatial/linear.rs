@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    math::{Aabb, Ray, Vec3},
+    scene::Entity,
+};
+
+use super::{push_nearest, sort_hits, SpatialIndex};
+
+/// Tests every entity on each query.
+#[derive(Debug, Clone, Default)]
+pub struct LinearIndex {
+    entries: BTreeMap<Entity, Aabb>,
+}
+
+impl LinearIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SpatialIndex for LinearIndex {
+    fn insert(&mut self, entity: Entity, bounds: Aabb) -> Option<Aabb> {
+        self.entries.insert(entity, bounds)
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<Aabb> {
+        self.entries.remove(&entity)
+    }
+
+    fn bounds(&self, entity: Entity) -> Option<Aabb> {
+        self.entries.get(&entity).copied()
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        self.entries.keys().copied().collect()
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity> {
+        self.entries
+            .iter()
+            .filter(|(_, bounds)| bounds.intersects(aabb))
+            .map(|(entity, _)| *entity)
+            .collect()
+    }
+
+    fn query_radius(&self, center: Vec3, radius: f32) -> Vec<Entity> {
+        self.entries
+            .iter()
+            .filter(|(_, bounds)| {
+                bounds.distance_squared(center) <= radius * radius
+            })
+            .map(|(entity, _)| *entity)
+            .collect()
+    }
+
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(Entity, f32)> {
+        let mut hits: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|(entity, bounds)| {
+                let distance = ray.intersect_aabb(bounds)?;
+                (distance <= max_distance).then_some((*entity, distance))
+            })
+            .collect();
+        sort_hits(&mut hits);
+        hits
+    }
+
+    fn nearest(&self, point: Vec3, k: usize) -> Vec<(Entity, f32)> {
+        let mut nearest = Vec::with_capacity(k + 1);
+        for (entity, bounds) in &self.entries {
+            let distance = bounds.distance_squared(point).sqrt();
+            push_nearest(&mut nearest, k, *entity, distance);
+        }
+        nearest
+    }
+}
@@ -0,0 +1,258 @@
+//! Spatial indexes answering "what is around here" without testing every
+//! entity.
+//!
+//! Every index implements `SpatialIndex`, storing the bounds of entities:
+//!
+//! - `UniformGrid`: hashes bounds into cells of a fixed size, the fastest
+//!   choice when objects have similar sizes.
+//! - `Bvh`: dynamic bounding volume hierarchy, adapts to any size and
+//!   distribution. Bounds are padded, so small movements do not touch the
+//!   tree.
+//! - `LinearIndex`: tests everything, the reference the others are
+//!   benchmarked against (`cargo bench -p unen-engine`).
+//!
+//! The `Engine` keeps a `Bvh` in sync with the entities that have a
+//! `SpatialBounds` and a `GlobalTransform`, updated on each `Engine::step()`
+//! after the transforms.
+//!
+//! ```
+//! # use unen_engine::{math::{Aabb, Vec3}, scene::Entity};
+//! # use unen_engine::spatial::{Bvh, SpatialIndex};
+//! let mut index = Bvh::new();
+//! let entity = Entity::from_raw(0, 0);
+//! index.insert(entity, Aabb::from_center_size(Vec3::ZERO, Vec3::ONE));
+//!
+//! assert_eq!(index.query_radius(Vec3::new(2.0, 0.0, 0.0), 1.6), [entity]);
+//! assert_eq!(index.nearest(Vec3::new(0.0, 3.5, 0.0), 1), [(entity, 3.0)]);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math::{Aabb, Ray, Vec3},
+    scene::{Entity, GlobalTransform, World},
+};
+
+pub mod bvh;
+pub mod grid;
+pub mod linear;
+
+pub use bvh::Bvh;
+pub use grid::UniformGrid;
+pub use linear::LinearIndex;
+
+/// Stores the bounds of entities and finds them by location.
+///
+/// Results are sorted, by entity for the overlap queries and by distance
+/// (then entity) for the others, so every index returns the same results.
+pub trait SpatialIndex {
+    /// Adds the entity or moves it if it was already there, returns the
+    /// bounds it had before.
+    fn insert(&mut self, entity: Entity, bounds: Aabb) -> Option<Aabb>;
+
+    /// Moves an entity already in the index, returns `false` if it is not.
+    fn update(&mut self, entity: Entity, bounds: Aabb) -> bool {
+        if self.bounds(entity).is_none() {
+            return false;
+        }
+        self.insert(entity, bounds);
+        true
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<Aabb>;
+
+    fn bounds(&self, entity: Entity) -> Option<Aabb>;
+
+    /// Every entity in the index, sorted.
+    fn entities(&self) -> Vec<Entity>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self);
+
+    /// Entities whose bounds overlap the box.
+    fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity>;
+
+    /// Entities whose bounds are at most `radius` away from the center.
+    fn query_radius(&self, center: Vec3, radius: f32) -> Vec<Entity>;
+
+    /// Entities whose bounds are hit by the ray before `max_distance`,
+    /// with the distance of the hit.
+    fn raycast(&self, ray: &Ray, max_distance: f32) -> Vec<(Entity, f32)>;
+
+    /// Closest entity hit by the ray.
+    fn raycast_first(
+        &self,
+        ray: &Ray,
+        max_distance: f32,
+    ) -> Option<(Entity, f32)> {
+        self.raycast(ray, max_distance).first().copied()
+    }
+
+    /// The `k` entities with the closest bounds to the point, with their
+    /// distance.
+    fn nearest(&self, point: Vec3, k: usize) -> Vec<(Entity, f32)>;
+}
+
+/// Bounds of an entity relative to its `Transform`, makes the `Engine` add
+/// it to its spatial index.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct SpatialBounds(pub Aabb);
+
+/// Inserts, moves and removes the entities with `SpatialBounds` so that the
+/// index matches the world, only changed bounds are touched.
+///
+/// _Run it after `propagate_transforms()`._
+pub fn sync_bounds<I: SpatialIndex + ?Sized>(index: &mut I, world: &World) {
+    crate::profile_scope!("spatial::sync_bounds", "spatial");
+
+    for entity in index.entities() {
+        if !world.has::<SpatialBounds>(entity)
+            || !world.has::<GlobalTransform>(entity)
+        {
+            index.remove(entity);
+        }
+    }
+
+    for (entity, bounds) in world.query::<SpatialBounds>() {
+        let Some(global) = world.get::<GlobalTransform>(entity) else {
+            continue;
+        };
+        let bounds = bounds.0.transformed(&global.0);
+        if index.bounds(entity) != Some(bounds) {
+            index.insert(entity, bounds);
+        }
+    }
+}
+
+/// Keeps the `k` closest entities, sorted by distance then entity.
+pub(crate) fn push_nearest(
+    nearest: &mut Vec<(Entity, f32)>,
+    k: usize,
+    entity: Entity,
+    distance: f32,
+) {
+    let position = nearest.partition_point(|(other, other_distance)| {
+        (*other_distance, *other) < (distance, entity)
+    });
+    if position < k {
+        nearest.insert(position, (entity, distance));
+        nearest.truncate(k);
+    }
+}
+
+/// Sorts raycast hits by distance then entity.
+pub(crate) fn sort_hits(hits: &mut [(Entity, f32)]) {
+    hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::math::Transform;
+
+    /// Deterministic boxes of different sizes spread in a cube.
+    pub fn random_boxes(count: usize, seed: u64) -> Vec<(Entity, Aabb)> {
+        let mut state = seed.max(1);
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        };
+
+        (0..count as u32)
+            .map(|i| {
+                let center =
+                    Vec3::new(random(), random(), random()) * 100.0 - 50.0;
+                let size = Vec3::new(random(), random(), random()) * 4.0;
+                (Entity::from_raw(i, 0), Aabb::from_center_size(center, size))
+            })
+            .collect()
+    }
+
+    /// Runs the same queries on the index and on a `LinearIndex`.
+    pub fn assert_matches_linear<I: SpatialIndex>(mut index: I) {
+        let mut linear = LinearIndex::new();
+        for (entity, bounds) in random_boxes(500, 7) {
+            index.insert(entity, bounds);
+            linear.insert(entity, bounds);
+        }
+        // Move some, remove others
+        for (entity, bounds) in random_boxes(100, 11) {
+            index.update(entity, bounds);
+            linear.update(entity, bounds);
+        }
+        for i in (100..200).step_by(3) {
+            let entity = Entity::from_raw(i, 0);
+            assert_eq!(index.remove(entity), linear.remove(entity));
+        }
+        assert_eq!(index.len(), linear.len());
+        assert_eq!(index.entities(), linear.entities());
+
+        for (_, probe) in random_boxes(20, 13) {
+            let center = probe.center();
+            let size = probe.size().x * 4.0;
+            assert_eq!(
+                index.query_aabb(&Aabb::from_center_size(
+                    center,
+                    Vec3::splat(size)
+                )),
+                linear.query_aabb(&Aabb::from_center_size(
+                    center,
+                    Vec3::splat(size)
+                ))
+            );
+            assert_eq!(
+                index.query_radius(center, size),
+                linear.query_radius(center, size)
+            );
+            assert_eq!(index.nearest(center, 5), linear.nearest(center, 5));
+
+            let ray =
+                Ray::new(center, probe.size() - Vec3::splat(2.0)).unwrap();
+            assert_eq!(index.raycast(&ray, 60.0), linear.raycast(&ray, 60.0));
+            assert_eq!(
+                index.raycast_first(&ray, f32::INFINITY),
+                linear.raycast_first(&ray, f32::INFINITY)
+            );
+        }
+
+        assert_eq!(index.nearest(Vec3::ZERO, 1000).len(), index.len());
+        index.clear();
+        assert!(index.is_empty());
+        assert!(index.nearest(Vec3::ZERO, 3).is_empty());
+    }
+
+    #[test]
+    fn sync_follows_the_world() {
+        let mut world = World::new();
+        let mut index = LinearIndex::new();
+        let bounds =
+            SpatialBounds(Aabb::from_center_size(Vec3::ZERO, Vec3::ONE));
+        let moving = world.spawn_with(Transform::IDENTITY);
+        world.insert(moving, bounds).unwrap();
+        // Without a transform it has no position
+        let unplaced = world.spawn_with(bounds);
+
+        crate::scene::propagate_transforms(&mut world);
+        sync_bounds(&mut index, &world);
+        assert_eq!(index.entities(), [moving]);
+
+        world.get_mut::<Transform>(moving).unwrap().translation.x = 10.0;
+        world.insert(unplaced, Transform::IDENTITY).unwrap();
+        crate::scene::propagate_transforms(&mut world);
+        sync_bounds(&mut index, &world);
+        assert_eq!(index.bounds(moving).unwrap().center().x, 10.0);
+        assert_eq!(index.len(), 2);
+
+        world.despawn(moving);
+        world.remove::<SpatialBounds>(unplaced);
+        sync_bounds(&mut index, &world);
+        assert!(index.is_empty());
+    }
+}
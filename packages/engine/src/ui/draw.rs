@@ -0,0 +1,196 @@
+use super::Error;
+use crate::{
+    math::{Rect, Vec2},
+    render::{
+        self,
+        text::{FontId, TextRenderer},
+        Color, DrawCall, Renderer, TextStyle, TextureId, UvRect, Vertex,
+    },
+};
+
+/// Something to draw, positions are in pixels from the top-left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    Rect {
+        rect: Rect,
+        color: Color,
+    },
+    Image {
+        rect: Rect,
+        texture: TextureId,
+        uv: UvRect,
+        tint: Color,
+    },
+    Text {
+        /// Area the text was measured to fit in.
+        rect: Rect,
+        text: String,
+        font_size: f32,
+        color: Color,
+    },
+}
+
+/// Draws the commands into the frame that is being recorded, consecutive
+/// commands sharing a texture are batched in a single draw call.
+///
+/// Text is drawn with the first font of the `TextRenderer`, it is skipped
+/// without one.
+pub fn paint(
+    renderer: &mut dyn Renderer,
+    surface_size: Vec2,
+    commands: &[DrawCommand],
+    mut text: Option<&mut TextRenderer>,
+) -> Result<(), Error> {
+    let mut batch = Batch::new(surface_size);
+    for command in commands {
+        let (rect, texture, uv, color) = match command {
+            DrawCommand::Rect { rect, color } => {
+                (rect, None, UvRect::FULL, color)
+            }
+            DrawCommand::Image {
+                rect,
+                texture,
+                uv,
+                tint,
+            } => (rect, Some(*texture), *uv, tint),
+            DrawCommand::Text {
+                rect,
+                text: content,
+                font_size,
+                color,
+            } => {
+                if let Some(text) = text.as_deref_mut() {
+                    batch.flush(renderer)?;
+                    // The rounding of the layout must not change the wrapping
+                    let style = TextStyle::new(FontId::DEFAULT, *font_size)
+                        .with_color(*color)
+                        .with_max_width(rect.size().x + 1.0);
+                    text.draw(
+                        renderer,
+                        surface_size,
+                        rect.min,
+                        content,
+                        &style,
+                    )?;
+                }
+                continue;
+            }
+        };
+        if batch.texture != texture {
+            batch.flush(renderer)?;
+            batch.texture = texture;
+        }
+        batch.push(rect, uv, *color);
+    }
+    batch.flush(renderer)?;
+    Ok(())
+}
+
+/// Quads waiting to be drawn with the same texture.
+struct Batch {
+    scale: Vec2,
+    texture: Option<TextureId>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl Batch {
+    fn new(surface_size: Vec2) -> Self {
+        Self {
+            scale: Vec2::new(2.0, -2.0) / surface_size,
+            texture: None,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, rect: &Rect, uv: UvRect, color: Color) {
+        let to_clip = |point: Vec2| point * self.scale + Vec2::new(-1.0, 1.0);
+        let first = self.vertices.len() as u32;
+        self.vertices.extend([
+            Vertex::new(to_clip(rect.min), uv.min, color),
+            Vertex::new(
+                to_clip(Vec2::new(rect.max.x, rect.min.y)),
+                Vec2::new(uv.max.x, uv.min.y),
+                color,
+            ),
+            Vertex::new(to_clip(rect.max), uv.max, color),
+            Vertex::new(
+                to_clip(Vec2::new(rect.min.x, rect.max.y)),
+                Vec2::new(uv.min.x, uv.max.y),
+                color,
+            ),
+        ]);
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].into_iter().map(|index| first + index));
+    }
+
+    fn flush(
+        &mut self,
+        renderer: &mut dyn Renderer,
+    ) -> Result<(), render::Error> {
+        if self.indices.is_empty() {
+            return Ok(());
+        }
+        let mut call = DrawCall::new(&self.vertices, &self.indices);
+        if let Some(texture) = self.texture {
+            call = call.with_texture(texture);
+        }
+        renderer.draw(&call)?;
+        self.vertices.clear();
+        self.indices.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{SoftwareRenderer, TextureDescriptor};
+
+    #[test]
+    fn batches_by_texture() {
+        let mut renderer = SoftwareRenderer::new();
+        let surface = renderer.create_offscreen_surface(40, 20).unwrap();
+        let texture = renderer
+            .create_texture(
+                &TextureDescriptor::new(1, 1),
+                &Color::GREEN.to_rgba8(),
+            )
+            .unwrap();
+        let left = Rect::new(Vec2::ZERO, Vec2::new(20.0, 20.0));
+        let commands = [
+            DrawCommand::Rect {
+                rect: left,
+                color: Color::RED,
+            },
+            DrawCommand::Text {
+                rect: left,
+                text: "skipped".to_string(),
+                font_size: 16.0,
+                color: Color::WHITE,
+            },
+            DrawCommand::Rect {
+                rect: Rect::new(Vec2::new(0.0, 10.0), Vec2::new(20.0, 20.0)),
+                color: Color::BLUE,
+            },
+            DrawCommand::Image {
+                rect: Rect::new(Vec2::new(20.0, 0.0), Vec2::new(40.0, 20.0)),
+                texture,
+                uv: UvRect::FULL,
+                tint: Color::WHITE,
+            },
+        ];
+
+        renderer.begin_frame(surface).unwrap();
+        renderer.clear(Color::BLACK).unwrap();
+        paint(&mut renderer, Vec2::new(40.0, 20.0), &commands, None).unwrap();
+        assert_eq!(renderer.end_frame().unwrap().draw_calls, 2);
+
+        let image = renderer.read_surface(surface).unwrap();
+        assert_eq!(image.pixel(5, 5), Color::RED.to_rgba8());
+        // Later commands are drawn on top, y grows downwards
+        assert_eq!(image.pixel(5, 15), Color::BLUE.to_rgba8());
+        assert_eq!(image.pixel(30, 10), Color::GREEN.to_rgba8());
+    }
+}
@@ -0,0 +1,117 @@
+//! Retained user interface.
+//!
+//! A `Ui` owns a tree of `Node`s (containers, text, images and buttons) laid
+//! out with flexbox by `taffy`. The tree is kept between frames: nodes are
+//! added once and changed when needed, `Ui::compute_layout()` gives every
+//! node its rectangle in window pixels.
+//!
+//! Mouse and keyboard events are fed with `Ui::handle_event()` and turned
+//! into `UiEvent`s: hovering, pressing and clicking interactive nodes, and
+//! focus changes. Focusable nodes can be reached with `Tab` and the arrow
+//! keys, gamepads can drive the same navigation with `Ui::navigate()` and
+//! `Ui::activate()`.
+//!
+//! `Ui::draw_list()` returns what to draw, `paint()` sends it to a
+//! `Renderer`. Text is measured with a `TextMeasure`: passing the `Fonts` of
+//! the `TextRenderer` makes the layout match the drawn text.
+//!
+//! ```
+//! # use unen_engine::{math::Vec2, ui::{Node, Style, Ui, UiEvent}};
+//! let mut ui = Ui::new();
+//! let menu = ui
+//!     .add(ui.root(), Node::container().with_style(Style::new().with_padding(8.0)))
+//!     .unwrap();
+//! let play = ui
+//!     .add(menu, Node::button().with_style(Style::new().with_size(100.0, 30.0)))
+//!     .unwrap();
+//! ui.compute_layout(Vec2::new(640.0, 480.0)).unwrap();
+//! assert_eq!(ui.rect(play).unwrap().min, Vec2::new(8.0, 8.0));
+//!
+//! ui.activate();
+//! assert_eq!(ui.poll_event(), None);
+//! ```
+
+use thiserror::Error;
+
+use crate::math::Vec2;
+
+pub mod draw;
+pub mod node;
+pub mod style;
+pub mod text;
+pub mod tree;
+
+pub use draw::{paint, DrawCommand};
+pub use node::{Node, NodeId, NodeKind};
+pub use style::{NodeState, Style, Visuals};
+pub use taffy::style as layout;
+pub use text::{FixedAdvance, TextMeasure};
+pub use tree::Ui;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The node was removed or never existed.
+    #[error("No such node '{0}'")]
+    NoSuchNode(NodeId),
+    /// The root is needed by the whole tree.
+    #[error("The root node can not be removed")]
+    CannotRemoveRoot,
+    /// Only text nodes have their text changed.
+    #[error("'{0}' is not a text node")]
+    NotText(NodeId),
+    #[error("'{0}' can not be focused")]
+    NotFocusable(NodeId),
+    #[error("Layout failed: {0}")]
+    Layout(String),
+    #[error(transparent)]
+    Render(#[from] crate::render::Error),
+    #[error(transparent)]
+    Text(#[from] crate::render::text::Error),
+}
+
+impl From<taffy::TaffyError> for Error {
+    fn from(err: taffy::TaffyError) -> Self {
+        Self::Layout(err.to_string())
+    }
+}
+
+/// Interaction with a node, see `Ui::poll_event()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiEvent {
+    HoverStarted(NodeId),
+    HoverEnded(NodeId),
+    /// The left button was pressed over the node.
+    Pressed(NodeId),
+    /// The button was released, wherever the pointer is.
+    Released(NodeId),
+    /// Released over the pressed node, or activated with the keyboard.
+    Clicked(NodeId),
+    FocusGained(NodeId),
+    FocusLost(NodeId),
+}
+
+/// Moves the focus, see `Ui::navigate()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Navigation {
+    /// Following focusable node in tree order, wraps around.
+    Next,
+    Previous,
+    /// Closest focusable node in a direction.
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Navigation {
+    /// Direction in window pixels, `Vec2::ZERO` for tree order.
+    fn direction(self) -> Vec2 {
+        match self {
+            Self::Up => Vec2::NEG_Y,
+            Self::Down => Vec2::Y,
+            Self::Left => Vec2::NEG_X,
+            Self::Right => Vec2::X,
+            Self::Next | Self::Previous => Vec2::ZERO,
+        }
+    }
+}
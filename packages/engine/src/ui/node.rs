@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+use super::Style;
+use crate::{
+    math::Vec2,
+    render::{TextureId, UvRect},
+};
+
+/// Identifies a node of a `Ui`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct NodeId(pub(crate) u64);
+
+impl std::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Node({})", self.0)
+    }
+}
+
+impl From<taffy::NodeId> for NodeId {
+    fn from(node: taffy::NodeId) -> Self {
+        Self(node.into())
+    }
+}
+
+impl From<NodeId> for taffy::NodeId {
+    fn from(node: NodeId) -> Self {
+        node.0.into()
+    }
+}
+
+/// Content of a node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    /// Only lays out its children.
+    Container,
+    /// Wraps inside the width given by the layout.
+    Text(String),
+    Image {
+        texture: TextureId,
+        /// Used when the layout does not set the size.
+        size: Vec2,
+        uv: UvRect,
+    },
+}
+
+/// Node to be added to a `Ui`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub style: Style,
+    /// Receives hover, press and click events, the children of an
+    /// interactive node do not.
+    pub interactive: bool,
+    /// Can receive focus from the keyboard or a gamepad.
+    pub focusable: bool,
+}
+
+impl Node {
+    pub fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            style: Style::default(),
+            interactive: false,
+            focusable: false,
+        }
+    }
+
+    pub fn container() -> Self {
+        Self::new(NodeKind::Container)
+    }
+
+    pub fn text(text: &str) -> Self {
+        Self::new(NodeKind::Text(text.to_string()))
+    }
+
+    pub fn image(texture: TextureId, size: Vec2) -> Self {
+        Self::new(NodeKind::Image {
+            texture,
+            size,
+            uv: UvRect::FULL,
+        })
+    }
+
+    /// Interactive and focusable container, usually with a text inside.
+    pub fn button() -> Self {
+        Self::container()
+            .with_interactive(true)
+            .with_focusable(true)
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    pub fn with_focusable(mut self, focusable: bool) -> Self {
+        self.focusable = focusable;
+        self
+    }
+}
@@ -0,0 +1,209 @@
+use taffy::{
+    style::{
+        AlignItems, Dimension, FlexDirection, JustifyContent, LengthPercentage,
+        LengthPercentageAuto,
+    },
+    Rect, Size,
+};
+
+use crate::render::Color;
+
+/// Colors of a node, swapped depending on its state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Visuals {
+    pub background: Color,
+    /// Drawn over the border widths of the layout.
+    pub border: Color,
+    pub text: Color,
+    /// Multiplied with images.
+    pub tint: Color,
+}
+
+impl Default for Visuals {
+    fn default() -> Self {
+        Self {
+            background: Color::TRANSPARENT,
+            border: Color::TRANSPARENT,
+            text: Color::WHITE,
+            tint: Color::WHITE,
+        }
+    }
+}
+
+impl Visuals {
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_border(mut self, border: Color) -> Self {
+        self.border = border;
+        self
+    }
+
+    pub fn with_text(mut self, text: Color) -> Self {
+        self.text = text;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+}
+
+/// Layout and appearance of a node.
+///
+/// The common layout properties have helpers, anything else can be set on
+/// `layout` directly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// Flexbox properties, see `taffy`.
+    pub layout: taffy::Style,
+    pub visuals: Visuals,
+    /// Replaces the visuals while the pointer is over the node.
+    pub hovered: Option<Visuals>,
+    /// Replaces the visuals while the node is pressed, wins over `hovered`.
+    pub pressed: Option<Visuals>,
+    /// Replaces the visuals while the node has focus, loses against the
+    /// others.
+    pub focused: Option<Visuals>,
+    /// Pixels.
+    pub font_size: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            layout: taffy::Style::default(),
+            visuals: Visuals::default(),
+            hovered: None,
+            pressed: None,
+            focused: None,
+            font_size: 16.0,
+        }
+    }
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fixed size in pixels.
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.layout.size = Size {
+            width: Dimension::Length(width),
+            height: Dimension::Length(height),
+        };
+        self
+    }
+
+    /// Size relative to the parent, `1.0` fills it.
+    pub fn with_relative_size(mut self, width: f32, height: f32) -> Self {
+        self.layout.size = Size {
+            width: Dimension::Percent(width),
+            height: Dimension::Percent(height),
+        };
+        self
+    }
+
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.layout.padding = Rect::length(padding);
+        self
+    }
+
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.layout.margin = Rect {
+            left: LengthPercentageAuto::Length(margin),
+            right: LengthPercentageAuto::Length(margin),
+            top: LengthPercentageAuto::Length(margin),
+            bottom: LengthPercentageAuto::Length(margin),
+        };
+        self
+    }
+
+    /// Border width in pixels, drawn with `Visuals::border`.
+    pub fn with_border(mut self, width: f32) -> Self {
+        self.layout.border = Rect::length(width);
+        self
+    }
+
+    /// Space between children.
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.layout.gap = Size {
+            width: LengthPercentage::Length(gap),
+            height: LengthPercentage::Length(gap),
+        };
+        self
+    }
+
+    pub fn with_direction(mut self, direction: FlexDirection) -> Self {
+        self.layout.flex_direction = direction;
+        self
+    }
+
+    /// Placement of the children along the direction.
+    pub fn with_justify(mut self, justify: JustifyContent) -> Self {
+        self.layout.justify_content = Some(justify);
+        self
+    }
+
+    /// Placement of the children across the direction.
+    pub fn with_align(mut self, align: AlignItems) -> Self {
+        self.layout.align_items = Some(align);
+        self
+    }
+
+    /// Share of the free space taken by the node.
+    pub fn with_grow(mut self, grow: f32) -> Self {
+        self.layout.flex_grow = grow;
+        self
+    }
+
+    pub fn with_visuals(mut self, visuals: Visuals) -> Self {
+        self.visuals = visuals;
+        self
+    }
+
+    pub fn with_hovered(mut self, visuals: Visuals) -> Self {
+        self.hovered = Some(visuals);
+        self
+    }
+
+    pub fn with_pressed(mut self, visuals: Visuals) -> Self {
+        self.pressed = Some(visuals);
+        self
+    }
+
+    pub fn with_focused(mut self, visuals: Visuals) -> Self {
+        self.focused = Some(visuals);
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Visuals for the passed state.
+    pub fn visuals_for(&self, state: NodeState) -> Visuals {
+        let overrides = [
+            (state.pressed, self.pressed),
+            (state.hovered, self.hovered),
+            (state.focused, self.focused),
+        ];
+        overrides
+            .into_iter()
+            .find_map(|(active, visuals)| visuals.filter(|_| active))
+            .unwrap_or(self.visuals)
+    }
+}
+
+/// Interaction state of a node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeState {
+    pub hovered: bool,
+    pub pressed: bool,
+    pub focused: bool,
+}
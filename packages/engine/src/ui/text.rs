@@ -0,0 +1,114 @@
+use crate::{
+    math::Vec2,
+    render::text::{FontId, Fonts, TextStyle},
+};
+
+/// Measures text for the layout.
+pub trait TextMeasure {
+    /// Size of the text wrapped at `max_width`, `None` to keep every line
+    /// whole.
+    fn measure(
+        &self,
+        text: &str,
+        font_size: f32,
+        max_width: Option<f32>,
+    ) -> Vec2;
+}
+
+/// Approximates every character with the same advance, enough for tests and
+/// placeholder text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedAdvance {
+    /// Relative to the font size.
+    pub advance: f32,
+    /// Relative to the font size.
+    pub line_height: f32,
+}
+
+impl Default for FixedAdvance {
+    fn default() -> Self {
+        Self {
+            advance: 0.5,
+            line_height: 1.25,
+        }
+    }
+}
+
+impl TextMeasure for FixedAdvance {
+    fn measure(
+        &self,
+        text: &str,
+        font_size: f32,
+        max_width: Option<f32>,
+    ) -> Vec2 {
+        let advance = self.advance * font_size;
+        let max_columns = max_width
+            .map_or(usize::MAX, |width| ((width / advance) as usize).max(1));
+
+        let mut lines = 0;
+        let mut widest = 0;
+        for line in text.lines() {
+            // Greedy wrapping at spaces, long words overflow
+            let mut column = 0;
+            lines += 1;
+            for word in line.split(' ') {
+                let length = word.chars().count();
+                if column > 0 && column + 1 + length > max_columns {
+                    widest = widest.max(column);
+                    lines += 1;
+                    column = length;
+                } else {
+                    column += length + usize::from(column > 0);
+                }
+            }
+            widest = widest.max(column);
+        }
+
+        Vec2::new(
+            widest as f32 * advance,
+            lines.max(1) as f32 * self.line_height * font_size,
+        )
+    }
+}
+
+/// Measures with the first font, the one `paint()` draws text with.
+impl TextMeasure for Fonts {
+    fn measure(
+        &self,
+        text: &str,
+        font_size: f32,
+        max_width: Option<f32>,
+    ) -> Vec2 {
+        let mut style = TextStyle::new(FontId::DEFAULT, font_size);
+        style.max_width = max_width;
+        self.layout(text, &style)
+            .map_or(Vec2::ZERO, |layout| layout.size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_advance_wraps_words() {
+        let measure = FixedAdvance {
+            advance: 0.5,
+            line_height: 1.0,
+        };
+        // 10 pixels per character
+        assert_eq!(
+            measure.measure("hello world", 20.0, None),
+            Vec2::new(110.0, 20.0)
+        );
+        assert_eq!(
+            measure.measure("hello world", 20.0, Some(80.0)),
+            Vec2::new(50.0, 40.0)
+        );
+        assert_eq!(
+            measure.measure("a\nbcd", 20.0, None),
+            Vec2::new(30.0, 40.0)
+        );
+        assert_eq!(measure.measure("", 20.0, None), Vec2::new(0.0, 20.0));
+    }
+}
@@ -0,0 +1,843 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use taffy::{AvailableSpace, Dimension, Size, TaffyTree};
+use winit::{
+    event::MouseButton,
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use super::{
+    text::{FixedAdvance, TextMeasure},
+    DrawCommand, Error, Navigation, Node, NodeId, NodeKind, NodeState, Style,
+    UiEvent,
+};
+use crate::{
+    core::{
+        event::{Event, KeyboardEvent, MouseEvent},
+        window::WindowId,
+    },
+    math::{Rect, Vec2},
+};
+
+/// Retained tree of nodes laid out with flexbox.
+///
+/// Input is fed with `::handle_event()` and the resulting interactions are
+/// read with `::poll_event()`. Positions are in pixels from the top-left
+/// corner of the window.
+pub struct Ui {
+    taffy: TaffyTree,
+    root: NodeId,
+    nodes: BTreeMap<NodeId, Node>,
+    /// Absolute border boxes as of the last layout.
+    rects: BTreeMap<NodeId, Rect>,
+    /// Every node from the root, parents before their children.
+    order: Vec<NodeId>,
+    text_measure: Box<dyn TextMeasure>,
+    /// Only events of this window are handled.
+    window: WindowId,
+    pointer: Option<Vec2>,
+    hovered: Option<NodeId>,
+    pressed: Option<NodeId>,
+    focused: Option<NodeId>,
+    shift: bool,
+    events: VecDeque<UiEvent>,
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        let mut taffy = TaffyTree::new();
+        let root = taffy
+            .new_leaf(taffy::Style::default())
+            .expect("the tree is empty")
+            .into();
+
+        Self {
+            taffy,
+            root,
+            nodes: BTreeMap::from([(root, Node::container())]),
+            rects: BTreeMap::new(),
+            order: Vec::new(),
+            text_measure: Box::new(FixedAdvance::default()),
+            window: WindowId::PRIMARY,
+            pointer: None,
+            hovered: None,
+            pressed: None,
+            focused: None,
+            shift: false,
+            events: VecDeque::new(),
+        }
+    }
+}
+
+impl Ui {
+    /// Creates an empty tree handling the primary window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_text_measure(
+        mut self,
+        measure: impl TextMeasure + 'static,
+    ) -> Self {
+        self.text_measure = Box::new(measure);
+        self
+    }
+
+    pub fn with_window(mut self, window: WindowId) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Container filling the whole window.
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// Adds a node as the last child of `parent`.
+    pub fn add(&mut self, parent: NodeId, node: Node) -> Result<NodeId, Error> {
+        self.node(parent).ok_or(Error::NoSuchNode(parent))?;
+        let id = self.taffy.new_leaf(node.style.layout.clone())?;
+        self.taffy.add_child(parent.into(), id)?;
+        self.nodes.insert(id.into(), node);
+        Ok(id.into())
+    }
+
+    /// Removes the node and its children.
+    pub fn remove(&mut self, node: NodeId) -> Result<(), Error> {
+        if node == self.root {
+            return Err(Error::CannotRemoveRoot);
+        }
+        self.node(node).ok_or(Error::NoSuchNode(node))?;
+
+        for child in self.children(node) {
+            self.remove(child)?;
+        }
+        self.taffy.remove(node.into())?;
+        self.nodes.remove(&node);
+        self.rects.remove(&node);
+        self.order.retain(|other| *other != node);
+        for state in [&mut self.hovered, &mut self.pressed, &mut self.focused] {
+            if *state == Some(node) {
+                *state = None;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn node(&self, node: NodeId) -> Option<&Node> {
+        self.nodes.get(&node)
+    }
+
+    pub fn children(&self, node: NodeId) -> Vec<NodeId> {
+        self.taffy
+            .children(node.into())
+            .map(|children| children.into_iter().map(NodeId::from).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.taffy.parent(node.into()).map(NodeId::from)
+    }
+
+    pub fn set_style(
+        &mut self,
+        node: NodeId,
+        style: Style,
+    ) -> Result<(), Error> {
+        let current =
+            self.nodes.get_mut(&node).ok_or(Error::NoSuchNode(node))?;
+        self.taffy.set_style(node.into(), style.layout.clone())?;
+        current.style = style;
+        Ok(())
+    }
+
+    /// Replaces the content of a text node.
+    pub fn set_text(&mut self, node: NodeId, text: &str) -> Result<(), Error> {
+        match self.nodes.get_mut(&node).map(|node| &mut node.kind) {
+            Some(NodeKind::Text(current)) => {
+                if current != text {
+                    *current = text.to_string();
+                    self.taffy.mark_dirty(node.into())?;
+                }
+                Ok(())
+            }
+            Some(_) => Err(Error::NotText(node)),
+            None => Err(Error::NoSuchNode(node)),
+        }
+    }
+
+    /// Lays out the tree inside a window of the passed size, the hovered
+    /// node is updated since the content may have moved.
+    pub fn compute_layout(&mut self, size: Vec2) -> Result<(), Error> {
+        crate::profile_scope!("Ui::compute_layout", "ui");
+
+        let root = self.root.into();
+        let mut style = self.taffy.style(root)?.clone();
+        style.size = Size {
+            width: Dimension::Length(size.x),
+            height: Dimension::Length(size.y),
+        };
+        self.taffy.set_style(root, style)?;
+
+        let (nodes, text_measure) = (&self.nodes, &self.text_measure);
+        self.taffy.compute_layout_with_measure(
+            root,
+            Size {
+                width: AvailableSpace::Definite(size.x),
+                height: AvailableSpace::Definite(size.y),
+            },
+            |known, available, id, _, _| {
+                let measured = match nodes
+                    .get(&id.into())
+                    .map(|node| &node.kind)
+                {
+                    Some(NodeKind::Text(text)) => {
+                        let max_width = known.width.or(match available.width {
+                            AvailableSpace::Definite(width) => Some(width),
+                            AvailableSpace::MinContent => Some(0.0),
+                            AvailableSpace::MaxContent => None,
+                        });
+                        let font_size = nodes[&id.into()].style.font_size;
+                        text_measure.measure(text, font_size, max_width)
+                    }
+                    // Keeps the aspect ratio if only one side is known
+                    Some(NodeKind::Image { size, .. }) => {
+                        match (known.width, known.height) {
+                            (Some(width), None) if size.x > 0.0 => {
+                                Vec2::new(width, width * size.y / size.x)
+                            }
+                            (None, Some(height)) if size.y > 0.0 => {
+                                Vec2::new(height * size.x / size.y, height)
+                            }
+                            _ => *size,
+                        }
+                    }
+                    _ => Vec2::ZERO,
+                };
+                Size {
+                    width: known.width.unwrap_or(measured.x),
+                    height: known.height.unwrap_or(measured.y),
+                }
+            },
+        )?;
+
+        self.rects.clear();
+        self.order.clear();
+        let mut stack = vec![(self.root, Vec2::ZERO)];
+        while let Some((node, offset)) = stack.pop() {
+            let layout = self.taffy.layout(node.into())?;
+            let min = offset + Vec2::new(layout.location.x, layout.location.y);
+            let size = Vec2::new(layout.size.width, layout.size.height);
+            self.rects.insert(node, Rect::new(min, min + size));
+            self.order.push(node);
+            // Reversed so the first child is visited first
+            stack.extend(
+                self.children(node)
+                    .into_iter()
+                    .rev()
+                    .map(|child| (child, min)),
+            );
+        }
+
+        self.update_hover();
+        Ok(())
+    }
+
+    /// Border box of the node in pixels, as of the last layout.
+    pub fn rect(&self, node: NodeId) -> Option<Rect> {
+        self.rects.get(&node).copied()
+    }
+
+    pub fn state(&self, node: NodeId) -> NodeState {
+        NodeState {
+            hovered: self.hovered == Some(node),
+            pressed: self.pressed == Some(node),
+            focused: self.focused == Some(node),
+        }
+    }
+
+    pub fn hovered(&self) -> Option<NodeId> {
+        self.hovered
+    }
+
+    pub fn focused(&self) -> Option<NodeId> {
+        self.focused
+    }
+
+    /// Moves the focus, `None` removes it.
+    pub fn set_focus(&mut self, node: Option<NodeId>) -> Result<(), Error> {
+        if let Some(node) = node {
+            if !self.node(node).ok_or(Error::NoSuchNode(node))?.focusable {
+                return Err(Error::NotFocusable(node));
+            }
+        }
+        if self.focused != node {
+            if let Some(previous) = self.focused {
+                self.events.push_back(UiEvent::FocusLost(previous));
+            }
+            if let Some(node) = node {
+                self.events.push_back(UiEvent::FocusGained(node));
+            }
+            self.focused = node;
+        }
+        Ok(())
+    }
+
+    /// Moves the focus, useful to drive the UI with a gamepad.
+    ///
+    /// Returns `false` if there is nowhere to go.
+    pub fn navigate(&mut self, navigation: Navigation) -> bool {
+        // Nodes that are not laid out or hidden can not be reached
+        let focusable = self
+            .order
+            .iter()
+            .copied()
+            .filter(|node| {
+                self.nodes.get(node).is_some_and(|node| node.focusable)
+                    && self
+                        .rects
+                        .get(node)
+                        .is_some_and(|rect| rect.area() > 0.0)
+            })
+            .collect::<Vec<_>>();
+        let Some(&first) = focusable.first() else {
+            return false;
+        };
+        let current = self.focused.and_then(|focused| {
+            focusable.iter().position(|&node| node == focused)
+        });
+        let Some(current) = current else {
+            return self.set_focus(Some(first)).is_ok();
+        };
+
+        let next = match navigation {
+            Navigation::Next => {
+                Some(focusable[(current + 1) % focusable.len()])
+            }
+            Navigation::Previous => Some(
+                focusable[(current + focusable.len() - 1) % focusable.len()],
+            ),
+            _ => {
+                let direction = navigation.direction();
+                let from = self.rects[&focusable[current]].center();
+                focusable
+                    .iter()
+                    .filter_map(|&node| {
+                        let offset = self.rects[&node].center() - from;
+                        let along = offset.dot(direction);
+                        // Straight ahead is better than diagonal
+                        let across = offset.perp_dot(direction).abs();
+                        (along > 0.0).then_some((node, along + across * 2.0))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(node, _)| node)
+            }
+        };
+
+        match next {
+            Some(next) => self.set_focus(Some(next)).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Clicks the focused node, returns `false` if there is none.
+    pub fn activate(&mut self) -> bool {
+        match self.focused {
+            Some(node) => {
+                self.events.push_back(UiEvent::Clicked(node));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Feeds an input event, returns `true` if the UI used it and the game
+    /// should ignore it.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Mouse(window, event) if *window == self.window => {
+                self.handle_mouse(event)
+            }
+            Event::Keyboard(window, event) if *window == self.window => {
+                self.handle_keyboard(event)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the next interaction, in the order they happened.
+    pub fn poll_event(&mut self) -> Option<UiEvent> {
+        self.events.pop_front()
+    }
+
+    /// Everything to draw, back to front, as of the last layout.
+    pub fn draw_list(&self) -> Vec<DrawCommand> {
+        let mut commands = Vec::new();
+        for node in &self.order {
+            let (Some(rect), Ok(layout)) =
+                (self.rects.get(node), self.taffy.layout((*node).into()))
+            else {
+                continue;
+            };
+            let Node { kind, style, .. } = &self.nodes[node];
+            let visuals = style.visuals_for(self.state(*node));
+
+            if visuals.background.a > 0.0 {
+                commands.push(DrawCommand::Rect {
+                    rect: *rect,
+                    color: visuals.background,
+                });
+            }
+
+            let border = layout.border;
+            if visuals.border.a > 0.0 {
+                let sides = [
+                    Rect::new(
+                        rect.min,
+                        Vec2::new(rect.max.x, rect.min.y + border.top),
+                    ),
+                    Rect::new(
+                        Vec2::new(rect.min.x, rect.max.y - border.bottom),
+                        rect.max,
+                    ),
+                    Rect::new(
+                        Vec2::new(rect.min.x, rect.min.y + border.top),
+                        Vec2::new(
+                            rect.min.x + border.left,
+                            rect.max.y - border.bottom,
+                        ),
+                    ),
+                    Rect::new(
+                        Vec2::new(
+                            rect.max.x - border.right,
+                            rect.min.y + border.top,
+                        ),
+                        Vec2::new(rect.max.x, rect.max.y - border.bottom),
+                    ),
+                ];
+                commands.extend(
+                    sides.into_iter().filter(|side| side.area() > 0.0).map(
+                        |rect| DrawCommand::Rect {
+                            rect,
+                            color: visuals.border,
+                        },
+                    ),
+                );
+            }
+
+            let padding = layout.padding;
+            let content = Rect::new(
+                rect.min
+                    + Vec2::new(
+                        border.left + padding.left,
+                        border.top + padding.top,
+                    ),
+                rect.max
+                    - Vec2::new(
+                        border.right + padding.right,
+                        border.bottom + padding.bottom,
+                    ),
+            );
+            match kind {
+                NodeKind::Container => {}
+                NodeKind::Text(text) => commands.push(DrawCommand::Text {
+                    rect: content,
+                    text: text.clone(),
+                    font_size: style.font_size,
+                    color: visuals.text,
+                }),
+                NodeKind::Image { texture, uv, .. } => {
+                    commands.push(DrawCommand::Image {
+                        rect: content,
+                        texture: *texture,
+                        uv: *uv,
+                        tint: visuals.tint,
+                    })
+                }
+            }
+        }
+        commands
+    }
+
+    fn handle_mouse(&mut self, event: &MouseEvent) -> bool {
+        match event {
+            MouseEvent::Moved(position) => {
+                self.pointer = Some(*position);
+                self.update_hover();
+                self.hovered.is_some()
+            }
+            MouseEvent::Pressed(MouseButton::Left) => {
+                let Some(node) = self.hovered else {
+                    // Clicking elsewhere removes the focus
+                    let _ = self.set_focus(None);
+                    return false;
+                };
+                self.pressed = Some(node);
+                self.events.push_back(UiEvent::Pressed(node));
+                if self.nodes.get(&node).is_some_and(|node| node.focusable) {
+                    let _ = self.set_focus(Some(node));
+                }
+                true
+            }
+            MouseEvent::Released(MouseButton::Left) => {
+                let Some(node) = self.pressed.take() else {
+                    return false;
+                };
+                self.events.push_back(UiEvent::Released(node));
+                // Moving away before releasing cancels the click
+                if self.hovered == Some(node) {
+                    self.events.push_back(UiEvent::Clicked(node));
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_keyboard(&mut self, event: &KeyboardEvent) -> bool {
+        let (key, pressed) = match event {
+            KeyboardEvent::Pressed(PhysicalKey::Code(key)) => (*key, true),
+            KeyboardEvent::Released(PhysicalKey::Code(key)) => (*key, false),
+            _ => return false,
+        };
+        if matches!(key, KeyCode::ShiftLeft | KeyCode::ShiftRight) {
+            self.shift = pressed;
+            return false;
+        }
+        if !pressed {
+            return false;
+        }
+
+        match key {
+            KeyCode::Tab if self.shift => self.navigate(Navigation::Previous),
+            KeyCode::Tab => self.navigate(Navigation::Next),
+            KeyCode::ArrowUp => self.navigate(Navigation::Up),
+            KeyCode::ArrowDown => self.navigate(Navigation::Down),
+            KeyCode::ArrowLeft => self.navigate(Navigation::Left),
+            KeyCode::ArrowRight => self.navigate(Navigation::Right),
+            KeyCode::Enter | KeyCode::NumpadEnter | KeyCode::Space => {
+                self.activate()
+            }
+            _ => false,
+        }
+    }
+
+    /// Finds the topmost interactive node under the pointer.
+    fn update_hover(&mut self) {
+        let hovered = self.pointer.and_then(|pointer| {
+            // Later nodes are drawn on top
+            self.order
+                .iter()
+                .rev()
+                .copied()
+                .filter(|node| {
+                    self.rects
+                        .get(node)
+                        .is_some_and(|rect| rect.contains_point(pointer))
+                })
+                .find_map(|node| self.interactive_ancestor(node))
+        });
+
+        if hovered != self.hovered {
+            if let Some(previous) = self.hovered {
+                self.events.push_back(UiEvent::HoverEnded(previous));
+            }
+            if let Some(node) = hovered {
+                self.events.push_back(UiEvent::HoverStarted(node));
+            }
+            self.hovered = hovered;
+        }
+    }
+
+    /// The node or the closest of its parents that is interactive.
+    fn interactive_ancestor(&self, node: NodeId) -> Option<NodeId> {
+        let mut current = Some(node);
+        while let Some(node) = current {
+            if self.nodes.get(&node).is_some_and(|node| node.interactive) {
+                return Some(node);
+            }
+            current = self.parent(node);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use taffy::style::FlexDirection;
+
+    use super::*;
+    use crate::{render::Color, ui::Visuals};
+
+    fn moved(x: f32, y: f32) -> Event {
+        Event::Mouse(WindowId::PRIMARY, MouseEvent::Moved(Vec2::new(x, y)))
+    }
+
+    fn left_button(pressed: bool) -> Event {
+        let button = MouseButton::Left;
+        let event = match pressed {
+            true => MouseEvent::Pressed(button),
+            false => MouseEvent::Released(button),
+        };
+        Event::Mouse(WindowId::PRIMARY, event)
+    }
+
+    fn key(key: KeyCode) -> Event {
+        Event::Keyboard(
+            WindowId::PRIMARY,
+            KeyboardEvent::Pressed(PhysicalKey::Code(key)),
+        )
+    }
+
+    fn events(ui: &mut Ui) -> Vec<UiEvent> {
+        std::iter::from_fn(|| ui.poll_event()).collect()
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect::new(Vec2::new(x, y), Vec2::new(x + width, y + height))
+    }
+
+    #[test]
+    fn flexbox_layout() {
+        let mut ui = Ui::new();
+        let column = ui
+            .add(
+                ui.root(),
+                Node::container().with_style(
+                    Style::new()
+                        .with_direction(FlexDirection::Column)
+                        .with_padding(10.0)
+                        .with_gap(5.0)
+                        .with_size(200.0, 300.0),
+                ),
+            )
+            .unwrap();
+        let header = ui
+            .add(column, Node::text("Title").with_style(Style::new()))
+            .unwrap();
+        let body = ui
+            .add(
+                column,
+                Node::container().with_style(Style::new().with_grow(1.0)),
+            )
+            .unwrap();
+        let row = ui
+            .add(
+                column,
+                Node::container().with_style(Style::new().with_gap(4.0)),
+            )
+            .unwrap();
+        let buttons = [40.0, 60.0].map(|width| {
+            ui.add(
+                row,
+                Node::button().with_style(Style::new().with_size(width, 20.0)),
+            )
+            .unwrap()
+        });
+        ui.compute_layout(Vec2::new(640.0, 480.0)).unwrap();
+
+        assert_eq!(ui.rect(ui.root()), Some(rect(0.0, 0.0, 640.0, 480.0)));
+        assert_eq!(ui.rect(column), Some(rect(0.0, 0.0, 200.0, 300.0)));
+        // 5 characters of 8 pixels, lines of 20 pixels
+        assert_eq!(ui.rect(header).unwrap().size(), Vec2::new(180.0, 20.0));
+        // Takes what is left: 280 - 20 - 20 - 2 gaps
+        assert_eq!(ui.rect(body), Some(rect(10.0, 35.0, 180.0, 230.0)));
+        assert_eq!(ui.rect(row), Some(rect(10.0, 270.0, 180.0, 20.0)));
+        assert_eq!(ui.rect(buttons[0]), Some(rect(10.0, 270.0, 40.0, 20.0)));
+        assert_eq!(ui.rect(buttons[1]), Some(rect(54.0, 270.0, 60.0, 20.0)));
+
+        // Text wraps when it does not fit
+        ui.set_text(header, "A title that is too long for one line")
+            .unwrap();
+        ui.compute_layout(Vec2::new(640.0, 480.0)).unwrap();
+        assert_eq!(ui.rect(header).unwrap().size(), Vec2::new(180.0, 40.0));
+        assert_eq!(ui.rect(body).unwrap().min.y, 55.0);
+
+        ui.remove(row).unwrap();
+        assert_eq!(ui.node(buttons[0]), None);
+        assert_eq!(ui.children(column), vec![header, body]);
+        assert_eq!(ui.remove(ui.root()), Err(Error::CannotRemoveRoot));
+        assert_eq!(ui.set_text(body, "x"), Err(Error::NotText(body)));
+    }
+
+    #[test]
+    fn pointer_interactions() {
+        let mut ui = Ui::new();
+        let button = ui
+            .add(
+                ui.root(),
+                Node::button().with_style(Style::new().with_size(100.0, 40.0)),
+            )
+            .unwrap();
+        // Children are part of the button
+        let label = ui.add(button, Node::text("Play")).unwrap();
+        ui.compute_layout(Vec2::new(640.0, 480.0)).unwrap();
+
+        assert!(ui.handle_event(&moved(20.0, 10.0)));
+        assert!(ui.handle_event(&left_button(true)));
+        assert!(ui.handle_event(&left_button(false)));
+        assert_eq!(
+            events(&mut ui),
+            vec![
+                UiEvent::HoverStarted(button),
+                UiEvent::Pressed(button),
+                UiEvent::FocusGained(button),
+                UiEvent::Released(button),
+                UiEvent::Clicked(button),
+            ]
+        );
+        assert_eq!(ui.hovered(), Some(button));
+        assert!(ui.state(button).focused);
+        assert!(!ui.state(label).hovered);
+
+        // Leaving before releasing cancels the click
+        ui.handle_event(&left_button(true));
+        assert!(!ui.handle_event(&moved(300.0, 300.0)));
+        ui.handle_event(&left_button(false));
+        assert_eq!(
+            events(&mut ui),
+            vec![
+                UiEvent::Pressed(button),
+                UiEvent::HoverEnded(button),
+                UiEvent::Released(button),
+            ]
+        );
+
+        // Other windows are ignored
+        let other = Event::Mouse(WindowId::new(), MouseEvent::Moved(Vec2::ONE));
+        assert!(!ui.handle_event(&other));
+        assert_eq!(ui.hovered(), None);
+    }
+
+    #[test]
+    fn removed_nodes_are_not_hovered() {
+        let mut ui = Ui::new();
+        let button = ui
+            .add(
+                ui.root(),
+                Node::button().with_style(Style::new().with_size(100.0, 40.0)),
+            )
+            .unwrap();
+        ui.add(button, Node::text("Play")).unwrap();
+        ui.compute_layout(Vec2::new(640.0, 480.0)).unwrap();
+
+        ui.remove(button).unwrap();
+        assert!(!ui.handle_event(&moved(20.0, 10.0)));
+        assert!(!ui.navigate(Navigation::Next));
+        assert_eq!(ui.hovered(), None);
+        assert!(ui.draw_list().is_empty());
+    }
+
+    #[test]
+    fn focus_navigation() {
+        let mut ui = Ui::new();
+        let grid = ui
+            .add(
+                ui.root(),
+                Node::container().with_style(
+                    Style::new()
+                        .with_size(100.0, 100.0)
+                        .with_gap(10.0)
+                        .with_direction(FlexDirection::Row),
+                ),
+            )
+            .unwrap();
+        let style = Style::new().with_size(40.0, 40.0);
+        let left = ui
+            .add(grid, Node::button().with_style(style.clone()))
+            .unwrap();
+        let right = ui.add(grid, Node::button().with_style(style)).unwrap();
+        ui.compute_layout(Vec2::new(640.0, 480.0)).unwrap();
+
+        assert!(ui.handle_event(&key(KeyCode::Tab)));
+        assert_eq!(ui.focused(), Some(left));
+        assert!(ui.handle_event(&key(KeyCode::ArrowRight)));
+        assert_eq!(ui.focused(), Some(right));
+        // Nothing further right
+        assert!(!ui.handle_event(&key(KeyCode::ArrowRight)));
+        assert!(!ui.handle_event(&key(KeyCode::ArrowDown)));
+        // Tab wraps around
+        assert!(ui.handle_event(&key(KeyCode::Tab)));
+        assert_eq!(ui.focused(), Some(left));
+        ui.handle_event(&key(KeyCode::ShiftLeft));
+        ui.handle_event(&key(KeyCode::Tab));
+        assert_eq!(ui.focused(), Some(right));
+
+        assert!(ui.handle_event(&key(KeyCode::Enter)));
+        assert_eq!(
+            events(&mut ui),
+            vec![
+                UiEvent::FocusGained(left),
+                UiEvent::FocusLost(left),
+                UiEvent::FocusGained(right),
+                UiEvent::FocusLost(right),
+                UiEvent::FocusGained(left),
+                UiEvent::FocusLost(left),
+                UiEvent::FocusGained(right),
+                UiEvent::Clicked(right),
+            ]
+        );
+        assert_eq!(ui.set_focus(Some(grid)), Err(Error::NotFocusable(grid)));
+    }
+
+    #[test]
+    fn draw_list_follows_state() {
+        let hovered = Color::rgb(0.0, 0.0, 0.5);
+        let mut ui = Ui::new();
+        let button = ui
+            .add(
+                ui.root(),
+                Node::button().with_style(
+                    Style::new()
+                        .with_size(50.0, 30.0)
+                        .with_padding(5.0)
+                        .with_border(1.0)
+                        .with_visuals(
+                            Visuals::default()
+                                .with_background(Color::BLACK)
+                                .with_border(Color::RED),
+                        )
+                        .with_hovered(
+                            Visuals::default()
+                                .with_background(hovered)
+                                .with_border(Color::RED),
+                        ),
+                ),
+            )
+            .unwrap();
+        ui.add(button, Node::text("Ok")).unwrap();
+        ui.compute_layout(Vec2::new(100.0, 100.0)).unwrap();
+
+        let commands = ui.draw_list();
+        assert_eq!(commands.len(), 6);
+        assert_eq!(
+            commands[0],
+            DrawCommand::Rect {
+                rect: rect(0.0, 0.0, 50.0, 30.0),
+                color: Color::BLACK,
+            }
+        );
+        assert_eq!(
+            commands[1],
+            DrawCommand::Rect {
+                rect: rect(0.0, 0.0, 50.0, 1.0),
+                color: Color::RED,
+            }
+        );
+        assert_eq!(
+            commands[5],
+            DrawCommand::Text {
+                rect: rect(6.0, 6.0, 16.0, 18.0),
+                text: "Ok".to_string(),
+                font_size: 16.0,
+                color: Color::WHITE,
+            }
+        );
+
+        ui.handle_event(&moved(10.0, 10.0));
+        assert!(matches!(
+            ui.draw_list()[0],
+            DrawCommand::Rect { color, .. } if color == hovered
+        ));
+    }
+}